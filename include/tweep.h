@@ -0,0 +1,52 @@
+/* C declarations for the tweep ffi module, enabled with the "ffi" feature.
+ * See the documentation of tweep::ffi for details. Strings are UTF-8, are not
+ * NUL-terminated, and are valid until the story they came from is freed.
+ *
+ * Every function accepts a NULL story, such as the one tweep_parse returns
+ * for input that is not valid UTF-8, and returns false, 0, or a TweepStr with
+ * a NULL ptr for it.
+ *
+ * Panics inside tweep are caught and never unwind into C. A function that
+ * panics returns NULL, false, 0, or a TweepStr with a NULL ptr instead, and
+ * tweep_story_free does nothing. */
+
+#ifndef TWEEP_H
+#define TWEEP_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct TweepStory TweepStory;
+
+typedef struct TweepStr {
+    const uint8_t *ptr;
+    size_t len;
+} TweepStr;
+
+TweepStory *tweep_parse(const uint8_t *data, size_t len);
+void tweep_story_free(TweepStory *story);
+bool tweep_story_is_ok(const TweepStory *story);
+TweepStr tweep_story_title(const TweepStory *story);
+TweepStr tweep_story_start(const TweepStory *story);
+
+size_t tweep_story_passage_count(const TweepStory *story);
+TweepStr tweep_story_passage_name(const TweepStory *story, size_t index);
+TweepStr tweep_story_passage_content(const TweepStory *story, size_t index);
+size_t tweep_story_passage_tag_count(const TweepStory *story, size_t index);
+TweepStr tweep_story_passage_tag(const TweepStory *story, size_t index, size_t tag);
+
+size_t tweep_story_warning_count(const TweepStory *story);
+TweepStr tweep_story_warning(const TweepStory *story, size_t index, size_t *line, size_t *column);
+size_t tweep_story_error_count(const TweepStory *story);
+TweepStr tweep_story_error(const TweepStory *story, size_t index, size_t *line, size_t *column);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
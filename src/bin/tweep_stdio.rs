@@ -0,0 +1,8 @@
+//! Runs the tweep stdio protocol over stdin and stdout. See `tweep::serve`
+//! for a description of the protocol
+
+fn main() -> std::io::Result<()> {
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    tweep::serve(stdin.lock(), stdout.lock())
+}
@@ -1,7 +1,8 @@
 use crate::context::Position;
 use crate::context::PositionKind;
+use crate::context::SpanError;
 use std::borrow::Borrow;
-use std::rc::Rc;
+use std::sync::Arc;
 
 /// A context that represents a span of twee code with a beginning, end, and
 /// contents, along with a file name and some helper functions
@@ -10,8 +11,8 @@ pub struct FullContext {
     file_name: Option<String>,
     start_position: Position,
     end_position: Position,
-    contents: Rc<String>,
-    line_starts: Rc<Vec<usize>>,
+    contents: Arc<String>,
+    line_starts: Arc<Vec<usize>>,
 }
 
 mod util {
@@ -21,9 +22,47 @@ mod util {
         std::iter::once(0).chain(s.match_indices('\n').map(|(i, _)| i + 1))
     }
 
-    pub(crate) fn to_byte_index(p: &Position, line_starts: &[usize], inclusive: bool) -> usize {
-        let mut x = line_starts[p.line - 1] + p.column;
-        if !inclusive {
+    /// Converts a position to a byte index in the contents, or the index after
+    /// it if `inclusive` is set. Returns `None` instead of overflowing or
+    /// indexing out of bounds if the line is not in the file
+    pub(crate) fn checked_byte_index(
+        p: &Position,
+        line_starts: &[usize],
+        inclusive: bool,
+    ) -> Option<usize> {
+        let x = line_starts
+            .get(p.line.checked_sub(1)?)?
+            .checked_add(p.column)?;
+        if inclusive {
+            Some(x)
+        } else {
+            x.checked_sub(1)
+        }
+    }
+
+    /// Like `checked_byte_index`, but clamps the position into `contents`, moving
+    /// a line of 0 to the start, a line past the end to the end, and an index
+    /// inside of a character back to the start of the character
+    pub(crate) fn clamped_byte_index(
+        p: &Position,
+        line_starts: &[usize],
+        contents: &str,
+        inclusive: bool,
+    ) -> usize {
+        let mut x = if p.line == 0 {
+            0
+        } else if p.line > line_starts.len() {
+            contents.len()
+        } else {
+            let x = line_starts[p.line - 1].saturating_add(p.column);
+            if inclusive {
+                x
+            } else {
+                x.saturating_sub(1)
+            }
+        };
+        x = x.min(contents.len());
+        while !contents.is_char_boundary(x) {
             x -= 1;
         }
         x
@@ -31,12 +70,14 @@ mod util {
 
     pub(crate) fn end_of_line(line: usize, line_starts: &[usize], contents: &str) -> Position {
         let start = line_starts[line - 1];
-        let len = if line >= line_starts.len() {
-            contents[start..].len()
+        let text = if line >= line_starts.len() {
+            &contents[start..]
         } else {
             // Don't want ending newline
-            line_starts[line] - start - 1
+            &contents[start..line_starts[line] - 1]
         };
+        // Nor the carriage return of a CRLF line ending
+        let len = text.strip_suffix('\r').unwrap_or(text).len();
         Position::abs(line, len)
     }
 }
@@ -46,8 +87,8 @@ impl FullContext {
         file_name: Option<String>,
         start_position: Position,
         end_position: Position,
-        contents: Rc<String>,
-        line_starts: Rc<Vec<usize>>,
+        contents: Arc<String>,
+        line_starts: Arc<Vec<usize>>,
     ) -> Self {
         FullContext {
             file_name,
@@ -86,12 +127,17 @@ impl FullContext {
         Position::abs(line, col)
     }
 
+    /// Gets the inclusive byte range of the given 1-indexed line of the file,
+    /// if the file has that line
     #[cfg(feature = "full-context")]
-    pub(crate) fn line_bytes(&self, line: usize) -> std::ops::RangeInclusive<usize> {
+    pub(crate) fn line_bytes(&self, line: usize) -> Option<std::ops::RangeInclusive<usize>> {
+        if line == 0 || line > self.line_starts.len() {
+            return None;
+        }
         let (start, end) = self.line_range(line, PositionKind::Absolute).into_inner();
-        let start_byte = util::to_byte_index(&start, &self.line_starts, false);
-        let end_byte = util::to_byte_index(&end, &self.line_starts, false);
-        start_byte..=end_byte
+        let start_byte = self.clamped_byte_index(&start, false);
+        let end_byte = self.clamped_byte_index(&end, false);
+        Some(start_byte..=end_byte)
     }
 
     #[cfg(feature = "full-context")]
@@ -107,6 +153,30 @@ impl FullContext {
         start..=self.end_of_line(line, kind)
     }
 
+    /// Returns the relative position of the given 1-indexed column of this
+    /// context as if all of its lines were joined into one, so that a column
+    /// past the end of the first line is moved onto the line it falls on.
+    /// This lets a header that continues onto later lines be parsed as one
+    /// line while keeping the spans within it correct
+    pub(crate) fn joined_position(&self, column: usize) -> Position {
+        let start = self.clamped_byte_index(&self.start_position, false);
+        let first_line_end = self
+            .line_starts
+            .get(self.start_position.line)
+            .copied()
+            .unwrap_or_else(|| self.contents.len() + 1);
+        let byte = start + column;
+        if column == 0 || byte <= first_line_end {
+            return Position::rel(1, column);
+        }
+        let byte = byte - 1;
+        let line = self.line_starts.partition_point(|&start| start <= byte);
+        Position::rel(
+            line + 1 - self.start_position.line,
+            byte - self.line_starts[line - 1] + 1,
+        )
+    }
+
     /// Creates a new context from the given file name and string
     pub fn from(file_name: Option<String>, contents: String) -> Self {
         let line_starts = util::line_starts(&contents).collect::<Vec<usize>>();
@@ -116,8 +186,8 @@ impl FullContext {
             file_name,
             start,
             end,
-            Rc::new(contents),
-            Rc::new(line_starts),
+            Arc::new(contents),
+            Arc::new(line_starts),
         )
     }
 
@@ -137,22 +207,100 @@ impl FullContext {
     }
 
     /// Gets the span of this context as line bytes within the contents
+    ///
+    /// Positions outside of the file, such as those of a context deserialized
+    /// from untrusted input, are clamped into it
     pub fn get_byte_range(&self) -> Range<usize> {
-        let start = util::to_byte_index(&self.start_position, &self.line_starts, false);
-        let end = util::to_byte_index(&self.end_position, &self.line_starts, true);
+        let start = self.clamped_byte_index(&self.start_position, false);
+        let end = self.clamped_byte_index(&self.end_position, true);
         start..end
     }
 
     /// Gets a reference to the contents of this context
+    ///
+    /// Positions outside of the file are clamped into it, so this never
+    /// panics, even for a context made from invalid positions
     pub fn get_contents(&self) -> &str {
-        let start = util::to_byte_index(&self.start_position, &self.line_starts, false);
-        let mut end = util::to_byte_index(&self.end_position, &self.line_starts, true);
+        let start = self.clamped_byte_index(&self.start_position, false);
+        let mut end = self.clamped_byte_index(&self.end_position, true);
         if end < start {
             end = start;
         }
         &self.contents[start..end]
     }
 
+    /// Converts a position to a byte index in the contents, clamping it into
+    /// the contents
+    fn clamped_byte_index(&self, p: &Position, inclusive: bool) -> usize {
+        util::clamped_byte_index(p, &self.line_starts, &self.contents, inclusive)
+    }
+
+    /// Gets the whole text of the file this context is in, shared with every
+    /// other context in the file, so that it can be held onto without copying
+    /// it
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use tweep::{FullContext, Position};
+    /// let context = FullContext::from(None, ":: Start\nHello".to_string());
+    /// let sub = context.subcontext(Position::rel(2, 1)..);
+    /// assert_eq!(sub.get_contents(), "Hello");
+    /// assert_eq!(sub.source().as_str(), ":: Start\nHello");
+    /// assert!(Arc::ptr_eq(sub.source(), context.source()));
+    /// ```
+    pub fn source(&self) -> &Arc<String> {
+        &self.contents
+    }
+
+    /// Iterates over the lines of this context, yielding the 1-indexed line
+    /// number in the file, the part of the line within this context without
+    /// its line ending, and the byte range of that part within the whole file
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{FullContext, Position};
+    /// let context = FullContext::from(None, ":: Start\r\nHello\r\nWorld".to_string());
+    /// let sub = context.subcontext(Position::rel(2, 3)..);
+    /// let lines: Vec<_> = sub.lines().collect();
+    /// assert_eq!(lines, [(2, "llo", 12..15), (3, "World", 17..22)]);
+    /// ```
+    pub fn lines(&self) -> impl Iterator<Item = (usize, &str, Range<usize>)> + '_ {
+        let first_line = self.start_position.line;
+        let mut offset = self.clamped_byte_index(&self.start_position, false);
+        self.get_contents()
+            .split('\n')
+            .enumerate()
+            .map(move |(i, line)| {
+                let start = offset;
+                offset += line.len() + 1;
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                (first_line + i, line, start..start + line.len())
+            })
+    }
+
+    /// Gets the contents of this context with any CRLF line endings replaced
+    /// by LF, for storing the text of a passage
+    pub(crate) fn get_normalized_contents(&self) -> String {
+        self.get_contents().replace("\r\n", "\n")
+    }
+
+    /// Gets the text of the given 1-indexed line of the whole file, without
+    /// the line ending
+    #[cfg(feature = "report")]
+    pub(crate) fn line_text(&self, line: usize) -> &str {
+        let start = match line.checked_sub(1).and_then(|i| self.line_starts.get(i)) {
+            Some(start) => *start,
+            None => return "",
+        };
+        let end = self
+            .line_starts
+            .get(line)
+            .map(|next| next - 1)
+            .unwrap_or_else(|| self.contents.len());
+        self.contents[start..end].trim_end_matches('\r')
+    }
+
     pub(crate) fn get_line_starts(&self) -> &Vec<usize> {
         self.line_starts.borrow()
     }
@@ -167,6 +315,73 @@ impl FullContext {
         self.inner_subcontext(start, end)
     }
 
+    /// Creates a subcontext out of the current context like [`subcontext`],
+    /// but returns a [`SpanError`] instead of panicking or producing a
+    /// context with invalid contents if the range does not fit in the file.
+    /// Use this for ranges that come from outside of tweep, such as from an
+    /// editor
+    ///
+    /// The end of the range may come one position before its start, which
+    /// makes an empty context.
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{FullContext, Position, SpanError};
+    /// let context = FullContext::from(None, ":: Start\nHello".to_string());
+    /// let sub = context.try_subcontext(Position::rel(2, 1)..=Position::rel(2, 5));
+    /// assert_eq!(sub.unwrap().get_contents(), "Hello");
+    /// let sub = context.try_subcontext(Position::rel(2, 1)..=Position::rel(2, 9));
+    /// assert_eq!(sub, Err(SpanError::OutOfRange(Position::abs(2, 9))));
+    /// ```
+    ///
+    /// [`subcontext`]: #method.subcontext
+    /// [`SpanError`]: enum.SpanError.html
+    pub fn try_subcontext<T>(&self, range: T) -> Result<Self, SpanError>
+    where
+        T: RangeBounds<Position>,
+    {
+        for bound in [range.start_bound(), range.end_bound()] {
+            if let Bound::Included(p) | Bound::Excluded(p) = bound {
+                if p.line == 0 {
+                    return Err(SpanError::ZeroPosition(*p));
+                }
+            }
+        }
+        if let Bound::Included(p) | Bound::Excluded(p) = range.start_bound() {
+            if p.column == 0 {
+                return Err(SpanError::ZeroPosition(*p));
+            }
+        }
+
+        let start = try_bound_to_position(self, range.start_bound(), true)?;
+        let end = try_bound_to_position(self, range.end_bound(), false)?;
+        let start_byte = self.checked_byte_index(&start, false)?;
+        let end_byte = self.checked_byte_index(&end, true)?;
+        if end_byte < start_byte {
+            return Err(SpanError::Reversed(start, end));
+        }
+        Ok(self.inner_subcontext(start, end))
+    }
+
+    /// Converts an absolute position to a byte index in the contents,
+    /// checking that it is within its line and on a character boundary
+    fn checked_byte_index(&self, p: &Position, inclusive: bool) -> Result<usize, SpanError> {
+        let index = util::checked_byte_index(p, &self.line_starts, inclusive)
+            .ok_or(SpanError::OutOfRange(*p))?;
+        let line_end = self
+            .line_starts
+            .get(p.line)
+            .copied()
+            .unwrap_or_else(|| self.contents.len());
+        if index > line_end {
+            Err(SpanError::OutOfRange(*p))
+        } else if !self.contents.is_char_boundary(index) {
+            Err(SpanError::NotCharBoundary(*p))
+        } else {
+            Ok(index)
+        }
+    }
+
     pub(crate) fn inner_subcontext(
         &self,
         start_position: Position,
@@ -196,6 +411,81 @@ impl FullContext {
     }
 }
 
+/// The most padding a deserialized [`FullContext`] may need to put its start
+/// position back where it was, so that untrusted input cannot make it allocate
+/// an arbitrary amount of memory
+///
+/// [`FullContext`]: struct.FullContext.html
+#[cfg(feature = "serde")]
+const MAX_PADDING: usize = 64 * 1024 * 1024;
+
+/// The serialized form of a [`FullContext`]. Only the contents covered by the
+/// context are kept, rather than the contents of the whole file
+///
+/// [`FullContext`]: struct.FullContext.html
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedContext {
+    file_name: Option<String>,
+    start_position: Position,
+    end_position: Position,
+    contents: String,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for FullContext {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedContext {
+            file_name: self.file_name.clone(),
+            start_position: self.start_position,
+            end_position: self.end_position,
+            contents: self.get_contents().to_string(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for FullContext {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let serialized = SerializedContext::deserialize(deserializer)?;
+        let start = serialized.start_position;
+        if start.line == 0 || start.column == 0 {
+            return Err(D::Error::custom("context positions are one-indexed"));
+        }
+        if (start.line - 1).saturating_add(start.column - 1) > MAX_PADDING {
+            return Err(D::Error::custom(
+                "context start position is too far into its file",
+            ));
+        }
+
+        // Pad the contents so that the start and end positions still point
+        // at the same lines and columns as they did in the original file
+        let mut contents = "\n".repeat(start.line - 1);
+        contents.push_str(&" ".repeat(start.column - 1));
+        contents.push_str(&serialized.contents);
+        let line_starts = util::line_starts(&contents).collect::<Vec<usize>>();
+        let past_end = match util::checked_byte_index(&serialized.end_position, &line_starts, true)
+        {
+            Some(end) => end > contents.len(),
+            None => true,
+        };
+        if past_end {
+            return Err(D::Error::custom(
+                "context end position is past its contents",
+            ));
+        }
+        Ok(Self::new_with_line_starts(
+            serialized.file_name,
+            start,
+            serialized.end_position,
+            Arc::new(contents),
+            Arc::new(line_starts),
+        ))
+    }
+}
+
 use std::ops::Bound;
 use std::ops::Range;
 use std::ops::RangeBounds;
@@ -205,6 +495,15 @@ pub trait SubContextRange {
 }
 
 fn bound_to_position(ctx: &FullContext, pos: Bound<&Position>, start: bool) -> Position {
+    try_bound_to_position(ctx, pos, start)
+        .unwrap_or_else(|_| panic!("Tried to take exclusive range ending at: {:?}", pos))
+}
+
+fn try_bound_to_position(
+    ctx: &FullContext,
+    pos: Bound<&Position>,
+    start: bool,
+) -> Result<Position, SpanError> {
     let ret = match pos {
         Bound::Included(p) => match p.kind {
             PositionKind::Absolute => *p,
@@ -221,12 +520,12 @@ fn bound_to_position(ctx: &FullContext, pos: Bound<&Position>, start: bool) -> P
                     column: abs.column - 1,
                     ..abs
                 }
-            } else if abs.line > 1 {
-                let line = p.line - 1;
+            } else if abs.line > 1 && abs.line <= ctx.get_line_starts().len() + 1 {
+                let line = abs.line - 1;
                 let col = ctx.end_of_line(line, PositionKind::Absolute).column;
                 Position::abs(line, col)
             } else {
-                panic!("Tried to take exclusive range ending at: {:?}", abs);
+                return Err(SpanError::OutOfRange(abs));
             }
         }
         Bound::Unbounded => {
@@ -238,7 +537,7 @@ fn bound_to_position(ctx: &FullContext, pos: Bound<&Position>, start: bool) -> P
         }
     };
     assert_eq!(ret.kind, PositionKind::Absolute);
-    ret
+    Ok(ret)
 }
 
 impl<T> SubContextRange for T
@@ -256,6 +555,8 @@ where
 mod tests {
     use super::FullContext;
     use super::Position;
+    use super::PositionKind;
+    use super::SpanError;
 
     #[test]
     fn test_construction() {
@@ -282,4 +583,124 @@ mod tests {
         assert_eq!(*sub.get_start_position(), Position::abs(1, 6));
         assert_eq!(*sub.get_end_position(), Position::abs(1, 9));
     }
+
+    #[test]
+    fn crlf() {
+        let c = FullContext::from(None, "Hail\r\nEris\r\n\r\nFnord\r".to_string());
+        assert_eq!(*c.get_end_position(), Position::abs(4, 5));
+        assert_eq!(c.get_contents(), "Hail\r\nEris\r\n\r\nFnord");
+        assert_eq!(c.get_normalized_contents(), "Hail\nEris\n\nFnord");
+
+        let sub = c.subcontext(Position::rel(2, 1)..=c.end_of_line(2, PositionKind::Relative));
+        assert_eq!(*sub.get_end_position(), Position::abs(2, 4));
+        assert_eq!(sub.get_contents(), "Eris");
+    }
+
+    #[test]
+    fn lines() {
+        let c = FullContext::from(None, "Hail\nEris\n\nFnord".to_string());
+        let lines: Vec<_> = c.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                (1, "Hail", 0..4),
+                (2, "Eris", 5..9),
+                (3, "", 10..10),
+                (4, "Fnord", 11..16)
+            ]
+        );
+
+        let sub = c.subcontext(Position::rel(2, 2)..=Position::rel(2, 3));
+        assert_eq!(sub.lines().collect::<Vec<_>>(), [(2, "ri", 6..8)]);
+    }
+
+    #[test]
+    fn try_subcontext() {
+        let c = FullContext::from(None, "Hail\nEris\n\u{e9}".to_string());
+        let sub = c
+            .try_subcontext(Position::rel(2, 1)..=Position::rel(2, 4))
+            .unwrap();
+        assert_eq!(sub.get_contents(), "Eris");
+        let sub = c
+            .try_subcontext(Position::rel(2, 1)..Position::rel(3, 1))
+            .unwrap();
+        assert_eq!(sub.get_contents(), "Eris");
+        let sub = c
+            .try_subcontext(Position::rel(2, 1)..=Position::abs(2, 0))
+            .unwrap();
+        assert_eq!(sub.get_contents(), "");
+
+        assert_eq!(
+            c.try_subcontext(Position::rel(0, 1)..),
+            Err(SpanError::ZeroPosition(Position::rel(0, 1)))
+        );
+        assert_eq!(
+            c.try_subcontext(Position::rel(1, 0)..),
+            Err(SpanError::ZeroPosition(Position::rel(1, 0)))
+        );
+        assert_eq!(
+            c.try_subcontext(..Position::rel(1, 1)),
+            Err(SpanError::OutOfRange(Position::abs(1, 1)))
+        );
+        assert_eq!(
+            c.try_subcontext(Position::rel(4, 1)..),
+            Err(SpanError::OutOfRange(Position::abs(4, 1)))
+        );
+        assert_eq!(
+            c.try_subcontext(Position::rel(1, 1)..=Position::rel(1, 9)),
+            Err(SpanError::OutOfRange(Position::abs(1, 9)))
+        );
+        assert_eq!(
+            c.try_subcontext(Position::rel(3, 2)..),
+            Err(SpanError::NotCharBoundary(Position::abs(3, 2)))
+        );
+        assert_eq!(
+            c.try_subcontext(Position::rel(2, 3)..=Position::rel(2, 1)),
+            Err(SpanError::Reversed(
+                Position::abs(2, 3),
+                Position::abs(2, 1)
+            ))
+        );
+    }
+
+    #[test]
+    fn clamping() {
+        let c = FullContext::from(None, "Hail\n\u{e9}ris".to_string());
+        let sub = c.subcontext(Position::abs(0, 3)..=Position::abs(9, 9));
+        assert_eq!(sub.get_contents(), "Hail\n\u{e9}ris");
+        assert_eq!(sub.get_byte_range(), 0..10);
+        let sub = c.subcontext(Position::abs(2, 2)..=Position::abs(2, 40));
+        assert_eq!(sub.get_contents(), "\u{e9}ris");
+        let sub = c.subcontext(Position::abs(5, 1)..);
+        assert_eq!(sub.get_contents(), "");
+        assert_eq!(sub.lines().collect::<Vec<_>>(), [(5, "", 10..10)]);
+    }
+
+    #[cfg(feature = "full-context")]
+    #[test]
+    fn line_bytes() {
+        let c = FullContext::from(None, "Hail\nEris".to_string());
+        assert_eq!(c.line_bytes(2), Some(5..=8));
+        assert_eq!(c.line_bytes(0), None);
+        assert_eq!(c.line_bytes(3), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let c = FullContext::from(Some("a.twee".to_string()), "Hail\nEris".to_string());
+        let sub = c.subcontext(Position::rel(2, 2)..=Position::rel(2, 4));
+        let json = serde_json::to_string(&sub).unwrap();
+        let de: FullContext = serde_json::from_str(&json).unwrap();
+        assert_eq!(de.get_contents(), "ris");
+        assert_eq!(de.get_file_name(), &Some("a.twee".to_string()));
+        assert_eq!(*de.get_start_position(), Position::abs(2, 2));
+        assert_eq!(*de.get_end_position(), Position::abs(2, 4));
+
+        let json = r#"{"file_name":null,"start_position":{"line":1,"column":1,"kind":"Absolute"},"end_position":{"line":7,"column":1,"kind":"Absolute"},"contents":"Hail"}"#;
+        assert!(serde_json::from_str::<FullContext>(json).is_err());
+
+        let json = r#"{"file_name":null,"start_position":{"line":18446744073709551615,"column":1,"kind":"Absolute"},"end_position":{"line":18446744073709551615,"column":4,"kind":"Absolute"},"contents":"Hail"}"#;
+        assert!(serde_json::from_str::<FullContext>(json).is_err());
+    }
 }
@@ -7,3 +7,11 @@ pub use full_context::FullContext;
 
 mod partial_context;
 pub use partial_context::PartialContext;
+
+mod span_error;
+pub use span_error::SpanError;
+
+mod span;
+pub use span::Span;
+#[cfg(feature = "markup")]
+pub(crate) use span::line_column;
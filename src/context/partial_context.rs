@@ -7,6 +7,7 @@ use crate::context::{Position, FullContext};
 ///
 /// [`FullContext`]: struct.FullContext.html
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PartialContext {
     file_name: Option<String>,
     start_position: Position,
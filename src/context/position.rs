@@ -1,5 +1,6 @@
 /// Indicates absolute/relative position
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PositionKind {
     /// Absolute position
     Absolute,
@@ -19,6 +20,7 @@ pub enum PositionKind {
 ///
 /// [`Context`]: struct.Context.html
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     /// The one-indexed line number
     pub line: usize,
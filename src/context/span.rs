@@ -0,0 +1,98 @@
+use crate::context::{FullContext, PartialContext, Position};
+use std::convert::TryFrom;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// A plain description of a range of source code, for passing locations to
+/// editors, serializers, and other programs without depending on the context
+/// types
+///
+/// Lines and columns are 1-indexed, as in a [`Position`], and the end is
+/// inclusive, as in a [`FullContext`]. Columns count bytes. `bytes` is the
+/// exclusive byte range of the span within its file. A `Span` can be made from
+/// a reference to either kind of context. Since a [`PartialContext`] only
+/// records where it starts, a span made from one ends where it starts and has
+/// an empty byte range.
+///
+/// # Examples
+/// ```
+/// use tweep::{FullContext, Position, Span};
+/// let context = FullContext::from(Some("a.tw".to_string()), ":: Start\nHi".to_string());
+/// let span = Span::from(&context.subcontext(Position::rel(2, 1)..=Position::rel(2, 2)));
+/// assert_eq!(span.file.as_deref(), Some(std::path::Path::new("a.tw")));
+/// assert_eq!((span.start, span.end), ((2, 1), (2, 2)));
+/// assert_eq!(span.bytes, 9..11);
+/// ```
+///
+/// [`Position`]: struct.Position.html
+/// [`FullContext`]: struct.FullContext.html
+/// [`PartialContext`]: struct.PartialContext.html
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    /// The name of the file the span is in, if any
+    pub file: Option<PathBuf>,
+
+    /// The line and column of the first character in the span
+    pub start: (u32, u32),
+
+    /// The line and column of the last byte in the span
+    pub end: (u32, u32),
+
+    /// The byte range of the span within its file
+    pub bytes: Range<usize>,
+}
+
+/// Converts a position to a line and column, saturating any number too large
+/// for a `u32`
+pub(crate) fn line_column(position: &Position) -> (u32, u32) {
+    let saturate = |n: usize| u32::try_from(n).unwrap_or(u32::MAX);
+    (saturate(position.line), saturate(position.column))
+}
+
+impl From<&FullContext> for Span {
+    fn from(context: &FullContext) -> Span {
+        Span {
+            file: context.get_file_name().as_ref().map(PathBuf::from),
+            start: line_column(context.get_start_position()),
+            end: line_column(context.get_end_position()),
+            bytes: context.get_byte_range(),
+        }
+    }
+}
+
+impl From<&PartialContext> for Span {
+    fn from(context: &PartialContext) -> Span {
+        let start = line_column(context.get_start_position());
+        Span {
+            file: context.get_file_name().as_ref().map(PathBuf::from),
+            start,
+            end: start,
+            bytes: 0..0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        let context = FullContext::from(Some("a.tw".to_string()), "ab\ncdé".to_string());
+        let span = Span::from(&context);
+        assert_eq!(span.file, Some(PathBuf::from("a.tw")));
+        assert_eq!((span.start, span.end), ((1, 1), (2, 4)));
+        assert_eq!(span.bytes, 0..7);
+
+        let sub = context.subcontext(Position::rel(2, 2)..=Position::rel(2, 4));
+        let span = Span::from(&sub);
+        assert_eq!((span.start, span.end), ((2, 2), (2, 4)));
+        assert_eq!(span.bytes, 4..7);
+
+        let partial: PartialContext = sub.into();
+        let span = Span::from(&partial);
+        assert_eq!((span.start, span.end), ((2, 2), (2, 2)));
+        assert_eq!(span.bytes, 0..0);
+    }
+}
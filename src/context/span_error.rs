@@ -0,0 +1,30 @@
+use crate::context::Position;
+
+/// The reason a range of positions could not be made into a [`FullContext`]
+/// by [`FullContext::try_subcontext`]
+///
+/// [`FullContext`]: struct.FullContext.html
+/// [`FullContext::try_subcontext`]: struct.FullContext.html#method.try_subcontext
+#[derive(Clone, Copy, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum SpanError {
+    /// A position has a line of 0, or the start of the range has a column of
+    /// 0, when they are 1-indexed. Contains the position
+    #[error("Position is not 1-indexed: {0}")]
+    ZeroPosition(Position),
+
+    /// A position is past the end of its line or of the file, or an
+    /// exclusive range ends at the start of the file. Contains the absolute
+    /// position
+    #[error("Position is out of range: {0}")]
+    OutOfRange(Position),
+
+    /// A position falls inside of a multi-byte character. Contains the
+    /// absolute position
+    #[error("Position is inside of a character: {0}")]
+    NotCharBoundary(Position),
+
+    /// The range ends before it starts. Contains the absolute start and end
+    /// positions
+    #[error("Range ends at {1}, before its start at {0}")]
+    Reversed(Position, Position),
+}
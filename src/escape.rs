@@ -0,0 +1,185 @@
+/// Escapes a passage name for use in a passage header, so that it is parsed
+/// back as the same name. A backslash is put before each `[`, `]`, `{`, and
+/// `}`, which would otherwise start or end the tags or metadata of the
+/// passage, and before each backslash
+///
+/// # Examples
+/// ```
+/// use tweep::{escape_passage_name, StoryPassages};
+/// let name = "Chapter [1] {draft}";
+/// let escaped = escape_passage_name(name);
+/// assert_eq!(escaped, "Chapter \\[1\\] \\{draft\\}");
+/// let input = format!(":: {}\nText\n", escaped);
+/// let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+/// assert!(story.passages.contains_key(name));
+/// ```
+pub fn escape_passage_name(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        if matches!(c, '[' | ']' | '{' | '}' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Removes the escapes from a passage name as written in a passage header. A
+/// backslash escapes the character after it, so `\[` becomes `[` and `\\`
+/// becomes a single backslash. A backslash at the end is dropped
+///
+/// # Examples
+/// ```
+/// use tweep::{escape_passage_name, unescape};
+/// assert_eq!(unescape("Chapter \\[1\\]"), "Chapter [1]");
+/// assert_eq!(unescape("Back\\\\slash"), "Back\\slash");
+/// assert_eq!(unescape(&escape_passage_name("a\\[b}")), "a\\[b}");
+/// ```
+pub fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            unescaped.extend(chars.next());
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+/// Produces the markup of a link to the passage `target` with the given
+/// display text, choosing a form that tweep parses back to the same target
+/// and text. Link syntax has no escapes, so returns `None` if no form can
+/// express the link: if the target is empty, has whitespace at either end, or
+/// ends with `]`, if either part contains `[[`, `]]`, or `][`, or if the text
+/// contains `|`. Without display text, a target containing `|` cannot be
+/// expressed either
+///
+/// # Examples
+/// ```
+/// use tweep::format_link;
+/// assert_eq!(format_link("Cave", None), Some("[[Cave]]".to_string()));
+/// assert_eq!(format_link("Up->Down", None), Some("[[Up->Down|Up->Down]]".to_string()));
+/// assert_eq!(format_link("A|B", Some("Go")), Some("[[Go|A|B]]".to_string()));
+/// assert_eq!(format_link("Room [2]", None), None);
+/// ```
+pub fn format_link(target: &str, text: Option<&str>) -> Option<String> {
+    let breaks_link = |part: &str| ["[[", "]]", "]["].iter().any(|s| part.contains(s));
+    if target.is_empty() || target.trim() != target || target.ends_with(']') || breaks_link(target)
+    {
+        return None;
+    }
+    match text {
+        None if !["|", "->", "<-"].iter().any(|s| target.contains(s)) => {
+            Some(format!("[[{}]]", target))
+        }
+        None => format_link(target, Some(target)),
+        Some(text) if text.contains('|') || breaks_link(text) => None,
+        Some(text) => Some(format!("[[{}|{}]]", text, target)),
+    }
+}
+
+/// Escapes the given text for use as HTML content or as a quoted attribute
+/// value, such as the `name` of a passage in a published story. The
+/// characters `&`, `<`, `>`, `"`, and `'` are replaced with character
+/// references, and control characters other than tabs and line breaks, which
+/// cannot appear in HTML, are dropped
+///
+/// # Examples
+/// ```
+/// use tweep::escape_html;
+/// assert_eq!(escape_html("<b>Tom & \"Jerry\"</b>"), "&lt;b&gt;Tom &amp; &quot;Jerry&quot;&lt;/b&gt;");
+/// assert_eq!(escape_html("Bell\u{7}"), "Bell");
+/// ```
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c if is_unsafe_control(c) => (),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Returns `true` if the given character is invisible or looks like a space
+/// but is not one, so that text containing it looks the same as text without
+/// it: zero-width spaces and joiners, word joiners, byte order marks, soft
+/// hyphens, and non-breaking spaces
+pub(crate) fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}' | '\u{00AD}' | '\u{00A0}' | '\u{202F}'
+    )
+}
+
+/// Quotes the given text, showing each invisible character as its code
+/// point, such as `"Cave<U+200B>"`
+pub(crate) fn reveal_invisible(text: &str) -> String {
+    let mut revealed = String::from("\"");
+    for c in text.chars() {
+        if is_invisible(c) {
+            revealed.push_str(&format!("<U+{:04X}>", c as u32));
+        } else {
+            revealed.push(c);
+        }
+    }
+    revealed.push('"');
+    revealed
+}
+
+/// Returns `true` if the given text contains angle brackets or control
+/// characters other than tabs and line breaks, which need escaping in HTML and
+/// are handled poorly by the Twine editor
+pub(crate) fn is_html_unsafe(text: &str) -> bool {
+    text.chars()
+        .any(|c| c == '<' || c == '>' || is_unsafe_control(c))
+}
+
+/// Returns `true` if the given character is a control character other than a
+/// tab or line break
+fn is_unsafe_control(c: char) -> bool {
+    c.is_control() && !matches!(c, '\t' | '\n' | '\r')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        for name in ["Plain", "[1]", "{x}", "a\\b", "\\[", "Ends\\"] {
+            assert_eq!(unescape(&escape_passage_name(name)), name);
+        }
+        assert_eq!(unescape("Trailing\\"), "Trailing");
+    }
+
+    #[test]
+    fn links() {
+        assert_eq!(
+            format_link("A<-B", Some("Text")),
+            Some("[[Text|A<-B]]".to_string())
+        );
+        assert_eq!(format_link("A|B", None), None);
+        assert_eq!(format_link(" A", None), None);
+        assert_eq!(format_link("A", Some("x|y")), None);
+        assert_eq!(format_link("A]]B", Some("Text")), None);
+        assert_eq!(format_link("A", Some("B][C")), None);
+        assert_eq!(format_link("[A]x", None), Some("[[[A]x]]".to_string()));
+    }
+
+    #[test]
+    fn html() {
+        assert_eq!(escape_html("It's <fine>"), "It&#39;s &lt;fine&gt;");
+        assert_eq!(escape_html("Line\nTab\t\u{0}"), "Line\nTab\t");
+        assert!(is_html_unsafe("A <B>"));
+        assert!(is_html_unsafe("Null\u{0}"));
+        assert!(!is_html_unsafe("Tom & Jerry's \"Place\"\n"));
+    }
+}
@@ -0,0 +1,499 @@
+//! A C ABI for embedding tweep in programs that are not written in Rust, such
+//! as game engines written in C or C++
+//!
+//! A story is parsed from a UTF-8 buffer with [`tweep_parse`], which returns
+//! an opaque [`TweepStory`] handle that must be released with
+//! [`tweep_story_free`]. The passages, warnings, and errors of the story are
+//! read through the handle by index. Strings are returned as a [`TweepStr`],
+//! a pointer and length that are valid until the handle is freed and are not
+//! NUL-terminated. A missing string has a null pointer
+//!
+//! Every function accepts a null handle, such as the one returned for input
+//! that is not valid UTF-8, and returns `false`, 0, or a null string for it
+//!
+//! A panic inside tweep never unwinds into C. Instead, the function that
+//! panicked returns null, `false`, 0, or a null string, and
+//! [`tweep_story_free`] does nothing
+//!
+//! To build a library that can be linked from C, run
+//! `cargo rustc --release --features ffi --crate-type staticlib` or
+//! `--crate-type cdylib`. The declarations are in `include/tweep.h`
+//!
+//! Enabled with "ffi" feature
+//!
+//! # Examples
+//! ```
+//! use tweep::ffi::*;
+//! let input = ":: Start\n[[Cave]]\n\n:: Cave\nDark";
+//! unsafe {
+//!     let story = tweep_parse(input.as_ptr(), input.len());
+//!     assert!(tweep_story_is_ok(story));
+//!     assert_eq!(tweep_story_passage_count(story), 2);
+//!     assert_eq!(tweep_story_passage_name(story, 0).as_str(), Some("Cave"));
+//!     tweep_story_free(story);
+//! }
+//! ```
+//!
+//! [`tweep_parse`]: fn.tweep_parse.html
+//! [`TweepStory`]: struct.TweepStory.html
+//! [`tweep_story_free`]: fn.tweep_story_free.html
+//! [`TweepStr`]: struct.TweepStr.html
+
+use crate::Context;
+use crate::Story;
+use crate::TwinePassage;
+use crate::Warning;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// A borrowed UTF-8 string returned to C, which is not NUL-terminated
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct TweepStr {
+    /// A pointer to the first byte of the string, or null if there is no
+    /// string
+    pub ptr: *const u8,
+
+    /// The length of the string in bytes
+    pub len: usize,
+}
+
+impl TweepStr {
+    /// Returns the string, or `None` if the pointer is null
+    ///
+    /// # Safety
+    /// The `TweepStr` must have been returned by this module, and the handle
+    /// it was returned from must not have been freed
+    pub unsafe fn as_str<'a>(&self) -> Option<&'a str> {
+        if self.ptr.is_null() {
+            None
+        } else {
+            let bytes = std::slice::from_raw_parts(self.ptr, self.len);
+            Some(std::str::from_utf8_unchecked(bytes))
+        }
+    }
+
+    fn new(s: Option<&str>) -> Self {
+        match s {
+            Some(s) => TweepStr {
+                ptr: s.as_ptr(),
+                len: s.len(),
+            },
+            None => TweepStr {
+                ptr: ptr::null(),
+                len: 0,
+            },
+        }
+    }
+}
+
+/// An issue found while parsing, with its message rendered ahead of time so
+/// that it can be borrowed by C
+struct Issue {
+    message: String,
+    line: usize,
+    column: usize,
+}
+
+impl Issue {
+    fn new(message: String, context: Option<&Context>) -> Self {
+        let (line, column) = context
+            .map(|context| {
+                let start = context.get_start_position();
+                (start.line, start.column)
+            })
+            .unwrap_or((0, 0));
+        Issue {
+            message,
+            line,
+            column,
+        }
+    }
+}
+
+/// An opaque handle to a parsed story, with its passages sorted by name, and
+/// the warnings and errors found while parsing and checking it
+pub struct TweepStory {
+    story: Option<Story>,
+    passages: Vec<String>,
+    warnings: Vec<Issue>,
+    errors: Vec<Issue>,
+}
+
+impl TweepStory {
+    fn passage(&self, index: usize) -> Option<&TwinePassage> {
+        let name = self.passages.get(index)?;
+        self.story.as_ref()?.passages.get(name)
+    }
+}
+
+/// Parses a story from a buffer of `len` bytes of UTF-8 Twee code, and
+/// returns a handle to it. The parse is checked as if by
+/// [`Story::validate`], and the warnings include those of the checks.
+/// Returns null if `data` is null or is not valid UTF-8, or if parsing panics
+///
+/// # Safety
+/// `data` must point to `len` readable bytes. The returned handle must be
+/// freed with `tweep_story_free`
+///
+/// [`Story::validate`]: ../struct.Story.html#method.validate
+#[no_mangle]
+pub unsafe extern "C" fn tweep_parse(data: *const u8, len: usize) -> *mut TweepStory {
+    guard(ptr::null_mut(), || {
+        if data.is_null() {
+            return ptr::null_mut();
+        }
+        let bytes = std::slice::from_raw_parts(data, len);
+        let input = match std::str::from_utf8(bytes) {
+            Ok(input) => input.to_string(),
+            Err(_) => return ptr::null_mut(),
+        };
+
+        let (res, mut warnings) = Story::from_string(input).take();
+        let (story, errors) = match res {
+            Ok(story) => {
+                warnings.append(&mut story.validate());
+                (Some(story), Vec::new())
+            }
+            #[cfg(feature = "full-context")]
+            Err(e) => (None, e.error_list.errors),
+            #[cfg(not(feature = "full-context"))]
+            Err(e) => (None, e.errors),
+        };
+        let mut passages: Vec<String> = story
+            .iter()
+            .flat_map(|story| story.passages.keys().cloned())
+            .collect();
+        passages.sort();
+        let warnings = warnings
+            .iter()
+            .map(|warning: &Warning| Issue::new(warning.to_string(), warning.context.as_ref()))
+            .collect();
+        let errors = errors
+            .iter()
+            .map(|error| Issue::new(error.kind.to_string(), error.context.as_ref()))
+            .collect();
+        Box::into_raw(Box::new(TweepStory {
+            story,
+            passages,
+            warnings,
+            errors,
+        }))
+    })
+}
+
+/// Frees a story handle. Does nothing if `story` is null
+///
+/// # Safety
+/// `story` must be null or a handle returned by `tweep_parse` that has not
+/// been freed
+#[no_mangle]
+pub unsafe extern "C" fn tweep_story_free(story: *mut TweepStory) {
+    guard((), || {
+        if !story.is_null() {
+            drop(Box::from_raw(story));
+        }
+    })
+}
+
+/// Returns `true` if the story was parsed without errors
+///
+/// # Safety
+/// `story` must be null or a handle returned by `tweep_parse` that has not
+/// been freed
+#[no_mangle]
+pub unsafe extern "C" fn tweep_story_is_ok(story: *const TweepStory) -> bool {
+    guard(false, || {
+        handle(story).is_some_and(|story| story.story.is_some())
+    })
+}
+
+/// Returns the title of the story
+///
+/// # Safety
+/// `story` must be null or a handle returned by `tweep_parse` that has not
+/// been freed
+#[no_mangle]
+pub unsafe extern "C" fn tweep_story_title(story: *const TweepStory) -> TweepStr {
+    guard(TweepStr::new(None), || {
+        let story = handle(story).and_then(|story| story.story.as_ref());
+        TweepStr::new(story.and_then(|s| s.title.as_deref()))
+    })
+}
+
+/// Returns the name of the start passage of the story
+///
+/// # Safety
+/// `story` must be null or a handle returned by `tweep_parse` that has not
+/// been freed
+#[no_mangle]
+pub unsafe extern "C" fn tweep_story_start(story: *const TweepStory) -> TweepStr {
+    guard(TweepStr::new(None), || {
+        let story = handle(story).and_then(|story| story.story.as_ref());
+        TweepStr::new(story.and_then(Story::get_start_passage_name))
+    })
+}
+
+/// Returns the number of normal passages in the story
+///
+/// # Safety
+/// `story` must be null or a handle returned by `tweep_parse` that has not
+/// been freed
+#[no_mangle]
+pub unsafe extern "C" fn tweep_story_passage_count(story: *const TweepStory) -> usize {
+    guard(0, || handle(story).map_or(0, |story| story.passages.len()))
+}
+
+/// Returns the name of the passage at `index`, in order of passage name
+///
+/// # Safety
+/// `story` must be null or a handle returned by `tweep_parse` that has not
+/// been freed
+#[no_mangle]
+pub unsafe extern "C" fn tweep_story_passage_name(
+    story: *const TweepStory,
+    index: usize,
+) -> TweepStr {
+    guard(TweepStr::new(None), || {
+        let name = handle(story).and_then(|story| story.passages.get(index));
+        TweepStr::new(name.map(String::as_str))
+    })
+}
+
+/// Returns the content of the passage at `index`
+///
+/// # Safety
+/// `story` must be null or a handle returned by `tweep_parse` that has not
+/// been freed
+#[no_mangle]
+pub unsafe extern "C" fn tweep_story_passage_content(
+    story: *const TweepStory,
+    index: usize,
+) -> TweepStr {
+    guard(TweepStr::new(None), || {
+        let passage = handle(story).and_then(|story| story.passage(index));
+        TweepStr::new(passage.map(|passage| passage.content.content.as_str()))
+    })
+}
+
+/// Returns the number of tags on the passage at `index`
+///
+/// # Safety
+/// `story` must be null or a handle returned by `tweep_parse` that has not
+/// been freed
+#[no_mangle]
+pub unsafe extern "C" fn tweep_story_passage_tag_count(
+    story: *const TweepStory,
+    index: usize,
+) -> usize {
+    guard(0, || {
+        handle(story)
+            .and_then(|story| story.passage(index))
+            .map_or(0, |passage| passage.tags().len())
+    })
+}
+
+/// Returns the tag at `tag` on the passage at `index`
+///
+/// # Safety
+/// `story` must be null or a handle returned by `tweep_parse` that has not
+/// been freed
+#[no_mangle]
+pub unsafe extern "C" fn tweep_story_passage_tag(
+    story: *const TweepStory,
+    index: usize,
+    tag: usize,
+) -> TweepStr {
+    guard(TweepStr::new(None), || {
+        let passage = handle(story).and_then(|story| story.passage(index));
+        TweepStr::new(
+            passage
+                .and_then(|passage| passage.tags().get(tag))
+                .map(|tag| tag.as_str()),
+        )
+    })
+}
+
+/// Returns the number of warnings
+///
+/// # Safety
+/// `story` must be null or a handle returned by `tweep_parse` that has not
+/// been freed
+#[no_mangle]
+pub unsafe extern "C" fn tweep_story_warning_count(story: *const TweepStory) -> usize {
+    guard(0, || handle(story).map_or(0, |story| story.warnings.len()))
+}
+
+/// Returns the message of the warning at `index`. If `line` and `column` are
+/// not null, they are set to the position of the warning, or to 0 if it has
+/// none
+///
+/// # Safety
+/// `story` must be null or a handle returned by `tweep_parse` that has not
+/// been freed, and `line` and `column` must each be null or valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn tweep_story_warning(
+    story: *const TweepStory,
+    index: usize,
+    line: *mut usize,
+    column: *mut usize,
+) -> TweepStr {
+    guard(TweepStr::new(None), || {
+        let warning = handle(story).and_then(|story| story.warnings.get(index));
+        issue(warning, line, column)
+    })
+}
+
+/// Returns the number of errors. A story with errors has no passages
+///
+/// # Safety
+/// `story` must be null or a handle returned by `tweep_parse` that has not
+/// been freed
+#[no_mangle]
+pub unsafe extern "C" fn tweep_story_error_count(story: *const TweepStory) -> usize {
+    guard(0, || handle(story).map_or(0, |story| story.errors.len()))
+}
+
+/// Returns the message of the error at `index`, setting `line` and `column`
+/// like `tweep_story_warning`
+///
+/// # Safety
+/// `story` must be null or a handle returned by `tweep_parse` that has not
+/// been freed, and `line` and `column` must each be null or valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn tweep_story_error(
+    story: *const TweepStory,
+    index: usize,
+    line: *mut usize,
+    column: *mut usize,
+) -> TweepStr {
+    guard(TweepStr::new(None), || {
+        let error = handle(story).and_then(|story| story.errors.get(index));
+        issue(error, line, column)
+    })
+}
+
+/// Runs `f`, returning `fallback` instead if it panics, so that a panic never
+/// unwinds across the C boundary
+fn guard<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+/// Borrows the story behind a handle, or returns `None` if it is null
+unsafe fn handle<'a>(story: *const TweepStory) -> Option<&'a TweepStory> {
+    story.as_ref()
+}
+
+/// Returns the message of an issue and writes its position to the given
+/// pointers, if they are not null
+unsafe fn issue(issue: Option<&Issue>, line: *mut usize, column: *mut usize) -> TweepStr {
+    if !line.is_null() {
+        *line = issue.map_or(0, |issue| issue.line);
+    }
+    if !column.is_null() {
+        *column = issue.map_or(0, |issue| issue.column);
+    }
+    TweepStr::new(issue.map(|issue| issue.message.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handles() {
+        let input = ":: StoryTitle\nFFI\n\n:: Start [a b]\n[[Nowhere]]\n\n:: End\nBye";
+        unsafe {
+            let story = tweep_parse(input.as_ptr(), input.len());
+            assert!(tweep_story_is_ok(story));
+            assert_eq!(tweep_story_title(story).as_str(), Some("FFI"));
+            assert_eq!(tweep_story_start(story).as_str(), Some("Start"));
+            assert_eq!(tweep_story_passage_count(story), 2);
+            assert_eq!(tweep_story_passage_name(story, 1).as_str(), Some("Start"));
+            assert_eq!(
+                tweep_story_passage_content(story, 1).as_str(),
+                Some("[[Nowhere]]\n")
+            );
+            assert_eq!(tweep_story_passage_tag_count(story, 1), 2);
+            assert_eq!(tweep_story_passage_tag(story, 1, 1).as_str(), Some("b"));
+            assert_eq!(tweep_story_passage_tag(story, 1, 2).as_str(), None);
+            assert_eq!(tweep_story_passage_name(story, 2).as_str(), None);
+            assert_eq!(tweep_story_error_count(story), 0);
+
+            let (mut line, mut column) = (0, 0);
+            let count = tweep_story_warning_count(story);
+            let messages: Vec<&str> = (0..count)
+                .map(|i| {
+                    tweep_story_warning(story, i, ptr::null_mut(), ptr::null_mut())
+                        .as_str()
+                        .unwrap()
+                })
+                .collect();
+            let dead = messages.iter().position(|m| m.contains("Nowhere")).unwrap();
+            tweep_story_warning(story, dead, &mut line, &mut column);
+            assert_eq!((line, column), (5, 1));
+            tweep_story_free(story);
+        }
+    }
+
+    #[test]
+    fn panics() {
+        assert_eq!(guard(0, || 1), 1);
+        assert_eq!(guard(0, || -> usize { panic!("from a test") }), 0);
+        assert!(guard(TweepStr::new(None), || panic!("from a test"))
+            .ptr
+            .is_null());
+    }
+
+    #[test]
+    fn failures() {
+        unsafe {
+            assert!(tweep_parse(ptr::null(), 0).is_null());
+            let invalid = [0xffu8];
+            assert!(tweep_parse(invalid.as_ptr(), 1).is_null());
+
+            let input = ":: Start [tag\nHello";
+            let story = tweep_parse(input.as_ptr(), input.len());
+            assert!(!tweep_story_is_ok(story));
+            assert_eq!(tweep_story_passage_count(story), 0);
+            assert_eq!(tweep_story_title(story).as_str(), None);
+            assert_eq!(tweep_story_error_count(story), 1);
+            let (mut line, mut column) = (9, 9);
+            let message = tweep_story_error(story, 0, &mut line, &mut column);
+            assert_eq!(
+                message.as_str(),
+                Some("Unclosed tag block in passage header")
+            );
+            assert_eq!(line, 1);
+            tweep_story_free(story);
+            tweep_story_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn null_handles() {
+        let story = ptr::null();
+        unsafe {
+            assert!(!tweep_story_is_ok(story));
+            assert_eq!(tweep_story_title(story).as_str(), None);
+            assert_eq!(tweep_story_start(story).as_str(), None);
+            assert_eq!(tweep_story_passage_count(story), 0);
+            assert_eq!(tweep_story_passage_name(story, 0).as_str(), None);
+            assert_eq!(tweep_story_passage_content(story, 0).as_str(), None);
+            assert_eq!(tweep_story_passage_tag_count(story, 0), 0);
+            assert_eq!(tweep_story_passage_tag(story, 0, 0).as_str(), None);
+            assert_eq!(tweep_story_warning_count(story), 0);
+            assert_eq!(tweep_story_error_count(story), 0);
+            let (mut line, mut column) = (9, 9);
+            assert_eq!(
+                tweep_story_warning(story, 0, &mut line, &mut column).as_str(),
+                None
+            );
+            assert_eq!((line, column), (0, 0));
+            assert_eq!(
+                tweep_story_error(story, 0, ptr::null_mut(), ptr::null_mut()).as_str(),
+                None
+            );
+        }
+    }
+}
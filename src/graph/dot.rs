@@ -0,0 +1,93 @@
+use crate::Story;
+use crate::StoryGraph;
+use crate::TagColor;
+use std::fmt::Write;
+
+/// Builds the DOT graph described in `Story::to_dot`
+pub(crate) fn dot(story: &Story) -> String {
+    let graph = StoryGraph::from(story);
+    let mut out = String::from("digraph story {\n");
+    if let Some(title) = &story.title {
+        writeln!(out, "    label={};", quoted(title)).unwrap();
+    }
+    out.push_str("    node [shape=box];\n");
+
+    for name in graph.nodes() {
+        let mut attributes = Vec::new();
+        if graph.start() == Some(name) {
+            attributes.push("peripheries=2".to_string());
+        }
+        if let Some(color) = tag_color(story, name) {
+            attributes.push(format!("style=filled, fillcolor={}", color.as_str()));
+        }
+        if attributes.is_empty() {
+            writeln!(out, "    {};", quoted(name)).unwrap();
+        } else {
+            writeln!(out, "    {} [{}];", quoted(name), attributes.join(", ")).unwrap();
+        }
+    }
+
+    for (from, to) in graph.edges() {
+        writeln!(out, "    {} -> {};", quoted(from), quoted(to)).unwrap();
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Finds the color of the first tag of the passage with the given name that
+/// has a color Twine can render in the `tag-colors` of the `StoryData`
+fn tag_color<'a>(story: &'a Story, name: &str) -> Option<&'a TagColor> {
+    let data = story.data.as_ref()?;
+    story.passages[name]
+        .tags()
+        .iter()
+        .filter_map(|tag| data.tag_color(tag))
+        .find(|color| color.is_known())
+}
+
+/// Quotes the given string as a DOT identifier
+fn quoted(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn graph() {
+        let input = r##":: StoryTitle
+The "Cave"
+
+:: StoryData
+{"ifid": "abc", "start": "Intro", "tag-colors": {"danger": "red", "odd": "#123456"}}
+
+:: Intro [odd danger]
+[[Cave]] [[Nowhere]] [[Cave]]
+
+:: Cave
+[[Intro]] [img[map.png]]
+
+:: Lost "Room"
+"##
+        .to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        assert_eq!(
+            dot(&story),
+            r#"digraph story {
+    label="The \"Cave\"";
+    node [shape=box];
+    "Cave";
+    "Intro" [peripheries=2, style=filled, fillcolor=red];
+    "Lost \"Room\"";
+    "Cave" -> "Intro";
+    "Intro" -> "Cave";
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,5 @@
+mod dot;
+pub(crate) use dot::dot;
+
+mod story_graph;
+pub use story_graph::StoryGraph;
@@ -0,0 +1,484 @@
+use crate::FastHashMap;
+use crate::FastHashSet;
+use crate::LinkKind;
+use crate::PassageContent;
+use crate::Story;
+use crate::StoryPassages;
+use crate::TwineLink;
+use std::collections::VecDeque;
+
+/// A directed graph of the passages in a story, where each node is a passage
+/// and each edge is a link from one passage to another
+///
+/// Links to passages that do not exist and image links are not included as
+/// edges. Edges are deduplicated, so a passage that links to another passage
+/// several times will only have a single edge to that passage.
+///
+/// # Examples
+/// ```
+/// use tweep::{Story, StoryGraph};
+/// let input = r#":: Start
+///Go [[North]] or [[South]]
+///
+///:: North
+///Back to the [[Start]]
+///
+///:: South
+///A dead end
+///
+///:: Island
+///Nothing links here
+///"#.to_string();
+/// let story = Story::from_string(input).take().0.ok().unwrap();
+/// let graph = StoryGraph::from(&story);
+/// assert_eq!(graph.successors("Start"), vec!["North", "South"]);
+/// assert_eq!(graph.predecessors("Start"), vec!["North"]);
+/// assert_eq!(graph.unreachable_passages(), vec!["Island"]);
+/// assert_eq!(graph.dead_ends(), vec!["Island", "South"]);
+/// ```
+#[derive(Debug, Default)]
+pub struct StoryGraph {
+    /// The sorted list of passage names
+    nodes: Vec<String>,
+
+    /// Map from passage name to the passages it links to, in link order
+    successors: FastHashMap<String, Vec<String>>,
+
+    /// Map from passage name to the passages that link to it
+    predecessors: FastHashMap<String, Vec<String>>,
+
+    /// The name of the start passage, if there is one
+    start: Option<String>,
+}
+
+impl StoryGraph {
+    /// Builds the graph from a list of passage names with their links and an
+    /// optional start passage name
+    pub(crate) fn build<'a, I>(passages: I, start: Option<&str>) -> Self
+    where
+        I: Iterator<Item = (&'a str, &'a Vec<TwineLink>)>,
+    {
+        let passages: Vec<(&str, &Vec<TwineLink>)> = passages.collect();
+        let mut nodes: Vec<String> = passages.iter().map(|(name, _)| name.to_string()).collect();
+        nodes.sort();
+        let names: FastHashSet<&str> = nodes.iter().map(|n| n.as_str()).collect();
+
+        let mut successors: FastHashMap<String, Vec<String>> = FastHashMap::default();
+        let mut predecessors: FastHashMap<String, Vec<String>> = FastHashMap::default();
+        for (name, links) in passages {
+            let mut targets: Vec<String> = Vec::new();
+            // Showing an image doesn't move the reader to another passage
+            for link in links.iter().filter(|l| l.kind != LinkKind::Image) {
+                // Trim the target to match the behavior of the dead link check
+                let target = link.target.trim();
+                if names.contains(target) && !targets.iter().any(|t| t == target) {
+                    targets.push(target.to_string());
+                }
+            }
+            for target in &targets {
+                predecessors
+                    .entry(target.clone())
+                    .or_default()
+                    .push(name.to_string());
+            }
+            successors.insert(name.to_string(), targets);
+        }
+
+        for preds in predecessors.values_mut() {
+            preds.sort();
+        }
+
+        let start = start.filter(|s| names.contains(s)).map(|s| s.to_string());
+        StoryGraph {
+            nodes,
+            successors,
+            predecessors,
+            start,
+        }
+    }
+
+    /// Returns the names of all passages in the graph, sorted by name
+    pub fn nodes(&self) -> Vec<&str> {
+        self.nodes.iter().map(|n| n.as_str()).collect()
+    }
+
+    /// Returns every edge in the graph as a `(from, to)` pair, sorted by the
+    /// name of the source passage and then in link order
+    pub fn edges(&self) -> Vec<(&str, &str)> {
+        self.nodes
+            .iter()
+            .flat_map(|from| {
+                self.successors(from)
+                    .into_iter()
+                    .map(move |to| (from.as_str(), to))
+            })
+            .collect()
+    }
+
+    /// Returns the name of the start passage, if one exists in the graph
+    pub fn start(&self) -> Option<&str> {
+        self.start.as_deref()
+    }
+
+    /// Returns `true` if the graph contains a passage with the given name
+    pub fn contains(&self, name: &str) -> bool {
+        self.successors.contains_key(name)
+    }
+
+    /// Returns the names of the passages linked to by the passage `name`, in
+    /// link order. Returns an empty list if no such passage exists
+    pub fn successors(&self, name: &str) -> Vec<&str> {
+        self.successors
+            .get(name)
+            .map(|s| s.iter().map(|n| n.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the names of the passages that link to the passage `name`,
+    /// sorted by name. Returns an empty list if no such passage exists
+    pub fn predecessors(&self, name: &str) -> Vec<&str> {
+        self.predecessors
+            .get(name)
+            .map(|p| p.iter().map(|n| n.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the names of all passages reachable by following links from the
+    /// passage `start`, including `start` itself, in breadth-first order.
+    /// Returns an empty list if no such passage exists
+    pub fn reachable_from(&self, start: &str) -> Vec<&str> {
+        let start = match self.nodes.iter().find(|n| *n == start) {
+            Some(s) => s.as_str(),
+            None => return Vec::new(),
+        };
+
+        let mut visited = FastHashSet::default();
+        let mut order = Vec::new();
+        let mut queue = VecDeque::new();
+        visited.insert(start);
+        queue.push_back(start);
+        while let Some(name) = queue.pop_front() {
+            order.push(name);
+            for next in self.successors(name) {
+                if visited.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        order
+    }
+
+    /// Returns the names of all passages that cannot be reached by following
+    /// links from the start passage, sorted by name. If there is no start
+    /// passage, every passage is unreachable
+    pub fn unreachable_passages(&self) -> Vec<&str> {
+        let reachable: FastHashSet<&str> = match &self.start {
+            Some(start) => self.reachable_from(start).into_iter().collect(),
+            None => FastHashSet::default(),
+        };
+        self.nodes
+            .iter()
+            .map(|n| n.as_str())
+            .filter(|n| !reachable.contains(n))
+            .collect()
+    }
+
+    /// Returns the names of all passages that do not link to any other
+    /// passage, sorted by name
+    pub fn dead_ends(&self) -> Vec<&str> {
+        self.nodes
+            .iter()
+            .map(|n| n.as_str())
+            .filter(|n| self.successors(n).is_empty())
+            .collect()
+    }
+
+    /// Returns the names of the dead ends that can be reached by following
+    /// links from the start passage, sorted by name. These are the endings of
+    /// the story, so if there is a start passage but this is empty, every path
+    /// through the story loops forever. If there is no start passage, this is
+    /// empty
+    pub fn reachable_endings(&self) -> Vec<&str> {
+        let mut endings: Vec<&str> = match &self.start {
+            Some(start) => self
+                .reachable_from(start)
+                .into_iter()
+                .filter(|n| self.successors(n).is_empty())
+                .collect(),
+            None => Vec::new(),
+        };
+        endings.sort_unstable();
+        endings
+    }
+
+    /// Returns the names of all passages, other than the start passage, that
+    /// are not linked to by any other passage, sorted by name
+    pub fn orphans(&self) -> Vec<&str> {
+        self.nodes
+            .iter()
+            .map(|n| n.as_str())
+            .filter(|n| Some(*n) != self.start() && self.predecessors(n).is_empty())
+            .collect()
+    }
+
+    /// Returns the groups of passages that link to each other in a loop, so
+    /// that each passage in a group can be reached from every other passage in
+    /// it. A passage that links to itself is a group of its own. Each group is
+    /// sorted by name, and the groups are sorted by their first name
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{Story, StoryGraph};
+    /// let input = ":: A\n[[B]]\n\n:: B\n[[C]] [[A]]\n\n:: C\n[[C]] [[D]]\n\n:: D\nThe end".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let graph = StoryGraph::from(&story);
+    /// assert_eq!(graph.find_cycles(), vec![vec!["A", "B"], vec!["C"]]);
+    /// ```
+    pub fn find_cycles(&self) -> Vec<Vec<&str>> {
+        // Tarjan's algorithm, with an explicit stack of passages and the index
+        // of the next successor to visit, so large stories can't overflow
+        let mut index: FastHashMap<&str, usize> = FastHashMap::default();
+        let mut low: FastHashMap<&str, usize> = FastHashMap::default();
+        let mut stack: Vec<&str> = Vec::new();
+        let mut on_stack: FastHashSet<&str> = FastHashSet::default();
+        let mut cycles = Vec::new();
+
+        for root in &self.nodes {
+            if index.contains_key(root.as_str()) {
+                continue;
+            }
+            let mut work: Vec<(&str, usize)> = vec![(root.as_str(), 0)];
+            while let Some((name, next)) = work.pop() {
+                if next == 0 {
+                    index.insert(name, index.len());
+                    low.insert(name, index[name]);
+                    stack.push(name);
+                    on_stack.insert(name);
+                }
+
+                let successors = self.successors(name);
+                if let Some(&succ) = successors.get(next) {
+                    work.push((name, next + 1));
+                    if !index.contains_key(succ) {
+                        work.push((succ, 0));
+                    } else if on_stack.contains(succ) {
+                        let low_name = low[name].min(index[succ]);
+                        low.insert(name, low_name);
+                    }
+                    continue;
+                }
+
+                if low[name] == index[name] {
+                    let mut group = Vec::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack.remove(member);
+                        group.push(member);
+                        if member == name {
+                            break;
+                        }
+                    }
+                    if group.len() > 1 || successors.contains(&name) {
+                        group.sort_unstable();
+                        cycles.push(group);
+                    }
+                }
+                if let Some((parent, _)) = work.last() {
+                    let low_parent = low[parent].min(low[name]);
+                    low.insert(parent, low_parent);
+                }
+            }
+        }
+
+        cycles.sort_unstable();
+        cycles
+    }
+
+    /// Returns every path through the story from the passage `start` to a
+    /// dead end that follows at most `max_depth` links and visits no passage
+    /// twice, in link order. The last passage of each path is an ending that
+    /// can be reached from `start`. Paths that loop back on themselves or are
+    /// longer than `max_depth` are left out. Returns an empty list if no such
+    /// passage exists
+    ///
+    /// The number of paths can grow very quickly with the size of the story,
+    /// so `max_depth` should be kept small for large stories
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{Story, StoryGraph};
+    /// let input = ":: Start\n[[Left]] [[Right]]\n\n:: Left\n[[Start]] [[Right]]\n\n:: Right\nThe end".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let graph = StoryGraph::from(&story);
+    /// assert_eq!(
+    ///     graph.enumerate_paths("Start", 5),
+    ///     vec![vec!["Start", "Left", "Right"], vec!["Start", "Right"]]
+    /// );
+    /// assert!(graph.enumerate_paths("Start", 0).is_empty());
+    /// ```
+    pub fn enumerate_paths(&self, start: &str, max_depth: usize) -> Vec<Vec<&str>> {
+        let start = match self.nodes.iter().find(|n| *n == start) {
+            Some(s) => s.as_str(),
+            None => return Vec::new(),
+        };
+        let mut paths = Vec::new();
+        self.extend_paths(&mut vec![start], max_depth, &mut paths);
+        paths
+    }
+
+    /// Adds every path to a dead end that continues the given path with at
+    /// most `depth` more links to `paths`
+    fn extend_paths<'a>(
+        &'a self,
+        path: &mut Vec<&'a str>,
+        depth: usize,
+        paths: &mut Vec<Vec<&'a str>>,
+    ) {
+        let successors = self.successors(path[path.len() - 1]);
+        if successors.is_empty() {
+            paths.push(path.clone());
+            return;
+        }
+        if depth == 0 {
+            return;
+        }
+        for next in successors {
+            if !path.contains(&next) {
+                path.push(next);
+                self.extend_paths(path, depth - 1, paths);
+                path.pop();
+            }
+        }
+    }
+}
+
+impl std::convert::From<&Story> for StoryGraph {
+    fn from(story: &Story) -> StoryGraph {
+        let passages = story
+            .passages
+            .iter()
+            .map(|(name, passage)| (name.as_str(), passage.content.get_links()));
+        StoryGraph::build(passages, story.get_start_passage_name())
+    }
+}
+
+impl std::convert::From<&StoryPassages> for StoryGraph {
+    fn from(story: &StoryPassages) -> StoryGraph {
+        let passages = story
+            .passages
+            .iter()
+            .filter_map(|(name, passage)| match &passage.content {
+                PassageContent::Normal(twine) => Some((name.as_str(), twine.get_links())),
+                _ => None,
+            });
+        StoryGraph::build(passages, story.get_start_passage_name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> StoryGraph {
+        let input = r#":: StoryData
+{
+"ifid": "abc",
+"start": "Hub"
+}
+
+:: Hub
+[[Left]] [[Right]] [[Left]] [[Nowhere]]
+
+:: Left
+[[Hub]] [[ Right ]]
+
+:: Right
+The end
+
+:: Orphan
+[[Hub]]
+
+:: Lonely
+Nothing here
+"#
+        .to_string();
+        let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+        StoryGraph::from(&story)
+    }
+
+    #[test]
+    fn edges() {
+        let graph = graph();
+        assert_eq!(graph.start(), Some("Hub"));
+        assert_eq!(
+            graph.nodes(),
+            vec!["Hub", "Left", "Lonely", "Orphan", "Right"]
+        );
+        assert_eq!(graph.successors("Hub"), vec!["Left", "Right"]);
+        assert_eq!(graph.successors("Left"), vec!["Hub", "Right"]);
+        assert!(graph.successors("Nowhere").is_empty());
+        assert_eq!(graph.predecessors("Hub"), vec!["Left", "Orphan"]);
+        assert_eq!(graph.predecessors("Right"), vec!["Hub", "Left"]);
+        assert_eq!(
+            graph.edges(),
+            vec![
+                ("Hub", "Left"),
+                ("Hub", "Right"),
+                ("Left", "Hub"),
+                ("Left", "Right"),
+                ("Orphan", "Hub")
+            ]
+        );
+    }
+
+    #[test]
+    fn reachability() {
+        let graph = graph();
+        assert_eq!(graph.reachable_from("Hub"), vec!["Hub", "Left", "Right"]);
+        assert_eq!(graph.reachable_from("Right"), vec!["Right"]);
+        assert!(graph.reachable_from("Nowhere").is_empty());
+        assert_eq!(graph.unreachable_passages(), vec!["Lonely", "Orphan"]);
+        assert_eq!(graph.dead_ends(), vec!["Lonely", "Right"]);
+        assert_eq!(graph.orphans(), vec!["Lonely", "Orphan"]);
+        assert_eq!(graph.reachable_endings(), vec!["Right"]);
+    }
+
+    #[test]
+    fn no_start() {
+        let input = ":: A\n[[B]]\n\n:: B\n[[A]]\n".to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        let graph = StoryGraph::from(&story);
+        assert_eq!(graph.start(), None);
+        assert_eq!(graph.unreachable_passages(), vec!["A", "B"]);
+        assert!(graph.reachable_endings().is_empty());
+    }
+
+    #[test]
+    fn cycles() {
+        let graph = graph();
+        assert_eq!(graph.find_cycles(), vec![vec!["Hub", "Left"]]);
+
+        let input =
+            ":: A\n[[B]] [[A]]\n\n:: B\n[[C]]\n\n:: C\n[[B]] [[D]]\n\n:: D\n[[C]]\n".to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        let graph = StoryGraph::from(&story);
+        assert_eq!(graph.find_cycles(), vec![vec!["A"], vec!["B", "C", "D"]]);
+    }
+
+    #[test]
+    fn paths() {
+        let graph = graph();
+        assert_eq!(
+            graph.enumerate_paths("Orphan", 3),
+            vec![
+                vec!["Orphan", "Hub", "Left", "Right"],
+                vec!["Orphan", "Hub", "Right"]
+            ]
+        );
+        assert_eq!(
+            graph.enumerate_paths("Orphan", 2),
+            vec![vec!["Orphan", "Hub", "Right"]]
+        );
+        assert_eq!(graph.enumerate_paths("Lonely", 0), vec![vec!["Lonely"]]);
+        assert!(graph.enumerate_paths("Nowhere", 3).is_empty());
+    }
+}
@@ -0,0 +1,42 @@
+/// The hash map used internally, which is the standard library `HashMap`
+/// unless the "fast-hash" feature is enabled
+#[cfg(not(feature = "fast-hash"))]
+pub(crate) type FastHashMap<K, V> = std::collections::HashMap<K, V>;
+
+/// The hash map used internally, which uses the faster, but not DoS
+/// resistant, FxHash algorithm
+#[cfg(feature = "fast-hash")]
+pub(crate) type FastHashMap<K, V> = std::collections::HashMap<K, V, rustc_hash::FxBuildHasher>;
+
+/// The hash set used internally, matching `FastHashMap`
+#[cfg(not(feature = "fast-hash"))]
+pub(crate) type FastHashSet<T> = std::collections::HashSet<T>;
+
+/// The hash set used internally, matching `FastHashMap`
+#[cfg(feature = "fast-hash")]
+pub(crate) type FastHashSet<T> = std::collections::HashSet<T, rustc_hash::FxBuildHasher>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Story;
+    use std::collections::HashMap;
+
+    #[test]
+    fn passages_map_is_std() {
+        let input = ":: Start\nHello\n".to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        let passages: &HashMap<String, crate::TwinePassage> = &story.passages;
+        assert_eq!(passages.len(), 1);
+    }
+
+    #[test]
+    fn internal_maps() {
+        let mut map: FastHashMap<&str, usize> = FastHashMap::default();
+        map.insert("Start", 1);
+        assert_eq!(map["Start"], 1);
+        let mut set: FastHashSet<&str> = FastHashSet::default();
+        assert!(set.insert("Start"));
+        assert!(!set.insert("Start"));
+    }
+}
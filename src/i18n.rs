@@ -0,0 +1,230 @@
+use crate::markup::span;
+use crate::passages::find_comments;
+use crate::tokenize;
+use crate::FullContext;
+use crate::MarkupKind;
+use crate::Position;
+use crate::Span;
+use crate::Story;
+use crate::StoryFormat;
+use crate::TwinePassage;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// A piece of passage text to be translated, as found by
+/// [`Story::extract_strings`]
+///
+/// Enabled with "i18n" feature
+///
+/// [`Story::extract_strings`]: struct.Story.html#method.extract_strings
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TranslatableString {
+    /// The name of the passage the text is in
+    pub passage: String,
+
+    /// The text, without surrounding whitespace
+    pub text: String,
+
+    /// Where the text is in its file
+    pub span: Span,
+}
+
+impl Story {
+    /// Finds the text in the normal passages of the story that should be
+    /// translated, in order of passage name and then position, for building
+    /// gettext catalogs and other translation files
+    ///
+    /// The content of a passage is split with [`tokenize`], using the story
+    /// format named in the `StoryData`. Links, macros, comments, and the code
+    /// between SugarCube's `<<script>>` and `<</script>>` are left out, and
+    /// the text between them is trimmed. Text with no letters or digits is
+    /// skipped. A passage of a story that was not parsed from a source has
+    /// spans within its content rather than within a file
+    ///
+    /// Enabled with "i18n" feature
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = r#":: StoryData
+    /// {"ifid": "abc", "format": "SugarCube"}
+    ///
+    /// :: Start
+    /// Hello there. <<set $x to 1>>[[Leave]]
+    /// /* A note */Goodbye!
+    /// "#
+    /// .to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let strings = story.extract_strings();
+    /// let texts: Vec<&str> = strings.iter().map(|s| s.text.as_str()).collect();
+    /// assert_eq!(texts, ["Hello there.", "Goodbye!"]);
+    /// assert_eq!(strings[0].passage, "Start");
+    /// assert_eq!(strings[1].span.start, (6, 13));
+    /// ```
+    ///
+    /// [`tokenize`]: fn.tokenize.html
+    pub fn extract_strings(&self) -> Vec<TranslatableString> {
+        let format = self.data.as_ref().and_then(|data| data.story_format());
+        let mut passages: Vec<(&String, &TwinePassage)> = self.passages.iter().collect();
+        passages.sort_by_key(|(name, _)| *name);
+
+        let mut strings = Vec::new();
+        for (name, passage) in passages {
+            let content = self
+                .context_of(name)
+                .and_then(|context| context.try_subcontext(Position::rel(2, 1)..).ok())
+                .unwrap_or_else(|| FullContext::from(None, passage.content.content.clone()));
+            for range in segments(&content, format) {
+                strings.push(TranslatableString {
+                    passage: name.clone(),
+                    text: content.get_contents()[range.clone()].replace("\r\n", "\n"),
+                    span: span(&content, range),
+                });
+            }
+        }
+        strings
+    }
+
+    /// Returns a copy of the story with the text found by
+    /// [`extract_strings`](#method.extract_strings) replaced by its
+    /// translation in the given map, which is keyed by the original text.
+    /// Text with no translation is left as it is
+    ///
+    /// Only the content of the passages is changed. The contexts of the
+    /// passages and their links still refer to the original source
+    ///
+    /// Enabled with "i18n" feature
+    ///
+    /// # Examples
+    /// ```
+    /// use std::collections::HashMap;
+    /// use tweep::Story;
+    /// let input = ":: Start\nHello! [[Bye|End]]\n\n:: End\nThe end".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let mut translations = HashMap::default();
+    /// translations.insert("Hello!".to_string(), "Bonjour !".to_string());
+    /// let translated = story.apply_translations(&translations);
+    /// assert_eq!(translated.passages["Start"].content.content, "Bonjour ! [[Bye|End]]\n");
+    /// assert_eq!(translated.passages["End"].content.content, "The end\n");
+    /// ```
+    pub fn apply_translations(&self, translations: &HashMap<String, String>) -> Story {
+        let format = self.data.as_ref().and_then(|data| data.story_format());
+        let mut story = self.clone();
+        for passage in story.passages.values_mut() {
+            let content = &mut passage.content.content;
+            let context = FullContext::from(None, content.clone());
+            for range in segments(&context, format).into_iter().rev() {
+                if let Some(translation) = translations.get(&content[range.clone()]) {
+                    content.replace_range(range, translation);
+                }
+            }
+        }
+        story
+    }
+}
+
+/// Finds the byte ranges of the translatable text within the contents of the
+/// given context
+fn segments(context: &FullContext, format: Option<StoryFormat>) -> Vec<Range<usize>> {
+    let contents = context.get_contents();
+    let base = context.get_byte_range().start;
+    let comments = find_comments(contents);
+
+    let mut ranges = Vec::new();
+    let mut in_script = false;
+    for token in tokenize(context, format) {
+        let range = token.span.bytes.start - base..token.span.bytes.end - base;
+        match token.kind {
+            MarkupKind::Macro(name) if format == Some(StoryFormat::SugarCube) => {
+                match name.as_str() {
+                    "script" => in_script = true,
+                    "/script" => in_script = false,
+                    _ => (),
+                }
+            }
+            MarkupKind::Text if !in_script => {
+                // Split the text around any comments inside of it
+                let mut start = range.start;
+                for comment in comments.iter().filter(|c| c.start < range.end) {
+                    if comment.end <= start {
+                        continue;
+                    }
+                    ranges.push(start..comment.start.max(start));
+                    start = comment.end;
+                }
+                if start < range.end {
+                    ranges.push(start..range.end);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .filter_map(|range| {
+            let text = &contents[range.clone()];
+            let start = range.start + (text.len() - text.trim_start().len());
+            let end = range.start + text.trim_end().len();
+            let has_words = text.chars().any(char::is_alphanumeric);
+            if has_words {
+                Some(start..end)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let input = r#":: StoryData
+{"ifid": "abc", "format": "SugarCube"}
+
+:: Start
+  Welcome, <<print $name>>!
+<<script>>alert("Hi")<</script>> <!-- -->
+[[Go on|Cave]] --
+
+:: Cave
+It is dark.
+It is <b>cold</b>.
+"#
+        .to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        let strings = story.extract_strings();
+        let texts: Vec<(&str, &str)> = strings
+            .iter()
+            .map(|s| (s.passage.as_str(), s.text.as_str()))
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                ("Cave", "It is dark.\nIt is <b>cold</b>."),
+                ("Start", "Welcome,")
+            ]
+        );
+        assert_eq!(strings[1].span.start, (5, 3));
+        assert_eq!(strings[1].span.end, (5, 10));
+
+        let mut translations = HashMap::default();
+        translations.insert(
+            "It is dark.\nIt is <b>cold</b>.".to_string(),
+            "Il fait noir.\nIl fait <b>froid</b>.".to_string(),
+        );
+        translations.insert("Welcome,".to_string(), "Bienvenue,".to_string());
+        let translated = story.apply_translations(&translations);
+        assert_eq!(
+            translated.passages["Cave"].content.content,
+            "Il fait noir.\nIl fait <b>froid</b>.\n"
+        );
+        assert_eq!(
+            translated.passages["Start"].content.content,
+            "  Bienvenue, <<print $name>>!\n<<script>>alert(\"Hi\")<</script>> <!-- -->\n[[Go on|Cave]] --\n"
+        );
+    }
+}
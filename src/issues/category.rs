@@ -0,0 +1,44 @@
+/// A broad grouping of the kinds of errors and warnings that `tweep` can
+/// produce, which lets issues be filtered without naming every kind
+///
+/// # Examples
+/// ```
+/// use tweep::{ErrorKind, IssueCategory, WarningKind};
+/// assert_eq!(ErrorKind::MissingSigil.category(), IssueCategory::Syntax);
+/// assert_eq!(WarningKind::DeadLink("x".to_string()).category(), IssueCategory::Structure);
+/// assert_eq!(WarningKind::WhitespaceInLink.category(), IssueCategory::Style);
+/// assert_eq!(WarningKind::JsonError("x".to_string()).category(), IssueCategory::Data);
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IssueCategory {
+    /// Malformed Twee, such as a bad passage header or an unclosed link
+    Syntax,
+
+    /// Problems with how the passages of a story fit together, such as dead
+    /// links, duplicate or missing passages, or with the parse as a whole
+    Structure,
+
+    /// Valid Twee that is likely not what was intended, such as escaped
+    /// characters in a passage name or errant whitespace in a link
+    Style,
+
+    /// Problems with the input files or with the data of a story, such as
+    /// unreadable files, bad JSON, or invalid passage metadata
+    Data,
+}
+
+impl std::fmt::Display for IssueCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                IssueCategory::Syntax => "syntax",
+                IssueCategory::Structure => "structure",
+                IssueCategory::Style => "style",
+                IssueCategory::Data => "data",
+            }
+        )
+    }
+}
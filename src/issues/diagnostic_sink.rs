@@ -0,0 +1,63 @@
+use crate::Warning;
+
+/// The action a [`DiagnosticSink`] asks the parser to take after receiving a
+/// diagnostic
+///
+/// [`DiagnosticSink`]: trait.DiagnosticSink.html
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SinkAction {
+    /// Continue parsing
+    Continue,
+
+    /// Stop parsing and return a [`ParseAborted`] error
+    ///
+    /// [`ParseAborted`]: enum.ErrorKind.html#variant.ParseAborted
+    Abort,
+}
+
+/// A receiver of diagnostics that are produced while parsing
+///
+/// When a sink is set in the [`ParseOptions`], each [`Warning`] is passed to
+/// the sink as soon as the file that produced it has been parsed, rather than
+/// being collected into the list of warnings in the [`Output`]. The sink can
+/// stop the parse early by returning [`SinkAction::Abort`].
+///
+/// A sink is implemented for any `Fn(Warning) -> SinkAction` that is `Send`
+/// and `Sync`.
+///
+/// # Examples
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use tweep::{DiagnosticSink, SinkAction, Warning};
+/// struct Collector(Mutex<Vec<Warning>>);
+///
+/// impl DiagnosticSink for Collector {
+///     fn warning(&self, warning: Warning) -> SinkAction {
+///         self.0.lock().unwrap().push(warning);
+///         SinkAction::Continue
+///     }
+/// }
+///
+/// let sink: Arc<dyn DiagnosticSink> = Arc::new(Collector(Mutex::new(Vec::new())));
+/// ```
+///
+/// [`ParseOptions`]: struct.ParseOptions.html
+/// [`Warning`]: struct.Warning.html
+/// [`Output`]: struct.Output.html
+/// [`SinkAction::Abort`]: enum.SinkAction.html#variant.Abort
+pub trait DiagnosticSink: Send + Sync {
+    /// Receives a single [`Warning`] and returns whether parsing should
+    /// continue
+    ///
+    /// [`Warning`]: struct.Warning.html
+    fn warning(&self, warning: Warning) -> SinkAction;
+}
+
+impl<F> DiagnosticSink for F
+where
+    F: Fn(Warning) -> SinkAction + Send + Sync,
+{
+    fn warning(&self, warning: Warning) -> SinkAction {
+        self(warning)
+    }
+}
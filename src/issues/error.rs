@@ -1,22 +1,44 @@
-use crate::ErrorKind;
 use crate::Context;
+use crate::ErrorKind;
+use std::sync::Arc;
 
-/// An error with an owned [`ErrorKind`] and [`Position`]
+/// An error with an owned [`ErrorKind`] and an optional [`Context`]
+///
+/// If the error was caused by an I/O failure, the original
+/// [`std::io::Error`] is kept and returned by [`source`], so that the cause can
+/// be inspected through the standard error chain.
+///
+/// # Examples
+/// ```
+/// use std::error::Error as _;
+/// use tweep::{Error, ErrorKind};
+/// let io_error = std::io::Error::other("disk on fire");
+/// let error = Error::from(io_error);
+/// assert_eq!(error.kind, ErrorKind::ReadError("disk on fire".to_string()));
+/// assert_eq!(error.source().unwrap().to_string(), "disk on fire");
+/// ```
 ///
 /// [`ErrorKind`]: enum.ErrorKind.html
-/// [`Position`]: enum.Position.html
-#[derive(Clone, Debug, Eq, PartialEq)]
+/// [`Context`]: type.Context.html
+/// [`source`]: #method.source
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Error {
     /// The type of error
     pub kind: ErrorKind,
 
     /// The context of the error
     pub context: Option<Context>,
+
+    /// The I/O error that caused this error, if any. This is not compared
+    /// when comparing `Error`s, and is not serialized
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub io_source: Option<Arc<std::io::Error>>,
 }
 
 impl Error {
-    /// Creates a new `Error` with the given [`ErrorKind`] and a default
-    /// [`Position`]
+    /// Creates a new `Error` with the given [`ErrorKind`] and optional
+    /// [`Context`]
     ///
     /// # Examples
     /// ```
@@ -28,13 +50,80 @@ impl Error {
     /// ```
     ///
     /// [`ErrorKind`]: enum.ErrorKind.html
-    /// [`Position`]: struct.Position.html
+    /// [`Context`]: type.Context.html
     pub fn new<T: Into<Context>>(kind: ErrorKind, context: Option<T>) -> Self {
         Error {
             kind,
             context: context.map(|t| t.into()),
+            io_source: None,
         }
     }
+
+    /// Builder method to attach the I/O error that caused this `Error`
+    ///
+    /// # Examples
+    /// ```
+    /// use std::error::Error as _;
+    /// use tweep::{Context, Error, ErrorKind};
+    /// let io_error = std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+    /// let error = Error::new::<Context>(ErrorKind::ReadError("gone".to_string()), None)
+    ///     .with_io_source(io_error);
+    /// assert!(error.source().is_some());
+    /// ```
+    pub fn with_io_source(mut self, source: std::io::Error) -> Self {
+        self.io_source = Some(Arc::new(source));
+        self
+    }
+
+    /// Gets the short code of this `Error`'s [`ErrorKind`], which also starts
+    /// its `Display` output
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{Context, Error, ErrorKind};
+    /// let error = Error::new::<Context>(ErrorKind::ReadError("gone".to_string()), None);
+    /// assert_eq!(error.code(), "E0012");
+    /// assert_eq!(error.to_string(), "[E0012] Error reading input: gone at None");
+    /// ```
+    ///
+    /// [`ErrorKind`]: enum.ErrorKind.html
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+}
+
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.context == other.context
+    }
+}
+
+impl Eq for Error {}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.io_source
+            .as_deref()
+            .map(|e| e as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}] {} at {:?}",
+            self.kind.code(),
+            self.kind,
+            self.context
+        )
+    }
+}
+
+impl std::convert::From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Error {
+        Error::new::<Context>(ErrorKind::ReadError(e.to_string()), None).with_io_source(e)
+    }
 }
 
 #[cfg(feature = "issue-names")]
@@ -47,14 +136,36 @@ impl Error {
     }
 }
 
-impl std::error::Error for Error {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        None
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FullContext;
+    use std::error::Error as _;
+
+    #[test]
+    fn io_source() {
+        let context = FullContext::from(None, "::".to_string());
+        let plain = Error::new(ErrorKind::EmptyName, Some(context.clone()));
+        assert!(plain.source().is_none());
+        let io_error = std::io::Error::other("oops");
+        let sourced = plain.clone().with_io_source(io_error);
+        assert_eq!(sourced, plain);
+        let source = sourced.source().unwrap();
+        assert_eq!(source.to_string(), "oops");
+        assert!(source.downcast_ref::<std::io::Error>().is_some());
+        assert_eq!(
+            sourced.to_string(),
+            format!(
+                "[E0001] Passage header has an empty name at {:?}",
+                plain.context
+            )
+        );
     }
-}
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} at {:?}", self.kind, self.context)
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+        assert_send_sync::<Error>();
+        assert_send_sync::<crate::ErrorList>();
     }
 }
@@ -4,6 +4,7 @@ use crate::Error;
 ///
 /// [`Error`]: struct.Error.html
 #[derive(Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ErrorList {
     /// The list of `Error`s
     pub errors: Vec<Error>,
@@ -48,6 +49,49 @@ impl ErrorList {
         self.errors.is_empty()
     }
 
+    /// Returns an iterator over the errors in the list
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{Error, ErrorKind, ErrorList, FullContext};
+    /// let mut errors = ErrorList::new();
+    /// errors.push(Error::new(ErrorKind::EmptyName, Some(FullContext::from(None, "::".to_string()))));
+    /// let kinds: Vec<&ErrorKind> = errors.iter().map(|error| &error.kind).collect();
+    /// assert_eq!(kinds, [&ErrorKind::EmptyName]);
+    /// ```
+    pub fn iter(&self) -> std::slice::Iter<'_, Error> {
+        self.errors.iter()
+    }
+
+    /// Sorts the errors by the file name, line, and column of the start of
+    /// their contexts. Errors with no context come last, and errors at the
+    /// same position keep their order
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{Error, ErrorKind, ErrorList, FullContext, Position};
+    /// let context = FullContext::from(Some("a.twee".to_string()), ":: A [\n::\n".to_string());
+    /// let mut errors = ErrorList::new();
+    /// errors.push(Error::new::<FullContext>(ErrorKind::ParseAborted, None));
+    /// errors.push(Error::new(ErrorKind::EmptyName, Some(context.subcontext(Position::abs(2, 1)..))));
+    /// errors.push(Error::new(ErrorKind::UnclosedTagBlock, Some(context.subcontext(Position::abs(1, 6)..))));
+    /// errors.sort_by_position();
+    /// let kinds: Vec<&ErrorKind> = errors.iter().map(|error| &error.kind).collect();
+    /// assert_eq!(
+    ///     kinds,
+    ///     [&ErrorKind::UnclosedTagBlock, &ErrorKind::EmptyName, &ErrorKind::ParseAborted]
+    /// );
+    /// ```
+    pub fn sort_by_position(&mut self) {
+        self.errors.sort_by_cached_key(|error| {
+            let position = error.context.as_ref().map(|context| {
+                let start = context.get_start_position();
+                (context.get_file_name().clone(), start.line, start.column)
+            });
+            (position.is_none(), position)
+        });
+    }
+
     /// Given two `Result`s with `ErrorList` as the `Err` type, returns:
     /// * `Ok(())` if both inputs are `Ok`
     /// * The `ErrorList` contained by the `Err` input if one input is `Err`
@@ -115,9 +159,15 @@ impl ErrorList {
     }
 }
 
+/// The source of an `ErrorList` is its first [`Error`], so that the cause of
+/// an I/O failure can be reached through the standard error chain
+///
+/// [`Error`]: struct.Error.html
 impl std::error::Error for ErrorList {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        None
+        self.errors
+            .first()
+            .map(|error| error as &(dyn std::error::Error + 'static))
     }
 }
 
@@ -134,6 +184,24 @@ impl std::fmt::Display for ErrorList {
     }
 }
 
+impl IntoIterator for ErrorList {
+    type Item = Error;
+    type IntoIter = std::vec::IntoIter<Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ErrorList {
+    type Item = &'a Error;
+    type IntoIter = std::slice::Iter<'a, Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.iter()
+    }
+}
+
 impl  std::convert::From<Error> for ErrorList {
     fn from(e: Error) -> ErrorList {
         let mut error_list = ErrorList::default();
@@ -214,4 +282,38 @@ mod tests {
         expected.append(&mut error_list_right().errors);
         assert_eq!(errs.errors, expected);
     }
+
+    #[test]
+    fn sort_by_position() {
+        let a = FullContext::from(Some("a.twee".to_string()), ":: A [\n::\n:: {".to_string());
+        let b = FullContext::from(Some("b.twee".to_string()), "Blah\n".to_string());
+        let at = |context: &FullContext, line: usize, column: usize| {
+            Some(context.subcontext(crate::Position::abs(line, column)..))
+        };
+        let mut errs = ErrorList::default();
+        errs.push(Error::new(ErrorKind::MissingSigil, at(&b, 1, 1)));
+        errs.push(Error::new::<FullContext>(ErrorKind::ParseAborted, None));
+        errs.push(Error::new(ErrorKind::UnclosedTagBlock, at(&a, 1, 6)));
+        errs.push(Error::new(ErrorKind::UnescapedOpenCurly, at(&a, 3, 4)));
+        errs.push(Error::new(ErrorKind::EmptyName, at(&a, 3, 4)));
+        errs.push(Error::new(ErrorKind::EmptyName, at(&a, 2, 1)));
+        errs.push(Error::new::<FullContext>(ErrorKind::HtmlInput, None));
+        errs.sort_by_position();
+
+        let kinds: Vec<&ErrorKind> = (&errs).into_iter().map(|e| &e.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &ErrorKind::UnclosedTagBlock,
+                &ErrorKind::EmptyName,
+                &ErrorKind::UnescapedOpenCurly,
+                &ErrorKind::EmptyName,
+                &ErrorKind::MissingSigil,
+                &ErrorKind::ParseAborted,
+                &ErrorKind::HtmlInput,
+            ]
+        );
+        let owned: Vec<Error> = errs.into_iter().collect();
+        assert_eq!(owned.len(), 7);
+    }
 }
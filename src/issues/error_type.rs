@@ -1,36 +1,154 @@
+use crate::IssueCategory;
+
 /// An enum of the types of errors that can be generated by `tweep`
-#[derive(Clone, Debug, Eq, PartialEq)]
+///
+/// New kinds of errors may be added in minor releases, so matches on this enum
+/// from outside of `tweep` must include a wildcard arm. Use [`category`] to
+/// handle groups of related errors, including ones added later
+///
+/// [`category`]: #method.category
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum ErrorKind {
     /// Passage header has no name specified
+    #[error("Passage header has an empty name")]
     EmptyName,
 
     /// Passage header has whitespace before sigil `::`
+    #[error("Passage header has whitespace before sigil (::)")]
     LeadingWhitespace,
 
     /// Passage header has metadata and tags in wrong order
+    #[error("Passage header has metadata before tags")]
     MetadataBeforeTags,
 
     /// Passage header is missing sigil `::`
+    #[error("Passage header missing sigil (::)")]
     MissingSigil,
 
     /// Passage name has an unescaped `[` character
+    #[error("Unescaped [ character in passage header")]
     UnescapedOpenSquare,
 
     /// Passage name has an unescaped `{` character
+    #[error("Unescaped {{ character in passage header")]
     UnescapedOpenCurly,
 
     /// Passage name has an unescaped `]` character
+    #[error("Unescaped ] character in passage header")]
     UnescapedCloseSquare,
 
     /// Passage name has an unescaped `}` character
+    #[error("Unescaped }} character in passage header")]
     UnescapedCloseCurly,
 
     /// Passage header has an unclosed tag block
+    #[error("Unclosed tag block in passage header")]
     UnclosedTagBlock,
 
+    /// Passage header ends with a backslash to continue onto the next line
+    /// while the [`HeaderContinuation`](enum.HeaderContinuation.html) is
+    /// `Reject`
+    #[error("Passage header continues onto the next line")]
+    ContinuedHeader,
+
     /// An error was encountered when attempting to parse from the given [`Path`](std::path::Path).
     /// Contains the path string and the error string
+    #[error("Error opening path {0}: {1}")]
     BadInputPath(String, String),
+
+    /// An error was encountered when attempting to read from the given
+    /// [`Read`](std::io::Read). Contains the error string
+    #[error("Error reading input: {0}")]
+    ReadError(String),
+
+    /// Parsing was stopped early by a [`DiagnosticSink`](trait.DiagnosticSink.html)
+    #[error("Parsing aborted by diagnostic sink")]
+    ParseAborted,
+
+    /// A warning with a [`Severity`](enum.Severity.html) of `Deny` was
+    /// produced, or warnings were produced while the
+    /// [`ParseOptions`](struct.ParseOptions.html) treat warnings as errors
+    #[error("Warnings were produced that are treated as errors")]
+    WarningsAsErrors,
+
+    /// A passage has the same name as an earlier one while the
+    /// [`DuplicatePolicy`](enum.DuplicatePolicy.html) is `Error`. Contains
+    /// the passage name
+    #[error("Found duplicate passage named {0}")]
+    DuplicatePassage(String),
+
+    /// The input is not UTF-8, such as a UTF-16 file or a file with invalid
+    /// byte sequences. Contains a description of the problem. The context
+    /// points at the first invalid byte if there is one, and otherwise names
+    /// the file
+    #[error("Input is not valid UTF-8: {0}")]
+    InvalidEncoding(String),
+
+    /// The input is a story exported from Twine as HTML, such as a published
+    /// story or an archive, rather than Twee code. The context is the line
+    /// the HTML starts on
+    #[error("Input is a Twine HTML file, not Twee code")]
+    HtmlInput,
+}
+
+impl ErrorKind {
+    /// Gets the [`IssueCategory`] this kind of error belongs to
+    ///
+    /// [`IssueCategory`]: enum.IssueCategory.html
+    pub fn category(&self) -> IssueCategory {
+        match self {
+            ErrorKind::EmptyName
+            | ErrorKind::LeadingWhitespace
+            | ErrorKind::MetadataBeforeTags
+            | ErrorKind::MissingSigil
+            | ErrorKind::UnescapedOpenSquare
+            | ErrorKind::UnescapedOpenCurly
+            | ErrorKind::UnescapedCloseSquare
+            | ErrorKind::UnescapedCloseCurly
+            | ErrorKind::UnclosedTagBlock
+            | ErrorKind::ContinuedHeader => IssueCategory::Syntax,
+            ErrorKind::ParseAborted
+            | ErrorKind::WarningsAsErrors
+            | ErrorKind::DuplicatePassage(_) => IssueCategory::Structure,
+            ErrorKind::BadInputPath(_, _)
+            | ErrorKind::ReadError(_)
+            | ErrorKind::InvalidEncoding(_)
+            | ErrorKind::HtmlInput => IssueCategory::Data,
+        }
+    }
+
+    /// Gets the short code of this kind of error, such as `E0001`, for
+    /// referring to it in tools and documentation. Codes are never changed or
+    /// reused, and new kinds of errors get the next unused code
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::ErrorKind;
+    /// assert_eq!(ErrorKind::EmptyName.code(), "E0001");
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorKind::EmptyName => "E0001",
+            ErrorKind::LeadingWhitespace => "E0002",
+            ErrorKind::MetadataBeforeTags => "E0003",
+            ErrorKind::MissingSigil => "E0004",
+            ErrorKind::UnescapedOpenSquare => "E0005",
+            ErrorKind::UnescapedOpenCurly => "E0006",
+            ErrorKind::UnescapedCloseSquare => "E0007",
+            ErrorKind::UnescapedCloseCurly => "E0008",
+            ErrorKind::UnclosedTagBlock => "E0009",
+            ErrorKind::ContinuedHeader => "E0010",
+            ErrorKind::BadInputPath(_, _) => "E0011",
+            ErrorKind::ReadError(_) => "E0012",
+            ErrorKind::ParseAborted => "E0013",
+            ErrorKind::WarningsAsErrors => "E0014",
+            ErrorKind::DuplicatePassage(_) => "E0015",
+            ErrorKind::InvalidEncoding(_) => "E0016",
+            ErrorKind::HtmlInput => "E0017",
+        }
+    }
 }
 
 #[cfg(feature = "issue-names")]
@@ -49,35 +167,14 @@ impl ErrorKind {
             ErrorKind::UnescapedCloseSquare => "UnescapedCloseSquare",
             ErrorKind::UnescapedCloseCurly => "UnescapedCloseCurly",
             ErrorKind::UnclosedTagBlock => "UnclosedTagBlock",
+            ErrorKind::ContinuedHeader => "ContinuedHeader",
             ErrorKind::BadInputPath(_, _) => "BadInputPath",
+            ErrorKind::ReadError(_) => "ReadError",
+            ErrorKind::ParseAborted => "ParseAborted",
+            ErrorKind::WarningsAsErrors => "WarningsAsErrors",
+            ErrorKind::DuplicatePassage(_) => "DuplicatePassage",
+            ErrorKind::InvalidEncoding(_) => "InvalidEncoding",
+            ErrorKind::HtmlInput => "HtmlInput",
         }
     }
 }
-
-impl std::fmt::Display for ErrorKind {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                ErrorKind::EmptyName => "Passage header has an empty name".to_string(),
-                ErrorKind::LeadingWhitespace =>
-                    "Passage header has whitespace before sigil (::)".to_string(),
-                ErrorKind::MetadataBeforeTags =>
-                    "Passage header has metadata before tags".to_string(),
-                ErrorKind::MissingSigil => "Passage header missing sigil (::)".to_string(),
-                ErrorKind::UnescapedOpenSquare =>
-                    "Unescaped [ character in passage header".to_string(),
-                ErrorKind::UnescapedOpenCurly =>
-                    "Unescaped { character in passage header".to_string(),
-                ErrorKind::UnescapedCloseSquare =>
-                    "Unescaped ] character in passage header".to_string(),
-                ErrorKind::UnescapedCloseCurly =>
-                    "Unescaped } character in passage header".to_string(),
-                ErrorKind::UnclosedTagBlock => "Unclosed tag block in passage header".to_string(),
-                ErrorKind::BadInputPath(path, err_str) =>
-                    format!("Error opening path {}: {}", path, err_str),
-            }
-        )
-    }
-}
@@ -0,0 +1,69 @@
+use crate::FullContext;
+use crate::PassageHeader;
+use crate::TwineContent;
+use crate::Warning;
+
+/// A user-defined check that is run on each normal passage of a story after
+/// the built-in checks
+///
+/// Lints are registered with [`ParseOptions::with_lint`], and are run by
+/// `check_with_options` and `validate_with_options`, including when a story is
+/// checked after being parsed from a `Path`. A lint can report any kind of
+/// [`Warning`], but [`WarningKind::Lint`] lets it give its own name and
+/// message.
+///
+/// A lint is implemented for any
+/// `Fn(&PassageHeader, &TwineContent, Option<&FullContext>) -> Vec<Warning>`
+/// that is `Send` and `Sync`.
+///
+/// # Examples
+/// ```
+/// use tweep::{FullContext, Lint, PassageHeader, TwineContent, Warning, WarningKind};
+/// struct NoTodo;
+///
+/// impl Lint for NoTodo {
+///     fn check(
+///         &self,
+///         header: &PassageHeader,
+///         content: &TwineContent,
+///         context: Option<&FullContext>,
+///     ) -> Vec<Warning> {
+///         if content.content.contains("TODO") {
+///             let kind = WarningKind::Lint("NoTodo".to_string(), format!("{} has a TODO", header.name));
+///             vec![Warning::new(kind, context.cloned())]
+///         } else {
+///             Vec::new()
+///         }
+///     }
+/// }
+/// ```
+///
+/// [`ParseOptions::with_lint`]: struct.ParseOptions.html#method.with_lint
+/// [`Warning`]: struct.Warning.html
+/// [`WarningKind::Lint`]: enum.WarningKind.html#variant.Lint
+pub trait Lint: Send + Sync {
+    /// Checks a single passage, given its header, its content, and its
+    /// context if it is known, and returns any [`Warning`]s
+    ///
+    /// [`Warning`]: struct.Warning.html
+    fn check(
+        &self,
+        header: &PassageHeader,
+        content: &TwineContent,
+        context: Option<&FullContext>,
+    ) -> Vec<Warning>;
+}
+
+impl<F> Lint for F
+where
+    F: Fn(&PassageHeader, &TwineContent, Option<&FullContext>) -> Vec<Warning> + Send + Sync,
+{
+    fn check(
+        &self,
+        header: &PassageHeader,
+        content: &TwineContent,
+        context: Option<&FullContext>,
+    ) -> Vec<Warning> {
+        self(header, content, context)
+    }
+}
@@ -1,3 +1,10 @@
+mod category;
+pub use category::IssueCategory;
+
+mod diagnostic_sink;
+pub use diagnostic_sink::DiagnosticSink;
+pub use diagnostic_sink::SinkAction;
+
 mod error;
 pub use error::Error;
 
@@ -7,8 +14,15 @@ pub use error_type::ErrorKind;
 mod error_list;
 pub use error_list::ErrorList;
 
+mod lint;
+pub use lint::Lint;
+
+mod severity;
+pub use severity::Severity;
+
 mod warning;
 pub use warning::Warning;
+pub(crate) use warning::truncate_warnings;
 
 mod warning_type;
 pub use warning_type::WarningKind;
@@ -0,0 +1,46 @@
+/// How serious a [`Warning`] is
+///
+/// Each [`WarningKind`] has a default severity, which can be overridden in the
+/// [`ParseOptions`]. A `Deny` warning makes the parse fail with a
+/// [`WarningsAsErrors`] error, while still being reported as a warning.
+///
+/// # Examples
+/// ```
+/// use tweep::{Severity, WarningKind};
+/// assert_eq!(WarningKind::DeadLink("x".to_string()).severity(), Severity::Warning);
+/// assert_eq!(WarningKind::EscapedOpenSquare.severity(), Severity::Info);
+/// assert!(Severity::Deny > Severity::Warning);
+/// ```
+///
+/// [`Warning`]: struct.Warning.html
+/// [`WarningKind`]: enum.WarningKind.html
+/// [`ParseOptions`]: struct.ParseOptions.html
+/// [`WarningsAsErrors`]: enum.ErrorKind.html#variant.WarningsAsErrors
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    /// Worth knowing about, but not a problem with the story. Never makes a
+    /// parse fail
+    Info,
+
+    /// A likely problem with the story. Makes a parse fail only if warnings
+    /// are treated as errors
+    Warning,
+
+    /// A problem that makes the parse fail
+    Deny,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Severity::Info => "info",
+                Severity::Warning => "warning",
+                Severity::Deny => "deny",
+            }
+        )
+    }
+}
@@ -1,8 +1,9 @@
 use crate::Context;
+use crate::Severity;
 use crate::WarningKind;
 
-/// A warning with a [`WarningKind`], [`Position`], and optionally a reference
-/// to another [`Position`]
+/// A warning with a [`WarningKind`], an optional [`Context`], and optionally a
+/// referent [`Context`] that points to a related location
 ///
 /// # Examples
 /// ```
@@ -15,8 +16,9 @@ use crate::WarningKind;
 /// ```
 ///
 /// [`WarningKind`]: enum.WarningKind.html
-/// [`Position`]: enum.Position.html
+/// [`Context`]: type.Context.html
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Warning {
     /// The warning type
     pub kind: WarningKind,
@@ -26,6 +28,11 @@ pub struct Warning {
 
     /// The location referenced by this warning
     pub referent: Option<Context>,
+
+    /// A suggested fix for this warning, such as the name of the passage that
+    /// a dead link was most likely meant to link to
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub suggestion: Option<String>,
 }
 
 impl Warning {
@@ -43,6 +50,7 @@ impl Warning {
             kind,
             context: context.map(|c| c.into()),
             referent: None,
+            suggestion: None,
         }
     }
 
@@ -108,6 +116,73 @@ impl Warning {
         self.set_referent(referent.into());
         self
     }
+
+    /// Gets the suggested fix for this warning, if there is one
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{StoryPassages, WarningKind};
+    /// let input = ":: Start\n[[Forrest]]\n\n:: Forest\n".to_string();
+    /// let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+    /// let warnings = story.check();
+    /// let dead_link = warnings
+    ///     .iter()
+    ///     .find(|w| w.kind == WarningKind::DeadLink("Forrest".to_string()))
+    ///     .unwrap();
+    /// assert_eq!(dead_link.get_suggestion(), Some("Forest"));
+    /// ```
+    pub fn get_suggestion(&self) -> Option<&str> {
+        self.suggestion.as_deref()
+    }
+
+    /// Moves the object, sets the suggested fix to the given string, and
+    /// returns the modified object
+    pub fn with_suggestion(mut self, suggestion: String) -> Self {
+        self.suggestion = Some(suggestion);
+        self
+    }
+
+    /// Gets the default [`Severity`] of this `Warning`'s `WarningKind`
+    ///
+    /// [`Severity`]: enum.Severity.html
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+
+    /// Gets the short code of this `Warning`'s `WarningKind`, which also
+    /// starts its `Display` output
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+}
+
+/// Truncates `warnings` so that it holds at most `max` warnings, followed by a
+/// [`WarningsSuppressed`] marker with the number of warnings removed. Any
+/// markers already in the list, such as from an earlier part of a parse that
+/// more warnings were added after, are removed and their counts carried over.
+///
+/// [`WarningsSuppressed`]: enum.WarningKind.html#variant.WarningsSuppressed
+pub(crate) fn truncate_warnings(warnings: &mut Vec<Warning>, max: usize) {
+    let mut suppressed = 0;
+    warnings.retain(|warning| match warning.kind {
+        WarningKind::WarningsSuppressed(count) => {
+            suppressed += count;
+            false
+        }
+        _ => true,
+    });
+
+    if warnings.len() > max {
+        suppressed += warnings.len() - max;
+        warnings.truncate(max);
+    }
+
+    if suppressed > 0 {
+        warnings.push(Warning::new::<Context>(
+            WarningKind::WarningsSuppressed(suppressed),
+            None,
+        ));
+    }
 }
 
 #[cfg(feature = "issue-names")]
@@ -128,7 +203,14 @@ impl std::fmt::Display for Warning {
         } else {
             String::new()
         };
-        write!(f, "{} at {:?}{}", self.kind, self.context, cause)
+        write!(
+            f,
+            "[{}] {} at {:?}{}",
+            self.kind.code(),
+            self.kind,
+            self.context,
+            cause
+        )
     }
 }
 
@@ -160,6 +242,41 @@ mod tests {
         assert_eq!(warning.get_referent(), Some(&ref_context.into()));
     }
 
+    #[test]
+    fn truncate() {
+        let context = FullContext::from(None, "[[".to_string());
+        let mut warnings = vec![Warning::new(WarningKind::UnclosedLink, Some(context)); 5];
+        truncate_warnings(&mut warnings, 5);
+        assert_eq!(warnings.len(), 5);
+
+        truncate_warnings(&mut warnings, 2);
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(warnings[2].kind, WarningKind::WarningsSuppressed(3));
+
+        // An existing marker is folded into the new one
+        warnings.insert(0, warnings[0].clone());
+        truncate_warnings(&mut warnings, 2);
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(warnings[2].kind, WarningKind::WarningsSuppressed(4));
+
+        // So is a marker that more warnings were added after
+        warnings.push(warnings[0].clone());
+        truncate_warnings(&mut warnings, 2);
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(warnings[2].kind, WarningKind::WarningsSuppressed(5));
+    }
+
+    #[test]
+    fn display() {
+        let context = FullContext::from(Some("a.tw".to_string()), "[[".to_string());
+        let warning = Warning::new(WarningKind::UnclosedLink, Some(context));
+        assert_eq!(warning.code(), "W0014");
+        assert_eq!(
+            warning.to_string(),
+            format!("[W0014] Unclosed passage link at {:?}", warning.context)
+        );
+    }
+
     #[test]
     #[cfg(feature = "issue-names")]
     fn test_name() {
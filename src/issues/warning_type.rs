@@ -1,5 +1,17 @@
+use crate::escape::reveal_invisible;
+use crate::IssueCategory;
+use crate::Severity;
+
 /// An enum of the types of warnings that can be produced by `tweep`
+///
+/// New kinds of warnings may be added in minor releases, so matches on this
+/// enum from outside of `tweep` must include a wildcard arm. Use [`category`]
+/// to handle groups of related warnings, including ones added later
+///
+/// [`category`]: #method.category
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum WarningKind {
     /// `\[` in a passage title
     EscapedOpenSquare,
@@ -22,6 +34,18 @@ pub enum WarningKind {
     /// `StoryData` passage encountered after parsing a `StoryData` passage
     DuplicateStoryData,
 
+    /// `StoryAuthor` passage encountered after parsing a `StoryAuthor` passage
+    DuplicateStoryAuthor,
+
+    /// `StorySubtitle` passage encountered after parsing a `StorySubtitle` passage
+    DuplicateStorySubtitle,
+
+    /// `StoryMenu` passage encountered after parsing a `StoryMenu` passage
+    DuplicateStoryMenu,
+
+    /// `StorySettings` passage encountered after parsing a `StorySettings` passage
+    DuplicateStorySettings,
+
     /// No `StoryTitle` passage parsed while parsing a [`Story`](struct.Story.html)
     MissingStoryTitle,
 
@@ -46,14 +70,293 @@ pub enum WarningKind {
 
     /// Encountered a duplicated passage name
     DuplicatePassage(String),
+
+    /// A file could not be read and was skipped. Contains the path string and
+    /// the error string
+    UnreadableFile(String, String),
+
+    /// Marks that the list of warnings was truncated. Contains the number of
+    /// warnings that were suppressed
+    WarningsSuppressed(usize),
+
+    /// A color in the `tag-colors` of the `StoryData` that Twine will not
+    /// render. Contains the tag and the color string
+    UnknownTagColor(String, String),
+
+    /// A `position` or `size` in passage metadata that is negative or NaN.
+    /// Contains the metadata key and the value
+    InvalidCoordinates(String, String),
+
+    /// A `.twee-ignore` file could not be read, or contains an invalid
+    /// pattern. Contains the path string and the error string
+    BadIgnoreFile(String, String),
+
+    /// Found a link to a non-existent passage that is defined in a file that
+    /// was skipped while parsing. Contains the link target and the path string
+    /// of the skipped file
+    ExcludedLink(String, String),
+
+    /// A file listed in a `StoryIncludes` passage is already being included,
+    /// directly or indirectly, by itself. Contains the listed path
+    IncludeCycle(String),
+
+    /// Two passage names are the same once normalized to Unicode
+    /// normalization form C, but are encoded differently. Contains the name
+    /// of the passage and the name of the passage it conflicts with
+    NormalizationConflict(String, String),
+
+    /// More than one passage has the same pid (Passage ID). Contains the pid
+    DuplicatePid(usize),
+
+    /// No passage has any of the pids in a range, even though a passage has a
+    /// higher pid. Contains the first and last missing pid
+    PidGap(usize, usize),
+
+    /// Encountered a link in a passage that points to the passage itself,
+    /// which is often a typo. Contains the passage name
+    SelfLink(String),
+
+    /// Encountered a Twine link with an empty passage name (e.g., `[[ ]]` or
+    /// `[[Text|]]`)
+    EmptyLinkTarget,
+
+    /// A registered [`Lint`] found a problem. Contains the name of the lint
+    /// and a description of the problem
+    ///
+    /// [`Lint`]: trait.Lint.html
+    Lint(String, String),
+
+    /// No passage without links to other passages can be reached from the
+    /// start passage, so every path through the story loops forever. Contains
+    /// the name of the start passage
+    NoReachableEnding(String),
+
+    /// A passage has a branching complexity above the maximum set in the
+    /// options. Contains the name of the passage, its complexity, and the
+    /// maximum
+    ComplexPassage(String, usize, usize),
+
+    /// `StoryTitle` passage content spans more than one line
+    MultiLineStoryTitle,
+
+    /// The story title contains angle brackets or control characters, which
+    /// need escaping in HTML. Contains the title
+    HtmlUnsafeTitle(String),
+
+    /// A passage name contains angle brackets or control characters, which
+    /// need escaping in HTML. Contains the passage name
+    HtmlUnsafeName(String),
+
+    /// A passage name contains `->`, `<-`, or `|`, so a link with just the
+    /// name, such as `[[a->b]]`, is read as a link to a different passage.
+    /// Contains the passage name
+    LinkSyntaxInName(String),
+
+    /// A key in the `StoryData` that is not defined by the specification but
+    /// is close to one that is, such as `formatVersion` for `format-version`.
+    /// Contains the key and the key it is close to
+    MisspelledStoryDataKey(String, String),
+
+    /// The `zoom` in the `StoryData` is not a number greater than 0 and at
+    /// most 1, the range of zoom levels Twine uses. Contains the zoom level
+    InvalidZoom(String),
+
+    /// A `position` or `size` in passage metadata that is not a string of two
+    /// comma separated numbers, such as `"600,400"`. Contains the metadata key
+    /// and the value
+    MalformedMetadataField(String, String),
+
+    /// A passage name or link target contains an invisible character, such as
+    /// a zero-width space, a byte order mark, or a non-breaking space, so it
+    /// looks the same as a name without it but does not match it. Contains
+    /// the name or target
+    InvisibleCharacter(String),
+
+    /// A SugarCube macro is invoked that is not built into SugarCube, defined
+    /// with `Macro.add` in a script passage, or defined with `<<widget>>` in a
+    /// passage tagged `widget`. Contains the name of the macro and the sorted
+    /// names of the widgets that are defined
+    UndefinedWidget(String, Vec<String>),
+
+    /// The condition of a conditional macro, such as `<<if>>` or `(if:)`, has
+    /// a bracket or quote that is never closed, or a closing bracket that does
+    /// not match. Contains the condition
+    UnbalancedCondition(String),
+
+    /// The condition of a conditional macro, such as `<<if>>` or `(if:)`,
+    /// assigns with `=` or `to` where a comparison such as `is` was likely
+    /// intended. Contains the condition
+    AssignmentInCondition(String),
+
+    /// A passage has a translation in some of the languages set in the
+    /// [`ParseOptions`] but not in another. Contains the name of the passage
+    /// without its language suffix and the missing language
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html#method.with_languages
+    MissingTranslation(String, String),
+
+    /// A passage in one of the languages set in the [`ParseOptions`] links to
+    /// a passage in another. Contains the link target and the language of the
+    /// passage containing the link
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html#method.with_languages
+    CrossLanguageLink(String, String),
+
+    /// The text of a passage contains one of the words flagged in the
+    /// [`ParseOptions`], such as a placeholder like `TODO` or `lorem`.
+    /// Contains the flagged word as it was given
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html#method.with_flagged_words
+    FlaggedWord(String),
+}
+
+impl WarningKind {
+    /// Gets the default [`Severity`] of this kind of warning
+    ///
+    /// [`Severity`]: enum.Severity.html
+    pub fn severity(&self) -> Severity {
+        match self {
+            WarningKind::EscapedOpenSquare
+            | WarningKind::EscapedCloseSquare
+            | WarningKind::EscapedOpenCurly
+            | WarningKind::EscapedCloseCurly
+            | WarningKind::WarningsSuppressed(_) => Severity::Info,
+            _ => Severity::Warning,
+        }
+    }
+
+    /// Gets the [`IssueCategory`] this kind of warning belongs to
+    ///
+    /// [`IssueCategory`]: enum.IssueCategory.html
+    pub fn category(&self) -> IssueCategory {
+        match self {
+            WarningKind::UnclosedLink
+            | WarningKind::EmptyLinkTarget
+            | WarningKind::UnbalancedCondition(_)
+            | WarningKind::AssignmentInCondition(_) => IssueCategory::Syntax,
+            WarningKind::DuplicateStoryTitle
+            | WarningKind::DuplicateStoryData
+            | WarningKind::DuplicateStoryAuthor
+            | WarningKind::DuplicateStorySubtitle
+            | WarningKind::DuplicateStoryMenu
+            | WarningKind::DuplicateStorySettings
+            | WarningKind::MissingStoryTitle
+            | WarningKind::MissingStoryData
+            | WarningKind::DeadLink(_)
+            | WarningKind::UndefinedWidget(_, _)
+            | WarningKind::MissingStartPassage
+            | WarningKind::DeadStartPassage(_)
+            | WarningKind::DuplicatePassage(_)
+            | WarningKind::WarningsSuppressed(_)
+            | WarningKind::ExcludedLink(_, _)
+            | WarningKind::IncludeCycle(_)
+            | WarningKind::SelfLink(_)
+            | WarningKind::NoReachableEnding(_)
+            | WarningKind::MissingTranslation(_, _)
+            | WarningKind::CrossLanguageLink(_, _) => IssueCategory::Structure,
+            WarningKind::EscapedOpenSquare
+            | WarningKind::EscapedCloseSquare
+            | WarningKind::EscapedOpenCurly
+            | WarningKind::EscapedCloseCurly
+            | WarningKind::WhitespaceInLink
+            | WarningKind::NormalizationConflict(_, _)
+            | WarningKind::FlaggedWord(_)
+            | WarningKind::Lint(_, _)
+            | WarningKind::ComplexPassage(_, _, _)
+            | WarningKind::MultiLineStoryTitle
+            | WarningKind::HtmlUnsafeTitle(_)
+            | WarningKind::HtmlUnsafeName(_)
+            | WarningKind::LinkSyntaxInName(_)
+            | WarningKind::InvisibleCharacter(_) => IssueCategory::Style,
+            WarningKind::JsonError(_)
+            | WarningKind::UnreadableFile(_, _)
+            | WarningKind::UnknownTagColor(_, _)
+            | WarningKind::InvalidCoordinates(_, _)
+            | WarningKind::BadIgnoreFile(_, _)
+            | WarningKind::DuplicatePid(_)
+            | WarningKind::PidGap(_, _)
+            | WarningKind::MisspelledStoryDataKey(_, _)
+            | WarningKind::InvalidZoom(_)
+            | WarningKind::MalformedMetadataField(_, _) => IssueCategory::Data,
+        }
+    }
+
+    /// Gets the short code of this kind of warning, such as `W0016`, for
+    /// referring to it in tools, documentation, and suppression pragmas.
+    /// Codes are never changed or reused, and new kinds of warnings get the
+    /// next unused code
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::WarningKind;
+    /// assert_eq!(WarningKind::DeadLink("Cave".to_string()).code(), "W0016");
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match self {
+            WarningKind::EscapedOpenSquare => "W0001",
+            WarningKind::EscapedCloseSquare => "W0002",
+            WarningKind::EscapedOpenCurly => "W0003",
+            WarningKind::EscapedCloseCurly => "W0004",
+            WarningKind::JsonError(_) => "W0005",
+            WarningKind::DuplicateStoryTitle => "W0006",
+            WarningKind::DuplicateStoryData => "W0007",
+            WarningKind::DuplicateStoryAuthor => "W0008",
+            WarningKind::DuplicateStorySubtitle => "W0009",
+            WarningKind::DuplicateStoryMenu => "W0010",
+            WarningKind::DuplicateStorySettings => "W0011",
+            WarningKind::MissingStoryTitle => "W0012",
+            WarningKind::MissingStoryData => "W0013",
+            WarningKind::UnclosedLink => "W0014",
+            WarningKind::WhitespaceInLink => "W0015",
+            WarningKind::DeadLink(_) => "W0016",
+            WarningKind::MissingStartPassage => "W0017",
+            WarningKind::DeadStartPassage(_) => "W0018",
+            WarningKind::DuplicatePassage(_) => "W0019",
+            WarningKind::UnreadableFile(_, _) => "W0020",
+            WarningKind::WarningsSuppressed(_) => "W0021",
+            WarningKind::UnknownTagColor(_, _) => "W0022",
+            WarningKind::InvalidCoordinates(_, _) => "W0023",
+            WarningKind::BadIgnoreFile(_, _) => "W0024",
+            WarningKind::ExcludedLink(_, _) => "W0025",
+            WarningKind::IncludeCycle(_) => "W0026",
+            WarningKind::NormalizationConflict(_, _) => "W0027",
+            WarningKind::DuplicatePid(_) => "W0028",
+            WarningKind::PidGap(_, _) => "W0029",
+            WarningKind::SelfLink(_) => "W0030",
+            WarningKind::EmptyLinkTarget => "W0031",
+            WarningKind::Lint(_, _) => "W0032",
+            WarningKind::NoReachableEnding(_) => "W0033",
+            WarningKind::ComplexPassage(_, _, _) => "W0034",
+            WarningKind::MultiLineStoryTitle => "W0035",
+            WarningKind::HtmlUnsafeTitle(_) => "W0036",
+            WarningKind::HtmlUnsafeName(_) => "W0037",
+            WarningKind::LinkSyntaxInName(_) => "W0038",
+            WarningKind::MisspelledStoryDataKey(_, _) => "W0039",
+            WarningKind::InvalidZoom(_) => "W0040",
+            WarningKind::MalformedMetadataField(_, _) => "W0041",
+            WarningKind::InvisibleCharacter(_) => "W0042",
+            WarningKind::UndefinedWidget(_, _) => "W0043",
+            WarningKind::UnbalancedCondition(_) => "W0044",
+            WarningKind::AssignmentInCondition(_) => "W0045",
+            WarningKind::MissingTranslation(_, _) => "W0046",
+            WarningKind::CrossLanguageLink(_, _) => "W0047",
+            WarningKind::FlaggedWord(_) => "W0048",
+        }
+    }
 }
 
-#[cfg(feature = "issue-names")]
 impl WarningKind {
     /// Gets a string representation of a `WarningKind` variant's name
     ///
     /// Enabled with "issue-names" feature
+    #[cfg(feature = "issue-names")]
     pub fn get_name(&self) -> &str {
+        self.name()
+    }
+
+    /// Gets the name of this kind of warning's variant, which is also how it
+    /// is named in a suppression pragma
+    pub(crate) fn name(&self) -> &'static str {
         match self {
             WarningKind::EscapedOpenSquare => "EscapedOpenSquare",
             WarningKind::EscapedCloseSquare => "EscapedCloseSquare",
@@ -62,6 +365,10 @@ impl WarningKind {
             WarningKind::JsonError(_) => "JsonError",
             WarningKind::DuplicateStoryData => "DuplicateStoryData",
             WarningKind::DuplicateStoryTitle => "DuplicateStoryTitle",
+            WarningKind::DuplicateStoryAuthor => "DuplicateStoryAuthor",
+            WarningKind::DuplicateStorySubtitle => "DuplicateStorySubtitle",
+            WarningKind::DuplicateStoryMenu => "DuplicateStoryMenu",
+            WarningKind::DuplicateStorySettings => "DuplicateStorySettings",
             WarningKind::MissingStoryData => "MissingStoryData",
             WarningKind::MissingStoryTitle => "MissingStoryTitle",
             WarningKind::UnclosedLink => "UnclosedLink",
@@ -70,6 +377,35 @@ impl WarningKind {
             WarningKind::MissingStartPassage => "MissingStartPassage",
             WarningKind::DeadStartPassage(_) => "DeadStartPassage",
             WarningKind::DuplicatePassage(_) => "DuplicatePassage",
+            WarningKind::UnreadableFile(_, _) => "UnreadableFile",
+            WarningKind::WarningsSuppressed(_) => "WarningsSuppressed",
+            WarningKind::UnknownTagColor(_, _) => "UnknownTagColor",
+            WarningKind::InvalidCoordinates(_, _) => "InvalidCoordinates",
+            WarningKind::BadIgnoreFile(_, _) => "BadIgnoreFile",
+            WarningKind::ExcludedLink(_, _) => "ExcludedLink",
+            WarningKind::IncludeCycle(_) => "IncludeCycle",
+            WarningKind::NormalizationConflict(_, _) => "NormalizationConflict",
+            WarningKind::DuplicatePid(_) => "DuplicatePid",
+            WarningKind::PidGap(_, _) => "PidGap",
+            WarningKind::SelfLink(_) => "SelfLink",
+            WarningKind::EmptyLinkTarget => "EmptyLinkTarget",
+            WarningKind::Lint(_, _) => "Lint",
+            WarningKind::NoReachableEnding(_) => "NoReachableEnding",
+            WarningKind::ComplexPassage(_, _, _) => "ComplexPassage",
+            WarningKind::MultiLineStoryTitle => "MultiLineStoryTitle",
+            WarningKind::HtmlUnsafeTitle(_) => "HtmlUnsafeTitle",
+            WarningKind::HtmlUnsafeName(_) => "HtmlUnsafeName",
+            WarningKind::LinkSyntaxInName(_) => "LinkSyntaxInName",
+            WarningKind::MisspelledStoryDataKey(_, _) => "MisspelledStoryDataKey",
+            WarningKind::InvalidZoom(_) => "InvalidZoom",
+            WarningKind::MalformedMetadataField(_, _) => "MalformedMetadataField",
+            WarningKind::InvisibleCharacter(_) => "InvisibleCharacter",
+            WarningKind::UndefinedWidget(_, _) => "UndefinedWidget",
+            WarningKind::UnbalancedCondition(_) => "UnbalancedCondition",
+            WarningKind::AssignmentInCondition(_) => "AssignmentInCondition",
+            WarningKind::MissingTranslation(_, _) => "MissingTranslation",
+            WarningKind::CrossLanguageLink(_, _) => "CrossLanguageLink",
+            WarningKind::FlaggedWord(_) => "FlaggedWord",
         }
     }
 }
@@ -93,6 +429,13 @@ impl std::fmt::Display for WarningKind {
                 WarningKind::DuplicateStoryData => "Multiple StoryData passages found".to_string(),
                 WarningKind::DuplicateStoryTitle =>
                     "Multiple StoryTitle passages found".to_string(),
+                WarningKind::DuplicateStoryAuthor =>
+                    "Multiple StoryAuthor passages found".to_string(),
+                WarningKind::DuplicateStorySubtitle =>
+                    "Multiple StorySubtitle passages found".to_string(),
+                WarningKind::DuplicateStoryMenu => "Multiple StoryMenu passages found".to_string(),
+                WarningKind::DuplicateStorySettings =>
+                    "Multiple StorySettings passages found".to_string(),
                 WarningKind::MissingStoryData => "No StoryData passage found".to_string(),
                 WarningKind::MissingStoryTitle => "No StoryTitle passage found".to_string(),
                 WarningKind::UnclosedLink => "Unclosed passage link".to_string(),
@@ -105,15 +448,111 @@ impl std::fmt::Display for WarningKind {
                 WarningKind::DeadStartPassage(start) =>
                     format!("Start passage set to {}, but no such passage found", start),
                 WarningKind::DuplicatePassage(name) => format!("Found duplicate passage named {}", name),
+                WarningKind::UnreadableFile(path, err_str) =>
+                    format!("Skipped unreadable file {}: {}", path, err_str),
+                WarningKind::WarningsSuppressed(count) =>
+                    format!("{} further diagnostics suppressed", count),
+                WarningKind::UnknownTagColor(tag, color) => format!(
+                    "Tag {} has color {}, which Twine will not display",
+                    tag, color
+                ),
+                WarningKind::InvalidCoordinates(key, value) =>
+                    format!("Invalid passage {} in metadata: {}", key, value),
+                WarningKind::BadIgnoreFile(path, err_str) =>
+                    format!("Error in ignore file {}: {}", path, err_str),
+                WarningKind::ExcludedLink(target, path) => format!(
+                    "Dead link to nonexistent passage: {} (target found in excluded file {})",
+                    target, path
+                ),
+                WarningKind::IncludeCycle(path) => format!("Skipped cyclic include of {}", path),
+                WarningKind::NormalizationConflict(name, other) => format!(
+                    "Passage names {} and {} differ only in Unicode normalization",
+                    name, other
+                ),
+                WarningKind::DuplicatePid(pid) =>
+                    format!("Passage ID {} is used by more than one passage", pid),
+                WarningKind::PidGap(first, last) if first == last =>
+                    format!("No passage has passage ID {}", first),
+                WarningKind::PidGap(first, last) =>
+                    format!("No passages have passage IDs {} through {}", first, last),
+                WarningKind::SelfLink(name) => format!("Passage {} links to itself", name),
+                WarningKind::EmptyLinkTarget => "Passage link has an empty target".to_string(),
+                WarningKind::Lint(name, message) => format!("{}: {}", name, message),
+                WarningKind::NoReachableEnding(start) =>
+                    format!("No ending can be reached from start passage {}", start),
+                WarningKind::ComplexPassage(name, complexity, max) => format!(
+                    "Passage {} has a complexity of {}, above the maximum of {}",
+                    name, complexity, max
+                ),
+                WarningKind::MultiLineStoryTitle =>
+                    "StoryTitle passage spans multiple lines".to_string(),
+                WarningKind::HtmlUnsafeTitle(title) =>
+                    format!("Story title {:?} contains characters unsafe in HTML", title),
+                WarningKind::HtmlUnsafeName(name) =>
+                    format!("Passage name {:?} contains characters unsafe in HTML", name),
+                WarningKind::LinkSyntaxInName(name) => format!(
+                    "Passage name {:?} contains link syntax, so [[{}]] does not link to it. Link \
+                     to it with display text, as in [[Text|{}]]",
+                    name, name, name
+                ),
+                WarningKind::MisspelledStoryDataKey(key, known) => format!(
+                    "StoryData key {} is not defined by the specification, did you mean {}?",
+                    key, known
+                ),
+                WarningKind::InvalidZoom(zoom) =>
+                    format!("StoryData zoom {} is not above 0 and at most 1", zoom),
+                WarningKind::MalformedMetadataField(key, value) => format!(
+                    "Passage {} in metadata is not two comma separated numbers: {}",
+                    key, value
+                ),
+                WarningKind::InvisibleCharacter(name) => format!(
+                    "Name {} contains invisible characters",
+                    reveal_invisible(name)
+                ),
+                WarningKind::UndefinedWidget(name, known) if known.is_empty() =>
+                    format!("Undefined widget <<{}>>, no widgets are defined", name),
+                WarningKind::UndefinedWidget(name, known) => format!(
+                    "Undefined widget <<{}>>, known widgets: {}",
+                    name,
+                    known.join(", ")
+                ),
+                WarningKind::UnbalancedCondition(condition) =>
+                    format!("Unbalanced brackets or quotes in condition: {}", condition),
+                WarningKind::AssignmentInCondition(condition) => format!(
+                    "Assignment in condition, did you mean to compare? {}",
+                    condition
+                ),
+                WarningKind::MissingTranslation(name, language) =>
+                    format!("Passage {} has no \"{}\" translation", name, language),
+                WarningKind::CrossLanguageLink(target, language) => format!(
+                    "Link to {} from a \"{}\" passage crosses languages",
+                    target, language
+                ),
+                WarningKind::FlaggedWord(word) => format!("Flagged word in passage text: {}", word),
             }
         )
     }
 }
 
-#[cfg(all(test, feature = "issue-names"))]
+#[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn categories() {
+        assert_eq!(WarningKind::UnclosedLink.category(), IssueCategory::Syntax);
+        assert_eq!(
+            WarningKind::MissingStartPassage.category(),
+            IssueCategory::Structure
+        );
+        assert_eq!(
+            WarningKind::EscapedOpenCurly.category(),
+            IssueCategory::Style
+        );
+        assert_eq!(WarningKind::PidGap(1, 2).category(), IssueCategory::Data);
+        assert_eq!(IssueCategory::Structure.to_string(), "structure");
+    }
+
     #[cfg(feature = "issue-names")]
     #[test]
     fn test_names() {
@@ -124,6 +563,10 @@ mod tests {
         assert_eq!(WarningKind::JsonError("x".to_string()).get_name(), "JsonError");
         assert_eq!(WarningKind::DuplicateStoryData.get_name(), "DuplicateStoryData");
         assert_eq!(WarningKind::DuplicateStoryTitle.get_name(), "DuplicateStoryTitle");
+        assert_eq!(WarningKind::DuplicateStoryAuthor.get_name(), "DuplicateStoryAuthor");
+        assert_eq!(WarningKind::DuplicateStorySubtitle.get_name(), "DuplicateStorySubtitle");
+        assert_eq!(WarningKind::DuplicateStoryMenu.get_name(), "DuplicateStoryMenu");
+        assert_eq!(WarningKind::DuplicateStorySettings.get_name(), "DuplicateStorySettings");
         assert_eq!(WarningKind::MissingStoryData.get_name(), "MissingStoryData");
         assert_eq!(WarningKind::MissingStoryTitle.get_name(), "MissingStoryTitle");
         assert_eq!(WarningKind::UnclosedLink.get_name(), "UnclosedLink");
@@ -132,5 +575,103 @@ mod tests {
         assert_eq!(WarningKind::MissingStartPassage.get_name(), "MissingStartPassage");
         assert_eq!(WarningKind::DeadStartPassage("x".to_string()).get_name(), "DeadStartPassage");
         assert_eq!(WarningKind::DuplicatePassage("x".to_string()).get_name(), "DuplicatePassage");
+        assert_eq!(WarningKind::UnreadableFile("x".to_string(), "y".to_string()).get_name(), "UnreadableFile");
+        assert_eq!(WarningKind::WarningsSuppressed(1).get_name(), "WarningsSuppressed");
+        assert_eq!(WarningKind::UnknownTagColor("x".to_string(), "y".to_string()).get_name(), "UnknownTagColor");
+        assert_eq!(
+            WarningKind::InvalidCoordinates("x".to_string(), "y".to_string()).get_name(),
+            "InvalidCoordinates"
+        );
+        assert_eq!(
+            WarningKind::BadIgnoreFile("x".to_string(), "y".to_string()).get_name(),
+            "BadIgnoreFile"
+        );
+        assert_eq!(
+            WarningKind::ExcludedLink("x".to_string(), "y".to_string()).get_name(),
+            "ExcludedLink"
+        );
+        assert_eq!(
+            WarningKind::IncludeCycle("x".to_string()).get_name(),
+            "IncludeCycle"
+        );
+        assert_eq!(
+            WarningKind::NormalizationConflict("x".to_string(), "y".to_string()).get_name(),
+            "NormalizationConflict"
+        );
+        assert_eq!(WarningKind::DuplicatePid(1).get_name(), "DuplicatePid");
+        assert_eq!(WarningKind::PidGap(1, 2).get_name(), "PidGap");
+        assert_eq!(
+            WarningKind::SelfLink("x".to_string()).get_name(),
+            "SelfLink"
+        );
+        assert_eq!(WarningKind::EmptyLinkTarget.get_name(), "EmptyLinkTarget");
+        assert_eq!(
+            WarningKind::Lint("x".to_string(), "y".to_string()).get_name(),
+            "Lint"
+        );
+        assert_eq!(
+            WarningKind::NoReachableEnding("x".to_string()).get_name(),
+            "NoReachableEnding"
+        );
+        assert_eq!(
+            WarningKind::ComplexPassage("x".to_string(), 2, 1).get_name(),
+            "ComplexPassage"
+        );
+        assert_eq!(
+            WarningKind::MultiLineStoryTitle.get_name(),
+            "MultiLineStoryTitle"
+        );
+        assert_eq!(
+            WarningKind::HtmlUnsafeTitle("x".to_string()).get_name(),
+            "HtmlUnsafeTitle"
+        );
+        assert_eq!(
+            WarningKind::HtmlUnsafeName("x".to_string()).get_name(),
+            "HtmlUnsafeName"
+        );
+        assert_eq!(
+            WarningKind::LinkSyntaxInName("x".to_string()).get_name(),
+            "LinkSyntaxInName"
+        );
+        assert_eq!(
+            WarningKind::MisspelledStoryDataKey("x".to_string(), "y".to_string()).get_name(),
+            "MisspelledStoryDataKey"
+        );
+        assert_eq!(
+            WarningKind::InvalidZoom("x".to_string()).get_name(),
+            "InvalidZoom"
+        );
+        assert_eq!(
+            WarningKind::MalformedMetadataField("x".to_string(), "y".to_string()).get_name(),
+            "MalformedMetadataField"
+        );
+        assert_eq!(
+            WarningKind::InvisibleCharacter("x".to_string()).get_name(),
+            "InvisibleCharacter"
+        );
+        assert_eq!(
+            WarningKind::UndefinedWidget("x".to_string(), Vec::new()).get_name(),
+            "UndefinedWidget"
+        );
+        assert_eq!(
+            WarningKind::UnbalancedCondition("x".to_string()).get_name(),
+            "UnbalancedCondition"
+        );
+        assert_eq!(
+            WarningKind::AssignmentInCondition("x".to_string()).get_name(),
+            "AssignmentInCondition"
+        );
+        assert_eq!(
+            WarningKind::MissingTranslation("x".to_string(), "y".to_string()).get_name(),
+            "MissingTranslation"
+        );
+        assert_eq!(
+            WarningKind::CrossLanguageLink("x".to_string(), "y".to_string()).get_name(),
+            "CrossLanguageLink"
+        );
+        assert_eq!(
+            WarningKind::FlaggedWord("x".to_string()).get_name(),
+            "FlaggedWord"
+        );
     }
 }
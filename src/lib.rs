@@ -36,6 +36,61 @@
 //! provides the same interface, but provides [`Passage`] objects in places
 //! where usually unnecessary information is stripped out.
 //!
+//! # Features
+//! * `fs` (default) - parsing from a `Path` and writing dependency files. With
+//!   this feature disabled, tweep does no file system access of its own and
+//!   stories can only be parsed from a `String` or a `Read`, which makes it
+//!   suitable for sandboxed environments such as wasm
+//! * `fast-hash` - uses the faster FxHash algorithm rather than SipHash for
+//!   the maps tweep builds internally: the edge lists of a [`StoryGraph`], the
+//!   lookups made while running checks, and the file ids and contexts tracked
+//!   while merging files. The map of passages in a story, and every other map
+//!   in the public API, is still a standard library `HashMap` using SipHash,
+//!   so this helps with graphs and checks of very large stories but not with
+//!   passage lookups. Not DoS resistant, so only use it for trusted input
+//! * `ffi` - adds the [`ffi`] module, a C ABI for parsing a story and reading
+//!   its passages and warnings, so tweep can be embedded in game engines and
+//!   other programs written in C or C++
+//! * `full-context` - errors and warnings carry the full source of the file
+//!   they were produced from
+//! * `i18n` - adds [`Story::extract_strings`], which finds the passage text
+//!   to translate, leaving out links, macros, and code, and
+//!   [`Story::apply_translations`], which replaces it with its translations,
+//!   for gettext and other localization workflows. Implies `markup`
+//! * `issue-names` - adds `get_name` methods to the error and warning kinds
+//! * `macro-links` - finds the links created by the macros of the SugarCube
+//!   and Harlowe story formats, such as `<<goto "Passage">>`, and checks them
+//!   for dead links along with normal links. Also finds every macro
+//!   invocation, with the arguments of common macros available as
+//!   [`MacroArgs`], and the SugarCube widget definitions, for the
+//!   [`PassageDependencies`] of each passage. Audio cues are collected as
+//!   [`MediaCue`]s, which can be exported as a JSON or CSV manifest
+//! * `markup` - adds [`tokenize`], which splits passage content into text,
+//!   links, and the macro invocations of the SugarCube and Harlowe story
+//!   formats, each with its [`Span`], for syntax highlighting and macro-aware
+//!   tools
+//! * `parallel` - allows the files in a directory to be parsed in parallel
+//! * `report` - renders errors and warnings as human-readable reports with
+//!   the source code they refer to, and collects the `TODO`, `FIXME`, and
+//!   `XXX` markers in passages into a list of [`TaskMarker`]s. Implies
+//!   `full-context`
+//! * `serde` - implements `Serialize` and `Deserialize` for stories, passages,
+//!   errors, and warnings, so parsed output can be cached and reloaded without
+//!   parsing again
+//! * `stdio-server` - adds [`serve`], which answers newline-delimited JSON
+//!   requests to parse, check, rename, and complete, and the `tweep-stdio`
+//!   binary that runs it over stdin and stdout, so editors and tools in any
+//!   language can drive tweep as a subprocess
+//! * `watch` - adds [`watch`], which watches a story's files for changes and
+//!   sends each new version of the story, along with a [`StoryDiff`] from the
+//!   last one, over a channel, for hot reloading in game engines and editors.
+//!   Implies `fs`
+//! * `wasm` - lets tweep run on `wasm32-unknown-unknown`, such as in a
+//!   browser-based linter, by not reading the clock for [`ParseMetrics`]
+//!   timings, which are left at zero. Use it with default features disabled,
+//!   so that only the `String` and `Read` parsing functions are available:
+//!   `tweep = { version = "0.3", default-features = false, features = ["wasm"] }`
+//!
 //! # Examples
 //! ```
 //! use tweep::Story;
@@ -100,6 +155,20 @@
 //! [`Story`]: struct.Story.html
 //! [`StoryPassages`]: struct.StoryPassages.html
 //! [`Passage`]: struct.Passage.html
+//! [`StoryGraph`]: struct.StoryGraph.html
+//! [`serve`]: fn.serve.html
+//! [`ffi`]: ffi/index.html
+//! [`ParseMetrics`]: struct.ParseMetrics.html
+//! [`watch`]: fn.watch.html
+//! [`tokenize`]: fn.tokenize.html
+//! [`Span`]: struct.Span.html
+//! [`StoryDiff`]: struct.StoryDiff.html
+//! [`PassageDependencies`]: struct.PassageDependencies.html
+//! [`MacroArgs`]: enum.MacroArgs.html
+//! [`MediaCue`]: struct.MediaCue.html
+//! [`TaskMarker`]: struct.TaskMarker.html
+//! [`Story::extract_strings`]: struct.Story.html#method.extract_strings
+//! [`Story::apply_translations`]: struct.Story.html#method.apply_translations
 
 #![warn(missing_docs)]
 #![warn(missing_doc_code_examples)]
@@ -135,25 +204,118 @@ pub use context::Position;
 pub use context::PositionKind;
 pub use context::FullContext;
 pub use context::PartialContext;
+pub use context::Span;
+pub use context::SpanError;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+mod escape;
+pub use escape::escape_html;
+pub use escape::escape_passage_name;
+pub use escape::format_link;
+pub use escape::unescape;
+
+mod graph;
+pub use graph::StoryGraph;
+
+#[cfg(feature = "i18n")]
+mod i18n;
+#[cfg(feature = "i18n")]
+pub use i18n::TranslatableString;
+
+mod hash;
+pub(crate) use hash::FastHashMap;
+pub(crate) use hash::FastHashSet;
 
 mod issues;
+pub use issues::DiagnosticSink;
 pub use issues::Error;
 pub use issues::ErrorList;
 pub use issues::ErrorKind;
+pub use issues::IssueCategory;
+pub use issues::Lint;
+pub use issues::Severity;
+pub use issues::SinkAction;
 pub use issues::Warning;
 pub use issues::WarningKind;
 
+#[cfg(feature = "markup")]
+mod markup;
+#[cfg(feature = "markup")]
+pub use markup::tokenize;
+#[cfg(feature = "markup")]
+pub use markup::MarkupKind;
+#[cfg(feature = "markup")]
+pub use markup::MarkupToken;
+
+mod metrics;
+pub use metrics::ParseMetrics;
+
+mod options;
+pub use options::DirectoryOrder;
+pub use options::DuplicatePolicy;
+pub use options::HeaderContinuation;
+pub use options::MergeResolution;
+pub use options::MultiLineTitle;
+pub use options::ParseOptions;
+pub use options::RouteRule;
+pub(crate) use options::WarningCollector;
+
 mod output;
 pub use output::Output;
 
+#[cfg(feature = "report")]
+mod report;
+#[cfg(feature = "report")]
+pub use report::render_error;
+#[cfg(feature = "report")]
+pub use report::render_tasks;
+#[cfg(feature = "report")]
+pub use report::render_warning;
+#[cfg(feature = "report")]
+pub use report::TaskMarker;
+
+#[cfg(feature = "stdio-server")]
+mod stdio_server;
+#[cfg(feature = "stdio-server")]
+pub use stdio_server::handle_request;
+#[cfg(feature = "stdio-server")]
+pub use stdio_server::serve;
+
+#[cfg(feature = "watch")]
+mod watch;
+#[cfg(feature = "watch")]
+pub use watch::watch;
+#[cfg(feature = "watch")]
+pub use watch::StoryUpdate;
+#[cfg(feature = "watch")]
+pub use watch::StoryWatcher;
+
+mod refactor;
+pub use refactor::rename_passage_in_source;
+pub use refactor::strip_invisible_in_source;
+pub use refactor::TextEdit;
+
 mod passages;
+pub use passages::LinkKind;
+#[cfg(feature = "macro-links")]
+pub use passages::MacroArgs;
+#[cfg(feature = "macro-links")]
+pub use passages::MacroCall;
 pub use passages::Passage;
+pub use passages::PassageBuilder;
 pub use passages::PassageContent;
 pub use passages::PassageHeader;
+pub use passages::PassageKind;
+pub use passages::PassageRef;
 pub use passages::ScriptContent;
+pub use passages::SpecialContent;
 pub use passages::StoryData;
+pub use passages::StoryFormat;
 pub use passages::StoryTitle;
 pub use passages::StylesheetContent;
+pub use passages::TagColor;
 pub use passages::TwineContent;
 pub use passages::TwineLink;
 pub use passages::TwinePassage;
@@ -163,5 +325,17 @@ mod stories;
 pub use stories::CodeMap;
 #[cfg(feature = "full-context")]
 pub use stories::ContextErrorList;
+#[cfg(feature = "macro-links")]
+pub use stories::MediaCue;
+#[cfg(feature = "macro-links")]
+pub use stories::PassageDependencies;
+pub use stories::PassageDiff;
+pub use stories::SourceFile;
+pub use stories::SourceFileKind;
 pub use stories::Story;
+pub use stories::StoryBuilder;
+pub use stories::StoryDiff;
 pub use stories::StoryPassages;
+pub use stories::StoryVisitor;
+pub use stories::walk;
+pub use stories::diff;
@@ -0,0 +1,265 @@
+use crate::context::line_column;
+use crate::passages::closing_paren;
+use crate::FullContext;
+use crate::Position;
+use crate::Span;
+use crate::Story;
+use crate::StoryFormat;
+use std::ops::Range;
+
+/// The kind of a [`MarkupToken`]
+///
+/// [`MarkupToken`]: struct.MarkupToken.html
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MarkupKind {
+    /// Plain text, including any markup that is not a link or macro
+    Text,
+
+    /// A `[[...]]` link
+    Link,
+
+    /// A macro invocation, with the name of the macro as written, such as
+    /// `if` for `<<if $x>>`, `/if` for `<</if>>`, or `link-goto` for
+    /// `(link-goto: "Go", "Cave")`
+    Macro(String),
+}
+
+/// A piece of passage content produced by [`tokenize`]
+///
+/// [`tokenize`]: fn.tokenize.html
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarkupToken {
+    /// The kind of token
+    pub kind: MarkupKind,
+
+    /// The text of the token
+    pub text: String,
+
+    /// Where the token is in its file
+    pub span: Span,
+}
+
+/// Splits the given content into text, links, and the macro invocations of
+/// the given [`StoryFormat`], for syntax highlighting and other tools that
+/// need to know the shape of a passage without running it
+///
+/// SugarCube macros are written `<<name ...>>` and Harlowe macros are written
+/// `(name: ...)`. Macros are only found for the given format, so with no
+/// format the content is split into text and links. Macros are not checked
+/// against the ones the format defines, and their arguments are not parsed,
+/// except to skip over quoted strings and nested parentheses when finding the
+/// end of a Harlowe macro. A link or macro with no end is left as text.
+///
+/// # Examples
+/// ```
+/// use tweep::{tokenize, FullContext, MarkupKind, StoryFormat};
+/// let context = FullContext::from(None, "Hi <<if $x>>[[Cave]]<</if>>".to_string());
+/// let tokens = tokenize(&context, Some(StoryFormat::SugarCube));
+/// let kinds: Vec<&MarkupKind> = tokens.iter().map(|t| &t.kind).collect();
+/// assert_eq!(
+///     kinds,
+///     [
+///         &MarkupKind::Text,
+///         &MarkupKind::Macro("if".to_string()),
+///         &MarkupKind::Link,
+///         &MarkupKind::Macro("/if".to_string()),
+///     ]
+/// );
+/// assert_eq!(tokens[2].text, "[[Cave]]");
+/// assert_eq!(tokens[2].span.bytes, 12..20);
+/// ```
+///
+/// [`StoryFormat`]: enum.StoryFormat.html
+pub fn tokenize(context: &FullContext, format: Option<StoryFormat>) -> Vec<MarkupToken> {
+    let contents = context.get_contents();
+    let mut tokens = Vec::new();
+    let mut push = |kind: MarkupKind, range: Range<usize>| {
+        tokens.push(MarkupToken {
+            kind,
+            text: contents[range.clone()].to_string(),
+            span: span(context, range),
+        });
+    };
+
+    let mut text_start = 0;
+    let mut i = 0;
+    while i < contents.len() {
+        let rest = &contents[i..];
+        let found = if let Some(after) = rest.strip_prefix("[[") {
+            after.find("]]").map(|x| (MarkupKind::Link, x + 4))
+        } else if let (Some(StoryFormat::SugarCube), Some(after)) =
+            (format, rest.strip_prefix("<<"))
+        {
+            after.find(">>").map(|x| {
+                let inner = &after[..x];
+                let name_end = inner.find(char::is_whitespace).unwrap_or(inner.len());
+                (MarkupKind::Macro(inner[..name_end].to_string()), x + 4)
+            })
+        } else if let (Some(StoryFormat::Harlowe), Some(after)) = (format, rest.strip_prefix('(')) {
+            let name_len = after
+                .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+                .unwrap_or(after.len());
+            match after[name_len..].strip_prefix(':') {
+                Some(args) if name_len > 0 => closing_paren(args).map(|x| {
+                    let name = after[..name_len].to_string();
+                    (MarkupKind::Macro(name), name_len + x + 3)
+                }),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        match found {
+            Some((kind, len)) => {
+                if text_start < i {
+                    push(MarkupKind::Text, text_start..i);
+                }
+                push(kind, i..i + len);
+                i += len;
+                text_start = i;
+            }
+            None => i += rest.chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    if text_start < contents.len() {
+        push(MarkupKind::Text, text_start..contents.len());
+    }
+    tokens
+}
+
+impl Story {
+    /// Splits the content of the passage with the given name into tokens with
+    /// [`tokenize`], using the story format named in the `StoryData`. Returns
+    /// `None` if there is no such passage
+    ///
+    /// Enabled with "markup" feature
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{MarkupKind, Story};
+    /// let input = r#":: StoryData
+    /// {"ifid": "ABC", "format": "Harlowe"}
+    ///
+    /// :: Start
+    /// (set: $x to 1)Hi
+    /// "#
+    /// .to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let tokens = story.tokenize_passage("Start").unwrap();
+    /// assert_eq!(tokens[0].kind, MarkupKind::Macro("set".to_string()));
+    /// assert_eq!(tokens[0].span.start, (5, 1));
+    /// assert!(story.tokenize_passage("Missing").is_none());
+    /// ```
+    ///
+    /// [`tokenize`]: fn.tokenize.html
+    pub fn tokenize_passage(&self, name: &str) -> Option<Vec<MarkupToken>> {
+        self.passages.get(name)?;
+        let context = self.context_of(name)?;
+        let format = self.data.as_ref().and_then(|data| data.story_format());
+        match context.try_subcontext(Position::rel(2, 1)..) {
+            Ok(content) => Some(tokenize(&content, format)),
+            Err(_) => Some(Vec::new()),
+        }
+    }
+}
+
+/// Makes the span of the given byte range within the contents of the given
+/// context
+pub(crate) fn span(context: &FullContext, range: Range<usize>) -> Span {
+    let base = context.get_byte_range().start;
+    let (start, end) = (base + range.start, base + range.end - 1);
+    let position = |byte: usize| {
+        context
+            .lines()
+            .take_while(|(_, _, line)| line.start <= byte)
+            .last()
+            .map(|(number, _, line)| line_column(&Position::abs(number, byte - line.start + 1)))
+            .unwrap_or((0, 0))
+    };
+    Span {
+        file: context.get_file_name().as_ref().map(Into::into),
+        start: position(start),
+        end: position(end),
+        bytes: start..end + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str, format: Option<StoryFormat>) -> Vec<(MarkupKind, String)> {
+        let context = FullContext::from(None, input.to_string());
+        tokenize(&context, format)
+            .into_iter()
+            .map(|token| (token.kind, token.text))
+            .collect()
+    }
+
+    fn text(s: &str) -> (MarkupKind, String) {
+        (MarkupKind::Text, s.to_string())
+    }
+
+    fn link(s: &str) -> (MarkupKind, String) {
+        (MarkupKind::Link, s.to_string())
+    }
+
+    fn macro_call(name: &str, s: &str) -> (MarkupKind, String) {
+        (MarkupKind::Macro(name.to_string()), s.to_string())
+    }
+
+    #[test]
+    fn formats() {
+        let input = "<<set $x to 1>>(print: \"a)b\" + (str: 2))[[Go]]";
+        assert_eq!(
+            kinds(input, Some(StoryFormat::SugarCube)),
+            [
+                macro_call("set", "<<set $x to 1>>"),
+                text("(print: \"a)b\" + (str: 2))"),
+                link("[[Go]]"),
+            ]
+        );
+        assert_eq!(
+            kinds(input, Some(StoryFormat::Harlowe)),
+            [
+                text("<<set $x to 1>>"),
+                macro_call("print", "(print: \"a)b\" + (str: 2))"),
+                link("[[Go]]"),
+            ]
+        );
+        assert_eq!(
+            kinds(input, None),
+            [
+                text("<<set $x to 1>>(print: \"a)b\" + (str: 2))"),
+                link("[[Go]]")
+            ]
+        );
+        assert_eq!(
+            kinds("(a) (b: [[x]] <<c", Some(StoryFormat::Harlowe)),
+            [text("(a) (b: "), link("[[x]]"), text(" <<c")]
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let context =
+            FullContext::from(Some("a.tw".to_string()), ":: A\né [[B]]\n<<x>>".to_string());
+        let content = context.subcontext(Position::rel(2, 1)..);
+        let tokens = tokenize(&content, Some(StoryFormat::SugarCube));
+        assert_eq!(tokens.len(), 4);
+        let link = &tokens[1].span;
+        assert_eq!(link.file, Some("a.tw".into()));
+        assert_eq!(
+            (link.start, link.end, link.bytes.clone()),
+            ((2, 4), (2, 8), 8..13)
+        );
+        let newline = &tokens[2].span;
+        assert_eq!((newline.start, newline.end), ((2, 9), (2, 9)));
+        let call = &tokens[3].span;
+        assert_eq!(
+            (call.start, call.end, call.bytes.clone()),
+            ((3, 1), (3, 5), 14..19)
+        );
+    }
+}
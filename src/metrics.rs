@@ -0,0 +1,207 @@
+use crate::PassageContent;
+#[cfg(feature = "fs")]
+use crate::SourceFile;
+use crate::StoryPassages;
+use std::time::Duration;
+#[cfg(not(feature = "wasm"))]
+use std::time::Instant;
+
+/// Counts and timings collected while parsing a story
+///
+/// Metrics are only collected by the parse functions that take
+/// [`ParseOptions`], and only if metrics are turned on in the options. They
+/// are attached to the resulting [`Output`].
+///
+/// # Examples
+/// ```
+/// use tweep::{ParseOptions, StoryPassages};
+/// let options = ParseOptions::new().with_metrics(true);
+/// let input = ":: Start\n[[End]]\n\n:: End\nBye\n".to_string();
+/// let out = StoryPassages::from_string_with_options(input.clone(), &options);
+/// let metrics = out.get_metrics().unwrap();
+/// assert_eq!(metrics.passages, 2);
+/// assert_eq!(metrics.links, 1);
+/// assert_eq!(metrics.bytes, input.len());
+/// ```
+///
+/// [`ParseOptions`]: struct.ParseOptions.html
+/// [`Output`]: struct.Output.html
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ParseMetrics {
+    /// The number of files that were read and parsed. This is `0` when parsing
+    /// from a `String` or a reader
+    pub files: usize,
+
+    /// The number of passages in the story, including special passages
+    pub passages: usize,
+
+    /// The number of links in the passages of the story
+    pub links: usize,
+
+    /// The number of bytes of input that were parsed
+    pub bytes: usize,
+
+    /// The time spent reading and parsing the input, including merging the
+    /// files of a directory. Always zero with the "wasm" feature enabled
+    pub parse_time: Duration,
+
+    /// The time spent checking the parsed story for dead links and other
+    /// problems. Always zero with the "wasm" feature enabled
+    pub check_time: Duration,
+
+    /// The highest [`complexity`] of any normal passage in the story, counting
+    /// the links and branches of macros if the story format is known and the
+    /// "macro-links" feature is enabled
+    ///
+    /// [`complexity`]: struct.TwineContent.html#method.complexity
+    pub max_complexity: usize,
+}
+
+impl ParseMetrics {
+    /// Returns the total time spent over all phases of the parse
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use tweep::ParseMetrics;
+    /// let metrics = ParseMetrics {
+    ///     parse_time: Duration::from_millis(3),
+    ///     check_time: Duration::from_millis(2),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(metrics.total_time(), Duration::from_millis(5));
+    /// ```
+    pub fn total_time(&self) -> Duration {
+        self.parse_time + self.check_time
+    }
+
+    /// Sets the file and byte counts from the files that were parsed out of
+    /// the given sources
+    #[cfg(feature = "fs")]
+    pub(crate) fn count_sources(&mut self, sources: &[SourceFile]) {
+        let parsed: Vec<&SourceFile> = sources.iter().filter(|s| s.is_parsed()).collect();
+        self.files = parsed.len();
+        self.bytes = parsed
+            .iter()
+            .filter_map(|source| std::fs::metadata(&source.path).ok())
+            .map(|metadata| metadata.len() as usize)
+            .sum();
+    }
+
+    /// Sets the passage and link counts from the given story
+    pub(crate) fn count(&mut self, story: &StoryPassages) {
+        self.passages = story.passages.len()
+            + story.scripts.len()
+            + story.stylesheets.len()
+            + [
+                &story.title,
+                &story.data,
+                &story.author,
+                &story.subtitle,
+                &story.menu,
+                &story.settings,
+            ]
+            .iter()
+            .filter(|passage| passage.is_some())
+            .count();
+        let format = story
+            .data
+            .as_ref()
+            .and_then(|passage| match &passage.content {
+                PassageContent::StoryData(Some(data)) => data.story_format(),
+                _ => None,
+            });
+        self.max_complexity = story
+            .passages
+            .values()
+            .map(|passage| match &passage.content {
+                PassageContent::Normal(twine) => twine.complexity(format),
+                _ => 0,
+            })
+            .max()
+            .unwrap_or(0);
+        self.links = story
+            .passages
+            .values()
+            .map(|passage| match &passage.content {
+                PassageContent::Normal(twine) => twine.get_links().len(),
+                _ => 0,
+            })
+            .sum();
+    }
+}
+
+/// Measures the time spent in a phase of a parse. With the "wasm" feature
+/// enabled, no time is measured, since there is no clock on
+/// `wasm32-unknown-unknown` and asking for the time panics
+pub(crate) struct Timer {
+    #[cfg(not(feature = "wasm"))]
+    start: Instant,
+}
+
+impl Timer {
+    /// Starts a new timer
+    pub(crate) fn start() -> Self {
+        Timer {
+            #[cfg(not(feature = "wasm"))]
+            start: Instant::now(),
+        }
+    }
+
+    /// Returns the time since the timer was started
+    #[cfg(not(feature = "wasm"))]
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Returns zero, since no time is measured
+    #[cfg(feature = "wasm")]
+    pub(crate) fn elapsed(&self) -> Duration {
+        Duration::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count() {
+        let input = r#":: StoryTitle
+Title
+
+:: Start
+[[A]] [[B]]
+
+:: A
+[[Start]]
+
+:: B
+
+:: StoryAuthor
+Me
+
+:: Script [script]
+alert("[[Not a link]]");
+"#
+        .to_string();
+        let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+        let mut metrics = ParseMetrics::default();
+        metrics.count(&story);
+        assert_eq!(metrics.passages, 6);
+        assert_eq!(metrics.links, 3);
+        assert_eq!(metrics.max_complexity, 2);
+        assert_eq!(metrics.files, 0);
+        assert_eq!(metrics.total_time(), Duration::default());
+    }
+
+    #[test]
+    #[cfg(feature = "wasm")]
+    fn no_clock() {
+        let options = crate::ParseOptions::new().with_metrics(true);
+        let out = StoryPassages::from_string_with_options(":: Start\n".to_string(), &options);
+        let metrics = out.get_metrics().unwrap();
+        assert_eq!(metrics.passages, 1);
+        assert_eq!(metrics.total_time(), Duration::default());
+    }
+}
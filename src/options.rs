@@ -0,0 +1,1454 @@
+use crate::issues::truncate_warnings;
+#[cfg(feature = "watch")]
+use crate::watch::ParseCache;
+use crate::Context;
+use crate::DiagnosticSink;
+use crate::IssueCategory;
+use crate::Lint;
+use crate::Passage;
+use crate::PassageHeader;
+use crate::Severity;
+use crate::SinkAction;
+use crate::Warning;
+use crate::WarningKind;
+use std::cmp::Ordering as CmpOrdering;
+use std::mem::Discriminant;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use unicode_normalization::UnicodeNormalization;
+
+/// The order in which the entries of a directory are parsed and merged
+///
+/// The order decides which file's `StoryTitle` and `StoryData` are kept when
+/// there are duplicates, and the order of the resulting warnings.
+///
+/// # Examples
+/// ```
+/// use tweep::{DirectoryOrder, ParseOptions};
+/// let options = ParseOptions::new()
+///     .with_directory_order(DirectoryOrder::Custom(|a, b| b.cmp(a)));
+/// assert!(matches!(options.directory_order(), DirectoryOrder::Custom(_)));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub enum DirectoryOrder {
+    /// Sorted by file name, comparing the raw bytes of the names. This is the
+    /// same on every platform
+    Name,
+
+    /// The order in which the filesystem lists the entries. This can differ
+    /// between platforms and filesystems
+    Filesystem,
+
+    /// Sorted by the given function, which is passed the full path of each
+    /// entry
+    Custom(fn(&Path, &Path) -> CmpOrdering),
+}
+
+impl DirectoryOrder {
+    /// Sorts the given directory entries into this order
+    #[cfg_attr(not(feature = "fs"), allow(dead_code))]
+    pub(crate) fn sort<T, F: Fn(&T) -> &Path>(&self, entries: &mut [T], path: F) {
+        match self {
+            DirectoryOrder::Name => {
+                entries.sort_by(|a, b| path(a).file_name().cmp(&path(b).file_name()))
+            }
+            DirectoryOrder::Filesystem => (),
+            DirectoryOrder::Custom(compare) => entries.sort_by(|a, b| compare(path(a), path(b))),
+        }
+    }
+}
+
+/// How a normal passage is resolved when it has the same name as one that was
+/// parsed before it, either earlier in the same file or in a file that was
+/// merged before it
+///
+/// # Examples
+/// ```
+/// use tweep::{DuplicatePolicy, ParseOptions, PassageContent, StoryPassages};
+/// let options = ParseOptions::new().with_duplicate_policy(DuplicatePolicy::KeepLast);
+/// let input = ":: Start\nFirst\n\n:: Start\nSecond\n".to_string();
+/// let out = StoryPassages::from_string_with_options(input, &options);
+/// let story = out.take().0.ok().unwrap();
+/// if let PassageContent::Normal(twine) = &story.passages["Start"].content {
+///     assert_eq!(twine.content, "Second\n");
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DuplicatePolicy {
+    /// The first passage is kept and the later one is dropped with a
+    /// [`DuplicatePassage`] warning
+    ///
+    /// [`DuplicatePassage`]: enum.WarningKind.html#variant.DuplicatePassage
+    #[default]
+    KeepFirst,
+
+    /// The later passage replaces the first one, which is dropped with a
+    /// [`DuplicatePassage`] warning
+    ///
+    /// [`DuplicatePassage`]: enum.WarningKind.html#variant.DuplicatePassage
+    KeepLast,
+
+    /// The content of the later passage is appended to the first one, along
+    /// with any tags and metadata fields that the first one does not have
+    Merge,
+
+    /// The parse fails with a [`DuplicatePassage`] error
+    ///
+    /// [`DuplicatePassage`]: enum.ErrorKind.html#variant.DuplicatePassage
+    Error,
+}
+
+/// How a duplicate passage is resolved by a closure passed to
+/// [`StoryPassages::merge_from_with`], which is given the existing passage and
+/// the incoming one
+///
+/// [`StoryPassages::merge_from_with`]: struct.StoryPassages.html#method.merge_from_with
+#[derive(Clone, Debug)]
+pub enum MergeResolution {
+    /// The existing passage is kept and the incoming one is dropped
+    KeepExisting,
+
+    /// The incoming passage replaces the existing one
+    KeepIncoming,
+
+    /// Both passages are replaced by the given one, such as a `StoryData`
+    /// whose fields are combined from the two
+    Replace(Box<Passage>),
+}
+
+/// How the content of a `StoryTitle` passage that spans more than one line
+/// is turned into the title of the story. A multi-line title produces a
+/// [`MultiLineStoryTitle`] warning whichever is chosen
+///
+/// # Examples
+/// ```
+/// use tweep::{MultiLineTitle, ParseOptions, PassageContent, StoryPassages};
+/// let options = ParseOptions::new().with_multi_line_title(MultiLineTitle::Join);
+/// let input = ":: StoryTitle\nThe Long\n  Dark Night\n\n:: Start\n".to_string();
+/// let story = StoryPassages::from_string_with_options(input, &options).take().0.ok().unwrap();
+/// if let PassageContent::StoryTitle(title) = &story.title.unwrap().content {
+///     assert_eq!(title.title, "The Long Dark Night");
+/// }
+/// ```
+///
+/// [`MultiLineStoryTitle`]: enum.WarningKind.html#variant.MultiLineStoryTitle
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MultiLineTitle {
+    /// The content is kept as it is, newlines included
+    #[default]
+    Keep,
+
+    /// Only the first line that is not blank is kept
+    FirstLine,
+
+    /// The lines that are not blank are joined with a single space
+    Join,
+}
+
+/// How a passage header that ends with a backslash is handled. Allowing the
+/// header to continue onto the next line lets a long list of tags or metadata
+/// be wrapped, such as in generated files
+///
+/// # Examples
+/// ```
+/// use tweep::{HeaderContinuation, ParseOptions, StoryPassages};
+/// let input = ":: Start [a b \\\n  c d] \\\n  {\"position\": \"100,200\"}\nHello\n".to_string();
+/// let options = ParseOptions::new().with_header_continuation(HeaderContinuation::Allow);
+/// let story = StoryPassages::from_string_with_options(input.clone(), &options).take().0.ok().unwrap();
+/// let header = &story.passages["Start"].header;
+/// assert_eq!(header.tags, ["a", "b", "c", "d"]);
+/// assert_eq!(header.position(), Some((100.0, 200.0)));
+///
+/// let options = ParseOptions::new().with_header_continuation(HeaderContinuation::Reject);
+/// assert!(StoryPassages::from_string_with_options(input, &options).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum HeaderContinuation {
+    /// The header ends at the end of its line, as in the Twee 3
+    /// specification, and a backslash at the end is part of it
+    #[default]
+    Ignore,
+
+    /// A header line that ends with a backslash continues onto the next line.
+    /// The backslash and the line break are read as whitespace, and the spans
+    /// of the header point at the lines they are on
+    Allow,
+
+    /// A header line that ends with a backslash fails the parse with a
+    /// [`ContinuedHeader`] error
+    ///
+    /// [`ContinuedHeader`]: enum.ErrorKind.html#variant.ContinuedHeader
+    Reject,
+}
+
+/// A rule that matches passages to route into a bucket of a
+/// [`StoryPassages`] rather than its main passage map
+///
+/// # Examples
+/// ```
+/// use tweep::{FullContext, PassageHeader, RouteRule};
+/// let context = FullContext::from(None, ":: Notes: Chapter 1 [draft]".to_string());
+/// let header = PassageHeader::parse(context).take().0.ok().unwrap();
+/// assert!(RouteRule::Tag("draft".to_string()).matches(&header));
+/// assert!(RouteRule::Name("Notes:*".to_string()).matches(&header));
+/// assert!(!RouteRule::Name("Notes".to_string()).matches(&header));
+/// ```
+///
+/// [`StoryPassages`]: struct.StoryPassages.html
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RouteRule {
+    /// Matches passages that have the given tag
+    Tag(String),
+
+    /// Matches passages whose whole name matches the given pattern, in which
+    /// `*` matches any run of characters
+    Name(String),
+}
+
+impl RouteRule {
+    /// Returns `true` if the passage with the given header matches this rule
+    pub fn matches(&self, header: &PassageHeader) -> bool {
+        match self {
+            RouteRule::Tag(tag) => header.has_tag(tag),
+            RouteRule::Name(pattern) => wildcard_match(pattern, &header.name),
+        }
+    }
+}
+
+/// Returns `true` if `name` matches `pattern`, in which `*` matches any run of
+/// characters and everything else matches itself
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let mut rest = match name.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts: Vec<&str> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some(split) => split,
+        // No wildcards, so the whole name must match
+        None => return rest.is_empty(),
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(x) => rest = &rest[x + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Options that control how a story is parsed
+///
+/// The default options match the behavior of the parse functions that do not
+/// take options.
+///
+/// # Examples
+/// ```
+/// use tweep::ParseOptions;
+/// let options = ParseOptions::new().with_skip_unreadable(true);
+/// assert!(options.skip_unreadable());
+/// ```
+#[derive(Clone)]
+pub struct ParseOptions {
+    /// Whether to skip files that cannot be read rather than failing the parse
+    skip_unreadable: bool,
+
+    /// The maximum number of warnings to keep, if any
+    max_warnings: Option<usize>,
+
+    /// Whether to parse the files in a directory in parallel
+    #[cfg(feature = "parallel")]
+    parallel: bool,
+
+    /// The sink that receives warnings as they are produced, if any
+    sink: Option<Arc<dyn DiagnosticSink>>,
+
+    /// The lints to run on each passage after the built-in checks
+    lints: Vec<Arc<dyn Lint>>,
+
+    /// File names in order of precedence for special passages
+    special_passage_precedence: Vec<String>,
+
+    /// File extensions, without the leading `.`, of the files to parse when
+    /// scanning a directory
+    extensions: Vec<String>,
+
+    /// Whether to parse the subdirectories of a directory
+    recursive: bool,
+
+    /// Whether any warning makes the parse fail
+    warnings_as_errors: bool,
+
+    /// The kinds of warnings to drop
+    suppressed_warnings: Vec<Discriminant<WarningKind>>,
+
+    /// The categories of warnings to drop
+    suppressed_categories: Vec<IssueCategory>,
+
+    /// The order in which directory entries are parsed and merged
+    directory_order: DirectoryOrder,
+
+    /// How passages with duplicate names are resolved
+    duplicate_policy: DuplicatePolicy,
+
+    /// Severities that override the defaults of the given kinds of warnings
+    severities: Vec<(Discriminant<WarningKind>, Severity)>,
+
+    /// Whether to pull in the files listed in `StoryIncludes` passages
+    includes: bool,
+
+    /// Whether to compare passage names in Unicode normalization form C
+    normalized_names: bool,
+
+    /// Whether to ignore differences in whitespace when comparing passage
+    /// names
+    collapsed_whitespace: bool,
+
+    /// Whether to collect metrics while parsing
+    metrics: bool,
+
+    /// Whether to remove comments from the content of passages
+    stripped_comments: bool,
+
+    /// Whether to check the targets of image links for dead links
+    checked_image_links: bool,
+
+    /// Whether to warn when no ending can be reached from the start passage
+    checked_endings: bool,
+
+    /// The highest branching complexity a passage can have without a warning
+    max_complexity: Option<usize>,
+
+    /// The languages of a story that is translated into more than one
+    languages: Vec<String>,
+
+    /// The words that are flagged wherever they appear in passage text
+    flagged_words: Vec<String>,
+
+    /// Whether a passage that fails to parse is replaced by a placeholder
+    recovery: bool,
+
+    /// Whether to decode input that is not valid UTF-8 instead of failing
+    lossy_encoding: bool,
+
+    /// How a `StoryTitle` that spans more than one line is normalized
+    multi_line_title: MultiLineTitle,
+
+    /// How a passage header that ends with a backslash is handled
+    header_continuation: HeaderContinuation,
+
+    /// The rules for routing passages into buckets, with the bucket names
+    routes: Vec<(RouteRule, String)>,
+
+    /// The name of the passage that separates the stories of an input that
+    /// holds more than one, if any
+    story_separator: Option<String>,
+
+    /// The cache of parsed files shared with a story watcher, if any
+    #[cfg(feature = "watch")]
+    cache: Option<Arc<ParseCache>>,
+
+    /// The flag that is set when a warning that makes the parse fail is
+    /// handled, if the parse is tracking them
+    fatal: Option<Arc<AtomicBool>>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            skip_unreadable: false,
+            max_warnings: None,
+            #[cfg(feature = "parallel")]
+            parallel: false,
+            sink: None,
+            lints: Vec::new(),
+            special_passage_precedence: Vec::new(),
+            extensions: vec!["tw".to_string(), "twee".to_string()],
+            recursive: false,
+            warnings_as_errors: false,
+            suppressed_warnings: Vec::new(),
+            suppressed_categories: Vec::new(),
+            directory_order: DirectoryOrder::Name,
+            duplicate_policy: DuplicatePolicy::KeepFirst,
+            severities: Vec::new(),
+            includes: false,
+            normalized_names: false,
+            collapsed_whitespace: false,
+            metrics: false,
+            stripped_comments: false,
+            checked_image_links: false,
+            checked_endings: false,
+            max_complexity: None,
+            languages: Vec::new(),
+            flagged_words: Vec::new(),
+            recovery: false,
+            lossy_encoding: false,
+            multi_line_title: MultiLineTitle::Keep,
+            header_continuation: HeaderContinuation::Ignore,
+            routes: Vec::new(),
+            story_separator: None,
+            #[cfg(feature = "watch")]
+            cache: None,
+            fatal: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for ParseOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("ParseOptions");
+        s.field("skip_unreadable", &self.skip_unreadable);
+        s.field("max_warnings", &self.max_warnings);
+        #[cfg(feature = "parallel")]
+        s.field("parallel", &self.parallel);
+        s.field("sink", &self.sink.is_some());
+        s.field("lints", &self.lints.len());
+        s.field(
+            "special_passage_precedence",
+            &self.special_passage_precedence,
+        );
+        s.field("extensions", &self.extensions);
+        s.field("recursive", &self.recursive);
+        s.field("warnings_as_errors", &self.warnings_as_errors);
+        s.field("suppressed_warnings", &self.suppressed_warnings);
+        s.field("suppressed_categories", &self.suppressed_categories);
+        s.field("directory_order", &self.directory_order);
+        s.field("duplicate_policy", &self.duplicate_policy);
+        s.field("severities", &self.severities);
+        s.field("includes", &self.includes);
+        s.field("normalized_names", &self.normalized_names);
+        s.field("collapsed_whitespace", &self.collapsed_whitespace);
+        s.field("metrics", &self.metrics);
+        s.field("stripped_comments", &self.stripped_comments);
+        s.field("checked_image_links", &self.checked_image_links);
+        s.field("checked_endings", &self.checked_endings);
+        s.field("max_complexity", &self.max_complexity);
+        s.field("languages", &self.languages);
+        s.field("flagged_words", &self.flagged_words);
+        s.field("recovery", &self.recovery);
+        s.field("lossy_encoding", &self.lossy_encoding);
+        s.field("multi_line_title", &self.multi_line_title);
+        s.field("header_continuation", &self.header_continuation);
+        s.field("routes", &self.routes);
+        s.field("story_separator", &self.story_separator);
+        #[cfg(feature = "watch")]
+        s.field("cache", &self.cache.is_some());
+        s.field("fatal", &self.fatal.is_some());
+        s.finish()
+    }
+}
+
+impl ParseOptions {
+    /// Creates a new `ParseOptions` with the default settings
+    pub fn new() -> Self {
+        ParseOptions::default()
+    }
+
+    /// Builder method to set whether files that cannot be read should be
+    /// skipped, producing an [`UnreadableFile`] warning, rather than failing
+    /// the whole parse with a [`BadInputPath`] error. Defaults to `false`
+    ///
+    /// [`UnreadableFile`]: enum.WarningKind.html#variant.UnreadableFile
+    /// [`BadInputPath`]: enum.ErrorKind.html#variant.BadInputPath
+    pub fn with_skip_unreadable(mut self, skip: bool) -> Self {
+        self.skip_unreadable = skip;
+        self
+    }
+
+    /// Returns `true` if files that cannot be read will be skipped
+    pub fn skip_unreadable(&self) -> bool {
+        self.skip_unreadable
+    }
+
+    /// Builder method to set the maximum number of warnings to keep. Any
+    /// further warnings are dropped and replaced with a single
+    /// [`WarningsSuppressed`] warning containing the number of warnings that
+    /// were dropped. Once the maximum is reached while parsing, further
+    /// warnings are only counted rather than kept, so a pathological input
+    /// doesn't use up memory with them. Defaults to no limit
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::ParseOptions;
+    /// let options = ParseOptions::new().with_max_warnings(100);
+    /// assert_eq!(options.max_warnings(), Some(100));
+    /// ```
+    ///
+    /// [`WarningsSuppressed`]: enum.WarningKind.html#variant.WarningsSuppressed
+    pub fn with_max_warnings(mut self, max: usize) -> Self {
+        self.max_warnings = Some(max);
+        self
+    }
+
+    /// Returns the maximum number of warnings to keep, if any
+    pub fn max_warnings(&self) -> Option<usize> {
+        self.max_warnings
+    }
+
+    /// Builder method to set whether the files in a directory should be parsed
+    /// in parallel. The parsed files are always merged in the same order as
+    /// they would be when parsing serially, so the output is the same either
+    /// way. Defaults to `false`
+    ///
+    /// Enabled with "parallel" feature
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::ParseOptions;
+    /// let options = ParseOptions::new().with_parallel(true);
+    /// assert!(options.parallel());
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn with_parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Returns `true` if the files in a directory will be parsed in parallel
+    ///
+    /// Enabled with "parallel" feature
+    #[cfg(feature = "parallel")]
+    pub fn parallel(&self) -> bool {
+        self.parallel
+    }
+
+    /// Builder method to set a [`DiagnosticSink`] that receives each
+    /// [`Warning`] as it is produced. Warnings passed to the sink are not
+    /// included in the warnings of the [`Output`]
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use tweep::{ParseOptions, SinkAction, Warning};
+    /// let options = ParseOptions::new().with_sink(Arc::new(|warning: Warning| {
+    ///     eprintln!("{}", warning);
+    ///     SinkAction::Continue
+    /// }));
+    /// assert!(options.sink().is_some());
+    /// ```
+    ///
+    /// [`DiagnosticSink`]: trait.DiagnosticSink.html
+    /// [`Warning`]: struct.Warning.html
+    /// [`Output`]: struct.Output.html
+    pub fn with_sink(mut self, sink: Arc<dyn DiagnosticSink>) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+
+    /// Returns the configured [`DiagnosticSink`], if any
+    ///
+    /// [`DiagnosticSink`]: trait.DiagnosticSink.html
+    pub fn sink(&self) -> Option<&Arc<dyn DiagnosticSink>> {
+        self.sink.as_ref()
+    }
+
+    /// Builder method to register a [`Lint`] that is run on each normal
+    /// passage after the built-in checks. Lints are run in the order they are
+    /// registered
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use tweep::{FullContext, ParseOptions, PassageHeader, Story, TwineContent, Warning, WarningKind};
+    /// let options = ParseOptions::new().with_lint(Arc::new(
+    ///     |header: &PassageHeader, _: &TwineContent, context: Option<&FullContext>| {
+    ///         if header.tags.is_empty() {
+    ///             let message = format!("{} has no tags", header.name);
+    ///             let kind = WarningKind::Lint("Untagged".to_string(), message);
+    ///             vec![Warning::new(kind, context.cloned())]
+    ///         } else {
+    ///             Vec::new()
+    ///         }
+    ///     },
+    /// ));
+    /// let input = ":: Start\nHello\n".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let warnings = story.validate_with_options(&options);
+    /// assert!(warnings.iter().any(|w| w.to_string().contains("Start has no tags")));
+    /// ```
+    ///
+    /// [`Lint`]: trait.Lint.html
+    pub fn with_lint(mut self, lint: Arc<dyn Lint>) -> Self {
+        self.lints.push(lint);
+        self
+    }
+
+    /// Returns the registered [`Lint`]s
+    ///
+    /// [`Lint`]: trait.Lint.html
+    pub fn lints(&self) -> &[Arc<dyn Lint>] {
+        &self.lints
+    }
+
+    /// Builder method to set the file names, in order of precedence, that
+    /// decide which `StoryTitle` and `StoryData` passage is kept when more than
+    /// one is found while merging files. A passage from a file earlier in the
+    /// list wins over one from a file later in the list or not in the list at
+    /// all. Otherwise, the first one found is kept. Defaults to an empty list
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::ParseOptions;
+    /// let options = ParseOptions::new()
+    ///     .with_special_passage_precedence(vec!["main.twee".to_string()]);
+    /// assert_eq!(options.special_passage_precedence(), ["main.twee"]);
+    /// ```
+    pub fn with_special_passage_precedence(mut self, files: Vec<String>) -> Self {
+        self.special_passage_precedence = files;
+        self
+    }
+
+    /// Returns the file names, in order of precedence, used to decide which
+    /// special passage is kept when merging files
+    pub fn special_passage_precedence(&self) -> &[String] {
+        &self.special_passage_precedence
+    }
+
+    /// Builder method to set the file extensions, without the leading `.`, of
+    /// the files to parse when scanning a directory. Files with any other
+    /// extension are skipped. Defaults to `tw` and `twee`
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::ParseOptions;
+    /// assert_eq!(ParseOptions::new().extensions(), ["tw", "twee"]);
+    /// let options = ParseOptions::new().with_extensions(vec!["txt".to_string()]);
+    /// assert_eq!(options.extensions(), ["txt"]);
+    /// ```
+    pub fn with_extensions(mut self, extensions: Vec<String>) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Returns the file extensions of the files to parse when scanning a
+    /// directory
+    pub fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    /// Builder method to set whether the subdirectories of a directory should
+    /// be scanned as well. Defaults to `false`, in which case subdirectories
+    /// are skipped
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::ParseOptions;
+    /// let options = ParseOptions::new().with_recursive(true);
+    /// assert!(options.recursive());
+    /// ```
+    pub fn with_recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// Returns `true` if subdirectories will be scanned
+    pub fn recursive(&self) -> bool {
+        self.recursive
+    }
+
+    /// Builder method to set whether warnings should be treated as errors. If
+    /// so, a parse that produces any warning with a [`Severity`] of `Warning`
+    /// or `Deny`, including any passed to a [`DiagnosticSink`], fails with a
+    /// [`WarningsAsErrors`] error. The warnings are still reported as usual.
+    /// Suppressed warnings and `Info` warnings are not counted. Defaults to
+    /// `false`
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{ParseOptions, Story};
+    /// let options = ParseOptions::new().with_warnings_as_errors(true);
+    /// let input = ":: Start\n[[Nowhere".to_string();
+    /// let out = Story::from_string_with_options(input, &options);
+    /// assert!(out.is_err());
+    /// assert!(out.has_warnings());
+    /// ```
+    ///
+    /// [`Severity`]: enum.Severity.html
+    /// [`DiagnosticSink`]: trait.DiagnosticSink.html
+    /// [`WarningsAsErrors`]: enum.ErrorKind.html#variant.WarningsAsErrors
+    pub fn with_warnings_as_errors(mut self, warnings_as_errors: bool) -> Self {
+        self.warnings_as_errors = warnings_as_errors;
+        self
+    }
+
+    /// Returns `true` if warnings will be treated as errors
+    pub fn warnings_as_errors(&self) -> bool {
+        self.warnings_as_errors
+    }
+
+    /// Builder method to suppress every warning of the same kind as the given
+    /// [`WarningKind`]. Only the variant is compared, so any contents of the
+    /// given `WarningKind` are ignored. Suppressed warnings are dropped before
+    /// they reach the [`Output`] or a [`DiagnosticSink`]
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{ParseOptions, WarningKind};
+    /// let options = ParseOptions::new()
+    ///     .with_suppressed_warning(WarningKind::DeadLink(String::new()));
+    /// assert!(options.is_suppressed(&WarningKind::DeadLink("Elsewhere".to_string())));
+    /// assert!(!options.is_suppressed(&WarningKind::MissingStoryTitle));
+    /// ```
+    ///
+    /// [`WarningKind`]: enum.WarningKind.html
+    /// [`Output`]: struct.Output.html
+    /// [`DiagnosticSink`]: trait.DiagnosticSink.html
+    pub fn with_suppressed_warning(mut self, kind: WarningKind) -> Self {
+        self.suppressed_warnings.push(std::mem::discriminant(&kind));
+        self
+    }
+
+    /// Builder method to suppress every warning whose [`WarningKind`] belongs
+    /// to the given [`IssueCategory`], including kinds of warnings added in
+    /// later versions of tweep
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{IssueCategory, ParseOptions, WarningKind};
+    /// let options = ParseOptions::new().with_suppressed_category(IssueCategory::Style);
+    /// assert!(options.is_suppressed(&WarningKind::WhitespaceInLink));
+    /// assert!(!options.is_suppressed(&WarningKind::UnclosedLink));
+    /// ```
+    ///
+    /// [`WarningKind`]: enum.WarningKind.html
+    /// [`IssueCategory`]: enum.IssueCategory.html
+    pub fn with_suppressed_category(mut self, category: IssueCategory) -> Self {
+        self.suppressed_categories.push(category);
+        self
+    }
+
+    /// Returns `true` if warnings of the same kind as the given
+    /// [`WarningKind`] are suppressed, either by kind or by category
+    ///
+    /// [`WarningKind`]: enum.WarningKind.html
+    pub fn is_suppressed(&self, kind: &WarningKind) -> bool {
+        self.suppressed_warnings
+            .contains(&std::mem::discriminant(kind))
+            || self.suppressed_categories.contains(&kind.category())
+    }
+
+    /// Builder method to set the [`DirectoryOrder`] in which the entries of a
+    /// directory are parsed and merged. Defaults to [`DirectoryOrder::Name`]
+    ///
+    /// [`DirectoryOrder`]: enum.DirectoryOrder.html
+    /// [`DirectoryOrder::Name`]: enum.DirectoryOrder.html#variant.Name
+    pub fn with_directory_order(mut self, order: DirectoryOrder) -> Self {
+        self.directory_order = order;
+        self
+    }
+
+    /// Returns the order in which the entries of a directory are parsed and
+    /// merged
+    pub fn directory_order(&self) -> DirectoryOrder {
+        self.directory_order
+    }
+
+    /// Builder method to set the [`DuplicatePolicy`] used when a normal
+    /// passage has the same name as one parsed before it. Defaults to
+    /// [`DuplicatePolicy::KeepFirst`]
+    ///
+    /// [`DuplicatePolicy`]: enum.DuplicatePolicy.html
+    /// [`DuplicatePolicy::KeepFirst`]: enum.DuplicatePolicy.html#variant.KeepFirst
+    pub fn with_duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = policy;
+        self
+    }
+
+    /// Returns how passages with duplicate names are resolved
+    pub fn duplicate_policy(&self) -> DuplicatePolicy {
+        self.duplicate_policy
+    }
+
+    /// Builder method to override the [`Severity`] of every warning of the
+    /// same kind as the given [`WarningKind`]. Only the variant is compared,
+    /// so any contents of the given `WarningKind` are ignored
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{ParseOptions, Severity, Story, WarningKind};
+    /// let options = ParseOptions::new()
+    ///     .with_severity(WarningKind::UnclosedLink, Severity::Deny);
+    /// assert_eq!(options.severity(&WarningKind::UnclosedLink), Severity::Deny);
+    /// let input = ":: Start\n[[Nowhere".to_string();
+    /// assert!(Story::from_string_with_options(input, &options).is_err());
+    /// ```
+    ///
+    /// [`Severity`]: enum.Severity.html
+    /// [`WarningKind`]: enum.WarningKind.html
+    pub fn with_severity(mut self, kind: WarningKind, severity: Severity) -> Self {
+        let discriminant = std::mem::discriminant(&kind);
+        self.severities.retain(|(d, _)| *d != discriminant);
+        self.severities.push((discriminant, severity));
+        self
+    }
+
+    /// Builder method to deny every warning of the same kind as the given
+    /// [`WarningKind`], making any parse that produces one fail. Shorthand
+    /// for `with_severity(kind, Severity::Deny)`
+    ///
+    /// [`WarningKind`]: enum.WarningKind.html
+    pub fn with_denied_warning(self, kind: WarningKind) -> Self {
+        self.with_severity(kind, Severity::Deny)
+    }
+
+    /// Returns the [`Severity`] of warnings of the same kind as the given
+    /// [`WarningKind`], taking any overrides into account
+    ///
+    /// [`Severity`]: enum.Severity.html
+    /// [`WarningKind`]: enum.WarningKind.html
+    pub fn severity(&self, kind: &WarningKind) -> Severity {
+        let discriminant = std::mem::discriminant(kind);
+        self.severities
+            .iter()
+            .find(|(d, _)| *d == discriminant)
+            .map(|(_, severity)| *severity)
+            .unwrap_or_else(|| kind.severity())
+    }
+
+    /// Builder method to set whether a `StoryIncludes` passage in a parsed file
+    /// pulls in other files. Each non-empty line of the passage is the path of
+    /// a file to include, relative to the directory of the file containing the
+    /// passage. Included files are parsed and merged as if they had been
+    /// passed alongside the file, and may include further files. The
+    /// `StoryIncludes` passage itself is removed from the story. Defaults to
+    /// `false`, in which case `StoryIncludes` is a normal passage
+    ///
+    /// Only applies when parsing from a `Path`
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::ParseOptions;
+    /// let options = ParseOptions::new().with_includes(true);
+    /// assert!(options.includes());
+    /// ```
+    pub fn with_includes(mut self, includes: bool) -> Self {
+        self.includes = includes;
+        self
+    }
+
+    /// Returns `true` if `StoryIncludes` passages pull in other files
+    pub fn includes(&self) -> bool {
+        self.includes
+    }
+
+    /// Builder method to set whether link targets are matched to passage names
+    /// after normalizing both to Unicode normalization form C, so that names
+    /// that look the same but are encoded differently still match. Defaults
+    /// to `false`
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::ParseOptions;
+    /// let options = ParseOptions::new().with_normalized_names(true);
+    /// assert_eq!(options.name_key("Cafe\u{301}"), options.name_key("Caf\u{e9}"));
+    /// ```
+    pub fn with_normalized_names(mut self, normalized: bool) -> Self {
+        self.normalized_names = normalized;
+        self
+    }
+
+    /// Returns `true` if passage names are compared after normalization
+    pub fn normalized_names(&self) -> bool {
+        self.normalized_names
+    }
+
+    /// Builder method to set whether link targets are matched to passage names
+    /// ignoring leading and trailing whitespace, and treating any run of
+    /// whitespace inside a name as a single space. Defaults to `false`
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::ParseOptions;
+    /// let options = ParseOptions::new().with_collapsed_whitespace(true);
+    /// assert_eq!(options.name_key(" The \t End "), "The End");
+    /// ```
+    pub fn with_collapsed_whitespace(mut self, collapsed: bool) -> Self {
+        self.collapsed_whitespace = collapsed;
+        self
+    }
+
+    /// Returns `true` if differences in whitespace are ignored when passage
+    /// names are compared
+    pub fn collapsed_whitespace(&self) -> bool {
+        self.collapsed_whitespace
+    }
+
+    /// Builder method to set whether [`ParseMetrics`] are collected while
+    /// parsing and attached to the resulting [`Output`]. Defaults to `false`
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::ParseOptions;
+    /// let options = ParseOptions::new().with_metrics(true);
+    /// assert!(options.metrics());
+    /// ```
+    ///
+    /// [`ParseMetrics`]: struct.ParseMetrics.html
+    /// [`Output`]: struct.Output.html
+    pub fn with_metrics(mut self, metrics: bool) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Returns `true` if metrics are collected while parsing
+    pub fn metrics(&self) -> bool {
+        self.metrics
+    }
+
+    /// Builder method to set whether `/* ... */` and `<!-- ... -->` comments
+    /// are removed from the content of normal passages after parsing. Links
+    /// inside of comments are never parsed, whether or not comments are
+    /// stripped. Defaults to `false`
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::ParseOptions;
+    /// let options = ParseOptions::new().with_stripped_comments(true);
+    /// assert!(options.stripped_comments());
+    /// ```
+    pub fn with_stripped_comments(mut self, stripped: bool) -> Self {
+        self.stripped_comments = stripped;
+        self
+    }
+
+    /// Returns `true` if comments are removed from the content of passages
+    pub fn stripped_comments(&self) -> bool {
+        self.stripped_comments
+    }
+
+    /// Builder method to set whether the targets of [`Image`] links are
+    /// checked for dead links like other links. This is useful for stories
+    /// that only use passages as images rather than URLs. Defaults to `false`
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{ParseOptions, StoryPassages};
+    /// let input = ":: Start\n[img[cat.png]]\n".to_string();
+    /// let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+    /// let dead_links = |options: &ParseOptions| {
+    ///     story.check_with_options(options).iter().filter(|w| w.to_string().contains("Dead")).count()
+    /// };
+    /// assert_eq!(dead_links(&ParseOptions::new()), 0);
+    /// assert_eq!(dead_links(&ParseOptions::new().with_checked_image_links(true)), 1);
+    /// ```
+    ///
+    /// [`Image`]: enum.LinkKind.html#variant.Image
+    pub fn with_checked_image_links(mut self, checked: bool) -> Self {
+        self.checked_image_links = checked;
+        self
+    }
+
+    /// Returns `true` if the targets of image links are checked for dead links
+    pub fn checked_image_links(&self) -> bool {
+        self.checked_image_links
+    }
+
+    /// Builder method to set whether a [`NoReachableEnding`] warning is
+    /// produced when no passage without links can be reached from the start
+    /// passage. Defaults to `false`, since stories that loop back to the start
+    /// on purpose are common
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{ParseOptions, StoryPassages, WarningKind};
+    /// let input = ":: Start\n[[Start]]\n".to_string();
+    /// let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+    /// let no_ending = |options: &ParseOptions| {
+    ///     story
+    ///         .check_with_options(options)
+    ///         .iter()
+    ///         .any(|w| matches!(w.kind, WarningKind::NoReachableEnding(_)))
+    /// };
+    /// assert!(!no_ending(&ParseOptions::new()));
+    /// assert!(no_ending(&ParseOptions::new().with_checked_endings(true)));
+    /// ```
+    ///
+    /// [`NoReachableEnding`]: enum.WarningKind.html#variant.NoReachableEnding
+    pub fn with_checked_endings(mut self, checked: bool) -> Self {
+        self.checked_endings = checked;
+        self
+    }
+
+    /// Returns `true` if a warning is produced when no ending can be reached
+    /// from the start passage
+    pub fn checked_endings(&self) -> bool {
+        self.checked_endings
+    }
+
+    /// Builder method to set the highest [`complexity`] a normal passage can
+    /// have before a [`ComplexPassage`] warning is generated for it by the
+    /// checks. By default, there is no maximum
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{ParseOptions, StoryPassages};
+    /// let input = ":: Start\n[[A]] [[B]] [[C]]\n\n:: A\n\n:: B\n\n:: C\n".to_string();
+    /// let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+    /// let complex = |options: &ParseOptions| {
+    ///     story.check_with_options(options).iter().filter(|w| w.to_string().contains("complexity")).count()
+    /// };
+    /// assert_eq!(complex(&ParseOptions::new()), 0);
+    /// assert_eq!(complex(&ParseOptions::new().with_max_complexity(3)), 0);
+    /// assert_eq!(complex(&ParseOptions::new().with_max_complexity(2)), 1);
+    /// ```
+    ///
+    /// [`complexity`]: struct.TwineContent.html#method.complexity
+    /// [`ComplexPassage`]: enum.WarningKind.html#variant.ComplexPassage
+    pub fn with_max_complexity(mut self, max: usize) -> Self {
+        self.max_complexity = Some(max);
+        self
+    }
+
+    /// Returns the highest complexity a passage can have without a warning,
+    /// if there is one
+    pub fn max_complexity(&self) -> Option<usize> {
+        self.max_complexity
+    }
+
+    /// Builder method to set the languages of a story that holds a copy of
+    /// each passage for each language, so that the checks report any passage
+    /// missing in some of the languages with a [`MissingTranslation`] warning
+    /// and any link from a passage in one language to a passage in another
+    /// with a [`CrossLanguageLink`] warning. By default, there are no
+    /// languages and neither check is run
+    ///
+    /// The language of a passage is given by a suffix on its name, such as
+    /// `Intro_de`, or failing that by a directory with the name of the
+    /// language in the path of its file, such as `de/intro.tw`. Passages with
+    /// neither are shared by every language and are not checked. Passages are
+    /// copies of each other if their names are the same once the language
+    /// suffix is removed
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{ParseOptions, StoryPassages};
+    /// let input = ":: Start\n[[Intro_en]]\n\n:: Intro_en\n[[Outro_de]]\n\n:: Intro_de\n\n:: Outro_de\n".to_string();
+    /// let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+    /// let options = ParseOptions::new().with_languages(vec!["en".to_string(), "de".to_string()]);
+    /// let warnings: Vec<String> = story
+    ///     .check_with_options(&options)
+    ///     .into_iter()
+    ///     .filter(|w| matches!(w.kind.code(), "W0046" | "W0047"))
+    ///     .map(|w| w.kind.to_string())
+    ///     .collect();
+    /// assert_eq!(
+    ///     warnings,
+    ///     [
+    ///         "Passage Outro has no \"en\" translation",
+    ///         "Link to Outro_de from a \"en\" passage crosses languages",
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// [`MissingTranslation`]: enum.WarningKind.html#variant.MissingTranslation
+    /// [`CrossLanguageLink`]: enum.WarningKind.html#variant.CrossLanguageLink
+    pub fn with_languages(mut self, languages: Vec<String>) -> Self {
+        self.languages = languages;
+        self
+    }
+
+    /// Returns the languages of a story that is translated into more than one
+    pub fn languages(&self) -> &[String] {
+        &self.languages
+    }
+
+    /// Builder method to set the words that the checks flag with a
+    /// [`FlaggedWord`] warning wherever they appear in the text of a normal
+    /// passage, such as placeholders like `TODO` and `lorem` that should not
+    /// ship, or words a content rating does not allow. Words are matched
+    /// whole and without regard to ASCII case, and may contain spaces. Links,
+    /// comments, and, with the "macro-links" feature enabled, the macros of
+    /// the story format are not passage text, and are skipped. Defaults to
+    /// no words
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{ParseOptions, StoryPassages, WarningKind};
+    /// let input = ":: Start\nTodo: write this. Lorem ipsum. /* TODO */ [[todo]]\n\n:: todo\n".to_string();
+    /// let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+    /// let options = ParseOptions::new()
+    ///     .with_flagged_words(vec!["TODO".to_string(), "lorem ipsum".to_string()]);
+    /// let flagged: Vec<(String, usize)> = story
+    ///     .check_with_options(&options)
+    ///     .into_iter()
+    ///     .filter(|w| matches!(w.kind, WarningKind::FlaggedWord(_)))
+    ///     .map(|w| (w.kind.to_string(), w.context.unwrap().get_start_position().column))
+    ///     .collect();
+    /// assert_eq!(
+    ///     flagged,
+    ///     [
+    ///         ("Flagged word in passage text: TODO".to_string(), 1),
+    ///         ("Flagged word in passage text: lorem ipsum".to_string(), 19),
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// [`FlaggedWord`]: enum.WarningKind.html#variant.FlaggedWord
+    pub fn with_flagged_words(mut self, words: Vec<String>) -> Self {
+        self.flagged_words = words;
+        self
+    }
+
+    /// Returns the words that are flagged wherever they appear in passage
+    /// text
+    pub fn flagged_words(&self) -> &[String] {
+        &self.flagged_words
+    }
+
+    /// Builder method to set whether a passage whose header cannot be parsed
+    /// is replaced by a placeholder rather than failing the parse. Defaults to
+    /// `false`
+    ///
+    /// The placeholder is a normal passage holding the content of the broken
+    /// one, named from the text of its header with any tags and metadata left
+    /// out, so that editors can still show the rest of the story alongside
+    /// the errors. The errors are kept in the [`recovered_errors`] of the
+    /// parsed story. A header with no usable name gives a placeholder named
+    /// `Untitled Passage` followed by the line it is on
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{ParseOptions, StoryPassages};
+    /// let options = ParseOptions::new().with_recovery(true);
+    /// let input = ":: Start\n[[Cave]]\n\n:: Cave [dark\n[[Start]]\n".to_string();
+    /// let story = StoryPassages::from_string_with_options(input, &options).take().0.ok().unwrap();
+    /// assert!(story.passages.contains_key("Cave"));
+    /// assert_eq!(story.recovered_errors.len(), 1);
+    /// ```
+    ///
+    /// [`recovered_errors`]: struct.StoryPassages.html#structfield.recovered_errors
+    pub fn with_recovery(mut self, recovery: bool) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
+    /// Returns `true` if a passage whose header cannot be parsed is replaced
+    /// by a placeholder
+    pub fn recovery(&self) -> bool {
+        self.recovery
+    }
+
+    /// Builder method to set whether input that is not valid UTF-8 is decoded
+    /// anyway rather than failing the parse with an [`InvalidEncoding`] error.
+    /// UTF-16 input that starts with a byte order mark is converted, and any
+    /// invalid byte sequences are replaced with U+FFFD. Defaults to `false`
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{ParseOptions, StoryPassages};
+    /// let input: &[u8] = b":: Start\nCaf\xE9\n";
+    /// assert!(StoryPassages::from_reader_with_options(input, &ParseOptions::new()).is_err());
+    /// let options = ParseOptions::new().with_lossy_encoding(true);
+    /// let story = StoryPassages::from_reader_with_options(input, &options).take().0.ok().unwrap();
+    /// assert!(story.passages["Start"].context.get_contents().contains("Caf\u{fffd}"));
+    /// ```
+    ///
+    /// [`InvalidEncoding`]: enum.ErrorKind.html#variant.InvalidEncoding
+    pub fn with_lossy_encoding(mut self, lossy: bool) -> Self {
+        self.lossy_encoding = lossy;
+        self
+    }
+
+    /// Returns `true` if input that is not valid UTF-8 is decoded anyway
+    pub fn lossy_encoding(&self) -> bool {
+        self.lossy_encoding
+    }
+
+    /// Builder method to set how the content of a `StoryTitle` passage that
+    /// spans more than one line is normalized. Defaults to
+    /// [`MultiLineTitle::Keep`]
+    ///
+    /// [`MultiLineTitle::Keep`]: enum.MultiLineTitle.html#variant.Keep
+    pub fn with_multi_line_title(mut self, policy: MultiLineTitle) -> Self {
+        self.multi_line_title = policy;
+        self
+    }
+
+    /// Returns how a `StoryTitle` that spans more than one line is normalized
+    pub fn multi_line_title(&self) -> MultiLineTitle {
+        self.multi_line_title
+    }
+
+    /// Builder method to set how a passage header that ends with a backslash
+    /// is handled. Defaults to [`HeaderContinuation::Ignore`]
+    ///
+    /// [`HeaderContinuation::Ignore`]: enum.HeaderContinuation.html#variant.Ignore
+    pub fn with_header_continuation(mut self, continuation: HeaderContinuation) -> Self {
+        self.header_continuation = continuation;
+        self
+    }
+
+    /// Returns how a passage header that ends with a backslash is handled
+    pub fn header_continuation(&self) -> HeaderContinuation {
+        self.header_continuation
+    }
+
+    /// Builder method to add a rule that routes the normal passages matching
+    /// it into the bucket with the given name in [`StoryPassages::buckets`],
+    /// rather than into the main passage map. Rules are tried in the order
+    /// they were added, and the first one that matches a passage decides its
+    /// bucket
+    ///
+    /// Routed passages are not part of the story when it is checked, so they
+    /// produce no warnings, and links to them are dead links.
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{ParseOptions, RouteRule, StoryPassages};
+    /// let options = ParseOptions::new()
+    ///     .with_route(RouteRule::Tag("notes".to_string()), "notes".to_string());
+    /// let input = ":: Start\nHello\n\n:: Todo [notes]\nFinish this\n".to_string();
+    /// let story = StoryPassages::from_string_with_options(input, &options)
+    ///     .take()
+    ///     .0
+    ///     .ok()
+    ///     .unwrap();
+    /// assert!(!story.passages.contains_key("Todo"));
+    /// assert!(story.buckets["notes"].contains_key("Todo"));
+    /// ```
+    ///
+    /// [`StoryPassages::buckets`]: struct.StoryPassages.html#structfield.buckets
+    pub fn with_route(mut self, rule: RouteRule, bucket: String) -> Self {
+        self.routes.push((rule, bucket));
+        self
+    }
+
+    /// Returns the name of the bucket that a normal passage with the given
+    /// header is routed into, or `None` if it stays in the main passage map
+    pub fn route(&self, header: &PassageHeader) -> Option<&str> {
+        self.routes
+            .iter()
+            .find(|(rule, _)| rule.matches(header))
+            .map(|(_, bucket)| bucket.as_str())
+    }
+
+    /// Builder method to set the name of the passage that separates the
+    /// stories of an input that holds more than one, such as an anthology
+    /// kept in a single file. When the input is parsed with
+    /// [`Story::split_from_string_with_options`], each passage with this name
+    /// ends one story and starts the next, and each story has its own
+    /// `StoryTitle`, `StoryData`, and passages. The separator passages
+    /// themselves are dropped. Defaults to `None`, so the input is one story
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::ParseOptions;
+    /// let options = ParseOptions::new().with_story_separator("StoryBreak".to_string());
+    /// assert_eq!(options.story_separator(), Some("StoryBreak"));
+    /// ```
+    ///
+    /// [`Story::split_from_string_with_options`]: struct.Story.html#method.split_from_string_with_options
+    pub fn with_story_separator(mut self, name: String) -> Self {
+        self.story_separator = Some(name);
+        self
+    }
+
+    /// Returns the name of the passage that separates the stories of an input
+    /// that holds more than one, if any
+    pub fn story_separator(&self) -> Option<&str> {
+        self.story_separator.as_deref()
+    }
+
+    /// Returns the form of the given passage name or link target that is
+    /// compared when matching links to passages, with any normalization
+    /// turned on in these options applied
+    pub fn name_key(&self, name: &str) -> String {
+        let name: String = if self.normalized_names {
+            name.nfc().collect()
+        } else {
+            name.to_string()
+        };
+        if self.collapsed_whitespace {
+            name.split_whitespace().collect::<Vec<&str>>().join(" ")
+        } else {
+            name
+        }
+    }
+
+    /// Returns `true` if a warning of the given kind makes the parse fail
+    pub(crate) fn is_fatal(&self, kind: &WarningKind) -> bool {
+        match self.severity(kind) {
+            Severity::Info => false,
+            Severity::Warning => self.warnings_as_errors,
+            Severity::Deny => true,
+        }
+    }
+
+    /// Handles a batch of newly produced warnings. Suppressed warnings are
+    /// dropped first, and the fatal flag, if it is being tracked, is set if
+    /// any of the rest makes the parse fail. If there is a sink, the remaining
+    /// warnings are drained into it, and `false` is returned if the sink
+    /// aborts. Otherwise, `warnings` is truncated to the configured maximum,
+    /// if there is one
+    pub(crate) fn handle_warnings(&self, warnings: &mut Vec<Warning>) -> bool {
+        if !self.suppressed_warnings.is_empty() || !self.suppressed_categories.is_empty() {
+            warnings.retain(|warning| !self.is_suppressed(&warning.kind));
+        }
+        if let Some(fatal) = &self.fatal {
+            if warnings.iter().any(|warning| self.is_fatal(&warning.kind)) {
+                fatal.store(true, Ordering::Relaxed);
+            }
+        }
+        match &self.sink {
+            Some(sink) => warnings
+                .drain(..)
+                .all(|warning| sink.warning(warning) == SinkAction::Continue),
+            None => {
+                if let Some(max) = self.max_warnings {
+                    truncate_warnings(warnings, max);
+                }
+                true
+            }
+        }
+    }
+
+    /// Makes parsing from a `Path` reuse the outputs for files in the given
+    /// cache, and store the outputs for any other files in it
+    #[cfg(feature = "watch")]
+    pub(crate) fn with_cache(mut self, cache: Arc<ParseCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Gets the cache of parsed files, if any
+    #[cfg(feature = "watch")]
+    pub(crate) fn cache(&self) -> Option<&ParseCache> {
+        self.cache.as_deref()
+    }
+
+    /// Returns a copy of these options that records in the returned flag
+    /// whether any warning it handles makes the parse fail. This lets
+    /// warnings that were passed to a sink, or dropped by the maximum number
+    /// of warnings, still make the parse fail
+    pub(crate) fn with_fatal_tracking(&self) -> (ParseOptions, Arc<AtomicBool>) {
+        let fatal = Arc::new(AtomicBool::new(false));
+        let mut options = self.clone();
+        options.fatal = Some(fatal.clone());
+        (options, fatal)
+    }
+
+    /// Returns the maximum number of warnings a [`WarningCollector`] keeps
+    /// with these options, if any. Warnings can only be counted rather than
+    /// kept if they don't go to a sink and the parse tracks whether any of
+    /// them is fatal
+    ///
+    /// [`WarningCollector`]: struct.WarningCollector.html
+    fn collected_warnings(&self) -> Option<usize> {
+        match (&self.sink, &self.fatal) {
+            (None, Some(_)) => self.max_warnings,
+            _ => None,
+        }
+    }
+}
+
+/// Collects the warnings produced while parsing, keeping at most the maximum
+/// number of warnings set in the [`ParseOptions`]. Once that many are kept,
+/// any further warnings are only counted, so pathological inputs can't use up
+/// memory with warnings that would be truncated anyway
+///
+/// [`ParseOptions`]: struct.ParseOptions.html
+pub(crate) struct WarningCollector<'a> {
+    /// The options of the parse
+    options: &'a ParseOptions,
+
+    /// The warnings kept so far
+    warnings: Vec<Warning>,
+
+    /// The number of warnings counted after the maximum was reached
+    suppressed: usize,
+}
+
+impl<'a> WarningCollector<'a> {
+    /// Creates an empty collector for a parse with the given options
+    pub(crate) fn new(options: &'a ParseOptions) -> Self {
+        WarningCollector {
+            options,
+            warnings: Vec::new(),
+            suppressed: 0,
+        }
+    }
+
+    /// Adds the given warning, dropping it if it is suppressed. If the
+    /// maximum number of warnings has been reached, the warning is only
+    /// counted, and the fatal flag is set if it makes the parse fail
+    pub(crate) fn push(&mut self, warning: Warning) {
+        if self.options.is_suppressed(&warning.kind) {
+            return;
+        }
+        match self.options.collected_warnings() {
+            Some(max) if self.warnings.len() >= max => {
+                if let Some(fatal) = &self.options.fatal {
+                    if self.options.is_fatal(&warning.kind) {
+                        fatal.store(true, Ordering::Relaxed);
+                    }
+                }
+                self.suppressed += 1;
+            }
+            _ => self.warnings.push(warning),
+        }
+    }
+
+    /// Adds each of the given warnings, as with [`push`]
+    ///
+    /// [`push`]: #method.push
+    pub(crate) fn extend<I: IntoIterator<Item = Warning>>(&mut self, warnings: I) {
+        for warning in warnings {
+            self.push(warning);
+        }
+    }
+
+    /// Keeps all of the given warnings that are not suppressed, even past the
+    /// maximum. This is for warnings that an inline pragma may still allow,
+    /// which can't be counted until the pragmas are applied
+    pub(crate) fn keep(&mut self, warnings: Vec<Warning>) {
+        let options = self.options;
+        self.warnings.extend(
+            warnings
+                .into_iter()
+                .filter(|warning| !options.is_suppressed(&warning.kind)),
+        );
+    }
+
+    /// Returns the kept warnings, followed by a [`WarningsSuppressed`] marker
+    /// with the number of warnings that were only counted, if there were any
+    ///
+    /// [`WarningsSuppressed`]: enum.WarningKind.html#variant.WarningsSuppressed
+    pub(crate) fn finish(mut self) -> Vec<Warning> {
+        if self.suppressed > 0 {
+            self.warnings.push(Warning::new::<Context>(
+                WarningKind::WarningsSuppressed(self.suppressed),
+                None,
+            ));
+        }
+        self.warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards() {
+        assert!(wildcard_match("Notes", "Notes"));
+        assert!(!wildcard_match("Notes", "Notes 2"));
+        assert!(wildcard_match("Notes*", "Notes 2"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("*end", "The end"));
+        assert!(!wildcard_match("*end", "The end?"));
+        assert!(wildcard_match("a*b*c", "a-b-b-c"));
+        assert!(!wildcard_match("a*b*c", "a-c"));
+        assert!(!wildcard_match("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn flagged_words_replaced() {
+        let options = ParseOptions::new()
+            .with_flagged_words(vec!["TODO".to_string()])
+            .with_flagged_words(vec!["FIXME".to_string(), "lorem".to_string()]);
+        assert_eq!(options.flagged_words(), ["FIXME", "lorem"]);
+    }
+}
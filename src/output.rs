@@ -1,21 +1,23 @@
+use crate::issues::truncate_warnings;
+use crate::ParseMetrics;
 use crate::Warning;
 
 /// Represents the output of an operation along with a [`Vec`] of any
 /// [`Warning`]s generated by the operation.
 ///
-/// If the contained type `T` implements the [`Positional`] trait, then
-/// `Output<T>` will also implement the [`Positional`] trait, and will apply any
-/// changes to both the contained output and the contained [`Warning`]s.
-///
 /// [`Vec`]: std::Vec
 /// [`Warning`]: struct.Warning.html
-/// [`Positional`]: trait.Positional.html
 pub struct Output<T> {
     /// The output of the operation
     output: T,
 
     /// The associated [`Warning`]s
     warnings: Vec<Warning>,
+
+    /// The [`ParseMetrics`] collected while producing the output, if any
+    ///
+    /// [`ParseMetrics`]: struct.ParseMetrics.html
+    metrics: Option<ParseMetrics>,
 }
 
 impl<T> Output<T> {
@@ -32,7 +34,11 @@ impl<T> Output<T> {
     /// assert!(!out.has_warnings());
     /// ```
     pub fn new(value: T) -> Self {
-        Output { output: value, warnings: Vec::new() }
+        Output {
+            output: value,
+            warnings: Vec::new(),
+            metrics: None,
+        }
     }
 
     /// Builder method to add [`Warning`]s to an `Output` and return the object
@@ -54,6 +60,23 @@ impl<T> Output<T> {
         self
     }
 
+    /// Builder method to attach [`ParseMetrics`] to an `Output` and return the
+    /// object
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{Output, ParseMetrics};
+    /// let metrics = ParseMetrics { files: 2, ..Default::default() };
+    /// let out:Output<u32> = Output::new(23).with_metrics(metrics);
+    /// assert_eq!(out.get_metrics(), Some(&metrics));
+    /// ```
+    ///
+    /// [`ParseMetrics`]: struct.ParseMetrics.html
+    pub fn with_metrics(mut self, metrics: ParseMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
     /// Returns a reference to the output field
     ///
     /// # Examples
@@ -79,6 +102,20 @@ impl<T> Output<T> {
         &mut self.output
     }
 
+    /// Returns the [`ParseMetrics`] attached to the output, if any
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Output;
+    /// let out = Output::new(5);
+    /// assert!(out.get_metrics().is_none());
+    /// ```
+    ///
+    /// [`ParseMetrics`]: struct.ParseMetrics.html
+    pub fn get_metrics(&self) -> Option<&ParseMetrics> {
+        self.metrics.as_ref()
+    }
+
     /// Returns `true` if the object has associated [`Warning`]s
     ///
     /// # Examples
@@ -109,6 +146,26 @@ impl<T> Output<T> {
         &self.warnings
     }
 
+    /// Truncates the associated [`Warning`]s to at most `max` entries. If any
+    /// warnings are removed, a [`WarningsSuppressed`] warning with the number
+    /// of removed warnings is appended to the list
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{FullContext, Output, Warning, WarningKind};
+    /// # let context = FullContext::from(None, String::new());
+    /// let warning = Warning::new(WarningKind::UnclosedLink, Some(context));
+    /// let mut out:Output<u8> = Output::new(5).with_warnings(vec![ warning; 10 ]);
+    /// out.truncate_warnings(3);
+    /// assert_eq!(out.get_warnings().len(), 4);
+    /// assert_eq!(out.get_warnings()[3].kind, WarningKind::WarningsSuppressed(7));
+    /// ```
+    ///
+    /// [`WarningsSuppressed`]: enum.WarningKind.html#variant.WarningsSuppressed
+    pub fn truncate_warnings(&mut self, max: usize) {
+        truncate_warnings(&mut self.warnings, max);
+    }
+
     /// Consumes the `Output` and returns the `output` and `warnings` as a tuple
     ///
     /// # Examples
@@ -177,9 +234,10 @@ impl<T,E> Output<Result<T,E>> {
     /// assert_eq!(*other.get_output(), Ok(23));
     /// ```
     pub fn into_ok<U, F>(self) -> Output<Result<U,F>> where T: Into<U> {
+        let metrics = self.metrics;
         let (res, warnings) = self.take();
         let ok:U = res.ok().unwrap().into();
-        Output::new(Ok(ok)).with_warnings(warnings)
+        Output { output: Ok(ok), warnings, metrics }
     }
 
     /// Converts this `Output<Result<T,E>>` into an `Output<Result<U,F>>`, where
@@ -199,9 +257,10 @@ impl<T,E> Output<Result<T,E>> {
     /// assert_eq!(*other.get_output(), Err(5));
     /// ```
     pub fn into_err<U, F>(self) -> Output<Result<U,F>> where E: Into<F> {
+        let metrics = self.metrics;
         let (res,warnings) = self.take();
         let err:F = res.err().unwrap().into();
-        Output::new(Err(err)).with_warnings(warnings)
+        Output { output: Err(err), warnings, metrics }
     }
 
     /// Converts this `Output<Result<T,E>>` into an `Output<Result<U,F>>`, where
@@ -289,6 +348,22 @@ mod tests {
         assert_eq!(y.get_output(), &Err(23));
     }
 
+    #[test]
+    fn metrics_kept() {
+        use crate::ParseMetrics;
+        let metrics = ParseMetrics {
+            passages: 3,
+            ..Default::default()
+        };
+        let ok_out: Output<Result<u8, u8>> = Output::new(Ok(5)).with_metrics(metrics);
+        let x: Output<Result<u32, u32>> = ok_out.into_result();
+        assert_eq!(x.get_metrics(), Some(&metrics));
+
+        let err_out: Output<Result<u8, u8>> = Output::new(Err(5)).with_metrics(metrics);
+        let y: Output<Result<u32, u32>> = err_out.into_err();
+        assert_eq!(y.get_metrics(), Some(&metrics));
+    }
+
     #[test]
     #[should_panic]
     fn into_ok_panic() {
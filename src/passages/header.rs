@@ -1,13 +1,14 @@
+use crate::escape::unescape;
 use crate::issues::*;
 use crate::FullContext;
 use crate::Output;
-use crate::Position;
 
 use std::ops::Range;
 
 use serde_json::json;
 
-/// A passage header, along with associated [`Position`], tags, and metadata
+/// A passage header, along with its tags, metadata, and the [`FullContext`] of
+/// each part within the header line
 ///
 /// # Parse Errors
 /// * [`LeadingWhitespace`] - Whitespace before the `::` sigil on a header line
@@ -26,6 +27,10 @@ use serde_json::json;
 /// * [`EscapedCloseCurly`] - `\}` present in passage name
 /// * [`EscapedOpenSquare`] - `\[` present in passage name
 /// * [`EscapedCloseSquare`] - `\]` present in passage name
+/// * [`InvalidCoordinates`] - `position` or `size` metadata that is negative
+///   or NaN
+/// * [`MalformedMetadataField`] - `position` or `size` metadata that cannot be
+///   parsed
 ///
 /// # Examples
 /// ```
@@ -39,7 +44,7 @@ use serde_json::json;
 /// # assert_eq!(header.name, "A passage");
 /// ```
 ///
-/// [`Position`]: enum.Position.html
+/// [`FullContext`]: struct.FullContext.html
 /// [`LeadingWhitespace`]: enum.ErrorKind.html#variant.LeadingWhitespace
 /// [`MissingSigil`]: enum.ErrorKind.html#variant.MissingSigil
 /// [`MetadataBeforeTags`]: enum.ErrorKind.html#variant.MetadataBeforeTags
@@ -54,7 +59,10 @@ use serde_json::json;
 /// [`EscapedCloseCurly`]: enum.WarningKind.html#variant.EscapedCloseCurly
 /// [`EscapedOpenSquare`]: enum.WarningKind.html#variant.EscapedOpenSquare
 /// [`EscapedCloseSquare`]: enum.WarningKind.html#variant.EscapedCloseSquare
-#[derive(Debug)]
+/// [`InvalidCoordinates`]: enum.WarningKind.html#variant.InvalidCoordinates
+/// [`MalformedMetadataField`]: enum.WarningKind.html#variant.MalformedMetadataField
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PassageHeader {
     /// The name of the header. This can be a Twine passage name or a special name
     pub name: String,
@@ -64,9 +72,75 @@ pub struct PassageHeader {
 
     /// A json object containing metadata for the passage
     pub metadata: serde_json::Map<String, serde_json::Value>,
+
+    /// The context of the passage name within the header line
+    name_span: FullContext,
+
+    /// The contexts of each tag within the header line
+    tag_spans: Vec<FullContext>,
+
+    /// The context of the metadata block within the header line, if present
+    metadata_span: Option<FullContext>,
 }
 
 impl PassageHeader {
+    /// The size of the grid that the Twine editor snaps passages to
+    pub const GRID_SIZE: f64 = 25.0;
+
+    /// Returns the `position` metadata as an `(x, y)` pair, if it is present
+    /// and can be parsed
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{FullContext, PassageHeader};
+    /// let context = FullContext::from(None, r#":: A passage {"position":"600,400"}"#.to_string());
+    /// let header = PassageHeader::parse(context).take().0.ok().unwrap();
+    /// assert_eq!(header.position(), Some((600.0, 400.0)));
+    /// assert_eq!(header.size(), Some((100.0, 100.0)));
+    /// ```
+    pub fn position(&self) -> Option<(f64, f64)> {
+        self.metadata.get("position").and_then(parse_coordinates)
+    }
+
+    /// Returns the `size` metadata as a `(width, height)` pair, if it is
+    /// present and can be parsed
+    pub fn size(&self) -> Option<(f64, f64)> {
+        self.metadata.get("size").and_then(parse_coordinates)
+    }
+
+    /// Sets the `position` metadata to the given `(x, y)` pair
+    pub fn set_position(&mut self, position: (f64, f64)) {
+        self.metadata
+            .insert("position".to_string(), format_coordinates(position));
+    }
+
+    /// Sets the `size` metadata to the given `(width, height)` pair
+    pub fn set_size(&mut self, size: (f64, f64)) {
+        self.metadata
+            .insert("size".to_string(), format_coordinates(size));
+    }
+
+    /// Normalizes the `position` metadata and snaps it to a grid with the
+    /// given cell size, such as [`GRID_SIZE`]. Does nothing if there is no
+    /// position that can be parsed
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{FullContext, PassageHeader};
+    /// let context = FullContext::from(None, r#":: A passage {"position":"612.5,-30"}"#.to_string());
+    /// let mut header = PassageHeader::parse(context).take().0.ok().unwrap();
+    /// header.snap_to_grid(PassageHeader::GRID_SIZE);
+    /// assert_eq!(header.position(), Some((625.0, 0.0)));
+    /// assert_eq!(header.metadata["position"], "625,0");
+    /// ```
+    ///
+    /// [`GRID_SIZE`]: #associatedconstant.GRID_SIZE
+    pub fn snap_to_grid(&mut self, grid_size: f64) {
+        if let Some(position) = self.position() {
+            self.set_position(snap_to_grid(position, grid_size));
+        }
+    }
+
     /// Returns `true` if this header is tagged with `str`
     ///
     /// # Examples
@@ -77,15 +151,92 @@ impl PassageHeader {
     /// assert!(out.get_output().as_ref().ok().unwrap().has_tag("foo"));
     /// ```
     pub fn has_tag(&self, tag: &str) -> bool {
-        let tag = tag.to_string();
-        self.tags.contains(&tag)
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Returns the context of the passage name within the header line,
+    /// without any surrounding whitespace
+    ///
+    /// The spans of a header are not changed when the header is, and always
+    /// refer to the original source
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{FullContext, PassageHeader};
+    /// let input = r#":: A passage [ tag1 tag2 ] {"size": "200,100"}"#;
+    /// let context = FullContext::from(None, input.to_string());
+    /// let header = PassageHeader::parse(context).take().0.ok().unwrap();
+    /// assert_eq!(header.name_span().get_contents(), "A passage");
+    /// let tags: Vec<&str> = header.tag_spans().iter().map(|t| t.get_contents()).collect();
+    /// assert_eq!(tags, ["tag1", "tag2"]);
+    /// assert_eq!(header.metadata_span().unwrap().get_contents(), r#"{"size": "200,100"}"#);
+    /// ```
+    pub fn name_span(&self) -> &FullContext {
+        &self.name_span
+    }
+
+    /// Returns the contexts of each tag within the header line, in the same
+    /// order as [`tags`]
+    ///
+    /// [`tags`]: #structfield.tags
+    pub fn tag_spans(&self) -> &[FullContext] {
+        &self.tag_spans
+    }
+
+    /// Returns the context of the metadata block within the header line,
+    /// including the braces, or `None` if the header has no metadata
+    pub fn metadata_span(&self) -> Option<&FullContext> {
+        self.metadata_span.as_ref()
+    }
+
+    /// Adds the tags of `other` that this header does not have, along with
+    /// their spans, and the metadata fields of `other` that this header does
+    /// not have. The name of `other` is ignored
+    pub(crate) fn merge(&mut self, other: PassageHeader) {
+        for (tag, span) in other.tags.into_iter().zip(other.tag_spans) {
+            if !self.has_tag(&tag) {
+                self.tags.push(tag);
+                self.tag_spans.push(span);
+            }
+        }
+        for (key, value) in other.metadata {
+            self.metadata.entry(key).or_insert(value);
+        }
+    }
+
+    /// Creates a header for a placeholder passage in place of the header in
+    /// the given context, which could not be parsed. The name is the text
+    /// after the sigil up to any tags or metadata, or the given fallback if
+    /// there is no such text
+    pub(crate) fn placeholder(context: FullContext, fallback: String) -> Self {
+        let joined = join_continuations(context.get_contents());
+        let name = joined
+            .strip_prefix("::")
+            .map(|rest| rest.split(['[', '{']).next().unwrap_or_default())
+            .map(|name| unescape(name.trim()))
+            .filter(|name| !name.is_empty())
+            .unwrap_or(fallback);
+        let metadata = json!({ "position": "10,10", "size":"100,100" });
+        let metadata = match metadata {
+            serde_json::Value::Object(map) => map,
+            _ => panic!("Unreachable: Failed to extract map from JSON object"),
+        };
+        PassageHeader {
+            name,
+            tags: Vec::new(),
+            metadata,
+            name_span: context,
+            tag_spans: Vec::new(),
+            metadata_span: None,
+        }
     }
 
     /// Parses a `PassageHeader` out of the given context
     pub fn parse(context: FullContext) -> Output<Result<Self, ErrorList>> {
         let mut warnings = Vec::new();
         let mut errors = ErrorList::default();
-        let input = context.get_contents();
+        let joined = join_continuations(context.get_contents());
+        let input = joined.as_str();
 
         // Check for sigil
         if !input.starts_with("::") {
@@ -113,19 +264,47 @@ impl PassageHeader {
             panic!("Unreachable: Failed to extract map from JSON object");
         };
 
+        let mut metadata_span = None;
         if let Some(range) = guess_metadata_range(input) {
             let pos = range.start;
             name_end_pos = pos;
+            metadata_span = Some(context.subcontext(
+                context.joined_position(range.start + 1)..=context.joined_position(range.end),
+            ));
 
             if find_last_unescaped(&input[range.end..], "[").is_some() {
-                let error = Error::new(ErrorKind::MetadataBeforeTags, Some(context.subcontext(Position::rel(1, pos+1)..)));
+                let error = Error::new(
+                    ErrorKind::MetadataBeforeTags,
+                    Some(context.subcontext(context.joined_position(pos + 1)..)),
+                );
                 errors.push(error);
             }
 
-            let meta_context = context.subcontext(Position::rel(1, range.start)..=Position::rel(1, range.end));
-            let res = parse_metadata(meta_context);
+            let meta_context = context.subcontext(
+                context.joined_position(range.start)..=context.joined_position(range.end),
+            );
+            let res = parse_metadata(
+                &input[range.start.saturating_sub(1)..range.end],
+                meta_context.clone(),
+            );
             if res.is_ok() {
                 for (k, v) in res.ok().unwrap().iter() {
+                    if k == "position" || k == "size" {
+                        let value = v
+                            .as_str()
+                            .map(|s| s.to_string())
+                            .unwrap_or_else(|| v.to_string());
+                        let kind = match parse_coordinates(v) {
+                            None => Some(WarningKind::MalformedMetadataField(k.to_string(), value)),
+                            Some((x, y)) if !(x >= 0.0 && y >= 0.0) => {
+                                Some(WarningKind::InvalidCoordinates(k.to_string(), value))
+                            }
+                            Some(_) => None,
+                        };
+                        if let Some(kind) = kind {
+                            warnings.push(Warning::new(kind, Some(meta_context.clone())));
+                        }
+                    }
                     metadata.insert(k.to_string(), v.clone());
                 }
             } else {
@@ -135,17 +314,25 @@ impl PassageHeader {
 
         // Check for tags
         let mut tags: Vec<String> = Vec::new();
+        let mut tag_spans = Vec::new();
         if let Some(pos) = find_last_unescaped(&input[..name_end_pos], "[") {
             let end_pos = find_last_unescaped(&input[pos + 1..name_end_pos], "]");
 
             if let Some(p) = end_pos {
-                tags = input[pos + 1..pos + 1 + p]
-                    .trim()
-                    .split_whitespace()
-                    .map(|s| s.to_string())
-                    .collect();
+                let block = &input[pos + 1..pos + 1 + p];
+                for tag in block.split_whitespace() {
+                    let start = pos + 1 + (tag.as_ptr() as usize - block.as_ptr() as usize);
+                    tag_spans.push(context.subcontext(
+                        context.joined_position(start + 1)
+                            ..=context.joined_position(start + tag.len()),
+                    ));
+                    tags.push(tag.to_string());
+                }
             } else {
-                let error = Error::new(ErrorKind::UnclosedTagBlock, Some(context.subcontext(Position::rel(1, pos+1)..)));
+                let error = Error::new(
+                    ErrorKind::UnclosedTagBlock,
+                    Some(context.subcontext(context.joined_position(pos + 1)..)),
+                );
                 errors.push(error);
             }
 
@@ -179,7 +366,12 @@ impl PassageHeader {
             // If there are unescaped special chars, return the error now. Pass
             // in 0 as the starting index because that way we don't have to
             // massage the character position of the error or warnings
-            let indices = check_name(context.subcontext(..=Position::rel(1, name_end_pos)), c, e);
+            let indices = check_name(
+                &input[..name_end_pos],
+                context.subcontext(..=context.joined_position(name_end_pos)),
+                c,
+                e,
+            );
             if indices.is_err() {
                 errors.push(indices.err().unwrap());
             } else {
@@ -187,19 +379,37 @@ impl PassageHeader {
 
                 // For any warning locations returned, add them to the warning list
                 for idx in indices {
-                    let warning = Warning::new(w.clone(), Some(context.subcontext(Position::rel(1, idx + 1)..=Position::rel(1, idx+2))));
+                    let warning = Warning::new(
+                        w.clone(),
+                        Some(context.subcontext(
+                            context.joined_position(idx + 1)..=context.joined_position(idx + 2),
+                        )),
+                    );
                     warnings.push(warning);
                 }
             }
         }
 
-        let name = if name_end_pos > 2 {
-            input[2..name_end_pos].trim().replace("\\", "")
+        let (name, name_span) = if name_end_pos > 2 {
+            let raw = &input[2..name_end_pos];
+            let start = 2 + raw.len() - raw.trim_start().len();
+            let end = 2 + raw.trim_end().len();
+            (
+                unescape(raw.trim()),
+                context
+                    .subcontext(context.joined_position(start + 1)..=context.joined_position(end)),
+            )
         } else {
-            String::default()
+            (
+                String::default(),
+                context.subcontext(context.joined_position(3)..),
+            )
         };
         if name.is_empty() {
-            let error = Error::new(ErrorKind::EmptyName, Some(context.subcontext(Position::rel(1,3)..)));
+            let error = Error::new(
+                ErrorKind::EmptyName,
+                Some(context.subcontext(context.joined_position(3)..)),
+            );
             errors.push(error);
         }
 
@@ -208,6 +418,9 @@ impl PassageHeader {
                 name,
                 tags,
                 metadata,
+                name_span,
+                tag_spans,
+                metadata_span,
             }))
             .with_warnings(warnings)
         } else {
@@ -216,10 +429,13 @@ impl PassageHeader {
     }
 }
 
-/// Given metadata in `meta_str`, parses out the metadata object, or returns a
+/// Given metadata in `meta_str`, which is the contents of `context` with any
+/// header continuations joined, parses out the metadata object, or returns a
 /// warning if the metadata can't be parsed
-fn parse_metadata(context: FullContext) -> Result<serde_json::Map<String, serde_json::Value>, Warning> {
-    let meta_str = context.get_contents();
+fn parse_metadata(
+    meta_str: &str,
+    context: FullContext,
+) -> Result<serde_json::Map<String, serde_json::Value>, Warning> {
     let res = serde_json::from_str(meta_str);
     if res.is_ok() {
         use serde_json::Value;
@@ -235,11 +451,57 @@ fn parse_metadata(context: FullContext) -> Result<serde_json::Map<String, serde_
         let col = err.column();
         // Get the error part of error string generated by serde
         let err_string = format!("{}", err).split(" at ").next().unwrap().to_string();
-        let warning = Warning::new(WarningKind::JsonError(err_string), Some(context.subcontext(Position::rel(1, col)..)));
+        let warning = Warning::new(
+            WarningKind::JsonError(err_string),
+            Some(context.subcontext(context.joined_position(col)..)),
+        );
         Err(warning)
     }
 }
 
+/// Parses a `"x,y"` metadata value into a pair of numbers
+fn parse_coordinates(value: &serde_json::Value) -> Option<(f64, f64)> {
+    let mut iter = value.as_str()?.split(',');
+    let x = iter.next()?.trim().parse().ok()?;
+    let y = iter.next()?.trim().parse().ok()?;
+    if iter.next().is_some() {
+        return None;
+    }
+    Some((x, y))
+}
+
+/// Formats a pair of numbers as a `"x,y"` metadata value
+fn format_coordinates((x, y): (f64, f64)) -> serde_json::Value {
+    serde_json::Value::String(format!("{},{}", x, y))
+}
+
+/// Rounds each coordinate to the nearest multiple of `grid_size`, replacing
+/// negative and NaN coordinates with zero
+fn snap_to_grid((x, y): (f64, f64), grid_size: f64) -> (f64, f64) {
+    let snap = |v: f64| {
+        if v.is_nan() || v <= 0.0 {
+            0.0
+        } else if grid_size > 0.0 {
+            (v / grid_size).round() * grid_size
+        } else {
+            v
+        }
+    };
+    (snap(x), snap(y))
+}
+
+/// Replaces each backslash at the end of a line, along with the line break
+/// after it, with spaces, so that a header that continues onto later lines
+/// can be parsed as one line. The result has the same length as the input, so
+/// byte offsets into it are offsets into the original
+fn join_continuations(input: &str) -> String {
+    let mut joined = input.to_string();
+    for ending in ["\\\r\n", "\\\n"].iter() {
+        joined = joined.replace(ending, &" ".repeat(ending.len()));
+    }
+    joined
+}
+
 /// Finds the last unescaped string `s` in the input string `input`
 fn find_last_unescaped(input: &str, s: &str) -> Option<usize> {
     // Check for last 's'
@@ -297,9 +559,13 @@ fn guess_metadata_range(input: &str) -> Option<Range<usize>> {
 /// the name contains any instances of that character but escaped, return a list
 /// of locations in the name where the escaped character is found so that
 /// warnings can be generated
-fn check_name(context: FullContext, unescaped_str: &str, error: ErrorKind) -> Result<Vec<usize>, Error> {
+fn check_name(
+    input: &str,
+    context: FullContext,
+    unescaped_str: &str,
+    error: ErrorKind,
+) -> Result<Vec<usize>, Error> {
     let escaped_str = format!("\\{}", unescaped_str);
-    let input = context.get_contents();
 
     let escaped: Vec<usize> = input.match_indices(&escaped_str).map(|(i, _)| i).collect();
     let unescaped: Vec<usize> = input
@@ -311,7 +577,8 @@ fn check_name(context: FullContext, unescaped_str: &str, error: ErrorKind) -> Re
     if unescaped.is_empty() {
         Ok(escaped)
     } else {
-        let err_range = Position::rel(1, unescaped[0] + 1)..=Position::rel(1, unescaped[0]+1);
+        let err_range =
+            context.joined_position(unescaped[0] + 1)..=context.joined_position(unescaped[0] + 1);
         let error = Error::new(error, Some(context.subcontext(err_range)));
         Err(error)
     }
@@ -320,6 +587,7 @@ fn check_name(context: FullContext, unescaped_str: &str, error: ErrorKind) -> Re
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Position;
 
     #[test]
     fn missing_sigil() {
@@ -554,6 +822,50 @@ mod tests {
         assert_eq!(meta["position"], "5,5");
     }
 
+    #[test]
+    fn coordinates() {
+        let context = FullContext::from(None, ":: Title {\"size\":\"23.5, 40\"}".to_string());
+        let mut ph = PassageHeader::parse(context).take().0.ok().unwrap();
+        assert_eq!(ph.size(), Some((23.5, 40.0)));
+        assert_eq!(ph.position(), Some((10.0, 10.0)));
+        ph.set_size((200.0, 100.0));
+        assert_eq!(ph.metadata["size"], "200,100");
+        ph.set_position((37.0, 12.4));
+        ph.snap_to_grid(PassageHeader::GRID_SIZE);
+        assert_eq!(ph.position(), Some((25.0, 0.0)));
+
+        ph.metadata.insert("position".to_string(), json!("a,b"));
+        assert_eq!(ph.position(), None);
+        ph.snap_to_grid(PassageHeader::GRID_SIZE);
+        assert_eq!(ph.metadata["position"], "a,b");
+
+        assert_eq!(snap_to_grid((f64::NAN, -5.0), 25.0), (0.0, 0.0));
+        assert_eq!(snap_to_grid((13.0, 7.0), 0.0), (13.0, 7.0));
+    }
+
+    #[test]
+    fn invalid_coordinates() {
+        let input = ":: Title {\"position\":\"-5,NaN\",\"size\":\"big\"}".to_string();
+        let context = FullContext::from(None, input);
+        let out = PassageHeader::parse(context.clone());
+        let (res, warnings) = out.take();
+        assert!(res.is_ok());
+        let meta_context = context.subcontext(Position::rel(1, 9)..=Position::rel(1, 43));
+        assert_eq!(
+            warnings,
+            vec![
+                Warning::new(
+                    WarningKind::InvalidCoordinates("position".to_string(), "-5,NaN".to_string()),
+                    Some(meta_context.clone()),
+                ),
+                Warning::new(
+                    WarningKind::MalformedMetadataField("size".to_string(), "big".to_string()),
+                    Some(meta_context),
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn multilevel_metadata() {
         let context = FullContext::from(
@@ -614,6 +926,10 @@ mod tests {
         assert_eq!(warnings.len(), 2);
         assert_eq!(warnings[0].kind, WarningKind::EscapedOpenCurly);
         assert_eq!(warnings[1].kind, WarningKind::EscapedCloseSquare);
+
+        let context = FullContext::from(None, ":: Back\\\\slash".to_string());
+        let ph = PassageHeader::parse(context).take().0.ok().unwrap();
+        assert_eq!(ph.name, "Back\\slash");
     }
 
     #[test]
@@ -663,4 +979,37 @@ mod tests {
         let ph = res.ok().unwrap();
         assert_eq!(ph.tags.len(), 0);
     }
+
+    #[test]
+    fn spans() {
+        let input = ":: \\[Caf\u{e9}\\]  [ one  two ] { \"size\": \"5,5\" }";
+        let context = FullContext::from(None, input.to_string());
+        let header = PassageHeader::parse(context.clone()).take().0.ok().unwrap();
+        assert_eq!(header.name, "[Caf\u{e9}]");
+        assert_eq!(
+            header.name_span(),
+            &context.subcontext(Position::rel(1, 4)..=Position::rel(1, 12))
+        );
+        assert_eq!(header.name_span().get_contents(), "\\[Caf\u{e9}\\]");
+        let tags: Vec<&str> = header
+            .tag_spans()
+            .iter()
+            .map(|t| t.get_contents())
+            .collect();
+        assert_eq!(tags, ["one", "two"]);
+        assert_eq!(
+            header.tag_spans()[1],
+            context.subcontext(Position::rel(1, 22)..=Position::rel(1, 24))
+        );
+        assert_eq!(
+            header.metadata_span().unwrap().get_contents(),
+            "{ \"size\": \"5,5\" }"
+        );
+
+        let context = FullContext::from(None, ":: Plain".to_string());
+        let header = PassageHeader::parse(context).take().0.ok().unwrap();
+        assert_eq!(header.name_span().get_contents(), "Plain");
+        assert!(header.tag_spans().is_empty());
+        assert!(header.metadata_span().is_none());
+    }
 }
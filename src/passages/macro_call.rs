@@ -0,0 +1,223 @@
+use crate::passages::slice_context;
+use crate::passages::split_link;
+use crate::FullContext;
+use crate::StoryFormat;
+
+/// An invocation of a story format macro, such as SugarCube's
+/// `<<include "Intro">>` or Harlowe's `(display: "Intro")`. Closing tags such
+/// as `<</if>>` are not invocations, and neither are the Harlowe macros nested
+/// inside the arguments of another
+///
+/// Enabled with "macro-links" feature
+///
+/// # Examples
+/// ```
+/// use tweep::{FullContext, StoryFormat, TwineContent};
+/// let input = "<<if $x>><<greet \"Ann\" $y>><</if>>".to_string();
+/// let content = TwineContent::parse(FullContext::from(None, input)).take().0.ok().unwrap();
+/// let call = content.get_macro_calls(StoryFormat::SugarCube).nth(1).unwrap();
+/// assert_eq!(call.name, "greet");
+/// assert_eq!(call.args, [Some("Ann".to_string()), None]);
+/// assert_eq!(call.context.get_contents(), "<<greet \"Ann\" $y>>");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MacroCall {
+    /// The name of the macro as written
+    pub name: String,
+
+    /// The story format whose syntax the macro is written in
+    pub format: StoryFormat,
+
+    /// The contents of each argument that is a string literal, or `None` for
+    /// any other argument
+    pub args: Vec<Option<String>>,
+
+    /// The context of the whole invocation
+    pub context: FullContext,
+}
+
+/// The arguments of a common macro, as parsed by [`MacroCall::parse_args`].
+/// Expressions are not parsed, only found, and each part is given by its
+/// context, from which its text and position can be read
+///
+/// Enabled with "macro-links" feature
+///
+/// [`MacroCall::parse_args`]: struct.MacroCall.html#method.parse_args
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MacroArgs {
+    /// The expression of SugarCube's `<<set>>` or Harlowe's `(set:)`, such as
+    /// `$gold to 5`
+    Set(FullContext),
+
+    /// The condition of SugarCube's `<<if>>` and `<<elseif>>` or Harlowe's
+    /// `(if:)`, `(else-if:)`, and `(unless:)`, such as `$gold > 5`
+    Condition(FullContext),
+
+    /// The arguments of SugarCube's `<<link>>`, written either as string
+    /// literals, such as `<<link "Leave" "Outside">>`, or as a link, such as
+    /// `<<link [[Leave|Outside][$left to true]]>>`. Arguments that are not
+    /// string literals are `None`
+    Link {
+        /// The display text of the link
+        text: Option<FullContext>,
+
+        /// The passage the link goes to
+        target: Option<FullContext>,
+
+        /// The setter run when the link is clicked
+        setter: Option<FullContext>,
+    },
+}
+
+impl MacroCall {
+    /// Parses the arguments of the macro into a [`MacroArgs`] if it is one of
+    /// the macros that it covers. Returns `None` for any other macro, or if
+    /// its expression is empty
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{FullContext, MacroArgs, StoryFormat, TwineContent};
+    /// let input = "(set: $gold to 5)".to_string();
+    /// let content = TwineContent::parse(FullContext::from(None, input)).take().0.ok().unwrap();
+    /// let call = content.get_macro_calls(StoryFormat::Harlowe).next().unwrap();
+    /// match call.parse_args() {
+    ///     Some(MacroArgs::Set(expression)) => {
+    ///         assert_eq!(expression.get_contents(), "$gold to 5");
+    ///         assert_eq!(expression.get_start_position().column, 7);
+    ///     }
+    ///     _ => panic!("Expected a set"),
+    /// }
+    /// ```
+    ///
+    /// [`MacroArgs`]: enum.MacroArgs.html
+    pub fn parse_args(&self) -> Option<MacroArgs> {
+        let contents = self.context.get_contents();
+        let (name, args) = match self.format {
+            StoryFormat::SugarCube => {
+                let inner = contents.get(2 + self.name.len()..contents.len() - 2)?;
+                (self.name.clone(), inner)
+            }
+            StoryFormat::Harlowe => {
+                let name = self.name.to_lowercase().replace(['-', '_'], "");
+                (name, contents.get(self.name.len() + 2..contents.len() - 1)?)
+            }
+        };
+        let slice = |text: &str| slice_context(&self.context, 0, contents, text.trim());
+
+        match (self.format, name.as_str()) {
+            (_, "set") => slice(args).map(MacroArgs::Set),
+            (_, "if") | (_, "elseif") | (StoryFormat::Harlowe, "unless") => {
+                slice(args).map(MacroArgs::Condition)
+            }
+            (StoryFormat::SugarCube, "link") => {
+                let args = args.trim_start();
+                if let Some(link) = args.strip_prefix("[[") {
+                    let link = &link[..link.rfind("]]")?];
+                    let (link, setter) = match link.find("][") {
+                        Some(x) => (&link[..x], Some(&link[x + 2..])),
+                        None => (link, None),
+                    };
+                    let (target, text, _) = split_link(link);
+                    Some(MacroArgs::Link {
+                        text: text.and_then(slice),
+                        target: slice(&link[target]),
+                        setter: setter.and_then(slice),
+                    })
+                } else {
+                    let args = crate::passages::string_args(args, false);
+                    let arg = |i: usize| args.get(i).copied().flatten();
+                    Some(MacroArgs::Link {
+                        text: arg(0).and_then(slice),
+                        target: arg(1).and_then(slice),
+                        setter: None,
+                    })
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TwineContent;
+
+    fn args(input: &str, format: StoryFormat) -> Vec<MacroArgs> {
+        let context = FullContext::from(None, input.to_string());
+        let content = TwineContent::parse(context).take().0.ok().unwrap();
+        content
+            .get_macro_calls(format)
+            .filter_map(MacroCall::parse_args)
+            .collect()
+    }
+
+    fn contents(context: &Option<FullContext>) -> Option<&str> {
+        context.as_ref().map(FullContext::get_contents)
+    }
+
+    #[test]
+    fn sugarcube() {
+        let input = "<<set $a to 1>><<if  $a is 1 >>x<<elseif>>\n<<link [[Go|Cave][$a to 2]]>><</link>><<link \"Run\" $b>><</link>><<goto \"Cave\">>";
+        let parsed = args(input, StoryFormat::SugarCube);
+        assert_eq!(parsed.len(), 4);
+        match &parsed[0] {
+            MacroArgs::Set(expression) => assert_eq!(expression.get_contents(), "$a to 1"),
+            other => panic!("Unexpected {:?}", other),
+        }
+        match &parsed[1] {
+            MacroArgs::Condition(condition) => {
+                assert_eq!(condition.get_contents(), "$a is 1");
+                assert_eq!(condition.get_byte_range(), 21..28);
+            }
+            other => panic!("Unexpected {:?}", other),
+        }
+        match &parsed[2] {
+            MacroArgs::Link {
+                text,
+                target,
+                setter,
+            } => {
+                assert_eq!(contents(text), Some("Go"));
+                assert_eq!(contents(target), Some("Cave"));
+                assert_eq!(contents(setter), Some("$a to 2"));
+                let target = target.as_ref().unwrap();
+                assert_eq!(target.get_start_position().line, 2);
+                assert_eq!(target.get_start_position().column, 13);
+            }
+            other => panic!("Unexpected {:?}", other),
+        }
+        match &parsed[3] {
+            MacroArgs::Link {
+                text,
+                target,
+                setter,
+            } => {
+                assert_eq!(contents(text), Some("Run"));
+                assert_eq!((target, setter), (&None, &None));
+            }
+            other => panic!("Unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn harlowe() {
+        let input = "(Else-If: $a > (str: 2))[x] (unless:) (set:$b to 1)";
+        let parsed = args(input, StoryFormat::Harlowe);
+        assert_eq!(parsed.len(), 2);
+        match &parsed[0] {
+            MacroArgs::Condition(condition) => {
+                assert_eq!(condition.get_contents(), "$a > (str: 2)")
+            }
+            other => panic!("Unexpected {:?}", other),
+        }
+        match &parsed[1] {
+            MacroArgs::Set(expression) => {
+                assert_eq!(expression.get_contents(), "$b to 1");
+                assert_eq!(expression.get_byte_range(), 43..50);
+            }
+            other => panic!("Unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,478 @@
+use crate::passages::closing_paren;
+use crate::passages::slice_context;
+use crate::passages::string_end;
+use crate::FullContext;
+use crate::LinkKind;
+use crate::MacroCall;
+use crate::Position;
+use crate::StoryFormat;
+use crate::TwineLink;
+use std::ops::Range;
+
+/// Finds the links created by the macros of every supported [`StoryFormat`]
+/// in the given content. Only macros whose passage argument is a string
+/// literal produce a link, since any other argument can't be resolved without
+/// running the story
+///
+/// The supported macros are:
+/// * SugarCube - `<<goto>>`, `<<include>>`, `<<display>>`, `<<link>>`,
+///   `<<button>>`, `<<choice>>`, and `<<actions>>`
+/// * Harlowe - `(goto:)`, `(display:)`, `(link-goto:)`, `(link-reveal-goto:)`,
+///   `(click-goto:)`, `(mouseover-goto:)`, and `(mouseout-goto:)`
+///
+/// Every macro invocation is returned as well, whether or not it creates a
+/// link. Macros that start inside of one of the given comment ranges are
+/// skipped
+///
+/// [`StoryFormat`]: enum.StoryFormat.html
+pub(crate) fn parse_macros(
+    context: &FullContext,
+    comments: &[Range<usize>],
+) -> (Vec<TwineLink>, Vec<MacroCall>) {
+    let mut links = Vec::new();
+    let mut calls = Vec::new();
+    let in_comment = |offset: usize| comments.iter().any(|c| c.contains(&offset));
+    let base = context.get_byte_range().start;
+    let first_line = context.get_start_position().line;
+    for (line_number, line, range) in context.lines() {
+        let (row, offset) = (line_number - first_line, range.start - base);
+        let mut make_link = |start: usize, end: usize, text: Option<&str>, target: &str, format| {
+            if in_comment(offset + start) {
+                return;
+            }
+            let link_context =
+                context.subcontext(Position::rel(row + 1, start + 1)..=Position::rel(row + 1, end));
+            let kind = LinkKind::Macro(format);
+            let target_span = slice_context(context, row, line, target);
+            let text_span = text.and_then(|text| slice_context(context, row, line, text));
+            let mut link = TwineLink::new(target.to_string(), link_context)
+                .with_passage_start(*context.get_start_position())
+                .with_spans(target_span, text_span);
+            link.kind = kind;
+            if let Some(text) = text {
+                link = link.with_display_text(text.to_string(), kind);
+            }
+            links.push(link);
+        };
+
+        // SugarCube macros: <<name "arg" "arg">>
+        let mut start = 0;
+        while let Some(x) = line[start..].find("<<") {
+            start += x;
+            let end = match line[start..].find(">>") {
+                Some(x) => start + x + 2,
+                None => break,
+            };
+            let inner = &line[start + 2..end - 2];
+            let name_end = inner.find(char::is_whitespace).unwrap_or(inner.len());
+            let args = string_args(&inner[name_end..], false);
+            let arg = |i: usize| args.get(i).copied().flatten();
+            let name = &inner[..name_end];
+            if !name.is_empty() && !name.starts_with('/') && !in_comment(offset + start) {
+                calls.push(MacroCall {
+                    name: name.to_string(),
+                    format: StoryFormat::SugarCube,
+                    args: args.iter().map(|arg| arg.map(str::to_string)).collect(),
+                    context: context.subcontext(
+                        Position::rel(row + 1, start + 1)..=Position::rel(row + 1, end),
+                    ),
+                });
+            }
+            match name {
+                "goto" | "include" | "display" | "choice" => {
+                    if let Some(target) = arg(0) {
+                        make_link(start, end, None, target, StoryFormat::SugarCube);
+                    }
+                }
+                "link" | "button" => {
+                    if let (Some(text), Some(target)) = (arg(0), arg(1)) {
+                        make_link(start, end, Some(text), target, StoryFormat::SugarCube);
+                    }
+                }
+                "actions" => {
+                    for target in args.iter().flatten() {
+                        make_link(start, end, None, target, StoryFormat::SugarCube);
+                    }
+                }
+                _ => (),
+            }
+            start = end;
+        }
+
+        // Harlowe macros: (name: "arg", "arg")
+        let mut start = 0;
+        while let Some(x) = line[start..].find('(') {
+            start += x;
+            let name_len = line[start + 1..]
+                .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+                .unwrap_or(line.len() - start - 1);
+            let name_end = start + 1 + name_len;
+            if name_len == 0 || !line[name_end..].starts_with(':') {
+                start += 1;
+                continue;
+            }
+            let end = match closing_paren(&line[name_end + 1..]) {
+                Some(x) => name_end + 1 + x + 1,
+                None => break,
+            };
+            let args = string_args(&line[name_end + 1..end - 1], true);
+            let arg = |i: usize| args.get(i).copied().flatten();
+            if !in_comment(offset + start) {
+                calls.push(MacroCall {
+                    name: line[start + 1..name_end].to_string(),
+                    format: StoryFormat::Harlowe,
+                    args: args.iter().map(|arg| arg.map(str::to_string)).collect(),
+                    context: context.subcontext(
+                        Position::rel(row + 1, start + 1)..=Position::rel(row + 1, end),
+                    ),
+                });
+            }
+            let name = line[start + 1..name_end]
+                .to_lowercase()
+                .replace(['-', '_'], "");
+            match name.as_str() {
+                "goto" | "display" => {
+                    if let Some(target) = arg(0) {
+                        make_link(start, end, None, target, StoryFormat::Harlowe);
+                    }
+                }
+                "linkgoto" | "linkrevealgoto" => match (args.len(), arg(0), arg(1)) {
+                    (1, Some(target), _) => {
+                        make_link(start, end, None, target, StoryFormat::Harlowe)
+                    }
+                    (_, Some(text), Some(target)) => {
+                        make_link(start, end, Some(text), target, StoryFormat::Harlowe)
+                    }
+                    _ => (),
+                },
+                "clickgoto" | "mouseovergoto" | "mouseoutgoto" => {
+                    if let Some(target) = arg(1) {
+                        make_link(start, end, None, target, StoryFormat::Harlowe);
+                    }
+                }
+                _ => (),
+            }
+            start = end;
+        }
+    }
+    (links, calls)
+}
+
+/// The names of the macros built into SugarCube, including the child tags of
+/// container macros such as `<<case>>` and `<<option>>`
+pub(crate) const SUGARCUBE_MACROS: &[&str] = &[
+    "actions",
+    "addclass",
+    "append",
+    "audio",
+    "back",
+    "break",
+    "button",
+    "cacheaudio",
+    "capture",
+    "case",
+    "checkbox",
+    "choice",
+    "click",
+    "continue",
+    "copy",
+    "createaudiogroup",
+    "createplaylist",
+    "cycle",
+    "default",
+    "display",
+    "do",
+    "done",
+    "else",
+    "elseif",
+    "for",
+    "forget",
+    "goto",
+    "if",
+    "include",
+    "link",
+    "linkappend",
+    "linkprepend",
+    "linkreplace",
+    "listbox",
+    "masteraudio",
+    "next",
+    "nobr",
+    "numberbox",
+    "option",
+    "optionsfrom",
+    "playlist",
+    "prepend",
+    "print",
+    "radiobutton",
+    "redo",
+    "remember",
+    "remove",
+    "removeaudiogroup",
+    "removeclass",
+    "removeplaylist",
+    "repeat",
+    "replace",
+    "return",
+    "run",
+    "script",
+    "set",
+    "setplaylist",
+    "silent",
+    "silently",
+    "stop",
+    "stopallaudio",
+    "switch",
+    "textarea",
+    "textbox",
+    "timed",
+    "toggleclass",
+    "track",
+    "type",
+    "unset",
+    "waitforaudio",
+    "widget",
+];
+
+/// Finds the names of the SugarCube macros defined with `Macro.add` in the
+/// given script, whether by a single name or an array of names. Only names
+/// that are string literals are found
+pub(crate) fn script_macros(script: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut start = 0;
+    while let Some(x) = script[start..].find("Macro.add(") {
+        start += x + "Macro.add(".len();
+        let rest = script[start..].trim_start();
+        let (rest, in_array) = match rest.strip_prefix('[') {
+            Some(rest) => (rest, true),
+            None => (rest, false),
+        };
+        let end = if in_array {
+            closing_bracket(rest)
+        } else {
+            rest.find(',')
+        };
+        let args = &rest[..end.unwrap_or(rest.len())];
+        names.extend(string_args(args, true).into_iter().flatten());
+    }
+    names
+}
+
+/// Counts the conditional branches opened by the macros of the given
+/// [`StoryFormat`] in the given content, which is used as part of the
+/// complexity of a passage. Each condition counts as a branch, so an `else`
+/// without a condition does not add one
+///
+/// The counted macros are:
+/// * SugarCube - `<<if>>`, `<<elseif>>`, `<<else if>>`, and `<<case>>`
+/// * Harlowe - `(if:)`, `(else-if:)`, and `(unless:)`
+///
+/// [`StoryFormat`]: enum.StoryFormat.html
+pub(crate) fn count_branches(content: &str, format: StoryFormat) -> usize {
+    let mut count = 0;
+    match format {
+        StoryFormat::SugarCube => {
+            let mut start = 0;
+            while let Some(x) = content[start..].find("<<") {
+                start += x + 2;
+                let mut words = content[start..].split(|c: char| c.is_whitespace() || c == '>');
+                match words.next() {
+                    Some("if") | Some("elseif") | Some("case") => count += 1,
+                    Some("else") if words.next() == Some("if") => count += 1,
+                    _ => (),
+                }
+            }
+        }
+        StoryFormat::Harlowe => {
+            let mut start = 0;
+            while let Some(x) = content[start..].find('(') {
+                start += x + 1;
+                let name_len = content[start..]
+                    .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+                    .unwrap_or(content.len() - start);
+                if !content[start + name_len..].starts_with(':') {
+                    continue;
+                }
+                let name = content[start..start + name_len]
+                    .to_lowercase()
+                    .replace(['-', '_'], "");
+                if matches!(name.as_str(), "if" | "elseif" | "unless") {
+                    count += 1;
+                }
+            }
+        }
+    }
+    count
+}
+
+/// Splits the arguments of a macro, returning the contents of each argument
+/// that is a string literal, or `None` for any other argument. Arguments are
+/// separated by commas if `commas` is `true`, otherwise by whitespace
+pub(crate) fn string_args(input: &str, commas: bool) -> Vec<Option<&str>> {
+    let is_separator = |c: char| c.is_whitespace() || (commas && c == ',');
+    let mut args = Vec::new();
+    let mut rest = input;
+    loop {
+        rest = rest.trim_start_matches(is_separator);
+        let quote = match rest.chars().next() {
+            Some(c) => c,
+            None => break,
+        };
+        if quote == '"' || quote == '\'' {
+            let end = string_end(&rest[1..], quote).map(|x| x + 1);
+            args.push(end.map(|end| &rest[1..end]));
+            rest = end.map_or("", |end| &rest[end + 1..]);
+        } else {
+            // Skip over an argument that isn't a string literal, along with
+            // anything nested inside of it
+            let mut depth = 0;
+            let end = rest
+                .char_indices()
+                .find(|&(_, c)| {
+                    match c {
+                        '(' | '[' | '{' => depth += 1,
+                        ')' | ']' | '}' => depth -= 1,
+                        _ => (),
+                    }
+                    depth <= 0 && is_separator(c)
+                })
+                .map_or(rest.len(), |(i, _)| i);
+            args.push(None);
+            rest = &rest[end..];
+        }
+    }
+    args
+}
+
+/// Returns the index of the `]` that closes an array, skipping over string
+/// literals
+fn closing_bracket(input: &str) -> Option<usize> {
+    let mut i = 0;
+    while i < input.len() {
+        let c = input[i..].chars().next()?;
+        match c {
+            '"' | '\'' => i += string_end(&input[i + 1..], c)? + 1,
+            ']' => return Some(i),
+            _ => (),
+        }
+        i += c.len_utf8();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets(input: &str) -> Vec<(String, Option<String>, LinkKind)> {
+        let context = FullContext::from(None, input.to_string());
+        parse_macros(&context, &[])
+            .0
+            .into_iter()
+            .map(|link| (link.target.to_string(), link.display_text, link.kind))
+            .collect()
+    }
+
+    #[test]
+    fn branches() {
+        let input = "<<if $a>>A<<elseif $b>>B<<else if $c>>C<<else>>D<</if>> <<switch $x>><<case 1>><</switch>> <<iffy>>";
+        assert_eq!(count_branches(input, StoryFormat::SugarCube), 4);
+        assert_eq!(count_branches(input, StoryFormat::Harlowe), 0);
+        let input = "(if: $a)[A](Else-If: $b)[B](else:)[C] (unless: $c)[D] (ifx: 1) (if $d)";
+        assert_eq!(count_branches(input, StoryFormat::Harlowe), 3);
+    }
+
+    #[test]
+    fn sugarcube() {
+        let sc = LinkKind::Macro(StoryFormat::SugarCube);
+        let input = r#"<<goto "Home">> <<link "Go \"now\"" 'Away'>><</link>>
+<<button "Text">><</button>> <<include $passage>> <<actions "A" $b "C">>"#;
+        assert_eq!(
+            targets(input),
+            [
+                ("Home".to_string(), None, sc),
+                ("Away".to_string(), Some("Go \\\"now\\\"".to_string()), sc),
+                ("A".to_string(), None, sc),
+                ("C".to_string(), None, sc),
+            ]
+        );
+    }
+
+    #[test]
+    fn harlowe() {
+        let h = LinkKind::Macro(StoryFormat::Harlowe);
+        let input = r#"(link-goto: "Run (fast)", "Exit") (Go-To: "Home")
+(click-goto: ?hook, "Hook") (link_goto: "Same") (goto: (str: "a", "b")) (print: "x")"#;
+        assert_eq!(
+            targets(input),
+            [
+                ("Exit".to_string(), Some("Run (fast)".to_string()), h),
+                ("Home".to_string(), None, h),
+                ("Hook".to_string(), None, h),
+                ("Same".to_string(), None, h),
+            ]
+        );
+    }
+
+    #[test]
+    fn contexts() {
+        let input = "A <<goto \"B\">>\n(display: 'C')".to_string();
+        let context = FullContext::from(None, input);
+        let links = parse_macros(&context, &[]).0;
+        assert_eq!(links[0].context.get_contents(), "<<goto \"B\">>");
+        assert_eq!(links[1].context.get_contents(), "(display: 'C')");
+        assert_eq!(links[1].target_span().unwrap().get_contents(), "C");
+        assert_eq!(
+            *links[1].target_span().unwrap().get_start_position(),
+            Position::abs(2, 12)
+        );
+
+        let context = FullContext::from(None, "<<link \"Go\" \"Go\">>".to_string());
+        let link = &parse_macros(&context, &[]).0[0];
+        assert_eq!(link.display_text_span().unwrap().get_byte_range(), 8..10);
+        assert_eq!(link.target_span().unwrap().get_byte_range(), 13..15);
+    }
+
+    #[test]
+    fn comments() {
+        let input = "/* <<goto \"A\">> */ (goto: \"B\")".to_string();
+        let context = FullContext::from(None, input);
+        let comment = 0..18;
+        let links = parse_macros(&context, std::slice::from_ref(&comment)).0;
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].target.to_string(), "B");
+    }
+
+    #[test]
+    fn calls() {
+        let input =
+            "<<set $x to 1>> /* <<a>> */\n<<b 'c' [1, 2] \"d\">><</b>> (c: 1) << >>".to_string();
+        let context = FullContext::from(None, input);
+        let comment = 16..27;
+        let calls = parse_macros(&context, std::slice::from_ref(&comment)).1;
+        let names: Vec<(&str, StoryFormat)> = calls
+            .iter()
+            .map(|call| (call.name.as_str(), call.format))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("set", StoryFormat::SugarCube),
+                ("b", StoryFormat::SugarCube),
+                ("c", StoryFormat::Harlowe)
+            ]
+        );
+        assert_eq!(
+            calls[1].args,
+            [Some("c".to_string()), None, Some("d".to_string())]
+        );
+        assert_eq!(calls[1].context.get_start_position().line, 2);
+        assert_eq!(calls[1].context.get_contents(), "<<b 'c' [1, 2] \"d\">>");
+    }
+
+    #[test]
+    fn scripts() {
+        let script = "Macro.add('a', {});\nMacro.add( [\"b\", 'c]', x], {});\nMacro.add(name, {});";
+        assert_eq!(script_macros(script), ["a", "b", "c]"]);
+    }
+}
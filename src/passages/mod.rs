@@ -1,28 +1,74 @@
 mod header;
 pub use header::PassageHeader;
 
+#[cfg(feature = "macro-links")]
+mod macro_call;
+#[cfg(feature = "macro-links")]
+pub use macro_call::MacroArgs;
+#[cfg(feature = "macro-links")]
+pub use macro_call::MacroCall;
+
+#[cfg(feature = "macro-links")]
+mod macro_links;
+#[cfg(feature = "macro-links")]
+pub(crate) use macro_links::script_macros;
+#[cfg(feature = "macro-links")]
+pub(crate) use macro_links::string_args;
+#[cfg(feature = "macro-links")]
+pub(crate) use macro_links::SUGARCUBE_MACROS;
+
 mod passage;
 pub use passage::Passage;
 
+mod passage_builder;
+pub use passage_builder::PassageBuilder;
+
 mod passage_content;
 pub use passage_content::PassageContent;
 
+mod passage_kind;
+pub use passage_kind::PassageKind;
+
+mod passage_ref;
+pub use passage_ref::PassageRef;
+
+#[cfg(any(feature = "macro-links", feature = "markup"))]
+mod scan;
+#[cfg(any(feature = "macro-links", feature = "markup"))]
+pub(crate) use scan::closing_paren;
+#[cfg(feature = "macro-links")]
+pub(crate) use scan::string_end;
+
 mod script_content;
 pub use script_content::ScriptContent;
 
+mod special_content;
+pub use special_content::SpecialContent;
+
 mod story_data;
 pub use story_data::StoryData;
 
+mod story_format;
+pub use story_format::StoryFormat;
+
 mod stylesheet_content;
 pub use stylesheet_content::StylesheetContent;
 
+mod tag_color;
+pub use tag_color::TagColor;
+
 mod story_title;
 pub use story_title::StoryTitle;
 
 mod twine_content;
+pub(crate) use twine_content::find_comments;
+#[cfg(feature = "macro-links")]
+pub(crate) use twine_content::slice_context;
+pub(crate) use twine_content::split_link;
 pub use twine_content::TwineContent;
 
 mod twine_link;
+pub use twine_link::LinkKind;
 pub use twine_link::TwineLink;
 
 mod twine_passage;
@@ -1,11 +1,16 @@
+use crate::Error;
+use crate::ErrorKind;
 use crate::ErrorList;
 use crate::FullContext;
+use crate::HeaderContinuation;
 use crate::Output;
 use crate::PassageContent;
 use crate::PassageHeader;
+use crate::PassageKind;
 use crate::Position;
 use crate::PositionKind;
 use crate::ScriptContent;
+use crate::SpecialContent;
 use crate::StoryData;
 use crate::StoryTitle;
 use crate::StylesheetContent;
@@ -21,7 +26,7 @@ use crate::TwineContent;
 ///
 /// [`PassageHeader`]: struct.PassageHeader.html
 /// [`PassageContent`]: enum.PassageContent.html
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Passage {
     /// The header
     pub header: PassageHeader,
@@ -95,8 +100,50 @@ impl Passage {
         &self.header.tags
     }
 
-    pub(crate) fn parse(context: FullContext) -> Output<Result<Self, ErrorList>> {
-        let header_context = context.subcontext(..=context.end_of_line(1, PositionKind::Relative));
+    /// Returns the [`PassageKind`] of this passage, based on its content
+    ///
+    /// [`PassageKind`]: enum.PassageKind.html
+    pub fn kind(&self) -> PassageKind {
+        match self.content {
+            PassageContent::Normal(_) => PassageKind::Normal,
+            PassageContent::StoryTitle(_) => PassageKind::StoryTitle,
+            PassageContent::StoryData(_) => PassageKind::StoryData,
+            PassageContent::Script(_) => PassageKind::Script,
+            PassageContent::Stylesheet(_) => PassageKind::Stylesheet,
+            PassageContent::StoryAuthor(_)
+            | PassageContent::StorySubtitle(_)
+            | PassageContent::StoryMenu(_)
+            | PassageContent::StorySettings(_) => PassageKind::SpecialOther,
+        }
+    }
+
+    /// Parses a `Passage` out of the given context, handling a header that
+    /// ends with a backslash as given
+    pub(crate) fn parse(
+        context: FullContext,
+        continuation: HeaderContinuation,
+    ) -> Output<Result<Self, ErrorList>> {
+        let first_line = context
+            .get_contents()
+            .split('\n')
+            .next()
+            .unwrap_or_default();
+        let first_line = first_line.trim_end_matches('\r');
+        let header_lines = match continuation {
+            HeaderContinuation::Ignore => 1,
+            HeaderContinuation::Allow => header_lines(context.get_contents()),
+            HeaderContinuation::Reject if first_line.ends_with('\\') => {
+                let end = Position::rel(1, first_line.len());
+                let error = Error::new(
+                    ErrorKind::ContinuedHeader,
+                    Some(context.subcontext(end..=end)),
+                );
+                return Output::new(Err(error.into()));
+            }
+            HeaderContinuation::Reject => 1,
+        };
+        let header_context =
+            context.subcontext(..=context.end_of_line(header_lines, PositionKind::Relative));
         // Parse the first line as the header
         let header = PassageHeader::parse(header_context);
 
@@ -110,15 +157,7 @@ impl Passage {
         // get the Ok side and unwrap it, getting a reference to the header
         let header_ref = header.get_output().as_ref().ok().unwrap();
 
-        // Find the position of the last non-empty line
-        let mut new_iter = context.get_contents().split('\n');
-        new_iter.rfind(|&x| !x.is_empty());
-        let len = new_iter.fold(0, |acc, _| acc + 1);
-
-        // Create the content's context
-        let content_context = context
-            .subcontext(Position::rel(2, 1)..=context.end_of_line(len + 1, PositionKind::Relative));
-        let trimmed_context = context.subcontext(..=content_context.get_end_position());
+        let (content_context, trimmed_context) = content_contexts(&context, header_lines);
 
         // Parse the content based on the type indicated by the header
         let content: Output<Result<PassageContent, ErrorList>>;
@@ -126,6 +165,9 @@ impl Passage {
             StoryTitle::parse(content_context).into_result()
         } else if header_ref.name == "StoryData" {
             StoryData::parse(content_context).into_result()
+        } else if let Some(variant) = special_variant(&header_ref.name) {
+            let (res, warnings) = SpecialContent::parse(content_context).take();
+            Output::new(res.map(variant)).with_warnings(warnings)
         } else if header_ref.has_tag("script") {
             ScriptContent::parse(content_context).into_result()
         } else if header_ref.has_tag("stylesheet") {
@@ -137,6 +179,74 @@ impl Passage {
         // Assemble and return the output
         Self::new(header, content, trimmed_context)
     }
+
+    /// Creates a normal passage out of the given context in place of a
+    /// passage whose header could not be parsed, keeping its content. See
+    /// [`ParseOptions::with_recovery`] for how the passage is named
+    ///
+    /// [`ParseOptions::with_recovery`]: struct.ParseOptions.html#method.with_recovery
+    pub(crate) fn placeholder(
+        context: FullContext,
+        continuation: HeaderContinuation,
+    ) -> Output<Result<Self, ErrorList>> {
+        let header_lines = match continuation {
+            HeaderContinuation::Ignore => 1,
+            _ => header_lines(context.get_contents()),
+        };
+        let header_context =
+            context.subcontext(..=context.end_of_line(header_lines, PositionKind::Relative));
+        let fallback = format!(
+            "Untitled Passage (line {})",
+            context.get_start_position().line
+        );
+        let header = PassageHeader::placeholder(header_context, fallback);
+        let (content_context, trimmed_context) = content_contexts(&context, header_lines);
+        let content = TwineContent::parse(content_context).into_result();
+        Self::new(Output::new(Ok(header)), content, trimmed_context)
+    }
+}
+
+/// Returns the context of the content of the given passage, whose header
+/// spans the given number of lines, along with the context of the passage
+/// without any trailing empty lines
+fn content_contexts(context: &FullContext, header_lines: usize) -> (FullContext, FullContext) {
+    // Find the position of the last non-empty line
+    let mut new_iter = context.get_contents().split('\n');
+    new_iter.rfind(|&x| !x.is_empty() && x != "\r");
+    let len = new_iter.fold(0, |acc, _| acc + 1);
+
+    // Create the content's context
+    let content_context = context.subcontext(
+        Position::rel(header_lines + 1, 1)..=context.end_of_line(len + 1, PositionKind::Relative),
+    );
+    let trimmed_context = context.subcontext(..=content_context.get_end_position());
+    (content_context, trimmed_context)
+}
+
+/// Counts the lines of the header at the start of the given passage, which
+/// continues onto the next line for as long as a line ends with a backslash
+fn header_lines(contents: &str) -> usize {
+    let lines: Vec<&str> = contents.split('\n').collect();
+    let mut count = 1;
+    while count < lines.len() && lines[count - 1].trim_end_matches('\r').ends_with('\\') {
+        count += 1;
+    }
+    count
+}
+
+/// Returns the [`PassageContent`] variant for the special passage with the
+/// given name, if it holds [`SpecialContent`]
+///
+/// [`PassageContent`]: enum.PassageContent.html
+/// [`SpecialContent`]: struct.SpecialContent.html
+fn special_variant(name: &str) -> Option<fn(SpecialContent) -> PassageContent> {
+    match name {
+        "StoryAuthor" => Some(PassageContent::StoryAuthor),
+        "StorySubtitle" => Some(PassageContent::StorySubtitle),
+        "StoryMenu" => Some(PassageContent::StoryMenu),
+        "StorySettings" => Some(PassageContent::StorySettings),
+        _ => None,
+    }
 }
 
 #[cfg(test)]
@@ -145,7 +255,7 @@ mod tests {
 
     fn story_title_subtest(input: String, expected_title: &str) {
         let context = FullContext::from(None, input);
-        let out = Passage::parse(context);
+        let out = Passage::parse(context, HeaderContinuation::Ignore);
         assert_eq!(out.has_warnings(), false);
         let (res, _) = out.take();
         assert_eq!(res.is_ok(), true);
@@ -176,7 +286,7 @@ mod tests {
     fn script_passage() {
         let input = ":: Script Passage [script]\nfoo\nbar".to_string();
         let context = FullContext::from(None, input);
-        let out = Passage::parse(context);
+        let out = Passage::parse(context, HeaderContinuation::Ignore);
         assert_eq!(out.has_warnings(), false);
         let (res, _) = out.take();
         assert_eq!(res.is_ok(), true);
@@ -197,7 +307,7 @@ mod tests {
     fn stylesheet_passage() {
         let input = ":: Style Passage [stylesheet]\nfoo\nbar".to_string();
         let context = FullContext::from(None, input);
-        let out = Passage::parse(context);
+        let out = Passage::parse(context, HeaderContinuation::Ignore);
         assert_eq!(out.has_warnings(), false);
         let (res, _) = out.take();
         assert_eq!(res.is_ok(), true);
@@ -226,7 +336,7 @@ That
 "#
         .to_string();
         let context = FullContext::from(None, input_string);
-        let out = Passage::parse(context);
+        let out = Passage::parse(context, HeaderContinuation::Ignore);
         assert_eq!(out.has_warnings(), false);
         let (res, _) = out.take();
         assert_eq!(res.is_ok(), true);
@@ -241,4 +351,89 @@ That
         };
         assert_eq!(expected, true);
     }
+
+    #[test]
+    fn kinds() {
+        for (input, kind) in [
+            (":: Start\nHello", PassageKind::Normal),
+            (":: StoryTitle\nTitle", PassageKind::StoryTitle),
+            (":: StoryData\n{}", PassageKind::StoryData),
+            (":: Code [script]\nx = 1;", PassageKind::Script),
+            (":: Style [stylesheet]\nbody {}", PassageKind::Stylesheet),
+            (":: StorySettings\nundo:on", PassageKind::SpecialOther),
+        ]
+        .iter()
+        {
+            let context = FullContext::from(None, input.to_string());
+            let passage = Passage::parse(context, HeaderContinuation::Ignore)
+                .take()
+                .0
+                .ok()
+                .unwrap();
+            assert_eq!(passage.kind(), *kind);
+        }
+    }
+
+    #[test]
+    fn header_continuation() {
+        let input = ":: Start [a b \\\r\n  c d] \\\n  {\"size\": \"1,2\"}\nHello\n".to_string();
+        let context = FullContext::from(None, input.clone());
+        let passage = Passage::parse(context, HeaderContinuation::Allow)
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        assert_eq!(passage.header.name, "Start");
+        assert_eq!(passage.header.tags, ["a", "b", "c", "d"]);
+        assert_eq!(passage.header.size(), Some((1.0, 2.0)));
+        let span = &passage.header.tag_spans()[2];
+        assert_eq!(span.get_contents(), "c");
+        assert_eq!(*span.get_start_position(), Position::abs(2, 3));
+        let span = passage.header.metadata_span().unwrap();
+        assert_eq!(span.get_contents(), "{\"size\": \"1,2\"}");
+        assert_eq!(*span.get_start_position(), Position::abs(3, 3));
+        match passage.content {
+            PassageContent::Normal(content) => assert_eq!(content.content, "Hello\n"),
+            _ => panic!("Expected normal passage"),
+        }
+
+        let context = FullContext::from(None, ":: Start {\"size\": \\\n  }\n".to_string());
+        let (res, warnings) = Passage::parse(context, HeaderContinuation::Allow).take();
+        assert!(res.is_ok());
+        assert_eq!(
+            warnings[0]
+                .context
+                .as_ref()
+                .unwrap()
+                .get_start_position()
+                .line,
+            2
+        );
+
+        let context = FullContext::from(None, input.clone());
+        let errors = Passage::parse(context, HeaderContinuation::Reject)
+            .take()
+            .0
+            .err()
+            .unwrap();
+        assert_eq!(errors.errors[0].kind, ErrorKind::ContinuedHeader);
+        let error_start = errors.errors[0]
+            .context
+            .as_ref()
+            .unwrap()
+            .get_start_position();
+        assert_eq!((error_start.line, error_start.column), (1, 15));
+
+        let context = FullContext::from(None, ":: Start [a] \\\nb".to_string());
+        let passage = Passage::parse(context, HeaderContinuation::Ignore)
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        assert_eq!(passage.header.tags, ["a"]);
+        match passage.content {
+            PassageContent::Normal(content) => assert_eq!(content.content, "b\n"),
+            _ => panic!("Expected normal passage"),
+        }
+    }
 }
@@ -0,0 +1,151 @@
+use crate::escape::escape_passage_name;
+use crate::ErrorList;
+use crate::FullContext;
+use crate::HeaderContinuation;
+use crate::Output;
+use crate::Passage;
+
+/// A builder for creating a [`Passage`] in code rather than parsing it from a
+/// file, such as when generating a story from another format
+///
+/// The passage is built by writing the Twee code for it and parsing that, so
+/// the built passage has a synthesized [`FullContext`] with no file name, and
+/// the links in its content are extracted just as they would be for a parsed
+/// passage. The kind of passage is decided by its name and tags in the same
+/// way, so a passage named `StoryTitle` holds a title and one tagged `script`
+/// holds a script.
+///
+/// # Examples
+/// ```
+/// use tweep::{PassageBuilder, PassageContent};
+/// let passage = PassageBuilder::new("Cave")
+///     .with_tag("dark")
+///     .with_metadata("position", serde_json::json!("600,400"))
+///     .with_content("It is dark. [[Leave|Start]]")
+///     .build()
+///     .take()
+///     .0
+///     .ok()
+///     .unwrap();
+/// assert_eq!(passage.header.name, "Cave");
+/// assert_eq!(passage.header.position(), Some((600.0, 400.0)));
+/// if let PassageContent::Normal(twine) = &passage.content {
+///     assert_eq!(twine.get_links()[0].target, "Start");
+/// }
+/// ```
+///
+/// [`Passage`]: struct.Passage.html
+/// [`FullContext`]: struct.FullContext.html
+#[derive(Clone, Debug, Default)]
+pub struct PassageBuilder {
+    name: String,
+    tags: Vec<String>,
+    metadata: serde_json::Map<String, serde_json::Value>,
+    content: String,
+}
+
+impl PassageBuilder {
+    /// Creates a builder for an empty passage with the given name
+    pub fn new(name: &str) -> Self {
+        PassageBuilder {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds a tag to the passage. Since tags are separated by whitespace, a
+    /// tag containing whitespace becomes several tags
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// Sets the metadata field `key` to `value`, replacing any earlier value
+    pub fn with_metadata(mut self, key: &str, value: serde_json::Value) -> Self {
+        self.metadata.insert(key.to_string(), value);
+        self
+    }
+
+    /// Sets the content of the passage
+    pub fn with_content(mut self, content: &str) -> Self {
+        self.content = content.to_string();
+        self
+    }
+
+    /// Writes the Twee code for the passage
+    pub(crate) fn to_twee(&self) -> String {
+        let mut twee = format!(":: {}", escape_passage_name(&self.name));
+        if !self.tags.is_empty() {
+            twee.push_str(&format!(" [{}]", self.tags.join(" ")));
+        }
+        if !self.metadata.is_empty() {
+            let metadata = serde_json::Value::Object(self.metadata.clone());
+            twee.push_str(&format!(" {}", metadata));
+        }
+        twee.push('\n');
+        twee.push_str(&self.content);
+        twee
+    }
+
+    /// Builds the passage, along with any warnings produced while parsing the
+    /// synthesized Twee code. See [`Passage`] for the possible errors and
+    /// warnings
+    ///
+    /// [`Passage`]: struct.Passage.html
+    pub fn build(self) -> Output<Result<Passage, ErrorList>> {
+        let context = FullContext::from(None, self.to_twee());
+        Passage::parse(context, HeaderContinuation::Ignore)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PassageContent;
+    use crate::WarningKind;
+
+    #[test]
+    fn build() {
+        let builder = PassageBuilder::new("A [b]")
+            .with_tag("x")
+            .with_tag("y")
+            .with_metadata("size", serde_json::json!("200,100"))
+            .with_content("Hi\n:: Not a header\n[[Next]]");
+        assert_eq!(
+            builder.to_twee(),
+            ":: A \\[b\\] [x y] {\"size\":\"200,100\"}\nHi\n:: Not a header\n[[Next]]"
+        );
+
+        let (res, warnings) = builder.build().take();
+        let kinds: Vec<WarningKind> = warnings.into_iter().map(|w| w.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                WarningKind::EscapedOpenSquare,
+                WarningKind::EscapedCloseSquare
+            ]
+        );
+        let passage = res.ok().unwrap();
+        assert_eq!(passage.header.name, "A [b]");
+        assert_eq!(passage.tags(), &["x", "y"]);
+        assert_eq!(passage.header.size(), Some((200.0, 100.0)));
+        assert_eq!(passage.context.get_file_name(), &None);
+        match &passage.content {
+            PassageContent::Normal(twine) => {
+                assert_eq!(twine.content, "Hi\n:: Not a header\n[[Next]]\n");
+                assert_eq!(twine.get_links().len(), 1);
+            }
+            _ => panic!("Expected a normal passage"),
+        }
+
+        let script = PassageBuilder::new("Code")
+            .with_tag("script")
+            .with_content("let x = 1;")
+            .build()
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        assert!(matches!(script.content, PassageContent::Script(_)));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::ScriptContent;
+use crate::SpecialContent;
 use crate::StoryData;
 use crate::StoryTitle;
 use crate::StylesheetContent;
@@ -7,7 +8,7 @@ use crate::TwineContent;
 /// An enum of the types of content that can be inside a [`Passage`]
 ///
 /// [`Passage`]: struct.Passage.html
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum PassageContent {
     /// A non-special passage that contains Twine content
     Normal(TwineContent),
@@ -18,6 +19,18 @@ pub enum PassageContent {
     /// A passage that contains the story data defined by the specification
     StoryData(Option<StoryData>),
 
+    /// A passage that contains the author of the story
+    StoryAuthor(SpecialContent),
+
+    /// A passage that contains the subtitle of the story
+    StorySubtitle(SpecialContent),
+
+    /// A passage that contains the menu of the story
+    StoryMenu(SpecialContent),
+
+    /// A passage that contains the settings of the story
+    StorySettings(SpecialContent),
+
     /// A passage that is tagged with `script` and contains a script
     Script(ScriptContent),
 
@@ -0,0 +1,39 @@
+/// The kind of a [`Passage`], as returned by [`Passage::kind`]
+///
+/// New kinds of passages may be added in minor releases, so matches on this
+/// enum from outside of `tweep` must include a wildcard arm.
+///
+/// # Examples
+/// ```
+/// use tweep::{PassageKind, StoryPassages};
+/// let input = ":: StoryTitle\nA Story\n\n:: StoryAuthor\nMe\n\n:: Start\nHello\n".to_string();
+/// let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+/// assert_eq!(story.title.unwrap().kind(), PassageKind::StoryTitle);
+/// assert_eq!(story.author.unwrap().kind(), PassageKind::SpecialOther);
+/// assert_eq!(story.passages["Start"].kind(), PassageKind::Normal);
+/// ```
+///
+/// [`Passage`]: struct.Passage.html
+/// [`Passage::kind`]: struct.Passage.html#method.kind
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum PassageKind {
+    /// A non-special passage that contains Twine content
+    Normal,
+
+    /// The `StoryTitle` passage
+    StoryTitle,
+
+    /// The `StoryData` passage
+    StoryData,
+
+    /// A passage tagged with `script`
+    Script,
+
+    /// A passage tagged with `stylesheet`
+    Stylesheet,
+
+    /// Any other special passage, such as `StoryAuthor` or `StoryMenu`
+    SpecialOther,
+}
@@ -0,0 +1,100 @@
+use crate::StoryData;
+use crate::TwinePassage;
+
+/// A borrowed view of any passage in a [`Story`], used to walk every part of
+/// a story uniformly
+///
+/// A [`Story`] only keeps the contents of its special, script, and stylesheet
+/// passages, so only a `Twine` passage has tags and metadata, and script and
+/// stylesheet passages have no name.
+///
+/// # Examples
+/// ```
+/// use tweep::{PassageRef, Story};
+/// let input = ":: StoryTitle\nMy Story\n\n:: Start [tag]\nHello\n".to_string();
+/// let story = Story::from_string(input).take().0.ok().unwrap();
+/// let names: Vec<&str> = story.iter_all().filter_map(|p| p.name()).collect();
+/// assert_eq!(names, vec!["StoryTitle", "Start"]);
+/// let start = story.iter_passages().next().unwrap();
+/// assert_eq!(start.tags(), &["tag".to_string()]);
+/// assert_eq!(start.content(), Some("Hello\n"));
+/// ```
+///
+/// [`Story`]: struct.Story.html
+#[derive(Clone, Copy, Debug)]
+pub enum PassageRef<'a> {
+    /// The title from the `StoryTitle` passage
+    StoryTitle(&'a str),
+
+    /// The parsed contents of the `StoryData` passage
+    StoryData(&'a StoryData),
+
+    /// The contents of the `StoryAuthor` passage
+    StoryAuthor(&'a str),
+
+    /// The contents of the `StorySubtitle` passage
+    StorySubtitle(&'a str),
+
+    /// The contents of the `StoryMenu` passage
+    StoryMenu(&'a str),
+
+    /// The contents of the `StorySettings` passage
+    StorySettings(&'a str),
+
+    /// A normal passage
+    Twine(&'a TwinePassage),
+
+    /// The contents of a passage tagged `script`
+    Script(&'a str),
+
+    /// The contents of a passage tagged `stylesheet`
+    Stylesheet(&'a str),
+}
+
+impl<'a> PassageRef<'a> {
+    /// Returns the name of the passage, if it is known
+    pub fn name(&self) -> Option<&'a str> {
+        match self {
+            PassageRef::StoryTitle(_) => Some("StoryTitle"),
+            PassageRef::StoryData(_) => Some("StoryData"),
+            PassageRef::StoryAuthor(_) => Some("StoryAuthor"),
+            PassageRef::StorySubtitle(_) => Some("StorySubtitle"),
+            PassageRef::StoryMenu(_) => Some("StoryMenu"),
+            PassageRef::StorySettings(_) => Some("StorySettings"),
+            PassageRef::Twine(passage) => Some(&passage.header.name),
+            PassageRef::Script(_) | PassageRef::Stylesheet(_) => None,
+        }
+    }
+
+    /// Returns the tags of the passage. This is empty for any passage other
+    /// than a `Twine` passage
+    pub fn tags(&self) -> &'a [String] {
+        match self {
+            PassageRef::Twine(passage) => passage.tags(),
+            _ => &[],
+        }
+    }
+
+    /// Returns the metadata of the passage, if it is a `Twine` passage
+    pub fn metadata(&self) -> Option<&'a serde_json::Map<String, serde_json::Value>> {
+        match self {
+            PassageRef::Twine(passage) => Some(passage.metadata()),
+            _ => None,
+        }
+    }
+
+    /// Returns the text content of the passage. This is `None` for the
+    /// `StoryData` passage, whose contents are only kept in parsed form
+    pub fn content(&self) -> Option<&'a str> {
+        match self {
+            PassageRef::StoryTitle(content)
+            | PassageRef::StoryAuthor(content)
+            | PassageRef::StorySubtitle(content)
+            | PassageRef::StoryMenu(content)
+            | PassageRef::StorySettings(content) => Some(content),
+            PassageRef::StoryData(_) => None,
+            PassageRef::Twine(passage) => Some(&passage.content.content),
+            PassageRef::Script(content) | PassageRef::Stylesheet(content) => Some(content),
+        }
+    }
+}
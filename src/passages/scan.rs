@@ -0,0 +1,55 @@
+/// Returns the index of the quote that closes a string literal, skipping any
+/// escaped quotes
+pub(crate) fn string_end(input: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in input.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            c if c == quote && !escaped => return Some(i),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+/// Returns the index of the `)` that closes a Harlowe macro, given the input
+/// just after the colon that follows the macro name, skipping over string
+/// literals and nested parentheses
+pub(crate) fn closing_paren(input: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < input.len() {
+        let c = input[i..].chars().next()?;
+        match c {
+            '"' | '\'' => i += string_end(&input[i + 1..], c)? + 1,
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            ')' => depth -= 1,
+            _ => (),
+        }
+        i += c.len_utf8();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings() {
+        assert_eq!(string_end("abc\" rest", '"'), Some(3));
+        assert_eq!(string_end("a\\\"b\"", '"'), Some(4));
+        assert_eq!(string_end("a\\\\\"b", '"'), Some(3));
+        assert_eq!(string_end("it's'", '"'), None);
+    }
+
+    #[test]
+    fn parens() {
+        assert_eq!(closing_paren(" \"Cave\")"), Some(7));
+        assert_eq!(closing_paren(" (a: 1), 'b)')"), Some(13));
+        assert_eq!(closing_paren(" \"a \\\" )\")"), Some(9));
+        assert_eq!(closing_paren(" \"unclosed)"), None);
+        assert_eq!(closing_paren(" (a: 1"), None);
+    }
+}
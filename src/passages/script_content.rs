@@ -13,7 +13,7 @@ use crate::Output;
 /// None
 ///
 /// [`Passage`]: struct.Passage.html
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ScriptContent {
     /// The full content of the passage
     pub content: String,
@@ -23,7 +23,7 @@ impl ScriptContent {
     /// Parses a `ScriptContent` out of the given context
     pub fn parse(context: FullContext) -> Output<Result<Self, ErrorList>> {
         Output::new(Ok(ScriptContent {
-            content: context.get_contents().to_string(),
+            content: context.get_normalized_contents(),
         }))
     }
 }
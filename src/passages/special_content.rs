@@ -0,0 +1,54 @@
+use crate::ErrorList;
+use crate::FullContext;
+use crate::Output;
+
+/// The content of one of the special passages that hold a piece of text about
+/// the story: `StoryAuthor`, `StorySubtitle`, `StoryMenu`, or `StorySettings`
+///
+/// No validation is done when parsing this content.
+///
+/// # Parse Errors
+/// None
+///
+/// # Parse Warnings
+/// None
+///
+/// # Examples
+/// ```
+/// use tweep::{FullContext, SpecialContent};
+/// let context = FullContext::from(None, "Jane Doe".to_string());
+/// let out = SpecialContent::parse(context);
+/// assert_eq!(out.get_output().as_ref().ok().unwrap().content, "Jane Doe");
+/// ```
+#[derive(Clone, Debug)]
+pub struct SpecialContent {
+    /// The full content of the passage
+    pub content: String,
+}
+
+impl SpecialContent {
+    /// Parses a `SpecialContent` out of the given context
+    pub fn parse(context: FullContext) -> Output<Result<Self, ErrorList>> {
+        Output::new(Ok(SpecialContent {
+            content: context.get_normalized_contents(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic() {
+        let input = r#"undo: on
+bookmark: off"#
+            .to_string();
+        let out = SpecialContent::parse(FullContext::from(None, input.clone()));
+        assert!(!out.has_warnings());
+        let (res, _) = out.take();
+        assert!(res.is_ok());
+        let content = res.ok().unwrap();
+        assert_eq!(content.content, input);
+    }
+}
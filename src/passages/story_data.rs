@@ -1,13 +1,26 @@
+use crate::stories::closest_name;
 use crate::ErrorList;
 use crate::FullContext;
 use crate::Output;
 use crate::Position;
+use crate::StoryFormat;
+use crate::TagColor;
 use crate::Warning;
 use crate::WarningKind;
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
+/// The keys of the fields defined by the specification
+const KNOWN_KEYS: [&str; 6] = [
+    "ifid",
+    "format",
+    "format-version",
+    "start",
+    "tag-colors",
+    "zoom",
+];
+
 /// The content of a special passage with the name StoryData that contains a
 /// JSON blob with various information about the story.
 ///
@@ -20,12 +33,23 @@ use serde::{Deserialize, Serialize};
 /// * tag-colors - Object of tag(string):color(string) pairs. Pairs map to <tw-tag> nodes as <tw-tag name>:<tw-tag color>.
 /// * zoom - Decimal. Maps to <tw-storydata zoom>.
 ///
+/// Any other fields are kept in `extra`.
+///
 /// # Parse Errors
 /// None
 ///
 /// # Parse Warnings
 /// * [`JsonError`] - Error encountered while parsing the JSON content
-#[derive(Debug, Serialize, Deserialize)]
+/// * [`UnknownTagColor`] - A color in `tag-colors` that Twine will not render
+/// * [`MisspelledStoryDataKey`] - A key that is close to one defined by the
+///   specification
+/// * [`InvalidZoom`] - A `zoom` that is not above 0 and at most 1
+///
+/// [`JsonError`]: enum.WarningKind.html#variant.JsonError
+/// [`UnknownTagColor`]: enum.WarningKind.html#variant.UnknownTagColor
+/// [`MisspelledStoryDataKey`]: enum.WarningKind.html#variant.MisspelledStoryDataKey
+/// [`InvalidZoom`]: enum.WarningKind.html#variant.InvalidZoom
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StoryData {
     /// Interactive Fiction IDentifier v4 UUID
     pub ifid: String,
@@ -40,37 +64,171 @@ pub struct StoryData {
     /// The starting passage
     pub start: Option<String>,
 
-    /// Map of tag name to color name for coloring tags
+    /// Map of tag name to color for coloring tags
     #[serde(rename = "tag-colors")]
-    pub tag_colors: Option<HashMap<String, String>>,
+    pub tag_colors: Option<HashMap<String, TagColor>>,
 
     /// Zoom level for editing in Twine
     pub zoom: Option<f32>,
+
+    /// Any fields that are not defined by the specification, such as ones
+    /// added by a story format or a tool. These are written back out when the
+    /// `StoryData` is serialized
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+
+    /// The whole JSON object the fields were parsed from, including any
+    /// fields that are not defined by the specification. Not serialized
+    #[serde(skip)]
+    raw: serde_json::Value,
 }
 
 impl StoryData {
+    /// Gets the color assigned to the given tag, if there is one
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{FullContext, StoryData, TagColor};
+    /// let input = r#"{"ifid": "abc", "tag-colors": {"scary": "red"}}"#.to_string();
+    /// let data = StoryData::parse(FullContext::from(None, input)).take().0.ok().unwrap().unwrap();
+    /// assert_eq!(data.tag_color("scary"), Some(&TagColor::Red));
+    /// assert_eq!(data.tag_color("happy"), None);
+    /// ```
+    pub fn tag_color(&self, tag: &str) -> Option<&TagColor> {
+        self.tag_colors.as_ref().and_then(|colors| colors.get(tag))
+    }
+
+    /// Returns the [`StoryFormat`] named in the `format` field, if it is one
+    /// that tweep understands
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{FullContext, StoryData, StoryFormat};
+    /// let input = r#"{"ifid": "abc", "format": "SugarCube"}"#.to_string();
+    /// let data = StoryData::parse(FullContext::from(None, input)).take().0.ok().unwrap().unwrap();
+    /// assert_eq!(data.story_format(), Some(StoryFormat::SugarCube));
+    /// ```
+    ///
+    /// [`StoryFormat`]: enum.StoryFormat.html
+    pub fn story_format(&self) -> Option<StoryFormat> {
+        self.format.as_deref().and_then(StoryFormat::from_name)
+    }
+
+    /// Returns the whole JSON object that this `StoryData` was parsed from,
+    /// including any fields that are not defined by the specification, such
+    /// as ones added by a story format or a tool. This is `Null` if the
+    /// `StoryData` was not parsed from JSON, such as one that was
+    /// deserialized
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{FullContext, StoryData};
+    /// let input = r#"{"ifid": "abc", "x-editor": {"theme": "dark"}}"#.to_string();
+    /// let data = StoryData::parse(FullContext::from(None, input)).take().0.ok().unwrap().unwrap();
+    /// assert_eq!(data.raw_json()["x-editor"]["theme"], "dark");
+    /// assert_eq!(data.raw_json()["ifid"], data.ifid.as_str());
+    /// ```
+    pub fn raw_json(&self) -> &serde_json::Value {
+        &self.raw
+    }
+
     /// Parses a `StoryData` out of the given context
     pub fn parse(context: FullContext) -> Output<Result<Option<Self>, ErrorList>> {
         let mut warnings = Vec::new();
         let res: serde_json::Result<StoryData> = serde_json::from_str(context.get_contents());
 
         let story_data = if res.is_ok() {
-            Some(res.ok().unwrap())
+            let mut story_data = res.ok().unwrap();
+            // The contents are known to be valid JSON at this point
+            story_data.raw = serde_json::from_str(context.get_contents()).unwrap_or_default();
+            let mut unknown: Vec<(&String, &TagColor)> = story_data
+                .tag_colors
+                .iter()
+                .flatten()
+                .filter(|(_, color)| !color.is_known())
+                .collect();
+            unknown.sort_by_key(|(tag, _)| *tag);
+            for (tag, color) in unknown {
+                warnings.push(Warning::new(
+                    WarningKind::UnknownTagColor(tag.clone(), color.to_string()),
+                    Some(StoryData::tag_context(&context, tag)),
+                ));
+            }
+            let mut extra: Vec<&String> = story_data.extra.keys().collect();
+            extra.sort();
+            for key in extra {
+                if let Some(known) = closest_name(key, KNOWN_KEYS.iter().copied()) {
+                    warnings.push(
+                        Warning::new(
+                            WarningKind::MisspelledStoryDataKey(key.clone(), known.to_string()),
+                            Some(StoryData::key_context(&context, key)),
+                        )
+                        .with_suggestion(known.to_string()),
+                    );
+                }
+            }
+            match story_data.zoom {
+                Some(zoom) if zoom <= 0.0 || zoom > 1.0 => warnings.push(Warning::new(
+                    WarningKind::InvalidZoom(story_data.raw["zoom"].to_string()),
+                    Some(StoryData::key_context(&context, "zoom")),
+                )),
+                _ => (),
+            }
+            Some(story_data)
         } else {
             let err = res.err().unwrap();
             // Get the error part of error string generated by serde
             let err_string = format!("{}", err).split(" at ").next().unwrap().to_string();
+            // serde reports a column of 0 for errors at the end of the input
+            let position = Position::rel(err.line(), err.column().max(1));
+            let err_context = context
+                .try_subcontext(position..=position)
+                .unwrap_or_else(|_| context.clone());
             warnings.push(Warning::new(
                 WarningKind::JsonError(err_string),
-                Some(context.subcontext(
-                    Position::rel(err.line(), err.column())
-                        ..=Position::rel(err.line(), err.column()),
-                )),
+                Some(err_context),
             ));
             None
         };
         Output::new(Ok(story_data)).with_warnings(warnings)
     }
+
+    /// Finds the context of the entry for `tag` in the `tag-colors` object,
+    /// falling back to the whole context if it cannot be found
+    fn tag_context(context: &FullContext, tag: &str) -> FullContext {
+        let contents = context.get_contents();
+        let offset = contents.find("\"tag-colors\"").and_then(|colors| {
+            contents[colors..]
+                .find(&format!("\"{}\"", tag))
+                .map(|offset| colors + offset)
+        });
+        StoryData::quoted_context(context, offset, tag)
+    }
+
+    /// Finds the context of the given key, falling back to the whole context
+    /// if it cannot be found
+    fn key_context(context: &FullContext, key: &str) -> FullContext {
+        let offset = context.get_contents().find(&format!("\"{}\"", key));
+        StoryData::quoted_context(context, offset, key)
+    }
+
+    /// Gets the context of `text` in quotes at the given byte offset, falling
+    /// back to the whole context if there is no offset
+    fn quoted_context(context: &FullContext, offset: Option<usize>, text: &str) -> FullContext {
+        let contents = context.get_contents();
+        let quoted = format!("\"{}\"", text);
+        match offset {
+            Some(offset) => {
+                let line = contents[..offset].matches('\n').count() + 1;
+                let line_start = contents[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+                let column = offset - line_start + 1;
+                context.subcontext(
+                    Position::rel(line, column)..=Position::rel(line, column + quoted.len() - 1),
+                )
+            }
+            None => context.clone(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -106,9 +264,9 @@ mod tests {
             assert_eq!(story_data.zoom, Some(0.25));
 
             let expected = if let Some(tag_colors) = story_data.tag_colors {
-                assert_eq!(tag_colors["bar"], "green");
-                assert_eq!(tag_colors["foo"], "red");
-                assert_eq!(tag_colors["qaz"], "blue");
+                assert_eq!(tag_colors["bar"], TagColor::Green);
+                assert_eq!(tag_colors["foo"], TagColor::Red);
+                assert_eq!(tag_colors["qaz"], TagColor::Blue);
 
                 true
             } else {
@@ -147,5 +305,106 @@ mod tests {
                 false
             }
         );
+
+        let (res, warnings) = StoryData::parse(FullContext::from(None, String::new())).take();
+        assert!(res.ok().unwrap().is_none());
+        assert_eq!(
+            warnings[0]
+                .context
+                .as_ref()
+                .unwrap()
+                .get_start_position()
+                .line,
+            1
+        );
+    }
+
+    #[test]
+    fn unknown_tag_colors() {
+        let input = r#"{
+	"ifid": "D674C58C-DEFA-4F70-B7A2-27742230C0FC",
+	"tag-colors": {
+		"bar": "green",
+		"foo": "teal"
+	}
+}"#
+        .to_string();
+        let context = FullContext::from(None, input);
+        let out = StoryData::parse(context.clone());
+        let (res, warnings) = out.take();
+        let data = res.ok().unwrap().unwrap();
+        assert_eq!(data.tag_color("bar"), Some(&TagColor::Green));
+        assert_eq!(
+            data.tag_color("foo"),
+            Some(&TagColor::Other("teal".to_string()))
+        );
+        assert_eq!(
+            warnings,
+            vec![Warning::new(
+                WarningKind::UnknownTagColor("foo".to_string(), "teal".to_string()),
+                Some(context.subcontext(Position::rel(5, 3)..=Position::rel(5, 7))),
+            )]
+        );
+    }
+
+    #[test]
+    fn raw_json() {
+        let input = r#"{
+	"ifid": "D674C58C-DEFA-4F70-B7A2-27742230C0FC",
+	"zoom": 1,
+	"x-custom": [1, 2, 3]
+}"#
+        .to_string();
+        let data = StoryData::parse(FullContext::from(None, input))
+            .take()
+            .0
+            .ok()
+            .unwrap()
+            .unwrap();
+        let raw = data.raw_json();
+        assert_eq!(raw["ifid"], "D674C58C-DEFA-4F70-B7A2-27742230C0FC");
+        assert_eq!(raw["zoom"], 1);
+        assert_eq!(raw["x-custom"], serde_json::json!([1, 2, 3]));
+        assert!(raw.get("format").is_none());
+    }
+
+    #[test]
+    fn extra_fields() {
+        let input = r#"{
+	"ifid": "D674C58C-DEFA-4F70-B7A2-27742230C0FC",
+	"formatVersion": "2.36.1",
+	"x-editor": {"theme": "dark"},
+	"zoom": 1.5
+}"#
+        .to_string();
+        let context = FullContext::from(None, input);
+        let (res, warnings) = StoryData::parse(context.clone()).take();
+        let data = res.ok().unwrap().unwrap();
+        assert_eq!(data.format_version, None);
+        assert_eq!(data.extra["formatVersion"], "2.36.1");
+        assert_eq!(data.extra["x-editor"]["theme"], "dark");
+        assert!(data.extra.get("ifid").is_none());
+        assert_eq!(
+            warnings,
+            vec![
+                Warning::new(
+                    WarningKind::MisspelledStoryDataKey(
+                        "formatVersion".to_string(),
+                        "format-version".to_string()
+                    ),
+                    Some(context.subcontext(Position::rel(3, 2)..=Position::rel(3, 16))),
+                )
+                .with_suggestion("format-version".to_string()),
+                Warning::new(
+                    WarningKind::InvalidZoom("1.5".to_string()),
+                    Some(context.subcontext(Position::rel(5, 2)..=Position::rel(5, 7))),
+                ),
+            ]
+        );
+
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(json["formatVersion"], "2.36.1");
+        assert_eq!(json["x-editor"]["theme"], "dark");
+        assert!(json.get("extra").is_none());
     }
 }
@@ -0,0 +1,57 @@
+/// A story format whose macros tweep understands, as named in the `format`
+/// field of [`StoryData`]
+///
+/// # Examples
+/// ```
+/// use tweep::StoryFormat;
+/// assert_eq!(StoryFormat::from_name("SugarCube"), Some(StoryFormat::SugarCube));
+/// assert_eq!(StoryFormat::from_name("harlowe"), Some(StoryFormat::Harlowe));
+/// assert_eq!(StoryFormat::from_name("Snowman"), None);
+/// ```
+///
+/// [`StoryData`]: struct.StoryData.html
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StoryFormat {
+    /// The Harlowe story format
+    Harlowe,
+
+    /// The SugarCube story format
+    SugarCube,
+}
+
+impl StoryFormat {
+    /// Returns the `StoryFormat` with the given name, ignoring case, or `None`
+    /// if the format is not one that tweep understands
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "harlowe" => Some(StoryFormat::Harlowe),
+            "sugarcube" => Some(StoryFormat::SugarCube),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for StoryFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoryFormat::Harlowe => write!(f, "Harlowe"),
+            StoryFormat::SugarCube => write!(f, "SugarCube"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        for format in [StoryFormat::Harlowe, StoryFormat::SugarCube].iter() {
+            let name = format.to_string();
+            assert_eq!(StoryFormat::from_name(&name), Some(*format));
+            assert_eq!(StoryFormat::from_name(&name.to_uppercase()), Some(*format));
+        }
+        assert_eq!(StoryFormat::from_name(""), None);
+    }
+}
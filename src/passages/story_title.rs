@@ -1,5 +1,6 @@
 use crate::ErrorList;
 use crate::FullContext;
+use crate::MultiLineTitle;
 use crate::Output;
 
 /// The content of a special passage with the `StoryTitle` name, which will be
@@ -18,7 +19,7 @@ use crate::Output;
 /// let out = StoryTitle::parse(context);
 /// assert_eq!(out.get_output().as_ref().ok().unwrap().title, "Example Story");
 /// ```
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct StoryTitle {
     /// The title content
     pub title: String,
@@ -28,9 +29,45 @@ impl StoryTitle {
     /// Parses a `StoryTitle` out of the given context
     pub fn parse(context: FullContext) -> Output<Result<Self, ErrorList>> {
         Output::new(Ok(StoryTitle {
-            title: context.get_contents().to_string(),
+            title: context.get_normalized_contents(),
         }))
     }
+
+    /// Returns `true` if the title spans more than one line, ignoring blank
+    /// lines at its start and end
+    pub fn is_multi_line(&self) -> bool {
+        self.title.trim().contains('\n')
+    }
+
+    /// Normalizes a title that spans more than one line according to the
+    /// given [`MultiLineTitle`]
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{FullContext, MultiLineTitle, StoryTitle};
+    /// let context = FullContext::from(None, "\nFirst\n\nSecond".to_string());
+    /// let mut title = StoryTitle::parse(context).take().0.ok().unwrap();
+    /// title.normalize(MultiLineTitle::FirstLine);
+    /// assert_eq!(title.title, "First");
+    /// assert!(!title.is_multi_line());
+    /// ```
+    ///
+    /// [`MultiLineTitle`]: enum.MultiLineTitle.html
+    pub fn normalize(&mut self, policy: MultiLineTitle) {
+        if !self.is_multi_line() {
+            return;
+        }
+        let mut lines = self
+            .title
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty());
+        match policy {
+            MultiLineTitle::Keep => (),
+            MultiLineTitle::FirstLine => self.title = lines.next().unwrap_or_default().to_string(),
+            MultiLineTitle::Join => self.title = lines.collect::<Vec<&str>>().join(" "),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -50,4 +87,32 @@ baz"#
         let content = res.ok().unwrap();
         assert_eq!(content.title, input);
     }
+
+    #[test]
+    fn normalize() {
+        let input = "  One\r\n\n Two \nThree".to_string();
+        let parse = || {
+            let out = StoryTitle::parse(FullContext::from(None, input.clone()));
+            out.take().0.ok().unwrap()
+        };
+        let mut title = parse();
+        assert!(title.is_multi_line());
+        title.normalize(MultiLineTitle::Keep);
+        assert_eq!(title.title, "  One\n\n Two \nThree");
+
+        let mut title = parse();
+        title.normalize(MultiLineTitle::FirstLine);
+        assert_eq!(title.title, "One");
+
+        let mut title = parse();
+        title.normalize(MultiLineTitle::Join);
+        assert_eq!(title.title, "One Two Three");
+
+        let mut title = StoryTitle {
+            title: " Single ".to_string(),
+        };
+        assert!(!title.is_multi_line());
+        title.normalize(MultiLineTitle::Join);
+        assert_eq!(title.title, " Single ");
+    }
 }
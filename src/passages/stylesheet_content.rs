@@ -13,7 +13,7 @@ use crate::Output;
 /// None
 ///
 /// [`Passage`]: struct.Passage.html
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct StylesheetContent {
     /// The stylesheet content
     pub content: String,
@@ -23,7 +23,7 @@ impl StylesheetContent {
     /// Parses a `StylesheetContent` out of the given context
     pub fn parse(context: FullContext) -> Output<Result<Self, ErrorList>> {
         Output::new(Ok(StylesheetContent {
-            content: context.get_contents().to_string(),
+            content: context.get_normalized_contents(),
         }))
     }
 }
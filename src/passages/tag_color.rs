@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+
+/// A color assigned to a tag in the `tag-colors` field of [`StoryData`]
+///
+/// The named variants are the colors that the Twine 2 editor can render. Any
+/// other value is kept as-is in the `Other` variant.
+///
+/// # Examples
+/// ```
+/// use tweep::TagColor;
+/// assert_eq!(TagColor::from("green"), TagColor::Green);
+/// assert_eq!(TagColor::from("#ff0000"), TagColor::Other("#ff0000".to_string()));
+/// assert!(!TagColor::from("#ff0000").is_known());
+/// ```
+///
+/// [`StoryData`]: struct.StoryData.html
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum TagColor {
+    /// `red`
+    Red,
+
+    /// `orange`
+    Orange,
+
+    /// `yellow`
+    Yellow,
+
+    /// `green`
+    Green,
+
+    /// `blue`
+    Blue,
+
+    /// `purple`
+    Purple,
+
+    /// A color that Twine will not render. Contains the color string
+    Other(String),
+}
+
+impl TagColor {
+    /// Returns `true` if this is a color that Twine can render
+    pub fn is_known(&self) -> bool {
+        !matches!(self, TagColor::Other(_))
+    }
+
+    /// Returns the color as it is written in the `StoryData`
+    pub fn as_str(&self) -> &str {
+        match self {
+            TagColor::Red => "red",
+            TagColor::Orange => "orange",
+            TagColor::Yellow => "yellow",
+            TagColor::Green => "green",
+            TagColor::Blue => "blue",
+            TagColor::Purple => "purple",
+            TagColor::Other(color) => color,
+        }
+    }
+}
+
+impl std::convert::From<&str> for TagColor {
+    fn from(color: &str) -> Self {
+        match color {
+            "red" => TagColor::Red,
+            "orange" => TagColor::Orange,
+            "yellow" => TagColor::Yellow,
+            "green" => TagColor::Green,
+            "blue" => TagColor::Blue,
+            "purple" => TagColor::Purple,
+            _ => TagColor::Other(color.to_string()),
+        }
+    }
+}
+
+impl std::convert::From<String> for TagColor {
+    fn from(color: String) -> Self {
+        match TagColor::from(color.as_str()) {
+            TagColor::Other(_) => TagColor::Other(color),
+            known => known,
+        }
+    }
+}
+
+impl std::convert::From<TagColor> for String {
+    fn from(color: TagColor) -> Self {
+        match color {
+            TagColor::Other(color) => color,
+            known => known.as_str().to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for TagColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let colors = ["red", "orange", "yellow", "green", "blue", "purple", "teal"];
+        for color in colors.iter() {
+            let tag_color = TagColor::from(color.to_string());
+            assert_eq!(tag_color.is_known(), *color != "teal");
+            assert_eq!(String::from(tag_color), *color);
+        }
+
+        let json: TagColor = serde_json::from_str("\"blue\"").unwrap();
+        assert_eq!(json, TagColor::Blue);
+        assert_eq!(
+            serde_json::to_string(&TagColor::Other("Red".to_string())).unwrap(),
+            "\"Red\""
+        );
+    }
+}
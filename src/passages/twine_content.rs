@@ -1,15 +1,20 @@
 use crate::ErrorList;
 use crate::FullContext;
+use crate::LinkKind;
+#[cfg(feature = "macro-links")]
+use crate::MacroCall;
 use crate::Output;
 use crate::Position;
+use crate::StoryFormat;
 use crate::TwineLink;
 use crate::Warning;
 use crate::WarningKind;
+use std::ops::Range;
 
 /// The contents of a Twine passage.
 ///
-/// Contains the content of the passage along with the [`Position`]. The
-/// `get_links` method can be used to retrieve all Twine links that were parsed
+/// Contains the content of the passage along with the contexts of its links
+/// and comments. The `get_links` method can be used to retrieve all Twine links that were parsed
 /// out of the passage.
 ///
 /// # Parse Errors
@@ -18,6 +23,8 @@ use crate::WarningKind;
 /// # Parse Warnings
 /// * [`UnclosedLink`] - An unclosed Twine link such as `[[Passage Name``
 /// * [`WhitespaceInLink`] - Errant whitespace in link such as `[[Display Text-> Passage Name]]`
+/// * [`EmptyLinkTarget`] - A link with no passage name such as `[[ ]]` or
+///   `[[Display Text|]]`. The link is not included in `get_links`
 ///
 /// # Notes
 /// Currently, the supported formats for links are the following:
@@ -28,6 +35,22 @@ use crate::WarningKind;
 /// [[Passage Name<-Display Text]]
 /// ```
 ///
+/// Any of these may be followed by a SugarCube-style setter component, such as
+/// `[[Display Text|Passage Name][$x to 5]]`. The display text, link format, and
+/// setter are available as fields of each [`TwineLink`].
+///
+/// SugarCube-style image links are also supported:
+/// ```tweev3
+/// [img[Image]]
+/// [img[Title|Image]]
+/// [img[Title|Image][Passage Name]]
+/// [img[Title|Image][Passage Name][$x to 5]]
+/// ```
+///
+/// Each of these produces an [`Image`] link whose target is the image, with the
+/// title as its display text. If there is a passage name, it also produces a
+/// [`Simple`] link to that passage, carrying the setter, if there is one.
+///
 /// # Examples
 /// ```
 /// use tweep::{FullContext, Position, TwineContent, TwineLink};
@@ -38,19 +61,40 @@ use crate::WarningKind;
 /// # assert!(!out.has_warnings());
 /// ```
 ///
-/// [`Position`]: enum.Position.html
+/// [`TwineLink`]: struct.TwineLink.html
+/// [`Image`]: enum.LinkKind.html#variant.Image
+/// [`Simple`]: enum.LinkKind.html#variant.Simple
 /// [`UnclosedLink`]: enum.WarningKind.html#variant.UnclosedLink
 /// [`WhitespaceInLink`]: enum.WarningKind.html#variant.WhitespaceInLink
-#[derive(Debug)]
+/// [`EmptyLinkTarget`]: enum.WarningKind.html#variant.EmptyLinkTarget
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TwineContent {
     /// The content of the passage
     pub content: String,
 
-    /// The pid (Passage ID) of the passage
+    /// The pid (Passage ID) of the passage. Pids count up from 1 in the order
+    /// the passages appear in the source: files in the order they are merged,
+    /// which for a directory is sorted by file name unless another
+    /// [`DirectoryOrder`] is set, then passages in the order they appear
+    /// within each file
+    ///
+    /// [`DirectoryOrder`]: enum.DirectoryOrder.html
     pub pid: usize,
 
     /// A list of parsed links in this content
     links: Vec<TwineLink>,
+
+    /// A list of the links created by story format macros in this content
+    #[cfg(feature = "macro-links")]
+    macro_links: Vec<TwineLink>,
+
+    /// A list of the story format macro invocations in this content
+    #[cfg(feature = "macro-links")]
+    macro_calls: Vec<MacroCall>,
+
+    /// The contexts of the comments in this content
+    comments: Vec<FullContext>,
 }
 
 impl TwineContent {
@@ -61,19 +105,161 @@ impl TwineContent {
         &self.links
     }
 
+    /// Gets a [`Vec`] of the contexts of all the `/* ... */` and `<!-- ... -->`
+    /// comments contained within this content. Links inside of a comment are
+    /// not parsed
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{FullContext, TwineContent};
+    /// let input = "Text /* [[Not a link]] */ <!-- Note -->".to_string();
+    /// let content = TwineContent::parse(FullContext::from(None, input)).take().0.ok().unwrap();
+    /// assert!(content.get_links().is_empty());
+    /// let comments: Vec<&str> = content
+    ///     .get_comments()
+    ///     .iter()
+    ///     .map(|comment| comment.get_contents())
+    ///     .collect();
+    /// assert_eq!(comments, ["/* [[Not a link]] */", "<!-- Note -->"]);
+    /// ```
+    ///
+    /// [`Vec`]: std::Vec
+    pub fn get_comments(&self) -> &Vec<FullContext> {
+        &self.comments
+    }
+
+    /// Removes every comment from the content of the passage
+    ///
+    /// The contexts of the links and comments are not changed, and still refer
+    /// to the original source
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{FullContext, TwineContent};
+    /// let input = "Go <!-- somewhere -->[[Home]]/* later */".to_string();
+    /// let mut content = TwineContent::parse(FullContext::from(None, input)).take().0.ok().unwrap();
+    /// content.strip_comments();
+    /// assert_eq!(content.content, "Go [[Home]]\n");
+    /// ```
+    pub fn strip_comments(&mut self) {
+        self.content = without_comments(&self.content);
+    }
+
+    /// Returns the branching complexity of the passage, which is the number of
+    /// links out of the passage. Image links are not counted, but the link
+    /// component of an image link is
+    ///
+    /// With the "macro-links" feature enabled and a [`StoryFormat`] given, the
+    /// links created by the macros of that format are counted as well, along
+    /// with each conditional branch, such as SugarCube's `<<if>>` and
+    /// `<<elseif>>` or Harlowe's `(if:)` and `(else-if:)`. Macros inside of
+    /// comments are not counted. A passage with a high complexity may be
+    /// worth splitting into several passages
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{FullContext, StoryFormat, TwineContent};
+    /// let input = "[[North]] [[South]] [img[map.png]]\n<<if $key>>[[Door]]<</if>>".to_string();
+    /// let content = TwineContent::parse(FullContext::from(None, input)).take().0.ok().unwrap();
+    /// assert_eq!(content.complexity(None), 3);
+    /// # #[cfg(feature = "macro-links")]
+    /// assert_eq!(content.complexity(Some(StoryFormat::SugarCube)), 4);
+    /// ```
+    ///
+    /// [`StoryFormat`]: enum.StoryFormat.html
+    pub fn complexity(&self, format: Option<StoryFormat>) -> usize {
+        let links = self
+            .links
+            .iter()
+            .filter(|link| link.kind != LinkKind::Image)
+            .count();
+        #[cfg(feature = "macro-links")]
+        if let Some(format) = format {
+            let branches = crate::passages::macro_links::count_branches(
+                &without_comments(&self.content),
+                format,
+            );
+            return links + self.get_macro_links(format).count() + branches;
+        }
+        #[cfg(not(feature = "macro-links"))]
+        let _ = format;
+        links
+    }
+
+    /// Returns an iterator over the links created by the macros of the given
+    /// [`StoryFormat`] in this content, such as SugarCube's
+    /// `<<goto "Passage Name">>` or Harlowe's `(link-goto: "Passage Name")`.
+    /// Only macros with a string literal as the passage name are found
+    ///
+    /// Enabled with "macro-links" feature
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{FullContext, StoryFormat, TwineContent};
+    /// let input = "<<link \"Leave\" \"Outside\">><</link>>".to_string();
+    /// let content = TwineContent::parse(FullContext::from(None, input)).take().0.ok().unwrap();
+    /// assert!(content.get_links().is_empty());
+    /// let links: Vec<&str> = content
+    ///     .get_macro_links(StoryFormat::SugarCube)
+    ///     .map(|link| &*link.target)
+    ///     .collect();
+    /// assert_eq!(links, ["Outside"]);
+    /// assert_eq!(content.get_macro_links(StoryFormat::Harlowe).count(), 0);
+    /// ```
+    ///
+    /// [`StoryFormat`]: enum.StoryFormat.html
+    #[cfg(feature = "macro-links")]
+    pub fn get_macro_links(&self, format: StoryFormat) -> impl Iterator<Item = &TwineLink> {
+        self.macro_links
+            .iter()
+            .filter(move |link| link.kind == LinkKind::Macro(format))
+    }
+
+    /// Returns an iterator over the invocations of the macros of the given
+    /// [`StoryFormat`] in this content, in the order they appear, skipping any
+    /// inside of a comment. See [`MacroCall`] for more information
+    ///
+    /// Enabled with "macro-links" feature
+    ///
+    /// [`StoryFormat`]: enum.StoryFormat.html
+    /// [`MacroCall`]: struct.MacroCall.html
+    #[cfg(feature = "macro-links")]
+    pub fn get_macro_calls(&self, format: StoryFormat) -> impl Iterator<Item = &MacroCall> {
+        self.macro_calls
+            .iter()
+            .filter(move |call| call.format == format)
+    }
+
     /// Parses a `TwineContent` out of the given context
     pub fn parse(context: FullContext) -> Output<Result<Self, ErrorList>> {
         let mut links = Vec::new();
         let mut warnings = Vec::new();
-        for (row, line) in context.get_contents().split('\n').enumerate() {
+        let comment_ranges = find_comments(context.get_contents());
+        let in_comment = |offset: usize| comment_ranges.iter().any(|c| c.contains(&offset));
+        let base = context.get_byte_range().start;
+        let first_line = context.get_start_position().line;
+        for (line_number, line, range) in context.lines() {
+            let (row, offset) = (line_number - first_line, range.start - base);
             let mut start = 0;
             loop {
-                start = match line[start..].find("[[") {
+                // Find the next link, which may be an image link: [img[Image]]
+                let twine = line[start..].find("[[");
+                let image = line[start..].find("[img[");
+                let is_image = match (twine, image) {
+                    (Some(t), Some(i)) => i < t,
+                    (None, Some(_)) => true,
+                    _ => false,
+                };
+                start = match if is_image { image } else { twine } {
                     Some(x) => start + x,
                     None => break,
                 };
-                let end = match line[start..].find("]]") {
-                    Some(x) => start + x,
+                if in_comment(offset + start) {
+                    start += 2;
+                    continue;
+                }
+                let end = match link_end(line, start) {
+                    Some(x) => x,
                     None => {
                         warnings.push({
                             Warning::new(
@@ -90,25 +276,39 @@ impl TwineContent {
                 let link_context = context.subcontext(
                     Position::rel(row + 1, start + 1)..=Position::rel(row + 1, end + 2),
                 );
+
+                if is_image {
+                    let image_links =
+                        parse_image_link(&context, row, line, start, end, link_context);
+                    let (mut image_links, mut image_warnings) = image_links;
+                    links.append(&mut image_links);
+                    warnings.append(&mut image_warnings);
+                    start = end;
+                    continue;
+                }
+
                 let link_content = &line[start + 2..end];
-                let linked_passage = if link_content.contains('|') {
-                    // Link format: [[Link Text|Passage Name]]
-                    let mut iter = link_content.split('|');
-                    let _ = iter.next();
-                    iter.next().unwrap()
-                } else if link_content.contains("<-") {
-                    // Link format: [[Passage Name<-Link Text]]
-                    link_content.split("<-").next().unwrap()
-                } else if link_content.contains("->") {
-                    // Link format: [[Link Text->Passage Name]]
-                    let mut iter = link_content.split("->");
-                    let _ = iter.next();
-                    iter.next().unwrap()
-                } else {
-                    // Link format: [[Passage Name]]
-                    link_content
+
+                // Split off a SugarCube-style setter: [[Link][$x to 5]]
+                let (link_content, setter) = match link_content.find("][") {
+                    Some(x) => (&link_content[..x], Some(&link_content[x + 2..])),
+                    None => (link_content, None),
                 };
 
+                let (target_range, display_text, kind) = split_link(link_content);
+                let linked_passage = &link_content[target_range];
+
+                // A link with no target can't be followed, so it is reported
+                // and left out of the links
+                if linked_passage.trim().is_empty() {
+                    warnings.push(Warning::new(
+                        WarningKind::EmptyLinkTarget,
+                        Some(link_context),
+                    ));
+                    start = end;
+                    continue;
+                }
+
                 if linked_passage.starts_with(char::is_whitespace)
                     || linked_passage.ends_with(char::is_whitespace)
                 {
@@ -117,24 +317,313 @@ impl TwineContent {
                     });
                 }
 
-                links.push(TwineLink {
-                    target: linked_passage.to_string(),
-                    context: link_context.clone(),
-                });
+                let target_span = slice_context(&context, row, line, linked_passage);
+                let text_span =
+                    display_text.and_then(|text| slice_context(&context, row, line, text));
+                let mut link = TwineLink::new(linked_passage.to_string(), link_context)
+                    .with_passage_start(*context.get_start_position())
+                    .with_spans(target_span, text_span);
+                if let Some(text) = display_text {
+                    link = link.with_display_text(text.to_string(), kind);
+                }
+                if let Some(setter) = setter {
+                    link = link.with_setter(setter.to_string());
+                }
+                links.push(link);
 
                 start = end;
             }
         }
 
-        let mut content = context.get_contents().to_string();
+        #[cfg(feature = "macro-links")]
+        let (macro_links, macro_calls) =
+            crate::passages::macro_links::parse_macros(&context, &comment_ranges);
+
+        let comments = comment_ranges
+            .iter()
+            .map(|range| {
+                let contents = context.get_contents();
+                context.subcontext(
+                    position_of(contents, range.start)..=position_of(contents, range.end - 1),
+                )
+            })
+            .collect();
+
+        let mut content = context.get_normalized_contents();
         content.push('\n');
         Output::new(Ok(TwineContent {
             content,
             links,
+            #[cfg(feature = "macro-links")]
+            macro_links,
+            #[cfg(feature = "macro-links")]
+            macro_calls,
+            comments,
             pid: 1,
         }))
         .with_warnings(warnings)
     }
+
+    /// Appends the content of `other` to this content, starting on a new
+    /// line, along with its links and comments
+    ///
+    /// The contexts of the appended links and comments are not changed, and
+    /// still refer to the original source
+    pub(crate) fn append(&mut self, other: TwineContent) {
+        if !self.content.is_empty() && !self.content.ends_with('\n') {
+            self.content.push('\n');
+        }
+        self.content.push_str(&other.content);
+        self.links.extend(other.links);
+        #[cfg(feature = "macro-links")]
+        self.macro_links.extend(other.macro_links);
+        #[cfg(feature = "macro-links")]
+        self.macro_calls.extend(other.macro_calls);
+        self.comments.extend(other.comments);
+    }
+
+    /// Changes the target of every link to the passage `old` so that it links
+    /// to the passage `new` instead, rewriting the content to match. Returns
+    /// `true` if any links were changed
+    ///
+    /// The contexts of the links are not changed, and still refer to the
+    /// original source
+    pub(crate) fn rename_link_target(&mut self, old: &str, new: &str) -> bool {
+        let mut renamed = false;
+        let mut content = String::with_capacity(self.content.len());
+        for (row, line) in self.content.split('\n').enumerate() {
+            if row > 0 {
+                content.push('\n');
+            }
+
+            // Scan for links the same way as parse, copying everything up to
+            // each target that needs to be replaced
+            let mut copied = 0;
+            let mut start = 0;
+            loop {
+                start = match line[start..].find("[[") {
+                    Some(x) => start + x,
+                    None => break,
+                };
+                let end = match line[start..].find("]]") {
+                    Some(x) => start + x,
+                    None => break,
+                };
+                let link_content = &line[start + 2..end];
+                let link_content = match link_content.find("][") {
+                    Some(x) => &link_content[..x],
+                    None => link_content,
+                };
+                let target_range = split_link(link_content).0;
+                if link_content[target_range.clone()].trim() == old {
+                    content.push_str(&line[copied..start + 2 + target_range.start]);
+                    content.push_str(new);
+                    copied = start + 2 + target_range.end;
+                    renamed = true;
+                }
+                start = end;
+            }
+            content.push_str(&line[copied..]);
+        }
+
+        for link in self.links.iter_mut() {
+            if link.kind != LinkKind::Image && link.target.trim() == old {
+                link.target = new.to_string();
+            }
+        }
+        self.content = content;
+        renamed
+    }
+}
+
+/// Finds the byte offset of the closing `]]` of the link that starts at byte
+/// offset `start` of the line. A setter may contain brackets of its own, as in
+/// `[[Link][$a to $b[1]]]`, so the end is moved past any closing brackets that
+/// are needed to balance the setter
+fn link_end(line: &str, start: usize) -> Option<usize> {
+    let mut end = start + line[start..].find("]]")?;
+    if let Some(x) = line[start..end].find("][") {
+        let setter = start + x + 2;
+        let unbalanced = |end: usize| {
+            let setter = &line[setter..end];
+            setter.matches('[').count() > setter.matches(']').count()
+        };
+        while unbalanced(end) && line[end + 2..].starts_with(']') {
+            end += 1;
+        }
+    }
+    Some(end)
+}
+
+/// Parses the body of a SugarCube-style image link, which is everything
+/// between `[img[` and the closing `]]` in `[img[Title|Image][Link][Setter]]`,
+/// found between byte offsets `start` and `end` of the given line at index
+/// `row`. Returns an
+/// [`Image`] link to the image, with the title as its display text, followed
+/// by a link to the passage named in the link component, if there is one,
+/// along with any warnings
+///
+/// [`Image`]: enum.LinkKind.html#variant.Image
+fn parse_image_link(
+    context: &FullContext,
+    row: usize,
+    line: &str,
+    start: usize,
+    end: usize,
+    link_context: FullContext,
+) -> (Vec<TwineLink>, Vec<Warning>) {
+    let body = &line[start + 5..end];
+    let mut links = Vec::new();
+    let mut warnings = Vec::new();
+    let passage_start = *context.get_start_position();
+    let mut parts = body.split("][");
+    let image_part = parts.next().unwrap_or_default();
+    let (title, image) = match image_part.find('|') {
+        Some(x) => (Some(&image_part[..x]), &image_part[x + 1..]),
+        None => (None, image_part),
+    };
+    if image.trim().is_empty() {
+        warnings.push(Warning::new(
+            WarningKind::EmptyLinkTarget,
+            Some(link_context.clone()),
+        ));
+    } else {
+        let image_span = slice_context(context, row, line, image);
+        let title_span = title.and_then(|title| slice_context(context, row, line, title));
+        let mut link = TwineLink::new(image.to_string(), link_context)
+            .with_passage_start(passage_start)
+            .with_spans(image_span, title_span);
+        link.kind = LinkKind::Image;
+        link.display_text = title.map(str::to_string);
+        links.push(link);
+    }
+
+    // The link component, if any, is a link to a passage: [img[Image][Link]]
+    if let Some(target) = parts.next() {
+        let open = start + 5 + image_part.len() + 1;
+        let target_context = context.subcontext(
+            Position::rel(row + 1, open + 1)..=Position::rel(row + 1, open + target.len() + 2),
+        );
+        if target.trim().is_empty() {
+            warnings.push(Warning::new(
+                WarningKind::EmptyLinkTarget,
+                Some(target_context),
+            ));
+        } else {
+            if target.starts_with(char::is_whitespace) || target.ends_with(char::is_whitespace) {
+                warnings.push(Warning::new(
+                    WarningKind::WhitespaceInLink,
+                    Some(target_context.clone()),
+                ));
+            }
+            let target_span = slice_context(context, row, line, target);
+            let mut link = TwineLink::new(target.to_string(), target_context)
+                .with_passage_start(passage_start)
+                .with_spans(target_span, None);
+            let setter: Vec<&str> = parts.collect();
+            if !setter.is_empty() {
+                link = link.with_setter(setter.join("]["));
+            }
+            links.push(link);
+        }
+    }
+    (links, warnings)
+}
+
+/// Makes the context of `text`, which must be a slice of `line`, the line at
+/// index `row` of the given context. Returns `None` if `text` is empty
+pub(crate) fn slice_context(
+    context: &FullContext,
+    row: usize,
+    line: &str,
+    text: &str,
+) -> Option<FullContext> {
+    if text.is_empty() {
+        return None;
+    }
+    let start = text.as_ptr() as usize - line.as_ptr() as usize;
+    Some(
+        context.subcontext(
+            Position::rel(row + 1, start + 1)..=Position::rel(row + 1, start + text.len()),
+        ),
+    )
+}
+
+/// Returns a copy of the given content with every comment removed
+fn without_comments(content: &str) -> String {
+    let mut stripped = String::with_capacity(content.len());
+    let mut copied = 0;
+    for range in find_comments(content) {
+        stripped.push_str(&content[copied..range.start]);
+        copied = range.end;
+    }
+    stripped.push_str(&content[copied..]);
+    stripped
+}
+
+/// Finds the byte ranges of the `/* ... */` and `<!-- ... -->` comments in the
+/// given content, including the delimiters. A comment may span multiple lines,
+/// but an opening delimiter without a matching closing delimiter does not start
+/// a comment
+pub(crate) fn find_comments(content: &str) -> Vec<Range<usize>> {
+    let mut comments = Vec::new();
+    let mut start = 0;
+    loop {
+        let block = content[start..].find("/*").map(|x| (start + x, "*/"));
+        let html = content[start..].find("<!--").map(|x| (start + x, "-->"));
+        let (open, close) = match (block, html) {
+            (Some(b), Some(h)) => std::cmp::min_by_key(b, h, |&(x, _)| x),
+            (Some(b), None) => b,
+            (None, Some(h)) => h,
+            (None, None) => break,
+        };
+        let body = open + if close == "*/" { 2 } else { 4 };
+        match content[body..].find(close) {
+            Some(x) => {
+                let end = body + x + close.len();
+                comments.push(open..end);
+                start = end;
+            }
+            None => start = body,
+        }
+    }
+    comments
+}
+
+/// Converts a byte offset into the given content into a relative [`Position`]
+///
+/// [`Position`]: struct.Position.html
+fn position_of(content: &str, offset: usize) -> Position {
+    let line_start = content[..offset].rfind('\n').map_or(0, |x| x + 1);
+    let row = content[..line_start].matches('\n').count() + 1;
+    Position::rel(row, offset - line_start + 1)
+}
+
+/// Splits the body of a link, without the brackets or setter, into the range
+/// of the target passage name, the display text if there is any, and the
+/// format of the link
+pub(crate) fn split_link(link_content: &str) -> (Range<usize>, Option<&str>, LinkKind) {
+    if let Some(x) = link_content.find('|') {
+        // Link format: [[Link Text|Passage Name]]
+        (
+            x + 1..link_content.len(),
+            Some(&link_content[..x]),
+            LinkKind::Pipe,
+        )
+    } else if let Some(x) = link_content.find("<-") {
+        // Link format: [[Passage Name<-Link Text]]
+        (0..x, Some(&link_content[x + 2..]), LinkKind::LeftArrow)
+    } else if let Some(x) = link_content.find("->") {
+        // Link format: [[Link Text->Passage Name]]
+        (
+            x + 2..link_content.len(),
+            Some(&link_content[..x]),
+            LinkKind::RightArrow,
+        )
+    } else {
+        // Link format: [[Passage Name]]
+        (0..link_content.len(), None, LinkKind::Simple)
+    }
 }
 
 #[cfg(test)]
@@ -163,19 +652,82 @@ mod tests {
         let content = res.ok().unwrap();
         let expected_targets = vec!["foo", "bar", "baz", "qux"];
         let expected_lens = vec![7, 17, 18, 19];
+        let expected_texts = [None, Some("Pipe link"), Some("Left link"), Some("Right link")];
+        let expected_kinds = [
+            LinkKind::Simple,
+            LinkKind::Pipe,
+            LinkKind::LeftArrow,
+            LinkKind::RightArrow,
+        ];
         let expected_links: Vec<TwineLink> = (1 as usize..5)
             .map(|row| {
-                TwineLink::new(
+                let link = TwineLink::new(
                     expected_targets[row - 1].to_string(),
                     context.subcontext(
                         Position::rel(row, 1)..=Position::rel(row, expected_lens[row - 1]),
                     ),
-                )
+                );
+                match expected_texts[row - 1] {
+                    Some(text) => link.with_display_text(text.to_string(), expected_kinds[row - 1]),
+                    None => link,
+                }
             })
             .collect();
         assert_eq!(content.get_links(), &expected_links);
     }
 
+    #[test]
+    fn link_positions() {
+        let file = FullContext::from(None, "x\nab [[A]]\ncd [[B]]\n".to_string());
+        let context = file.subcontext(Position::abs(2, 3)..=Position::abs(3, 8));
+        let content = TwineContent::parse(context).take().0.ok().unwrap();
+        let positions: Vec<(Position, Position)> = content
+            .get_links()
+            .iter()
+            .map(|link| (link.passage_position(), link.file_position()))
+            .collect();
+        assert_eq!(
+            positions,
+            [
+                (Position::rel(1, 2), Position::abs(2, 4)),
+                (Position::rel(2, 4), Position::abs(3, 4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn link_spans() {
+        let file = FullContext::from(
+            None,
+            ":: A\n[[B<-go]] [[ C ][$x to 1]]\n[img[Map|map.png][D]] [[|E]]\n".to_string(),
+        );
+        let context = file.subcontext(Position::abs(2, 1)..);
+        let content = TwineContent::parse(context).take().0.ok().unwrap();
+        let spans: Vec<(&str, Option<Position>, Option<&str>)> = content
+            .get_links()
+            .iter()
+            .map(|link| {
+                let target = link.target_span().unwrap();
+                assert_eq!(target.get_contents(), &*link.target);
+                (
+                    target.get_contents(),
+                    Some(*target.get_start_position()),
+                    link.display_text_span().map(|span| span.get_contents()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                ("B", Some(Position::abs(2, 3)), Some("go")),
+                (" C ", Some(Position::abs(2, 13)), None),
+                ("map.png", Some(Position::abs(3, 10)), Some("Map")),
+                ("D", Some(Position::abs(3, 19)), None),
+                ("E", Some(Position::abs(3, 26)), None),
+            ]
+        );
+    }
+
     #[test]
     fn unclosed_link() {
         let context = FullContext::from(None, "blah [[unclosed\nlink]] blah blah\n\n".to_string());
@@ -222,16 +774,205 @@ mod tests {
         let expected_targets = vec![
             " foo", "bar ", "baz ", " qux", "quux ", " quuz", " corge", "grault ",
         ];
+        let expected_kinds = [
+            LinkKind::Simple,
+            LinkKind::Simple,
+            LinkKind::Pipe,
+            LinkKind::Pipe,
+            LinkKind::LeftArrow,
+            LinkKind::LeftArrow,
+            LinkKind::RightArrow,
+            LinkKind::RightArrow,
+        ];
         let expected_links: Vec<TwineLink> = (1 as usize..9)
             .map(|row| {
-                TwineLink::new(
+                let link = TwineLink::new(
                     expected_targets[row - 1].to_string(),
                     context.subcontext(
                         Position::rel(row, 1)..=Position::rel(row, expected_lens[row - 1]),
                     ),
-                )
+                );
+                match expected_kinds[row - 1] {
+                    LinkKind::Simple => link,
+                    kind => link.with_display_text("text".to_string(), kind),
+                }
             })
             .collect();
         assert_eq!(content.get_links(), &expected_links);
     }
+
+    #[test]
+    fn setter_links() {
+        let input = "[[Text|Target][$var to 5]]\n[[Target][$x to 1]]\n[[Text->Target][$y to 2]]"
+            .to_string();
+        let context = FullContext::from(None, input);
+        let out = TwineContent::parse(context.clone());
+        let (res, warnings) = out.take();
+        assert!(warnings.is_empty());
+        let content = res.ok().unwrap();
+        let links = content.get_links();
+        assert_eq!(links.len(), 3);
+
+        assert_eq!(links[0].target, "Target");
+        assert_eq!(links[0].display_text, Some("Text".to_string()));
+        assert_eq!(links[0].kind, LinkKind::Pipe);
+        assert_eq!(links[0].setter, Some("$var to 5".to_string()));
+        assert_eq!(
+            links[0].context,
+            context.subcontext(Position::rel(1, 1)..=Position::rel(1, 26))
+        );
+
+        assert_eq!(links[1].target, "Target");
+        assert_eq!(links[1].get_display_text(), "Target");
+        assert_eq!(links[1].kind, LinkKind::Simple);
+        assert_eq!(links[1].setter, Some("$x to 1".to_string()));
+
+        assert_eq!(links[2].target, "Target");
+        assert_eq!(links[2].get_display_text(), "Text");
+        assert_eq!(links[2].kind, LinkKind::RightArrow);
+        assert_eq!(links[2].setter, Some("$y to 2".to_string()));
+    }
+
+    #[test]
+    fn bracketed_setters() {
+        let input = "[[Text->Target][$a to $b[1]]] [[Next]]]".to_string();
+        let context = FullContext::from(None, input);
+        let (res, warnings) = TwineContent::parse(context.clone()).take();
+        assert!(warnings.is_empty());
+        let content = res.ok().unwrap();
+        let links = content.get_links();
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].target, "Target");
+        assert_eq!(links[0].setter, Some("$a to $b[1]".to_string()));
+        assert_eq!(
+            links[0].context,
+            context.subcontext(Position::rel(1, 1)..=Position::rel(1, 29))
+        );
+        assert_eq!(links[1].target, "Next");
+        assert_eq!(links[1].setter, None);
+    }
+
+    #[test]
+    fn image_links() {
+        let input = "[img[cat.png]] [[A]] [img[A cat|cat.png][Cat][$pet to \"cat\"]]\n[img[dog.png][ Dog ]] [img[ ]] [img[x.png][]] [img[unclosed"
+            .to_string();
+        let context = FullContext::from(None, input);
+        let (res, warnings) = TwineContent::parse(context.clone()).take();
+        let content = res.ok().unwrap();
+        let links = content.get_links();
+        let summary: Vec<(&str, LinkKind, Option<&str>)> = links
+            .iter()
+            .map(|l| (l.target.as_str(), l.kind, l.display_text.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("cat.png", LinkKind::Image, None),
+                ("A", LinkKind::Simple, None),
+                ("cat.png", LinkKind::Image, Some("A cat")),
+                ("Cat", LinkKind::Simple, None),
+                ("dog.png", LinkKind::Image, None),
+                (" Dog ", LinkKind::Simple, None),
+                ("x.png", LinkKind::Image, None),
+            ]
+        );
+        assert_eq!(
+            links[0].context,
+            context.subcontext(Position::rel(1, 1)..=Position::rel(1, 14))
+        );
+        assert_eq!(links[2].setter, None);
+        assert_eq!(links[3].setter, Some("$pet to \"cat\"".to_string()));
+        assert_eq!(
+            links[3].context,
+            context.subcontext(Position::rel(1, 41)..=Position::rel(1, 45))
+        );
+
+        let kinds: Vec<&WarningKind> = warnings.iter().map(|w| &w.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &WarningKind::WhitespaceInLink,
+                &WarningKind::EmptyLinkTarget,
+                &WarningKind::EmptyLinkTarget,
+                &WarningKind::UnclosedLink
+            ]
+        );
+        assert_eq!(
+            warnings[0].context.as_ref().unwrap().get_start_position(),
+            &Position::abs(2, 14)
+        );
+    }
+
+    #[test]
+    fn complexity() {
+        let input = r#"[[A]] [[B]] [img[map.png][C]]
+/* [[Commented]] <<if $x>> */
+<<if $a>>[[D]]<<elseif $b>><<goto "E">><<else>>F<</if>>
+(if: $c)[(link-goto: "G")]"#
+            .to_string();
+        let content = TwineContent::parse(FullContext::from(None, input))
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        assert_eq!(content.complexity(None), 4);
+        #[cfg(feature = "macro-links")]
+        {
+            assert_eq!(content.complexity(Some(StoryFormat::SugarCube)), 7);
+            assert_eq!(content.complexity(Some(StoryFormat::Harlowe)), 6);
+        }
+    }
+
+    #[test]
+    fn rename_links() {
+        let input = "[[Old]] [[Text|Old][$x to 1]] [[ Old ]]\n[[Old<-Back]] [[Oldest]]\n[[Go->Old]] [[unclosed Old"
+            .to_string();
+        let out = TwineContent::parse(FullContext::from(None, input));
+        let mut content = out.take().0.ok().unwrap();
+        assert!(content.rename_link_target("Old", "New"));
+        assert_eq!(
+            content.content,
+            "[[New]] [[Text|New][$x to 1]] [[New]]\n[[New<-Back]] [[Oldest]]\n[[Go->New]] [[unclosed Old\n"
+        );
+        let targets: Vec<&str> = content
+            .get_links()
+            .iter()
+            .map(|l| l.target.as_str())
+            .collect();
+        assert_eq!(targets, ["New", "New", "New", "New", "Oldest", "New"]);
+        assert!(!content.rename_link_target("Missing", "New"));
+    }
+
+    #[test]
+    fn comments() {
+        let input = "a /* [[b]]\n[[c */ [[d]] <!-- [[e]] --> /* [[f]]\n<!-- [[g\n".to_string();
+        let context = FullContext::from(None, input);
+        let out = TwineContent::parse(context.clone());
+        let (res, warnings) = out.take();
+        let mut content = res.ok().unwrap();
+        let targets: Vec<&str> = content
+            .get_links()
+            .iter()
+            .map(|l| l.target.as_str())
+            .collect();
+        assert_eq!(targets, ["d", "f"]);
+        assert_eq!(
+            warnings,
+            [Warning::new(
+                WarningKind::UnclosedLink,
+                Some(context.subcontext(Position::rel(3, 6)..=Position::rel(3, 8)))
+            )]
+        );
+        assert_eq!(
+            content.get_comments(),
+            &vec![
+                context.subcontext(Position::rel(1, 3)..=Position::rel(2, 6)),
+                context.subcontext(Position::rel(2, 14)..=Position::rel(2, 27)),
+            ]
+        );
+
+        content.strip_comments();
+        assert_eq!(content.content, "a  [[d]]  /* [[f]]\n<!-- [[g\n\n");
+        assert_eq!(content.get_comments().len(), 2);
+    }
 }
@@ -1,23 +1,251 @@
 use crate::FullContext;
+use crate::Position;
+use crate::StoryFormat;
+
+/// An enum of the link formats that can be used within a twee passage
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LinkKind {
+    /// A link with no display text: `[[Passage Name]]`
+    Simple,
+
+    /// A link with a pipe separator: `[[Display Text|Passage Name]]`
+    Pipe,
+
+    /// A link with a right arrow: `[[Display Text->Passage Name]]`
+    RightArrow,
+
+    /// A link with a left arrow: `[[Passage Name<-Display Text]]`
+    LeftArrow,
+
+    /// A SugarCube-style image link: `[img[Title|Image]]`. The target is the
+    /// image, which may be a URL rather than a passage name, so image links
+    /// are not checked for dead links unless enabled in the [`ParseOptions`]
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    Image,
+
+    /// A link created by a macro of the given story format, such as
+    /// SugarCube's `<<link "Display Text" "Passage Name">>`. Only found with
+    /// the "macro-links" feature enabled
+    Macro(StoryFormat),
+}
 
 /// A link to a twee passage contained within a twee passage
-#[derive(Debug, Eq, PartialEq)]
+///
+/// # Examples
+/// ```
+/// use tweep::{FullContext, LinkKind, TwineLink};
+/// let context = FullContext::from(None, "[[Go home|Home][$visited to true]]".to_string());
+/// let link = TwineLink::new("Home".to_string(), context)
+///     .with_display_text("Go home".to_string(), LinkKind::Pipe)
+///     .with_setter("$visited to true".to_string());
+/// assert_eq!(link.get_display_text(), "Go home");
+/// assert_eq!(link.kind, LinkKind::Pipe);
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TwineLink {
     /// The name of the passage this link points to
     pub target: String,
 
+    /// The display text of the link, if it is different from the target
+    pub display_text: Option<String>,
+
+    /// The format used to write the link
+    pub kind: LinkKind,
+
+    /// The SugarCube-style setter component of the link, such as `$x to 5` in
+    /// `[[Text|Target][$x to 5]]`, if present
+    pub setter: Option<String>,
+
     /// The context of the link
     pub context: FullContext,
+
+    /// The absolute position of the start of the content of the passage
+    /// containing the link. This is not compared when comparing `TwineLink`s
+    passage_start: Position,
+
+    /// The context of the target within the link, if it is known. This is not
+    /// compared when comparing `TwineLink`s
+    target_span: Option<FullContext>,
+
+    /// The context of the display text within the link, if it is known. This
+    /// is not compared when comparing `TwineLink`s
+    display_text_span: Option<FullContext>,
+}
+
+impl PartialEq for TwineLink {
+    fn eq(&self, other: &Self) -> bool {
+        self.target == other.target
+            && self.display_text == other.display_text
+            && self.kind == other.kind
+            && self.setter == other.setter
+            && self.context == other.context
+    }
 }
 
+impl Eq for TwineLink {}
+
 impl TwineLink {
-    /// Creates a new link with a default [`Position`]
+    /// Creates a new [`Simple`] link with no display text or setter
     ///
-    /// [`Position`]: enum.Position.html
+    /// [`Simple`]: enum.LinkKind.html#variant.Simple
     pub fn new(target: String, context: FullContext) -> Self {
         TwineLink {
             target,
+            display_text: None,
+            kind: LinkKind::Simple,
+            setter: None,
+            passage_start: *context.get_start_position(),
             context,
+            target_span: None,
+            display_text_span: None,
         }
     }
+
+    /// Moves the object, sets the contexts of the target and display text
+    /// within the link, and returns the modified object
+    pub(crate) fn with_spans(
+        mut self,
+        target: Option<FullContext>,
+        display_text: Option<FullContext>,
+    ) -> Self {
+        self.target_span = target;
+        self.display_text_span = display_text;
+        self
+    }
+
+    /// Moves the object, sets the absolute position of the start of the
+    /// content of the passage containing the link, and returns the modified
+    /// object
+    pub(crate) fn with_passage_start(mut self, passage_start: Position) -> Self {
+        self.passage_start = passage_start;
+        self
+    }
+
+    /// Moves the object, sets the display text and the kind of link, and
+    /// returns the modified object
+    pub fn with_display_text(mut self, display_text: String, kind: LinkKind) -> Self {
+        self.display_text = Some(display_text);
+        self.kind = kind;
+        self
+    }
+
+    /// Moves the object, sets the setter component, and returns the modified
+    /// object
+    pub fn with_setter(mut self, setter: String) -> Self {
+        self.setter = Some(setter);
+        self
+    }
+
+    /// Gets the text that will be displayed for this link. This is the display
+    /// text if there is one, or the target otherwise
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{FullContext, TwineLink};
+    /// let context = FullContext::from(None, "[[Home]]".to_string());
+    /// let link = TwineLink::new("Home".to_string(), context);
+    /// assert_eq!(link.get_display_text(), "Home");
+    /// ```
+    pub fn get_display_text(&self) -> &str {
+        self.display_text.as_deref().unwrap_or(&self.target)
+    }
+
+    /// Gets the context of the target within this link, such as `Home` in
+    /// `[[Go home|Home]]`, for placing a cursor or diagnostic on just the
+    /// target. The context covers the text of [`target`] exactly, including
+    /// any whitespace around it. For a link created by a macro, it covers the
+    /// contents of the string literal. Returns `None` if the link was not
+    /// parsed out of a passage
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{Position, StoryPassages};
+    /// let input = ":: Start\nGo [[Go home|Home]]\n\n:: Home\n".to_string();
+    /// let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+    /// # use tweep::PassageContent;
+    /// if let PassageContent::Normal(twine) = &story.passages["Start"].content {
+    ///     let link = &twine.get_links()[0];
+    ///     let target = link.target_span().unwrap();
+    ///     assert_eq!(target.get_contents(), "Home");
+    ///     assert_eq!(*target.get_start_position(), Position::abs(2, 14));
+    ///     assert_eq!(link.display_text_span().unwrap().get_contents(), "Go home");
+    /// }
+    /// ```
+    ///
+    /// [`target`]: #structfield.target
+    pub fn target_span(&self) -> Option<&FullContext> {
+        self.target_span.as_ref()
+    }
+
+    /// Gets the context of the display text within this link, such as
+    /// `Go home` in `[[Go home|Home]]`. Returns `None` if the link has no
+    /// display text, if the display text is empty, or if the link was not
+    /// parsed out of a passage
+    pub fn display_text_span(&self) -> Option<&FullContext> {
+        self.display_text_span.as_ref()
+    }
+
+    /// Gets the position of the start of this link within the file it was
+    /// parsed from, which is the start of its [`context`]. This is the frame
+    /// used by editors and diagnostics
+    ///
+    /// [`context`]: #structfield.context
+    pub fn file_position(&self) -> Position {
+        *self.context.get_start_position()
+    }
+
+    /// Gets the position of the start of this link relative to the content of
+    /// the passage containing it, where line 1 is the first line after the
+    /// passage header. This is the frame used by story formats at runtime. A
+    /// link that was not parsed out of a passage is at line 1, column 1
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{Position, StoryPassages};
+    /// let input = ":: Start\nIntro\n\n:: Cave\nDark\nGo [[Start]]\n".to_string();
+    /// let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+    /// # use tweep::PassageContent;
+    /// if let PassageContent::Normal(twine) = &story.passages["Cave"].content {
+    ///     let link = &twine.get_links()[0];
+    ///     assert_eq!(link.passage_position(), Position::rel(2, 4));
+    ///     assert_eq!(link.file_position(), Position::abs(6, 4));
+    /// }
+    /// ```
+    pub fn passage_position(&self) -> Position {
+        let start = self.file_position();
+        // The context is public, so it may have been replaced with one that
+        // starts before the passage
+        let column = if start.line == self.passage_start.line {
+            start.column.saturating_sub(self.passage_start.column) + 1
+        } else {
+            start.column
+        };
+        Position::rel(
+            start.line.saturating_sub(self.passage_start.line) + 1,
+            column,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moved_context() {
+        let context = FullContext::from(None, "Intro\nGo [[Start]]\n".to_string());
+        let link_context = context.subcontext(Position::rel(2, 4)..=Position::rel(2, 12));
+        let mut link = TwineLink::new("Start".to_string(), link_context.clone())
+            .with_passage_start(Position::abs(2, 1));
+        assert_eq!(link.passage_position(), Position::rel(1, 4));
+
+        link.context = context.subcontext(Position::rel(1, 1)..=Position::rel(1, 5));
+        assert_eq!(link.passage_position(), Position::rel(1, 1));
+        link.context = link_context;
+        link = link.with_passage_start(Position::abs(2, 8));
+        assert_eq!(link.passage_position(), Position::rel(1, 1));
+    }
 }
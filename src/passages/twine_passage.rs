@@ -7,12 +7,17 @@ use crate::TwineContent;
 /// through an enum to get the passage content
 ///
 /// [`Story`]: struct.Story.html
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TwinePassage {
     /// The header
     pub header: PassageHeader,
 
     /// The content
     pub content: TwineContent,
+
+    /// The file id and start line of the passage
+    pub(crate) source_order: (usize, usize),
 }
 
 impl TwinePassage {
@@ -25,16 +30,72 @@ impl TwinePassage {
     pub fn tags(&self) -> &Vec<String> {
         &self.header.tags
     }
+
+    /// Returns the `position` metadata as an `(x, y)` pair, if it is present
+    /// and can be parsed
+    pub fn position(&self) -> Option<(f64, f64)> {
+        self.header.position()
+    }
+
+    /// Returns the `size` metadata as a `(width, height)` pair, if it is
+    /// present and can be parsed
+    pub fn size(&self) -> Option<(f64, f64)> {
+        self.header.size()
+    }
+
+    /// Returns the pid (Passage ID) of the passage, which is assigned in the
+    /// order described on [`TwineContent::pid`]
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = ":: Zebra\nFirst\n\n:: Aardvark\nSecond\n".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// assert_eq!(story.passages["Zebra"].pid(), 1);
+    /// assert_eq!(story.passages["Aardvark"].pid(), 2);
+    /// ```
+    ///
+    /// [`TwineContent::pid`]: struct.TwineContent.html#structfield.pid
+    pub fn pid(&self) -> usize {
+        self.content.pid
+    }
+
+    /// Returns the position of the passage in the source files as a pair of a
+    /// file id and the line the passage starts on, so that sorting passages by
+    /// it puts them in the order they were written. File ids count up from 0
+    /// in the order the files appear among the parsed `sources` of the
+    /// [`Story`], and the id is 0 for a story parsed from a string
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = ":: Zebra\nFirst\n\n:: Aardvark\nSecond\n".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let mut passages: Vec<_> = story.passages.values().collect();
+    /// passages.sort_by_key(|passage| passage.source_order());
+    /// assert_eq!(passages[0].header.name, "Zebra");
+    /// assert_eq!(passages[1].source_order(), (0, 4));
+    /// ```
+    ///
+    /// [`Story`]: struct.Story.html
+    pub fn source_order(&self) -> (usize, usize) {
+        self.source_order
+    }
 }
 
 impl std::convert::From<Passage> for TwinePassage {
     fn from(passage: Passage) -> Self {
         let header = passage.header;
+        let source_order = (0, passage.context.get_start_position().line);
         let content = if let PassageContent::Normal(content) = passage.content {
             content
         } else {
             panic!("");
         };
-        TwinePassage { header, content }
+        TwinePassage {
+            header,
+            content,
+            source_order,
+        }
     }
 }
@@ -0,0 +1,136 @@
+use crate::escape::is_invisible;
+use crate::refactor::rename::{image_target, link_target, macro_target};
+use crate::FullContext;
+use crate::LinkKind;
+use crate::PassageContent;
+use crate::StoryPassages;
+use crate::TextEdit;
+use std::ops::Range;
+
+/// Produces the [`TextEdit`]s that remove the invisible characters from the
+/// passage names and link targets in the source files of the given story,
+/// fixing the problems reported as [`InvisibleCharacter`] warnings
+///
+/// Zero-width characters, byte order marks, and soft hyphens are removed, and
+/// non-breaking spaces are replaced with normal spaces. With the
+/// "macro-links" feature enabled, the targets of links created by the macros
+/// of the story format are fixed as well. Invisible characters anywhere else
+/// in a passage are left alone, since they may be intended.
+///
+/// # Examples
+/// ```
+/// use tweep::{strip_invisible_in_source, StoryPassages, TextEdit};
+/// let input = ":: Start\n[[Dark\u{a0}Cave\u{200b}]]\n\n:: Dark Cave\nThe end".to_string();
+/// let story = StoryPassages::from_string(input.clone()).take().0.ok().unwrap();
+/// let edits = strip_invisible_in_source(&story);
+/// assert_eq!(edits.len(), 2);
+/// assert_eq!(
+///     TextEdit::apply(&input, &edits),
+///     ":: Start\n[[Dark Cave]]\n\n:: Dark Cave\nThe end"
+/// );
+/// ```
+///
+/// [`TextEdit`]: struct.TextEdit.html
+/// [`InvisibleCharacter`]: enum.WarningKind.html#variant.InvisibleCharacter
+pub fn strip_invisible_in_source(story: &StoryPassages) -> Vec<TextEdit> {
+    #[cfg(feature = "macro-links")]
+    let format = story.data.as_ref().and_then(|data| match &data.content {
+        PassageContent::StoryData(Some(data)) => data.story_format(),
+        _ => None,
+    });
+
+    let mut edits = Vec::new();
+    for passage in story.passages.values() {
+        let name_span = passage.header.name_span();
+        edits.extend(strip(name_span, 0..name_span.get_contents().len()));
+
+        let twine = match &passage.content {
+            PassageContent::Normal(twine) => twine,
+            _ => continue,
+        };
+        let links = twine.get_links().iter();
+        #[cfg(feature = "macro-links")]
+        let links = links.chain(format.into_iter().flat_map(|f| twine.get_macro_links(f)));
+        for link in links {
+            if !link.target.chars().any(is_invisible) {
+                continue;
+            }
+            let contents = link.context.get_contents();
+            let range = match link.kind {
+                LinkKind::Macro(_) => macro_target(contents, &link.target).map(|(range, _)| range),
+                LinkKind::Image => image_target(contents),
+                _ => link_target(contents),
+            };
+            if let Some(range) = range {
+                edits.extend(strip(&link.context, range));
+            }
+        }
+    }
+
+    edits.sort_by(|a, b| {
+        a.file_name
+            .cmp(&b.file_name)
+            .then(a.range.start.cmp(&b.range.start))
+    });
+    edits.dedup();
+    edits
+}
+
+/// Produces the edits that remove or replace each invisible character in the
+/// given byte range of the given context
+fn strip(context: &FullContext, range: Range<usize>) -> Vec<TextEdit> {
+    let contents = context.get_contents();
+    contents[range.clone()]
+        .char_indices()
+        .filter(|(_, c)| is_invisible(*c))
+        .map(|(i, c)| {
+            let start = range.start + i;
+            let new_text = match c {
+                '\u{00A0}' | '\u{202F}' => " ",
+                _ => "",
+            };
+            TextEdit::within(context, start..start + c.len_utf8(), new_text.to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WarningKind;
+
+    #[test]
+    fn names_and_links() {
+        let input = ":: Start\u{feff}\n[[Go|Cave\u{200b}]] [[Dark\u{a0}Room]] [[Not\u{200b}here->Start]]\n\n:: Cave\nA\n\n:: Dark Room\nB\n";
+        let story = StoryPassages::from_string(input.to_string())
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        let mut invisible: Vec<WarningKind> = story
+            .check()
+            .into_iter()
+            .map(|w| w.kind)
+            .filter(|kind| matches!(kind, WarningKind::InvisibleCharacter(_)))
+            .collect();
+        invisible.sort_by_key(|kind| kind.to_string());
+        assert_eq!(
+            invisible,
+            [
+                WarningKind::InvisibleCharacter("Cave\u{200b}".to_string()),
+                WarningKind::InvisibleCharacter("Dark\u{a0}Room".to_string()),
+                WarningKind::InvisibleCharacter("Start\u{feff}".to_string()),
+            ]
+        );
+        assert_eq!(
+            invisible[0].to_string(),
+            "Name \"Cave<U+200B>\" contains invisible characters"
+        );
+
+        let edits = strip_invisible_in_source(&story);
+        assert_eq!(
+            TextEdit::apply(input, &edits),
+            ":: Start\n[[Go|Cave]] [[Dark Room]] [[Not\u{200b}here->Start]]\n\n:: Cave\nA\n\n:: Dark Room\nB\n"
+        );
+    }
+}
@@ -0,0 +1,8 @@
+mod invisible;
+pub use invisible::strip_invisible_in_source;
+
+mod rename;
+pub use rename::rename_passage_in_source;
+
+mod text_edit;
+pub use text_edit::TextEdit;
@@ -0,0 +1,236 @@
+use crate::escape::escape_passage_name;
+use crate::passages::split_link;
+use crate::LinkKind;
+use crate::PassageContent;
+use crate::StoryPassages;
+use crate::TextEdit;
+use std::ops::Range;
+
+/// Produces the [`TextEdit`]s that rename the passage `old` to `new` in the
+/// source files of the given story, without changing the story itself. This
+/// lets an editor apply a rename across every file of a multi-file story
+///
+/// The edits rewrite the name in the passage header, the target of every link
+/// to the passage, and the start passage in the `StoryData` if it is `old`.
+/// With the "macro-links" feature enabled, the targets of links created by
+/// the macros of the story format are rewritten as well. Returns no edits if
+/// there is no passage named `old`, or if a passage named `new` already
+/// exists
+///
+/// # Examples
+/// ```
+/// use tweep::{rename_passage_in_source, StoryPassages, TextEdit};
+/// let input = ":: Start\nGo [[Next]] or [[Skip->Next]]\n\n:: Next\nThe end".to_string();
+/// let story = StoryPassages::from_string(input.clone()).take().0.ok().unwrap();
+/// let edits = rename_passage_in_source(&story, "Next", "Finale [2]");
+/// assert_eq!(edits.len(), 3);
+/// assert_eq!(
+///     TextEdit::apply(&input, &edits),
+///     ":: Start\nGo [[Finale [2]]] or [[Skip->Finale [2]]]\n\n:: Finale \\[2\\]\nThe end"
+/// );
+/// ```
+///
+/// [`TextEdit`]: struct.TextEdit.html
+pub fn rename_passage_in_source(story: &StoryPassages, old: &str, new: &str) -> Vec<TextEdit> {
+    let passage = match story.passages.get(old) {
+        Some(passage) if old != new && !story.passages.contains_key(new) => passage,
+        _ => return Vec::new(),
+    };
+
+    let name_span = passage.header.name_span();
+    let mut edits = vec![TextEdit::within(
+        name_span,
+        0..name_span.get_contents().len(),
+        escape_passage_name(new),
+    )];
+
+    for link in story.backlinks(old) {
+        let contents = link.context.get_contents();
+        let edit = match link.kind {
+            LinkKind::Macro(_) => macro_target(contents, old).map(|(range, quote)| {
+                let escaped = new.replace(quote, &format!("\\{}", quote));
+                (range, escaped)
+            }),
+            LinkKind::Image => image_target(contents).map(|range| (range, new.to_string())),
+            _ => link_target(contents).map(|range| (range, new.to_string())),
+        };
+        if let Some((range, new_text)) = edit {
+            edits.push(TextEdit::within(&link.context, range, new_text));
+        }
+    }
+
+    if let Some(data) = &story.data {
+        if let PassageContent::StoryData(Some(story_data)) = &data.content {
+            if story_data.start.as_deref() == Some(old) {
+                if let Some(range) = start_value(data.context.get_contents()) {
+                    let new_text = serde_json::Value::from(new).to_string();
+                    edits.push(TextEdit::within(&data.context, range, new_text));
+                }
+            }
+        }
+    }
+
+    edits.sort_by(|a, b| {
+        a.file_name
+            .cmp(&b.file_name)
+            .then(a.range.start.cmp(&b.range.start))
+    });
+    edits.dedup();
+    edits
+}
+
+/// Finds the range of the target passage name within a `[[...]]` link, or
+/// within the `[...]` link component of an image link, without any surrounding
+/// whitespace
+pub(super) fn link_target(link: &str) -> Option<Range<usize>> {
+    let range = match link.strip_prefix("[[") {
+        Some(body) => {
+            let body = body.strip_suffix("]]")?;
+            let body = match body.find("][") {
+                Some(x) => &body[..x],
+                None => body,
+            };
+            let range = split_link(body).0;
+            range.start + 2..range.end + 2
+        }
+        None => 1..link.strip_prefix('[')?.strip_suffix(']')?.len() + 1,
+    };
+    Some(trimmed(link, range))
+}
+
+/// Finds the range of the image within a `[img[...]]` link, without any
+/// surrounding whitespace
+pub(super) fn image_target(link: &str) -> Option<Range<usize>> {
+    let body = link.strip_prefix("[img[")?;
+    let end = body.find("][").or_else(|| body.find("]]"))?;
+    let start = body[..end].find('|').map_or(0, |x| x + 1);
+    Some(trimmed(link, start + 5..end + 5))
+}
+
+/// Shrinks the range of `text` to leave out any whitespace at either end
+fn trimmed(text: &str, range: Range<usize>) -> Range<usize> {
+    let inner = &text[range.clone()];
+    let start = range.start + inner.len() - inner.trim_start().len();
+    let end = range.start + inner.trim_end().len();
+    start..end
+}
+
+/// Finds the range of the last string literal in a macro whose contents are
+/// `target`, without the quotes, along with the quote character used
+pub(super) fn macro_target(contents: &str, target: &str) -> Option<(Range<usize>, char)> {
+    ['"', '\'']
+        .iter()
+        .filter_map(|&quote| {
+            let literal = format!("{}{}{}", quote, target, quote);
+            contents
+                .rfind(&literal)
+                .map(|x| (x + 1..x + 1 + target.len(), quote))
+        })
+        .max_by_key(|(range, _)| range.start)
+}
+
+/// Finds the range of the JSON string value of the `start` field in the
+/// contents of a `StoryData` passage, including the quotes
+fn start_value(contents: &str) -> Option<Range<usize>> {
+    let key = contents.find("\"start\"")? + "\"start\"".len();
+    let rest = &contents[key..];
+    let colon = rest.find(|c: char| !c.is_whitespace())?;
+    if !rest[colon..].starts_with(':') {
+        return None;
+    }
+    let after_colon = &rest[colon + 1..];
+    let open = colon + 1 + after_colon.find(|c: char| !c.is_whitespace())?;
+    if !rest[open..].starts_with('"') {
+        return None;
+    }
+    let mut escaped = false;
+    for (i, c) in rest[open + 1..].char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return Some(key + open..key + open + i + 2),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_story() {
+        let input = r#":: StoryData
+{
+  "ifid": "abc",
+  "format": "SugarCube",
+  "start": "Home"
+}
+
+:: Home
+[[ Home ]] [[Go|Away][$x to 1]]
+
+:: Away
+[[Home<-Back]] <<link "Home" "Home">><</link>> <<goto 'Home'>>
+[img[Go|Away][ Home ][$x to 2]] [img[Home]]
+"#
+        .to_string();
+        let story = StoryPassages::from_string(input.clone())
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        let edits = rename_passage_in_source(&story, "Home", "Hall \"A\"");
+        let output = TextEdit::apply(&input, &edits);
+        #[cfg(feature = "macro-links")]
+        let expected_macros = r#"<<link "Home" "Hall \"A\"">><</link>> <<goto 'Hall "A"'>>"#;
+        #[cfg(not(feature = "macro-links"))]
+        let expected_macros = r#"<<link "Home" "Home">><</link>> <<goto 'Home'>>"#;
+        assert_eq!(
+            output,
+            format!(
+                r#":: StoryData
+{{
+  "ifid": "abc",
+  "format": "SugarCube",
+  "start": "Hall \"A\""
+}}
+
+:: Hall "A"
+[[ Hall "A" ]] [[Go|Away][$x to 1]]
+
+:: Away
+[[Hall "A"<-Back]] {}
+[img[Go|Away][ Hall "A" ][$x to 2]] [img[Hall "A"]]
+"#,
+                expected_macros
+            )
+        );
+    }
+
+    #[test]
+    fn no_edits() {
+        let input = ":: Start\n[[Next]]\n\n:: Next\n".to_string();
+        let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+        assert!(rename_passage_in_source(&story, "Missing", "Other").is_empty());
+        assert!(rename_passage_in_source(&story, "Next", "Start").is_empty());
+        assert!(rename_passage_in_source(&story, "Next", "Next").is_empty());
+    }
+
+    #[test]
+    fn files() {
+        let first =
+            crate::FullContext::from(Some("a.twee".to_string()), ":: A\n[[B]]\n".to_string());
+        let second =
+            crate::FullContext::from(Some("b.twee".to_string()), ":: B\n[[A]]\n".to_string());
+        let mut story = StoryPassages::from_context(first).take().0.ok().unwrap();
+        let other = StoryPassages::from_context(second).take().0.ok().unwrap();
+        story.merge_from(other);
+        let edits = rename_passage_in_source(&story, "B", "C");
+        let files: Vec<(Option<&str>, Range<usize>)> = edits
+            .iter()
+            .map(|edit| (edit.file_name.as_deref(), edit.range.clone()))
+            .collect();
+        assert_eq!(files, [(Some("a.twee"), 7..8), (Some("b.twee"), 3..4)]);
+    }
+}
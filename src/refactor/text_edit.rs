@@ -0,0 +1,66 @@
+use crate::FullContext;
+use std::ops::Range;
+
+/// A replacement of a range of bytes in a source file, as produced by the
+/// refactoring functions such as [`rename_passage_in_source`]
+///
+/// Edits refer to the original contents of the file, so the edits for one
+/// file should be applied together, such as with [`apply`], rather than one
+/// after another.
+///
+/// # Examples
+/// ```
+/// use tweep::TextEdit;
+/// let edit = TextEdit {
+///     file_name: None,
+///     range: 6..11,
+///     new_text: "there".to_string(),
+/// };
+/// assert_eq!(TextEdit::apply("Hello world!", &[edit]), "Hello there!");
+/// ```
+///
+/// [`rename_passage_in_source`]: fn.rename_passage_in_source.html
+/// [`apply`]: #method.apply
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextEdit {
+    /// The name of the file to edit, or `None` if the story was not parsed
+    /// from a file
+    pub file_name: Option<String>,
+
+    /// The range of bytes in the original contents of the file to replace
+    pub range: Range<usize>,
+
+    /// The text to put in place of the range
+    pub new_text: String,
+}
+
+impl TextEdit {
+    /// Creates an edit that replaces the given byte range within the given
+    /// context with `new_text`
+    pub(crate) fn within(context: &FullContext, range: Range<usize>, new_text: String) -> Self {
+        let start = context.get_byte_range().start;
+        TextEdit {
+            file_name: context.get_file_name().clone(),
+            range: start + range.start..start + range.end,
+            new_text,
+        }
+    }
+
+    /// Applies the given edits to the contents of a file and returns the
+    /// result. The edits must all be for the given contents and must not
+    /// overlap; the file names of the edits are not checked
+    pub fn apply(contents: &str, edits: &[TextEdit]) -> String {
+        let mut edits: Vec<&TextEdit> = edits.iter().collect();
+        edits.sort_by_key(|edit| edit.range.start);
+        let mut result = String::with_capacity(contents.len());
+        let mut copied = 0;
+        for edit in edits {
+            result.push_str(&contents[copied..edit.range.start]);
+            result.push_str(&edit.new_text);
+            copied = edit.range.end;
+        }
+        result.push_str(&contents[copied..]);
+        result
+    }
+}
@@ -0,0 +1,428 @@
+use crate::passages::find_comments;
+use crate::Error;
+use crate::FullContext;
+use crate::Position;
+use crate::Span;
+use crate::Story;
+use crate::TwinePassage;
+use crate::Warning;
+use std::ops::Range;
+
+/// The markers that start a task in passage text
+const TASK_MARKERS: &[&str] = &["TODO", "FIXME", "XXX"];
+
+/// A task left in the text of a passage, such as `TODO: write the ending`,
+/// as found by [`Story::tasks`]
+///
+/// Enabled with "report" feature
+///
+/// [`Story::tasks`]: struct.Story.html#method.tasks
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TaskMarker {
+    /// The marker that starts the task: `TODO`, `FIXME`, or `XXX`
+    pub marker: String,
+
+    /// The text after the marker up to the end of the line or comment,
+    /// without a leading colon or surrounding whitespace
+    pub text: String,
+
+    /// The name of the passage the task is in
+    pub passage: String,
+
+    /// Whether the task is inside of a `/* ... */` or `<!-- ... -->` comment
+    pub in_comment: bool,
+
+    /// Where the marker and its text are in their file
+    pub span: Span,
+}
+
+impl Story {
+    /// Finds the `TODO`, `FIXME`, and `XXX` markers in the normal passages of
+    /// the story, whether in comments or in the text itself, in order of
+    /// passage name and then position. Markers are matched as whole words, in
+    /// upper case only. Writing teams can use the list as a backlog, and
+    /// [`render_tasks`] turns it into a report
+    ///
+    /// A passage of a story that was not parsed from a source has spans
+    /// within its content rather than within a file
+    ///
+    /// Enabled with "report" feature
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = ":: Start\nHello /* TODO: greet by name */\n\n:: End\nFIXME\n".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let tasks = story.tasks();
+    /// assert_eq!(tasks[0].passage, "End");
+    /// assert_eq!(tasks[1].marker, "TODO");
+    /// assert_eq!(tasks[1].text, "greet by name");
+    /// assert!(tasks[1].in_comment);
+    /// assert_eq!(tasks[1].span.start, (2, 10));
+    /// ```
+    ///
+    /// [`render_tasks`]: fn.render_tasks.html
+    pub fn tasks(&self) -> Vec<TaskMarker> {
+        let mut passages: Vec<(&String, &TwinePassage)> = self.passages.iter().collect();
+        passages.sort_by_key(|(name, _)| *name);
+
+        let mut tasks = Vec::new();
+        for (name, passage) in passages {
+            let content = self
+                .context_of(name)
+                .and_then(|context| context.try_subcontext(Position::rel(2, 1)..).ok())
+                .unwrap_or_else(|| FullContext::from(None, passage.content.content.clone()));
+            let contents = content.get_contents();
+            let comments = find_comments(contents);
+            let links = link_ranges(contents);
+
+            let mut found = Vec::new();
+            for marker in TASK_MARKERS {
+                for (start, _) in contents.match_indices(marker) {
+                    let end = start + marker.len();
+                    let is_word =
+                        |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+                    let in_link = links.iter().any(|r| r.start <= start && end <= r.end);
+                    if !in_link
+                        && !is_word(contents[..start].chars().next_back())
+                        && !is_word(contents[end..].chars().next())
+                    {
+                        found.push((start, end, *marker));
+                    }
+                }
+            }
+            found.sort();
+
+            for (start, end, marker) in found {
+                let comment = comments.iter().find(|c| c.start <= start && end <= c.end);
+                let line_end = contents[end..]
+                    .find('\n')
+                    .map_or(contents.len(), |i| end + i);
+                let mut text = &contents[end..line_end];
+                if let Some(comment) = comment.filter(|c| c.end <= line_end) {
+                    // Leave out the closing delimiter of the comment
+                    let close = if contents[..comment.end].ends_with("*/") {
+                        2
+                    } else {
+                        3
+                    };
+                    text = &contents[end..comment.end - close];
+                }
+                let trimmed = text.trim_start().trim_start_matches(':').trim();
+                let text_end = if trimmed.is_empty() {
+                    end
+                } else {
+                    trimmed.as_ptr() as usize - contents.as_ptr() as usize + trimmed.len()
+                };
+
+                let position = |offset: usize| {
+                    let line = contents[..offset].matches('\n').count() + 1;
+                    let line_start = contents[..offset].rfind('\n').map_or(0, |i| i + 1);
+                    Position::rel(line, offset - line_start + 1)
+                };
+                let context = content.subcontext(position(start)..=position(text_end - 1));
+                tasks.push(TaskMarker {
+                    marker: marker.to_string(),
+                    text: trimmed.replace('\r', ""),
+                    passage: name.clone(),
+                    in_comment: comment.is_some(),
+                    span: Span::from(&context),
+                });
+            }
+        }
+        tasks
+    }
+}
+
+/// Finds the byte ranges of the `[[...]]` links in the given content,
+/// including the brackets
+fn link_ranges(content: &str) -> Vec<Range<usize>> {
+    let mut links = Vec::new();
+    let mut start = 0;
+    while let Some(open) = content[start..].find("[[").map(|x| start + x) {
+        match content[open..].find("]]") {
+            Some(close) => {
+                links.push(open..open + close + 2);
+                start = open + close + 2;
+            }
+            None => break,
+        }
+    }
+    links
+}
+
+/// Renders the given [`TaskMarker`]s as a task list, with a line for each
+/// task giving its location, marker, passage, and text
+///
+/// Enabled with "report" feature
+///
+/// # Examples
+/// ```
+/// use tweep::{render_tasks, Story};
+/// let input = ":: Start\nXXX: check the maths\n".to_string();
+/// let story = Story::from_string(input).take().0.ok().unwrap();
+/// assert_eq!(
+///     render_tasks(&story.tasks()),
+///     "<input>:2:1: XXX in `Start`: check the maths\n"
+/// );
+/// ```
+///
+/// [`TaskMarker`]: struct.TaskMarker.html
+pub fn render_tasks(tasks: &[TaskMarker]) -> String {
+    let mut report = String::new();
+    for task in tasks {
+        let file = task
+            .span
+            .file
+            .as_ref()
+            .map_or_else(|| "<input>".to_string(), |file| file.display().to_string());
+        report.push_str(&format!(
+            "{}:{}:{}: {} in `{}`",
+            file, task.span.start.0, task.span.start.1, task.marker, task.passage
+        ));
+        if !task.text.is_empty() {
+            report.push_str(&format!(": {}", task.text));
+        }
+        report.push('\n');
+    }
+    report
+}
+
+/// Renders an [`Error`] as a human-readable report, including the file name,
+/// the line and column, and the offending source code with the span
+/// underlined
+///
+/// Enabled with "report" feature
+///
+/// # Examples
+/// ```
+/// use tweep::{render_error, Story};
+/// let input = ":: Start [tag\nHello".to_string();
+/// let (res, _) = Story::from_string(input).take();
+/// let errors = res.err().unwrap().error_list;
+/// let report = render_error(&errors.errors[0]);
+/// assert!(report.starts_with("error: Unclosed tag block in passage header"));
+/// assert!(report.contains("1 | :: Start [tag"));
+/// ```
+///
+/// [`Error`]: struct.Error.html
+pub fn render_error(error: &Error) -> String {
+    let mut report = format!("error: {}\n", error.kind);
+    if let Some(context) = &error.context {
+        render_snippet(&mut report, context, '^');
+    }
+    report
+}
+
+/// Renders a [`Warning`] as a human-readable report, labelled with its
+/// default [`Severity`]. If the warning has a referent, the referent's source
+/// code is shown as well, and if it has a suggestion, the suggestion is shown
+/// last
+///
+/// Enabled with "report" feature
+///
+/// # Examples
+/// ```
+/// use tweep::{render_warning, Story};
+/// let input = ":: Start\n[[Nowhere".to_string();
+/// let (_, warnings) = Story::from_string(input).take();
+/// let report = render_warning(&warnings[0]);
+/// assert_eq!(report, "\
+/// warning: Unclosed passage link
+///  --> <input>:2:1
+///   |
+/// 2 | [[Nowhere
+///   | ^^^^^^^^^
+/// ");
+/// ```
+///
+/// [`Warning`]: struct.Warning.html
+/// [`Severity`]: enum.Severity.html
+pub fn render_warning(warning: &Warning) -> String {
+    let mut report = format!("{}: {}\n", warning.severity(), warning.kind);
+    if let Some(context) = &warning.context {
+        render_snippet(&mut report, context, '^');
+    }
+    if let Some(referent) = warning.get_referent() {
+        report.push_str("note: related code\n");
+        render_snippet(&mut report, referent, '-');
+    }
+    if let Some(suggestion) = warning.get_suggestion() {
+        report.push_str(&format!("help: did you mean `{}`?\n", suggestion));
+    }
+    report
+}
+
+/// Appends the location of the given context and the lines it spans to
+/// `report`, with the span underlined using `marker`. Long spans are cut off
+/// after a few lines
+fn render_snippet(report: &mut String, context: &FullContext, marker: char) {
+    const MAX_LINES: usize = 3;
+
+    let start = context.get_start_position();
+    let end = context.get_end_position();
+    let file_name = context.get_file_name().as_deref().unwrap_or("<input>");
+    let last_line = end.line.max(start.line);
+    let gutter = " ".repeat(last_line.to_string().len());
+    report.push_str(&format!(
+        "{}--> {}:{}:{}\n",
+        gutter, file_name, start.line, start.column
+    ));
+    report.push_str(&format!("{} |\n", gutter));
+
+    for line in start.line..=last_line {
+        if line - start.line == MAX_LINES {
+            report.push_str(&format!("{} | ...\n", gutter));
+            break;
+        }
+        let text = context.line_text(line);
+        report.push_str(&format!(
+            "{:>width$} | {}\n",
+            line,
+            text,
+            width = gutter.len()
+        ));
+
+        // Columns are byte offsets, but the underline is drawn in characters
+        let first = if line == start.line { start.column } else { 1 };
+        let last = if line == end.line {
+            end.column
+        } else {
+            text.len()
+        };
+        let chars_before = |column: usize| {
+            let byte = (column - 1).min(text.len());
+            text.get(..byte)
+                .map_or(byte, |prefix| prefix.chars().count())
+        };
+        let offset = chars_before(first);
+        let width = (chars_before(last + 1) - offset).max(1);
+        report.push_str(&format!(
+            "{} | {}{}\n",
+            gutter,
+            " ".repeat(offset),
+            marker.to_string().repeat(width)
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ErrorKind, Position, WarningKind};
+
+    #[test]
+    fn error_report() {
+        let context = FullContext::from(
+            Some("story.twee".to_string()),
+            ":: A\nfirst\n:: Bad {\nbody".to_string(),
+        );
+        let error = Error::new(
+            ErrorKind::UnclosedTagBlock,
+            Some(context.subcontext(Position::abs(3, 8)..=Position::abs(3, 8))),
+        );
+        assert_eq!(
+            render_error(&error),
+            "\
+error: Unclosed tag block in passage header
+ --> story.twee:3:8
+  |
+3 | :: Bad {
+  |        ^
+"
+        );
+
+        let error = Error::new::<FullContext>(ErrorKind::ParseAborted, None);
+        assert_eq!(
+            render_error(&error),
+            "error: Parsing aborted by diagnostic sink\n"
+        );
+    }
+
+    #[test]
+    fn warning_report() {
+        let context = FullContext::from(
+            Some("story.twee".to_string()),
+            ":: Start\nHé [[x]]\n".to_string(),
+        );
+        let warning = Warning::new(
+            WarningKind::DuplicatePassage("Start".to_string()),
+            Some(context.subcontext(Position::abs(2, 5)..=Position::abs(2, 9))),
+        )
+        .with_referent(context.subcontext(Position::abs(1, 1)..=Position::abs(2, 1)));
+        assert_eq!(
+            render_warning(&warning),
+            "\
+warning: Found duplicate passage named Start
+ --> story.twee:2:5
+  |
+2 | Hé [[x]]
+  |    ^^^^^
+note: related code
+ --> story.twee:1:1
+  |
+1 | :: Start
+  | --------
+2 | Hé [[x]]
+  | -
+"
+        );
+    }
+
+    #[test]
+    fn suggestion_report() {
+        let context = FullContext::from(None, ":: Start\n[[Forrest]]\n".to_string());
+        let warning = Warning::new(
+            WarningKind::DeadLink("Forrest".to_string()),
+            Some(context.subcontext(Position::abs(2, 1)..=Position::abs(2, 11))),
+        )
+        .with_suggestion("Forest".to_string());
+        assert_eq!(
+            render_warning(&warning),
+            "\
+warning: Dead link to nonexistant passage: Forrest
+ --> <input>:2:1
+  |
+2 | [[Forrest]]
+  | ^^^^^^^^^^^
+help: did you mean `Forest`?
+"
+        );
+    }
+
+    #[test]
+    fn tasks() {
+        let input = ":: Start\nTODOS todo XXXL [[TODO]]\n<!-- FIXME -->\n\n:: Cave\nIt is dark. TODO:  describe the walls\n/* XXX: too long\nstill */\n".to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        let tasks = story.tasks();
+        let found: Vec<(&str, &str, &str, bool)> = tasks
+            .iter()
+            .map(|t| {
+                (
+                    t.passage.as_str(),
+                    t.marker.as_str(),
+                    t.text.as_str(),
+                    t.in_comment,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("Cave", "TODO", "describe the walls", false),
+                ("Cave", "XXX", "too long", true),
+                ("Start", "FIXME", "", true),
+            ]
+        );
+        assert_eq!((tasks[0].span.start, tasks[0].span.end), ((6, 13), (6, 37)));
+        assert_eq!((tasks[2].span.start, tasks[2].span.end), ((3, 6), (3, 10)));
+        assert_eq!(
+            render_tasks(&tasks[..2]),
+            "\
+<input>:6:13: TODO in `Cave`: describe the walls
+<input>:7:4: XXX in `Cave`: too long
+"
+        );
+        assert_eq!(render_tasks(&[]), "");
+    }
+}
@@ -0,0 +1,306 @@
+use crate::rename_passage_in_source;
+use crate::stories::debug_json;
+use crate::stories::error_json;
+use crate::stories::warning_json;
+#[cfg(feature = "full-context")]
+use crate::ContextErrorList;
+use crate::Error;
+#[cfg(not(feature = "full-context"))]
+use crate::ErrorList;
+use crate::Output;
+use crate::Story;
+use crate::StoryPassages;
+use crate::Warning;
+use serde_json::json;
+use serde_json::Value;
+use std::io::BufRead;
+use std::io::Write;
+
+#[cfg(not(feature = "full-context"))]
+type ParseOutput = Output<Result<StoryPassages, ErrorList>>;
+#[cfg(feature = "full-context")]
+type ParseOutput = Output<Result<StoryPassages, ContextErrorList>>;
+
+/// Serves requests in the tweep stdio protocol, reading one JSON request per
+/// line from `input` and writing one JSON response per line to `output`,
+/// until `input` ends. This lets programs written in any language drive
+/// tweep by running it as a subprocess
+///
+/// Each request is an object with an `id`, which is returned unchanged in the
+/// response, a `method`, and `params`. Every method reads a story from either
+/// a `source` param holding Twee code or, with the "fs" feature, a `path`
+/// param naming a file or directory. The methods are:
+/// * `parse` - Returns the story in the `story` field of the result, in the
+///   format of [`Story::to_debug_json`], or `null` if the parse failed
+/// * `check` - Returns only the diagnostics
+/// * `rename` - Takes `old` and `new` params and returns the `edits` that
+///   rename the passage, as produced by [`rename_passage_in_source`]. Each
+///   edit has a `file`, a `start` and `end` byte offset, and `new_text`
+/// * `complete` - Takes an optional `prefix` param and returns the sorted
+///   names of the passages that start with it as `items`, for completing
+///   link targets
+///
+/// The result of each method also has `errors`, the errors that stopped the
+/// parse, and `diagnostics`, the warnings produced while parsing and by the
+/// checks of [`StoryPassages::validate`]. A response has either a `result` or
+/// an `error` object with a `message`, for requests that are not valid JSON,
+/// have an unknown method, or are missing a param
+///
+/// Enabled with "stdio-server" feature
+///
+/// # Examples
+/// ```
+/// use tweep::serve;
+/// let input = r#"{"id": 1, "method": "complete", "params": {"source": ":: Start\n[[Cave]]\n\n:: Cave\n", "prefix": "C"}}"#;
+/// let mut output = Vec::new();
+/// serve(input.as_bytes(), &mut output).unwrap();
+/// let response: serde_json::Value = serde_json::from_slice(&output).unwrap();
+/// assert_eq!(response["id"], 1);
+/// assert_eq!(response["result"]["items"], serde_json::json!(["Cave"]));
+/// ```
+///
+/// [`Story::to_debug_json`]: struct.Story.html#method.to_debug_json
+/// [`rename_passage_in_source`]: fn.rename_passage_in_source.html
+/// [`StoryPassages::validate`]: struct.StoryPassages.html#method.validate
+pub fn serve<R: BufRead, W: Write>(input: R, mut output: W) -> std::io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        writeln!(output, "{}", handle_request(&line))?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// Handles a single request in the stdio protocol and returns the response.
+/// See [`serve`] for a description of the protocol
+///
+/// Enabled with "stdio-server" feature
+///
+/// # Examples
+/// ```
+/// use tweep::handle_request;
+/// let response = handle_request(r#"{"id": "a", "method": "fly", "params": {}}"#);
+/// let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+/// assert_eq!(response["error"]["message"], "Unknown method: fly");
+/// ```
+///
+/// [`serve`]: fn.serve.html
+pub fn handle_request(request: &str) -> String {
+    let (id, result) = match serde_json::from_str::<Value>(request) {
+        Ok(request) => {
+            let id = request.get("id").cloned().unwrap_or(Value::Null);
+            (id, respond(&request))
+        }
+        Err(e) => (Value::Null, Err(format!("Invalid request: {}", e))),
+    };
+    let response = match result {
+        Ok(result) => json!({ "id": id, "result": result }),
+        Err(message) => json!({ "id": id, "error": { "message": message } }),
+    };
+    response.to_string()
+}
+
+/// Produces the result of a request, or the message of an error response
+fn respond(request: &Value) -> Result<Value, String> {
+    let method = request
+        .get("method")
+        .and_then(Value::as_str)
+        .ok_or("Missing method")?;
+    let params = request.get("params").unwrap_or(&Value::Null);
+    match method {
+        "parse" | "check" | "rename" | "complete" => (),
+        _ => return Err(format!("Unknown method: {}", method)),
+    }
+
+    let (res, warnings) = parse(params)?.take();
+    let mut result = match res {
+        Ok(story) => {
+            let mut warnings = warnings;
+            if params.get("source").is_some() {
+                // Parsing from a path already runs the checks
+                warnings.append(&mut story.validate());
+            }
+            let mut result = match method {
+                "parse" => json!({ "story": debug_json(&Story::from(story)) }),
+                "rename" => rename(&story, params)?,
+                "complete" => complete(&story, params),
+                _ => json!({}),
+            };
+            result["errors"] = json!([]);
+            result["diagnostics"] = diagnostics(&warnings);
+            result
+        }
+        Err(errors) => {
+            let mut result = match method {
+                "parse" => json!({ "story": null }),
+                "rename" => json!({ "edits": [] }),
+                "complete" => json!({ "items": [] }),
+                _ => json!({}),
+            };
+            let errors: Vec<Value> = error_list(errors).iter().map(error_json).collect();
+            result["errors"] = Value::Array(errors);
+            result["diagnostics"] = diagnostics(&warnings);
+            result
+        }
+    };
+    if method == "check" {
+        result = json!({ "errors": result["errors"], "diagnostics": result["diagnostics"] });
+    }
+    Ok(result)
+}
+
+/// Parses the story named by the `source` or `path` param
+fn parse(params: &Value) -> Result<ParseOutput, String> {
+    if let Some(source) = params.get("source").and_then(Value::as_str) {
+        return Ok(StoryPassages::from_string(source.to_string()));
+    }
+    #[cfg(feature = "fs")]
+    if let Some(path) = params.get("path").and_then(Value::as_str) {
+        return Ok(StoryPassages::from_path(path));
+    }
+    Err("Missing source or path param".to_string())
+}
+
+/// Builds the JSON value of a list of warnings
+fn diagnostics(warnings: &[Warning]) -> Value {
+    Value::Array(warnings.iter().map(warning_json).collect())
+}
+
+/// Takes the errors out of the error list of a failed parse
+#[cfg(not(feature = "full-context"))]
+fn error_list(errors: ErrorList) -> Vec<Error> {
+    errors.errors
+}
+
+/// Takes the errors out of the error list of a failed parse
+#[cfg(feature = "full-context")]
+fn error_list(errors: ContextErrorList) -> Vec<Error> {
+    errors.error_list.errors
+}
+
+/// Produces the result of a `rename` request
+fn rename(story: &StoryPassages, params: &Value) -> Result<Value, String> {
+    let param = |name: &str| {
+        params
+            .get(name)
+            .and_then(Value::as_str)
+            .ok_or(format!("Missing {} param", name))
+    };
+    let (old, new) = (param("old")?, param("new")?);
+    let edits: Vec<Value> = rename_passage_in_source(story, old, new)
+        .into_iter()
+        .map(|edit| {
+            json!({
+                "file": edit.file_name,
+                "start": edit.range.start,
+                "end": edit.range.end,
+                "new_text": edit.new_text,
+            })
+        })
+        .collect();
+    Ok(json!({ "edits": edits }))
+}
+
+/// Produces the result of a `complete` request
+fn complete(story: &StoryPassages, params: &Value) -> Value {
+    let prefix = params.get("prefix").and_then(Value::as_str).unwrap_or("");
+    let mut items: Vec<&str> = story
+        .passages
+        .keys()
+        .map(String::as_str)
+        .filter(|name| name.starts_with(prefix))
+        .collect();
+    items.sort_unstable();
+    json!({ "items": items })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, params: Value) -> Value {
+        let request = json!({ "id": 7, "method": method, "params": params });
+        serde_json::from_str(&handle_request(&request.to_string())).unwrap()
+    }
+
+    #[test]
+    fn methods() {
+        let source = ":: StoryTitle\nServer\n\n:: Start\n[[Next]] [[Nowhere]\n\n:: Next\nBye\n";
+
+        let response = request("parse", json!({ "source": source }));
+        assert_eq!(response["id"], 7);
+        let result = &response["result"];
+        assert_eq!(result["story"]["title"], "Server");
+        assert_eq!(result["errors"], json!([]));
+        let messages: Vec<&str> = result["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| d["message"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            messages,
+            ["Unclosed passage link", "No StoryData passage found"]
+        );
+
+        let response = request("check", json!({ "source": source }));
+        let result = response["result"].as_object().unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result["diagnostics"].as_array().unwrap().len(), 2);
+
+        let response = request(
+            "rename",
+            json!({ "source": source, "old": "Next", "new": "End" }),
+        );
+        let edits = response["result"]["edits"].as_array().unwrap();
+        assert_eq!(edits.len(), 2);
+        let start = edits[0]["start"].as_u64().unwrap() as usize;
+        assert_eq!(&source[start..start + 4], "Next");
+        assert_eq!(edits[0]["new_text"], "End");
+
+        let response = request("complete", json!({ "source": source }));
+        assert_eq!(response["result"]["items"], json!(["Next", "Start"]));
+
+        let response = request("parse", json!({ "source": ":: [tag\n" }));
+        let result = &response["result"];
+        assert_eq!(result["story"], Value::Null);
+        assert_eq!(result["errors"][0]["severity"], "error");
+    }
+
+    #[test]
+    fn bad_requests() {
+        let response: Value = serde_json::from_str(&handle_request("{")).unwrap();
+        assert_eq!(response["id"], Value::Null);
+        assert!(response["error"]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Invalid request"));
+
+        let response = request("parse", json!({}));
+        assert_eq!(response["error"]["message"], "Missing source or path param");
+
+        let response = request("rename", json!({ "source": ":: Start\n", "old": "Start" }));
+        assert_eq!(response["error"]["message"], "Missing new param");
+    }
+
+    #[test]
+    fn serving() {
+        let input =
+            "{\"id\": 1, \"method\": \"check\", \"params\": {\"source\": \"\"}}\n\n{\"id\": 2}\n";
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output).unwrap();
+        let lines: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["id"], 1);
+        assert!(lines[0]["result"].is_object());
+        assert_eq!(lines[1]["id"], 2);
+        assert_eq!(lines[1]["error"]["message"], "Missing method");
+    }
+}
@@ -0,0 +1,31 @@
+use crate::StoryFormat;
+use crate::TwineContent;
+use crate::TwineLink;
+
+/// Finds every link in the given passage contents whose target is `name`,
+/// ignoring surrounding whitespace. The links are ordered by the name of the
+/// passage containing them, then by their position. With the "macro-links"
+/// feature enabled, the links created by the macros of `format` are included
+#[cfg_attr(not(feature = "macro-links"), allow(unused_variables))]
+pub(crate) fn backlinks<'a>(
+    contents: impl Iterator<Item = (&'a str, &'a TwineContent)>,
+    format: Option<StoryFormat>,
+    name: &str,
+) -> Vec<&'a TwineLink> {
+    let mut contents: Vec<(&str, &TwineContent)> = contents.collect();
+    contents.sort_by_key(|(passage, _)| *passage);
+
+    let mut links = Vec::new();
+    for (_, twine) in contents {
+        let mut found: Vec<&TwineLink> = twine.get_links().iter().collect();
+        #[cfg(feature = "macro-links")]
+        found.extend(format.into_iter().flat_map(|f| twine.get_macro_links(f)));
+        found.retain(|link| link.target.trim() == name);
+        found.sort_by_key(|link| {
+            let position = link.file_position();
+            (position.line, position.column)
+        });
+        links.extend(found);
+    }
+    links
+}
@@ -0,0 +1,870 @@
+use crate::escape::is_html_unsafe;
+use crate::escape::is_invisible;
+use crate::passages::find_comments;
+use crate::stories::suggest::closest_name;
+use crate::Context;
+use crate::FastHashMap;
+use crate::FastHashSet;
+use crate::FullContext;
+use crate::LinkKind;
+#[cfg(feature = "macro-links")]
+use crate::MacroArgs;
+use crate::ParseOptions;
+use crate::PassageHeader;
+use crate::Position;
+use crate::StoryData;
+use crate::StoryFormat;
+use crate::StoryGraph;
+use crate::TwineContent;
+use crate::Warning;
+use crate::WarningKind;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use unicode_normalization::UnicodeNormalization;
+
+/// A normal passage as seen by the post-parse checks
+pub(crate) struct CheckedPassage<'a> {
+    /// The header of the passage
+    pub header: &'a PassageHeader,
+
+    /// The content of the passage
+    pub content: &'a TwineContent,
+
+    /// The context of the passage, if it is known
+    pub context: Option<&'a FullContext>,
+}
+
+impl CheckedPassage<'_> {
+    /// Returns `true` if the passage has inline pragmas, which may allow some
+    /// of its warnings
+    pub(crate) fn has_pragmas(&self) -> bool {
+        Pragmas::new(self).is_some()
+    }
+}
+
+/// A pass of the post-parse checks that can be run on its own
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum CheckPass {
+    /// The checks for missing `StoryTitle` and `StoryData` passages
+    SpecialPassages,
+
+    /// The checks for a missing start passage
+    Start,
+
+    /// The checks of link targets
+    Links,
+}
+
+/// A borrowed view of the parts of a story that the post-parse checks look
+/// at, which can be built from either a `Story` or a `StoryPassages`
+#[derive(Default)]
+pub(crate) struct CheckedStory<'a> {
+    /// If the story has a `StoryTitle` passage, its title and its context, if
+    /// it is known
+    pub title: Option<(&'a str, Option<&'a FullContext>)>,
+
+    /// If the story has a `StoryData` passage, its parsed data, if the parse
+    /// succeeded, and its context, if it is known
+    pub data: Option<(Option<&'a StoryData>, Option<&'a FullContext>)>,
+
+    /// Map from passage name to each normal passage
+    pub passages: HashMap<&'a str, CheckedPassage<'a>>,
+
+    /// Map from passage name to the file it was defined in for passages that
+    /// are only defined in skipped files
+    pub excluded_passages: Option<&'a HashMap<String, PathBuf>>,
+
+    /// Map from passage name to the path of the file it was parsed from, for
+    /// passages parsed from a `Path`
+    pub passage_files: Option<&'a HashMap<String, PathBuf>>,
+
+    /// The contents of the script passages, which may define macros
+    #[cfg(feature = "macro-links")]
+    pub scripts: Vec<&'a str>,
+}
+
+impl<'a> CheckedStory<'a> {
+    /// Performs the post-parse checks, followed by any lints registered in the
+    /// given options, and returns a list of any warnings
+    pub(crate) fn check(&self, options: &ParseOptions) -> Vec<Warning> {
+        let mut warnings = self.special_passages();
+        warnings.append(&mut self.start(options));
+        warnings.append(&mut self.links(options));
+        #[cfg(feature = "macro-links")]
+        if let Some(format) = self.format() {
+            warnings.append(&mut self.conditions(format));
+            if format == StoryFormat::SugarCube {
+                warnings.append(&mut self.undefined_widgets());
+            }
+        }
+
+        if let Some(max) = options.max_complexity() {
+            warnings.append(&mut self.complex_passages(self.format(), max));
+        }
+        if !options.languages().is_empty() {
+            warnings.append(&mut self.translations(options.languages()));
+        }
+        if !options.flagged_words().is_empty() {
+            warnings.append(&mut self.flagged_words(options.flagged_words()));
+        }
+        warnings.append(&mut self.normalization_conflicts());
+        warnings.append(&mut self.names_matching(is_html_unsafe, WarningKind::HtmlUnsafeName));
+        warnings.append(&mut self.names_matching(has_link_syntax, WarningKind::LinkSyntaxInName));
+        warnings.append(&mut self.names_matching(has_invisible, WarningKind::InvisibleCharacter));
+        warnings.append(&mut self.pid_problems());
+        if options.checked_endings() {
+            warnings.extend(self.missing_ending());
+        }
+
+        // Run the lints in order of passage name so their warnings are stable
+        for lint in options.lints() {
+            for passage in self.sorted_passages() {
+                warnings.append(&mut lint.check(passage.header, passage.content, passage.context));
+            }
+        }
+
+        self.allowed(warnings)
+    }
+
+    /// Performs a single pass of the post-parse checks, and returns the
+    /// warnings that are not allowed by the pragmas of their passages
+    pub(crate) fn check_pass(&self, pass: CheckPass, options: &ParseOptions) -> Vec<Warning> {
+        let warnings = match pass {
+            CheckPass::SpecialPassages => self.special_passages(),
+            CheckPass::Start => self.start(options),
+            CheckPass::Links => self.links(options),
+        };
+        self.allowed(warnings)
+    }
+
+    /// Drops the warnings that are allowed by the pragmas of their passages
+    pub(crate) fn allowed(&self, mut warnings: Vec<Warning>) -> Vec<Warning> {
+        let pragmas: Vec<Pragmas> = self
+            .sorted_passages()
+            .into_iter()
+            .filter_map(Pragmas::new)
+            .collect();
+        if !pragmas.is_empty() {
+            warnings.retain(|warning| !pragmas.iter().any(|p| p.allows(warning)));
+        }
+        warnings
+    }
+
+    /// Gets the normal passages in order of passage name
+    fn sorted_passages(&self) -> Vec<&CheckedPassage<'a>> {
+        let mut passages: Vec<&CheckedPassage> = self.passages.values().collect();
+        passages.sort_by(|a, b| a.header.name.cmp(&b.header.name));
+        passages
+    }
+
+    /// Gets the story format named in the `StoryData`, if it is known
+    fn format(&self) -> Option<StoryFormat> {
+        self.data
+            .and_then(|(data, _)| data)
+            .and_then(StoryData::story_format)
+    }
+
+    /// Gets the keys of the passage names, if the given options change how
+    /// names are compared
+    fn name_keys(&self, options: &ParseOptions) -> Option<FastHashSet<String>> {
+        if options.normalized_names() || options.collapsed_whitespace() {
+            Some(
+                self.passages
+                    .keys()
+                    .map(|name| options.name_key(name))
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if there is a passage with the given name, matching by
+    /// key if there are keys
+    fn exists(&self, name: &str, keys: &Option<FastHashSet<String>>, options: &ParseOptions) -> bool {
+        match keys {
+            Some(keys) => keys.contains(&options.name_key(name)),
+            None => self.passages.contains_key(name),
+        }
+    }
+
+    /// Checks that there are `StoryTitle` and `StoryData` passages, and that
+    /// the title is safe to put in HTML
+    fn special_passages(&self) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        match self.title {
+            None => warnings.push(Warning::new::<Context>(
+                WarningKind::MissingStoryTitle,
+                None,
+            )),
+            Some((title, context)) if is_html_unsafe(title) => warnings.push(Warning::new(
+                WarningKind::HtmlUnsafeTitle(title.to_string()),
+                context.cloned(),
+            )),
+            Some(_) => (),
+        }
+
+        // There is no StoryData, generate a warning
+        if self.data.is_none() {
+            warnings.push(Warning::new::<Context>(WarningKind::MissingStoryData, None));
+        }
+        warnings
+    }
+
+    /// Checks that the start passage exists
+    fn start(&self, options: &ParseOptions) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        let mut missing_start = !self.passages.contains_key("Start");
+
+        // If there is parsed StoryData with a start field
+        if let Some((Some(data), context)) = self.data {
+            if let Some(start) = &data.start {
+                // Even if the start field is a dead link, it's not missing a
+                // start passage
+                missing_start = false;
+
+                // Check if the configured start passage exists
+                if !self.exists(start, &self.name_keys(options), options) {
+                    // There is an alternate start passage specified, but it
+                    // does not exist
+                    warnings.push(Warning::new(
+                        WarningKind::DeadStartPassage(start.clone()),
+                        context.cloned(),
+                    ));
+                }
+            }
+        }
+
+        if missing_start {
+            warnings.push(Warning::new::<Context>(
+                WarningKind::MissingStartPassage,
+                None,
+            ));
+        }
+        warnings
+    }
+
+    /// Checks the targets of the links in each passage, finding dead links,
+    /// links to the passage itself, and targets with invisible characters
+    fn links(&self, options: &ParseOptions) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        let keys = self.name_keys(options);
+
+        // Links created by macros are only checked if the story format is known
+        #[cfg(feature = "macro-links")]
+        let format = self.format();
+
+        for passage in self.passages.values() {
+            let links = passage.content.get_links().iter();
+            #[cfg(feature = "macro-links")]
+            let links = links.chain(
+                format
+                    .into_iter()
+                    .flat_map(|f| passage.content.get_macro_links(f)),
+            );
+            for link in links {
+                // The target of an image link is usually a URL
+                if link.kind == LinkKind::Image && !options.checked_image_links() {
+                    continue;
+                }
+
+                if has_invisible(link.target.trim()) {
+                    warnings.push(Warning::new(
+                        WarningKind::InvisibleCharacter(link.target.to_string()),
+                        Some(link.context.clone()),
+                    ));
+                }
+
+                // Trim the target so that a whitespace warning and a dead
+                // link warning aren't both generated
+                let target = link.target.trim();
+                let name = &passage.header.name;
+                let is_self = if keys.is_some() {
+                    options.name_key(target) == options.name_key(name)
+                } else {
+                    target == name
+                };
+                if is_self {
+                    warnings.push(Warning::new(
+                        WarningKind::SelfLink(name.clone()),
+                        Some(link.context.clone()),
+                    ));
+                } else if !self.exists(target, &keys, options) {
+                    let excluded = self
+                        .excluded_passages
+                        .and_then(|excluded| excluded.get(target));
+                    let kind = match excluded {
+                        Some(path) => WarningKind::ExcludedLink(
+                            link.target.clone(),
+                            path.to_string_lossy().to_string(),
+                        ),
+                        None => WarningKind::DeadLink(link.target.clone()),
+                    };
+                    let mut warning = Warning::new(kind, Some(link.context.clone()));
+                    if let WarningKind::DeadLink(_) = warning.kind {
+                        let names = self.passages.keys().copied();
+                        if let Some(name) = closest_name(target, names) {
+                            warning = warning.with_suggestion(name.to_string());
+                        }
+                    }
+                    warnings.push(warning);
+                }
+            }
+        }
+        warnings
+    }
+
+    /// Checks that each SugarCube macro invoked in a passage is built into
+    /// SugarCube, defined in a script, or defined as a widget
+    #[cfg(feature = "macro-links")]
+    fn undefined_widgets(&self) -> Vec<Warning> {
+        let passages = self
+            .passages
+            .iter()
+            .map(|(name, passage)| (*name, passage.header, passage.content));
+        let widgets = crate::stories::widget_definitions(passages);
+        let scripted: FastHashSet<&str> = self
+            .scripts
+            .iter()
+            .flat_map(|script| crate::passages::script_macros(script))
+            .collect();
+        let mut known: Vec<String> = widgets.keys().cloned().collect();
+        known.sort();
+
+        let mut warnings = Vec::new();
+        for passage in self.sorted_passages() {
+            for call in passage.content.get_macro_calls(StoryFormat::SugarCube) {
+                let name = call.name.as_str();
+                // Skip anything that can't be a macro name, such as the `=`
+                // of `<<= $x>>`
+                if !name.starts_with(|c: char| c.is_alphabetic())
+                    || crate::passages::SUGARCUBE_MACROS.contains(&name)
+                    || scripted.contains(name)
+                    || widgets.contains_key(name)
+                {
+                    continue;
+                }
+                let mut warning = Warning::new(
+                    WarningKind::UndefinedWidget(name.to_string(), known.clone()),
+                    Some(call.context.clone()),
+                );
+                if let Some(widget) = closest_name(name, known.iter().map(String::as_str)) {
+                    warning = warning.with_suggestion(widget.to_string());
+                }
+                warnings.push(warning);
+            }
+        }
+        warnings
+    }
+
+    /// Checks the conditions of the conditional macros of the given story
+    /// format for likely typos, in order of passage name
+    #[cfg(feature = "macro-links")]
+    fn conditions(&self, format: StoryFormat) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        for passage in self.sorted_passages() {
+            for call in passage.content.get_macro_calls(format) {
+                if let Some(MacroArgs::Condition(condition)) = call.parse_args() {
+                    warnings.append(&mut condition_problems(&condition));
+                }
+            }
+        }
+        warnings
+    }
+
+    /// Finds the passages with a complexity above the given maximum, in order
+    /// of passage name
+    fn complex_passages(&self, format: Option<StoryFormat>, max: usize) -> Vec<Warning> {
+        let mut passages: Vec<(&CheckedPassage, usize)> = self
+            .passages
+            .values()
+            .map(|passage| (passage, passage.content.complexity(format)))
+            .filter(|(_, complexity)| *complexity > max)
+            .collect();
+        passages.sort_by(|(a, _), (b, _)| a.header.name.cmp(&b.header.name));
+        passages
+            .into_iter()
+            .map(|(passage, complexity)| {
+                Warning::new(
+                    WarningKind::ComplexPassage(passage.header.name.clone(), complexity, max),
+                    passage.context.cloned(),
+                )
+            })
+            .collect()
+    }
+
+    /// Checks a story translated into the given languages for passages that
+    /// are missing in some of the languages, in order of name and then
+    /// language, followed by links between passages in different languages,
+    /// in order of passage name
+    fn translations(&self, languages: &[String]) -> Vec<Warning> {
+        let mut translated: FastHashMap<&str, Vec<(&str, &CheckedPassage)>> =
+            FastHashMap::default();
+        for passage in self.passages.values() {
+            if let Some((language, name)) = self.language_of(passage, languages) {
+                translated
+                    .entry(name)
+                    .or_default()
+                    .push((language, passage));
+            }
+        }
+
+        let mut names: Vec<&str> = translated.keys().copied().collect();
+        names.sort_unstable();
+        let mut warnings = Vec::new();
+        for name in names {
+            let found = &translated[name];
+            // Report on the passage in the first language that has one
+            let first = languages
+                .iter()
+                .find_map(|l| found.iter().find(|(language, _)| language == l))
+                .map(|(_, passage)| passage);
+            for language in languages {
+                if !found.iter().any(|(l, _)| l == language) {
+                    warnings.push(Warning::new(
+                        WarningKind::MissingTranslation(name.to_string(), language.clone()),
+                        first.and_then(|passage| passage.context.cloned()),
+                    ));
+                }
+            }
+        }
+
+        #[cfg(feature = "macro-links")]
+        let format = self.format();
+        for passage in self.sorted_passages() {
+            let language = match self.language_of(passage, languages) {
+                Some((language, _)) => language,
+                None => continue,
+            };
+            let links = passage.content.get_links().iter();
+            #[cfg(feature = "macro-links")]
+            let links = links.chain(
+                format
+                    .into_iter()
+                    .flat_map(|f| passage.content.get_macro_links(f)),
+            );
+            for link in links.filter(|link| link.kind != LinkKind::Image) {
+                let target = link.target.trim();
+                let other = self
+                    .passages
+                    .get(target)
+                    .and_then(|target| self.language_of(target, languages));
+                if other.is_some_and(|(other, _)| other != language) {
+                    warnings.push(Warning::new(
+                        WarningKind::CrossLanguageLink(target.to_string(), language.to_string()),
+                        Some(link.context.clone()),
+                    ));
+                }
+            }
+        }
+        warnings
+    }
+
+    /// Finds the language of the given passage out of the given languages,
+    /// along with its name without the language suffix. The language is taken
+    /// from the suffix of the name, or else from a directory in the path of
+    /// the passage's file
+    fn language_of<'b>(
+        &self,
+        passage: &CheckedPassage<'b>,
+        languages: &'b [String],
+    ) -> Option<(&'b str, &'b str)> {
+        let name = passage.header.name.as_str();
+        for language in languages {
+            let base = name
+                .strip_suffix(language.as_str())
+                .and_then(|base| base.strip_suffix('_'))
+                .filter(|base| !base.is_empty());
+            if let Some(base) = base {
+                return Some((language, base));
+            }
+        }
+
+        let path = self.passage_files?.get(name)?;
+        languages
+            .iter()
+            .find(|language| {
+                path.parent()
+                    .is_some_and(|dir| dir.iter().any(|d| d == language.as_str()))
+            })
+            .map(|language| (language.as_str(), name))
+    }
+
+    /// Finds the given words in the text of each passage, skipping links,
+    /// comments, and the macros of the story format, in order of passage name
+    /// and then position
+    fn flagged_words(&self, words: &[String]) -> Vec<Warning> {
+        #[cfg(feature = "macro-links")]
+        let format = self.format();
+
+        let mut warnings = Vec::new();
+        for passage in self.sorted_passages() {
+            // Search the source of the content so that the spans are right
+            let context = passage
+                .context
+                .and_then(|context| context.try_subcontext(Position::rel(2, 1)..).ok());
+            let context = match context {
+                Some(context) => context,
+                None => continue,
+            };
+            let contents = context.get_contents();
+            let base = context.get_byte_range().start;
+
+            let mut skipped = find_comments(contents);
+            let links = passage.content.get_links().iter().map(|link| &link.context);
+            #[cfg(feature = "macro-links")]
+            let links = links.chain(
+                format
+                    .into_iter()
+                    .flat_map(|f| passage.content.get_macro_calls(f))
+                    .map(|call| &call.context),
+            );
+            skipped.extend(links.map(|link| {
+                let range = link.get_byte_range();
+                range.start.saturating_sub(base)..range.end.saturating_sub(base)
+            }));
+
+            let lower = contents.to_ascii_lowercase();
+            let mut found = Vec::new();
+            for word in words.iter().filter(|word| !word.trim().is_empty()) {
+                let needle = word.to_ascii_lowercase();
+                for (start, _) in lower.match_indices(&needle) {
+                    let end = start + needle.len();
+                    let is_word =
+                        |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+                    let whole = !is_word(contents[..start].chars().next_back())
+                        && !is_word(contents[end..].chars().next());
+                    if whole && !skipped.iter().any(|r| r.start <= start && end <= r.end) {
+                        found.push((start, end, word));
+                    }
+                }
+            }
+            found.sort();
+
+            for (start, end, word) in found {
+                let position = |offset: usize| {
+                    let line = contents[..offset].matches('\n').count() + 1;
+                    let line_start = contents[..offset].rfind('\n').map_or(0, |i| i + 1);
+                    Position::rel(line, offset - line_start + 1)
+                };
+                warnings.push(Warning::new(
+                    WarningKind::FlaggedWord(word.clone()),
+                    Some(context.subcontext(position(start)..=position(end - 1))),
+                ));
+            }
+        }
+        warnings
+    }
+
+    /// Finds the passages with names that match the given predicate, in order
+    /// of passage name, and produces a warning of the given kind on each one
+    fn names_matching(
+        &self,
+        matches: fn(&str) -> bool,
+        kind: fn(String) -> WarningKind,
+    ) -> Vec<Warning> {
+        let mut passages: Vec<&CheckedPassage> = self
+            .passages
+            .values()
+            .filter(|passage| matches(&passage.header.name))
+            .collect();
+        passages.sort_by(|a, b| a.header.name.cmp(&b.header.name));
+        passages
+            .into_iter()
+            .map(|passage| {
+                Warning::new(kind(passage.header.name.clone()), passage.context.cloned())
+            })
+            .collect()
+    }
+
+    /// Builds the graph of links between passages and, if the start passage
+    /// exists but no dead end can be reached from it, returns a warning on the
+    /// start passage
+    fn missing_ending(&self) -> Option<Warning> {
+        let start = self
+            .data
+            .and_then(|(data, _)| data)
+            .and_then(|data| data.start.as_deref())
+            .unwrap_or("Start");
+        let passages = self
+            .passages
+            .iter()
+            .map(|(name, passage)| (*name, passage.content.get_links()));
+        let graph = StoryGraph::build(passages, Some(start));
+        let start = graph.start()?;
+        if !graph.reachable_endings().is_empty() {
+            return None;
+        }
+        Some(Warning::new(
+            WarningKind::NoReachableEnding(start.to_string()),
+            self.passages[start].context.cloned(),
+        ))
+    }
+
+    /// Finds passages that share a pid, and ranges of pids below the highest
+    /// pid that no passage has. A duplicate is reported for each passage after
+    /// the first in name order, with the first as the referent, and a gap is
+    /// reported on the passage that follows it
+    fn pid_problems(&self) -> Vec<Warning> {
+        let mut pids: Vec<(usize, &CheckedPassage)> = self
+            .passages
+            .values()
+            .map(|passage| (passage.content.pid, passage))
+            .collect();
+        pids.sort_by(|(a, a_passage), (b, b_passage)| {
+            a.cmp(b)
+                .then_with(|| a_passage.header.name.cmp(&b_passage.header.name))
+        });
+
+        let mut warnings = Vec::new();
+        let mut next = 1;
+        let mut first: Option<&CheckedPassage> = None;
+        for (pid, passage) in pids {
+            if pid + 1 == next {
+                if let Some(first) = first {
+                    let mut warning =
+                        Warning::new(WarningKind::DuplicatePid(pid), passage.context.cloned());
+                    if let Some(context) = first.context {
+                        warning = warning.with_referent(context.clone());
+                    }
+                    warnings.push(warning);
+                }
+                continue;
+            }
+            if pid > next {
+                warnings.push(Warning::new(
+                    WarningKind::PidGap(next, pid - 1),
+                    passage.context.cloned(),
+                ));
+            }
+            first = Some(passage);
+            next = pid + 1;
+        }
+        warnings
+    }
+
+    /// Finds passages whose names are equal to another passage's name once
+    /// both are normalized to NFC, but differ as written. Each one after the
+    /// first in name order produces a warning, with the first as the referent
+    fn normalization_conflicts(&self) -> Vec<Warning> {
+        let mut groups: FastHashMap<String, Vec<&CheckedPassage>> = FastHashMap::default();
+        for (name, passage) in &self.passages {
+            groups
+                .entry(name.nfc().collect())
+                .or_default()
+                .push(passage);
+        }
+
+        let mut warnings = Vec::new();
+        let mut conflicts: Vec<Vec<&CheckedPassage>> = groups
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .collect();
+        for group in conflicts.iter_mut() {
+            group.sort_by(|a, b| a.header.name.cmp(&b.header.name));
+        }
+        conflicts.sort_by(|a, b| a[0].header.name.cmp(&b[0].header.name));
+        for group in conflicts {
+            let first = group[0];
+            for passage in &group[1..] {
+                let mut warning = Warning::new(
+                    WarningKind::NormalizationConflict(
+                        passage.header.name.clone(),
+                        first.header.name.clone(),
+                    ),
+                    passage.context.cloned(),
+                );
+                if let Some(context) = first.context {
+                    warning = warning.with_referent(context.clone());
+                }
+                warnings.push(warning);
+            }
+        }
+        warnings
+    }
+}
+
+/// Returns `true` if the given passage name contains `->`, `<-`, or `|`, which
+/// split the body of a link into a target and display text
+fn has_link_syntax(name: &str) -> bool {
+    name.contains("->") || name.contains("<-") || name.contains('|')
+}
+
+/// Returns `true` if the given name contains an invisible character
+fn has_invisible(name: &str) -> bool {
+    name.chars().any(is_invisible)
+}
+
+/// Checks the given condition for an unbalanced bracket or quote, and for an
+/// assignment with `=` or `to` where a comparison was likely intended. Each
+/// warning has the context of the character or operator at fault
+#[cfg(feature = "macro-links")]
+fn condition_problems(condition: &FullContext) -> Vec<Warning> {
+    let text = condition.get_contents();
+    let at = |start: usize, len: usize| {
+        Some(condition.subcontext(Position::rel(1, start + 1)..=Position::rel(1, start + len)))
+    };
+    let mut warnings = Vec::new();
+    let mut open: Vec<(usize, char)> = Vec::new();
+    let mut quote: Option<(usize, char)> = None;
+    let mut escaped = false;
+    let mut unbalanced = None;
+    for (i, c) in text.char_indices() {
+        if let Some((_, q)) = quote {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                c if c == q => quote = None,
+                _ => (),
+            }
+            continue;
+        }
+        let prev = text[..i].chars().next_back();
+        let next = text[i + c.len_utf8()..].chars().next();
+        match c {
+            '"' | '\'' | '`' => quote = Some((i, c)),
+            '(' | '[' | '{' => open.push((i, c)),
+            ')' | ']' | '}' => {
+                let expected = match c {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                if open.pop().map(|(_, o)| o) != Some(expected) {
+                    unbalanced.get_or_insert(i);
+                }
+            }
+            '=' if !matches!(prev, Some('=' | '!' | '<' | '>'))
+                && !matches!(next, Some('=' | '>')) =>
+            {
+                warnings.push(assignment(text, at(i, 1)));
+            }
+            't' if text[i..].starts_with("to")
+                && prev.is_none_or(char::is_whitespace)
+                && text[i + 2..].chars().next().is_none_or(char::is_whitespace) =>
+            {
+                warnings.push(assignment(text, at(i, 2)));
+            }
+            _ => (),
+        }
+    }
+
+    let unbalanced = unbalanced
+        .or_else(|| quote.map(|(i, _)| i))
+        .or_else(|| open.last().map(|(i, _)| *i));
+    if let Some(i) = unbalanced {
+        warnings.push(Warning::new(
+            WarningKind::UnbalancedCondition(text.to_string()),
+            at(i, 1),
+        ));
+    }
+    warnings
+}
+
+/// Makes the warning for an assignment in the given condition
+#[cfg(feature = "macro-links")]
+fn assignment(condition: &str, context: Option<FullContext>) -> Warning {
+    Warning::new(
+        WarningKind::AssignmentInCondition(condition.to_string()),
+        context,
+    )
+    .with_suggestion("is".to_string())
+}
+
+/// The warnings allowed in a passage by inline pragmas: the names listed in
+/// the `tweep-allow` field of its metadata, which apply to the whole passage,
+/// and the names listed in its `tweep-disable-next-line` comments, which apply
+/// to the line after the comment. A comment that lists no names allows every
+/// warning
+struct Pragmas<'a> {
+    /// The context of the passage
+    context: &'a FullContext,
+
+    /// The keys of the names allowed in the whole passage
+    passage: Vec<String>,
+
+    /// The absolute line numbers with the keys of the names allowed on them
+    lines: Vec<(usize, Vec<String>)>,
+}
+
+impl<'a> Pragmas<'a> {
+    /// Finds the pragmas in the given passage, or returns `None` if it has
+    /// none or its context is not known
+    fn new(passage: &CheckedPassage<'a>) -> Option<Self> {
+        let context = passage.context?;
+        let passage_names = match passage.header.metadata.get("tweep-allow") {
+            Some(serde_json::Value::Array(names)) => names
+                .iter()
+                .filter_map(|name| name.as_str())
+                .map(pragma_key)
+                .collect(),
+            Some(serde_json::Value::String(name)) => vec![pragma_key(name)],
+            _ => Vec::new(),
+        };
+        let lines: Vec<(usize, Vec<String>)> = passage
+            .content
+            .get_comments()
+            .iter()
+            .filter_map(|comment| {
+                let text = comment.get_contents();
+                let text = text
+                    .strip_prefix("/*")
+                    .and_then(|t| t.strip_suffix("*/"))
+                    .or_else(|| text.strip_prefix("<!--")?.strip_suffix("-->"))?;
+                let names = text.trim().strip_prefix("tweep-disable-next-line")?;
+                let names = names
+                    .split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|name| !name.is_empty())
+                    .map(pragma_key)
+                    .collect();
+                Some((comment.get_end_position().line + 1, names))
+            })
+            .collect();
+        if passage_names.is_empty() && lines.is_empty() {
+            return None;
+        }
+        Some(Pragmas {
+            context,
+            passage: passage_names,
+            lines,
+        })
+    }
+
+    /// Returns `true` if the given warning is in this passage and is allowed
+    /// by one of its pragmas
+    fn allows(&self, warning: &Warning) -> bool {
+        let warning_context = match &warning.context {
+            Some(context) => context,
+            None => return false,
+        };
+        let line = warning_context.get_start_position().line;
+        let in_passage = warning_context.get_file_name() == self.context.get_file_name()
+            && (self.context.get_start_position().line..=self.context.get_end_position().line)
+                .contains(&line);
+        if !in_passage {
+            return false;
+        }
+        let mut keys = vec![
+            pragma_key(warning.kind.name()),
+            pragma_key(warning.kind.code()),
+        ];
+        if let WarningKind::Lint(name, _) = &warning.kind {
+            keys.push(pragma_key(name));
+        }
+        let named = |names: &Vec<String>| names.iter().any(|name| keys.contains(name));
+        named(&self.passage)
+            || self
+                .lines
+                .iter()
+                .any(|(l, names)| *l == line && (names.is_empty() || named(names)))
+    }
+}
+
+/// Makes the key that a warning name in a pragma is matched by, ignoring case
+/// and separators so that `DeadLink`, `dead-link`, and `dead_link` match
+fn pragma_key(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use crate::FastHashMap;
 use crate::FullContext;
 use bimap::BiMap;
 use std::ops::Range;
@@ -7,10 +7,11 @@ use std::ops::Range;
 ///
 /// The code map consists of a `BiMap` between file ids (usize) and file names
 /// (String) along with a `HashMap` of file id to contexts
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CodeMap {
     pub(crate) id_file_map: BiMap<usize, String>,
-    pub(crate) contexts: HashMap<usize, FullContext>,
+    pub(crate) contexts: FastHashMap<usize, FullContext>,
 }
 
 impl CodeMap {
@@ -34,10 +35,11 @@ impl CodeMap {
         self.get_context(id).map(|context| context.get_line_starts())
     }
 
-    /// Gets the byte range of the line `line` for file id `id`
+    /// Gets the byte range of the line `line` for file id `id`, if there is
+    /// such a file and it has that line
     pub fn line_range(&self, id: usize, line: usize) -> Option<Range<usize>> {
         self.get_context(id).and_then(|ctx| {
-            let (start, end) = ctx.line_bytes(line).into_inner();
+            let (start, end) = ctx.line_bytes(line)?.into_inner();
             Some(start..end+1)
         })
     }
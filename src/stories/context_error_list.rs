@@ -12,9 +12,64 @@ pub struct ContextErrorList {
     pub code_map: CodeMap,
 }
 
+impl ContextErrorList {
+    /// Returns an iterator over the errors in the list
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = ":: Start [tag\nHello\n\n::\n".to_string();
+    /// let errors = Story::from_string(input).take().0.err().unwrap();
+    /// assert_eq!(errors.iter().count(), 2);
+    /// ```
+    pub fn iter(&self) -> std::slice::Iter<'_, Error> {
+        self.error_list.iter()
+    }
+
+    /// Sorts the errors by the file name, line, and column of the start of
+    /// their contexts. See [`ErrorList::sort_by_position`] for more
+    /// information
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{ErrorKind, Story};
+    /// let input = ":: Start [tag\nHello\n\n::\n".to_string();
+    /// let mut errors = Story::from_string(input).take().0.err().unwrap();
+    /// errors.sort_by_position();
+    /// let lines: Vec<usize> = errors
+    ///     .iter()
+    ///     .map(|error| error.context.as_ref().unwrap().get_start_position().line)
+    ///     .collect();
+    /// assert_eq!(lines, [1, 4]);
+    /// ```
+    ///
+    /// [`ErrorList::sort_by_position`]: struct.ErrorList.html#method.sort_by_position
+    pub fn sort_by_position(&mut self) {
+        self.error_list.sort_by_position();
+    }
+}
+
+impl IntoIterator for ContextErrorList {
+    type Item = Error;
+    type IntoIter = std::vec::IntoIter<Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.error_list.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ContextErrorList {
+    type Item = &'a Error;
+    type IntoIter = std::slice::Iter<'a, Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.error_list.iter()
+    }
+}
+
 impl std::error::Error for ContextErrorList {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        None
+        self.error_list.source()
     }
 }
 
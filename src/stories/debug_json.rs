@@ -0,0 +1,347 @@
+use crate::Context;
+#[cfg(feature = "stdio-server")]
+use crate::Error;
+use crate::FullContext;
+use crate::LinkKind;
+use crate::Position;
+use crate::Story;
+use crate::StoryData;
+use crate::StoryFormat;
+use crate::TwineLink;
+use crate::TwinePassage;
+use crate::Warning;
+use serde_json::json;
+use serde_json::Value;
+
+/// The version of the schema produced by `Story::to_debug_json`, which is
+/// increased whenever a field is changed or removed
+pub(crate) const DEBUG_JSON_VERSION: u64 = 1;
+
+/// The version of the schema produced by `Story::to_json`, which is increased
+/// whenever a field is changed or removed
+pub(crate) const JSON_VERSION: u64 = 1;
+
+/// Builds the JSON value described in `Story::to_debug_json`
+pub(crate) fn debug_json(story: &Story) -> Value {
+    let mut passages: Vec<&TwinePassage> = story.passages.values().collect();
+    passages.sort_by(|a, b| a.header.name.cmp(&b.header.name));
+    let passages: Vec<Value> = passages
+        .into_iter()
+        .map(|passage| {
+            let links = passage.content.get_links().iter().map(link_json).collect();
+            passage_json(passage, story.context_of(&passage.header.name), links)
+        })
+        .collect();
+    let diagnostics: Vec<Value> = sorted_warnings(story).iter().map(warning_json).collect();
+
+    json!({
+        "version": DEBUG_JSON_VERSION,
+        "title": story.title,
+        "author": story.author,
+        "subtitle": story.subtitle,
+        "menu": story.menu,
+        "settings": story.settings,
+        "data": story.data.as_ref().map(data_json),
+        "start": story.get_start_passage_name(),
+        "passages": passages,
+        "scripts": story.scripts,
+        "stylesheets": story.stylesheets,
+        "diagnostics": diagnostics,
+    })
+}
+
+/// Builds the JSON value described in `Story::to_json`
+pub(crate) fn story_json(story: &Story) -> Value {
+    #[cfg(feature = "macro-links")]
+    let format = story.data.as_ref().and_then(StoryData::story_format);
+    let mut passages: Vec<&TwinePassage> = story.passages.values().collect();
+    passages.sort_by(|a, b| a.header.name.cmp(&b.header.name));
+    let passages: Vec<Value> = passages
+        .into_iter()
+        .map(|passage| {
+            let links = passage.content.get_links().iter();
+            #[cfg(feature = "macro-links")]
+            let links = links.chain(
+                format
+                    .into_iter()
+                    .flat_map(|f| passage.content.get_macro_links(f)),
+            );
+            let links = links.map(typed_link_json).collect();
+            passage_json(passage, story.context_of(&passage.header.name), links)
+        })
+        .collect();
+    let warnings: Vec<Value> = sorted_warnings(story).iter().map(warning_json).collect();
+
+    json!({
+        "version": JSON_VERSION,
+        "title": story.title,
+        "author": story.author,
+        "subtitle": story.subtitle,
+        "menu": story.menu,
+        "settings": story.settings,
+        "data": story.data.as_ref().map(data_json),
+        "start": story.get_start_passage_name(),
+        "passages": passages,
+        "scripts": story.scripts,
+        "stylesheets": story.stylesheets,
+        "warnings": warnings,
+    })
+}
+
+/// Runs every check on the story, sorting the warnings by position and then
+/// by message so that the output is the same every time
+fn sorted_warnings(story: &Story) -> Vec<Warning> {
+    let mut warnings = story.validate();
+    warnings.sort_by_cached_key(|warning| {
+        let position = warning.context.as_ref().map(|context| {
+            let start = context.get_start_position();
+            (context.get_file_name().clone(), start.line, start.column)
+        });
+        (position, warning.kind.to_string())
+    });
+    warnings
+}
+
+/// Builds the JSON value of a position
+fn position_json(position: &Position) -> Value {
+    json!({ "line": position.line, "column": position.column })
+}
+
+/// Builds the JSON value of the span covered by a context
+fn span_json(context: &FullContext) -> Value {
+    json!({
+        "file": context.get_file_name(),
+        "start": position_json(context.get_start_position()),
+        "end": position_json(context.get_end_position()),
+    })
+}
+
+/// Builds the JSON value of a `StoryData`, with the tag colors sorted by tag
+fn data_json(data: &StoryData) -> Value {
+    let tag_colors = data.tag_colors.as_ref().map(|colors| {
+        let mut colors: Vec<(&String, String)> = colors
+            .iter()
+            .map(|(tag, color)| (tag, color.to_string()))
+            .collect();
+        colors.sort();
+        colors
+            .into_iter()
+            .map(|(tag, color)| (tag.clone(), Value::String(color)))
+            .collect::<serde_json::Map<String, Value>>()
+    });
+    json!({
+        "ifid": data.ifid,
+        "format": data.format,
+        "format_version": data.format_version,
+        "start": data.start,
+        "tag_colors": tag_colors,
+        "zoom": data.zoom,
+    })
+}
+
+/// Builds the JSON value of a normal passage with the given links
+fn passage_json(passage: &TwinePassage, context: Option<&FullContext>, links: Vec<Value>) -> Value {
+    let tags: Vec<&str> = passage.tags().iter().map(|tag| tag.as_str()).collect();
+    json!({
+        "name": passage.header.name,
+        "tags": tags,
+        "metadata": passage.metadata(),
+        "pid": passage.content.pid,
+        "span": context.map(span_json),
+        "content": passage.content.content,
+        "links": links,
+    })
+}
+
+/// Builds the JSON value of a link
+fn link_json(link: &TwineLink) -> Value {
+    json!({
+        "target": link.target.as_str(),
+        "display_text": link.display_text,
+        "kind": format!("{:?}", link.kind),
+        "setter": link.setter,
+        "span": span_json(&link.context),
+    })
+}
+
+/// Builds the JSON value of a link for `Story::to_json`, which names its kind
+/// in `snake_case` and gives the story format of a macro link separately
+fn typed_link_json(link: &TwineLink) -> Value {
+    let (kind, format) = match link.kind {
+        LinkKind::Simple => ("simple", None),
+        LinkKind::Pipe => ("pipe", None),
+        LinkKind::RightArrow => ("right_arrow", None),
+        LinkKind::LeftArrow => ("left_arrow", None),
+        LinkKind::Image => ("image", None),
+        LinkKind::Macro(format) => ("macro", Some(format)),
+    };
+    json!({
+        "target": link.target.as_str(),
+        "display_text": link.display_text,
+        "kind": kind,
+        "format": format.as_ref().map(StoryFormat::to_string),
+        "setter": link.setter,
+        "span": span_json(&link.context),
+    })
+}
+
+/// Builds the JSON value of the position of an error or warning
+fn issue_position_json(context: &Context) -> Value {
+    let start = context.get_start_position();
+    json!({
+        "file": context.get_file_name(),
+        "line": start.line,
+        "column": start.column,
+    })
+}
+
+/// Builds the JSON value of a warning
+pub(crate) fn warning_json(warning: &Warning) -> Value {
+    json!({
+        "code": warning.kind.code(),
+        "message": warning.kind.to_string(),
+        "severity": warning.severity().to_string(),
+        "category": warning.kind.category().to_string(),
+        "position": warning.context.as_ref().map(issue_position_json),
+        "suggestion": warning.get_suggestion(),
+    })
+}
+
+/// Builds the JSON value of an error, which has the same fields as a warning
+/// with a severity of `error`
+#[cfg(feature = "stdio-server")]
+pub(crate) fn error_json(error: &Error) -> Value {
+    json!({
+        "code": error.kind.code(),
+        "message": error.kind.to_string(),
+        "severity": "error",
+        "category": error.kind.category().to_string(),
+        "position": error.context.as_ref().map(issue_position_json),
+        "suggestion": null,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dump() {
+        let input = r#":: StoryTitle
+Dump
+
+:: StoryData
+{"ifid": "ABC", "start": "Begin", "tag-colors": {"b": "red", "a": "blue"}}
+
+:: Begin [a b] {"position": "100,200"}
+[[Go|End]] [[Nowhere]]
+
+:: End
+Bye
+
+:: Code [script]
+let x = 1;
+"#
+        .to_string();
+        let story = Story::from_string(input.clone()).take().0.ok().unwrap();
+        let dump = story.to_debug_json();
+        for _ in 0..5 {
+            let other = Story::from_string(input.clone()).take().0.ok().unwrap();
+            assert_eq!(other.to_debug_json(), dump);
+        }
+
+        let json: Value = serde_json::from_str(&dump).unwrap();
+        assert_eq!(json["title"], "Dump");
+        assert_eq!(json["author"], Value::Null);
+        assert_eq!(json["start"], "Begin");
+        assert_eq!(json["data"]["ifid"], "ABC");
+        assert_eq!(
+            serde_json::to_string(&json["data"]["tag_colors"]).unwrap(),
+            r#"{"a":"blue","b":"red"}"#
+        );
+        assert_eq!(json["scripts"][0], "let x = 1;");
+
+        let names: Vec<&str> = json["passages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["Begin", "End"]);
+        let begin = &json["passages"][0];
+        assert_eq!(begin["pid"], 1);
+        assert_eq!(json["passages"][1]["pid"], 2);
+        assert_eq!(begin["metadata"]["position"], "100,200");
+        assert_eq!(begin["span"]["start"], json!({"line": 7, "column": 1}));
+        assert_eq!(begin["links"][0]["kind"], "Pipe");
+        assert_eq!(begin["links"][0]["display_text"], "Go");
+        assert_eq!(
+            begin["links"][0]["span"],
+            json!({
+                "file": null,
+                "start": {"line": 8, "column": 1},
+                "end": {"line": 8, "column": 10},
+            })
+        );
+
+        let diagnostics = json["diagnostics"].as_array().unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0]["message"],
+            "Dead link to nonexistant passage: Nowhere"
+        );
+        assert_eq!(diagnostics[0]["code"], "W0016");
+        assert_eq!(diagnostics[0]["category"], "structure");
+        assert_eq!(diagnostics[0]["position"]["line"], 8);
+        assert_eq!(diagnostics[0]["position"]["column"], 12);
+    }
+
+    #[test]
+    fn story() {
+        let input = r#":: StoryTitle
+Test
+
+:: StoryData
+{"ifid": "ABC", "format": "SugarCube"}
+
+:: Start
+[[Go|End]] [img[map.png]] <<goto "Nowhere">>
+
+:: End
+Bye
+"#
+        .to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        let json: Value = serde_json::from_str(&story.to_json()).unwrap();
+        assert_eq!(json["version"], JSON_VERSION);
+        assert_eq!(json["data"]["format"], "SugarCube");
+        assert!(json.get("diagnostics").is_none());
+
+        let links = json["passages"][1]["links"].as_array().unwrap();
+        let kinds: Vec<(&str, &Value)> = links
+            .iter()
+            .map(|link| (link["kind"].as_str().unwrap(), &link["format"]))
+            .collect();
+        if cfg!(feature = "macro-links") {
+            assert_eq!(kinds.len(), 3);
+            assert_eq!(kinds[2], ("macro", &json!("SugarCube")));
+            assert_eq!(links[2]["target"], "Nowhere");
+            assert_eq!(links[2]["span"]["start"], json!({"line": 8, "column": 27}));
+        } else {
+            assert_eq!(kinds.len(), 2);
+        }
+        assert_eq!(kinds[0], ("pipe", &Value::Null));
+        assert_eq!(kinds[1], ("image", &Value::Null));
+
+        let warnings = json["warnings"].as_array().unwrap();
+        let codes: Vec<&str> = warnings
+            .iter()
+            .map(|warning| warning["code"].as_str().unwrap())
+            .collect();
+        if cfg!(feature = "macro-links") {
+            assert_eq!(codes, ["W0016"]);
+        } else {
+            assert!(codes.is_empty());
+        }
+    }
+}
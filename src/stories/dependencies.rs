@@ -0,0 +1,313 @@
+use crate::PassageHeader;
+use crate::Story;
+use crate::StoryData;
+use crate::StoryFormat;
+use crate::TwineContent;
+use crate::TwinePassage;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+
+/// The passages and widgets that a passage depends on, as found by
+/// [`Story::dependencies`]. All lists are sorted, with duplicates removed
+///
+/// Enabled with "macro-links" feature
+///
+/// [`Story::dependencies`]: struct.Story.html#method.dependencies
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PassageDependencies {
+    /// The names of the passages included with SugarCube's `<<include>>` and
+    /// `<<display>>` or Harlowe's `(display:)`
+    pub includes: Vec<String>,
+
+    /// The names of every SugarCube macro invoked, including widgets and the
+    /// macros built into the story format
+    pub macros: Vec<String>,
+
+    /// The names of the invoked macros that are widgets defined in the story
+    pub widgets: Vec<String>,
+
+    /// The names of the `widget` passages that define the invoked widgets
+    pub widget_passages: Vec<String>,
+}
+
+impl Story {
+    /// Returns the widgets defined with `<<widget "name">>` in the passages
+    /// tagged `widget`, mapped to the name of the passage that defines them.
+    /// If a widget is defined more than once, the passage that comes first by
+    /// name is used. Widgets are only found when the story format named in the
+    /// `StoryData` is SugarCube
+    ///
+    /// Enabled with "macro-links" feature
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = r#":: StoryData
+    /// {"ifid": "abc", "format": "SugarCube"}
+    ///
+    /// :: Widgets [widget]
+    /// <<widget "greet">>Hi<</widget>>
+    /// "#
+    /// .to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// assert_eq!(story.widgets()["greet"], "Widgets");
+    /// ```
+    pub fn widgets(&self) -> HashMap<String, String> {
+        if self.story_format() != Some(StoryFormat::SugarCube) {
+            return HashMap::default();
+        }
+        let passages = self
+            .passages
+            .iter()
+            .map(|(name, passage)| (name.as_str(), &passage.header, &passage.content));
+        widget_definitions(passages)
+    }
+
+    /// Returns the passages and widgets that the passage with the given name
+    /// depends on, found from the macros of the story format named in the
+    /// `StoryData`, or `None` if there is no such passage. Compilers can use
+    /// this to leave out the `widget` passages that no included passage needs,
+    /// or to report widgets that are invoked but never defined
+    ///
+    /// Only includes whose passage argument is a string literal are found.
+    /// Macros and widgets are only found when the story format is SugarCube
+    ///
+    /// Enabled with "macro-links" feature
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = r#":: StoryData
+    /// {"ifid": "abc", "format": "SugarCube"}
+    ///
+    /// :: Start
+    /// <<include "Header">><<greet>><<set $x to 1>><<greet>>
+    ///
+    /// :: Header
+    /// Welcome
+    ///
+    /// :: Widgets [widget]
+    /// <<widget "greet">>Hi<</widget>>
+    /// "#
+    /// .to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let dependencies = story.dependencies("Start").unwrap();
+    /// assert_eq!(dependencies.includes, ["Header"]);
+    /// assert_eq!(dependencies.macros, ["greet", "include", "set"]);
+    /// assert_eq!(dependencies.widgets, ["greet"]);
+    /// assert_eq!(dependencies.widget_passages, ["Widgets"]);
+    /// assert!(story.dependencies("Missing").is_none());
+    /// ```
+    pub fn dependencies(&self, name: &str) -> Option<PassageDependencies> {
+        let passage = self.passages.get(name)?;
+        Some(self.dependencies_of(passage, &self.widgets()))
+    }
+
+    /// Returns the names of the passages tagged `widget` that define no widget
+    /// invoked from any other passage, sorted by name. See
+    /// [`dependencies`](#method.dependencies) for more information
+    ///
+    /// Enabled with "macro-links" feature
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = r#":: StoryData
+    /// {"ifid": "abc", "format": "SugarCube"}
+    ///
+    /// :: Start
+    /// <<greet>>
+    ///
+    /// :: Used [widget]
+    /// <<widget "greet">>Hi<</widget>>
+    ///
+    /// :: Unused [widget]
+    /// <<widget "wave">>Bye<</widget>>
+    /// "#
+    /// .to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// assert_eq!(story.unused_widget_passages(), ["Unused"]);
+    /// ```
+    pub fn unused_widget_passages(&self) -> Vec<&str> {
+        let defined = self.widgets();
+        let used: BTreeSet<String> = self
+            .passages
+            .iter()
+            .flat_map(|(name, passage)| {
+                self.dependencies_of(passage, &defined)
+                    .widget_passages
+                    .into_iter()
+                    .filter(move |widgets| widgets != name)
+            })
+            .collect();
+        let mut unused: Vec<&str> = self
+            .passages
+            .iter()
+            .filter(|(_, passage)| passage.tags().iter().any(|tag| &**tag == "widget"))
+            .map(|(name, _)| name.as_str())
+            .filter(|name| !used.contains(*name))
+            .collect();
+        unused.sort_unstable();
+        unused
+    }
+
+    /// Finds the dependencies of the given passage, given the widgets defined
+    /// in the story
+    fn dependencies_of(
+        &self,
+        passage: &TwinePassage,
+        defined: &HashMap<String, String>,
+    ) -> PassageDependencies {
+        let format = match self.story_format() {
+            Some(format) => format,
+            None => return PassageDependencies::default(),
+        };
+
+        let includes: BTreeSet<String> = passage
+            .content
+            .get_macro_links(format)
+            .filter(|link| is_include(link.context.get_contents()))
+            .map(|link| link.target.trim().to_string())
+            .collect();
+
+        let mut macros = BTreeSet::new();
+        if format == StoryFormat::SugarCube {
+            macros.extend(
+                passage
+                    .content
+                    .get_macro_calls(StoryFormat::SugarCube)
+                    .map(|call| call.name.clone()),
+            );
+        }
+
+        let widgets: Vec<String> = macros
+            .iter()
+            .filter(|name| defined.contains_key(*name))
+            .cloned()
+            .collect();
+        let widget_passages: BTreeSet<String> =
+            widgets.iter().map(|name| defined[name].clone()).collect();
+
+        PassageDependencies {
+            includes: includes.into_iter().collect(),
+            macros: macros.into_iter().collect(),
+            widgets,
+            widget_passages: widget_passages.into_iter().collect(),
+        }
+    }
+
+    /// Returns the story format named in the `StoryData`, if any
+    fn story_format(&self) -> Option<StoryFormat> {
+        self.data.as_ref().and_then(StoryData::story_format)
+    }
+}
+
+/// Finds the widgets defined with `<<widget "name">>` in the given passages
+/// that are tagged `widget`, mapped to the name of the passage that defines
+/// them. If a widget is defined more than once, the passage that comes first
+/// by name is used
+pub(crate) fn widget_definitions<'a>(
+    passages: impl Iterator<Item = (&'a str, &'a PassageHeader, &'a TwineContent)>,
+) -> HashMap<String, String> {
+    let mut passages: Vec<(&str, &TwineContent)> = passages
+        .filter(|(_, header, _)| header.tags.iter().any(|tag| &**tag == "widget"))
+        .map(|(name, _, content)| (name, content))
+        .collect();
+    passages.sort_by_key(|(name, _)| *name);
+
+    let mut widgets = HashMap::default();
+    for (name, content) in passages {
+        for call in content.get_macro_calls(StoryFormat::SugarCube) {
+            if call.name != "widget" {
+                continue;
+            }
+            if let Some(Some(widget)) = call.args.first() {
+                widgets
+                    .entry(widget.clone())
+                    .or_insert_with(|| name.to_string());
+            }
+        }
+    }
+    widgets
+}
+
+/// Returns `true` if the given macro includes the passage it links to
+fn is_include(contents: &str) -> bool {
+    let name: String = contents
+        .trim_start_matches(['<', '('])
+        .chars()
+        .take_while(|c| !(c.is_whitespace() || *c == ':' || *c == '>'))
+        .filter(|c| *c != '-' && *c != '_')
+        .collect();
+    matches!(name.to_lowercase().as_str(), "include" | "display")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dependencies() {
+        let input = r#":: StoryData
+{"ifid": "abc", "format": "SugarCube"}
+
+:: Start
+<<include "Intro">> /* <<wave>> */ <<goto "Cave">> <<display 'Intro'>>
+<<greet>><<wave>><<missing>>
+
+:: Intro
+<<greet>>
+
+:: Cave
+Dark
+
+:: A [widget]
+<<widget "greet">><<wave>><</widget>>
+<<widget "wave">><</widget>>
+
+:: B [widget]
+<<widget "wave">><</widget>>
+
+:: C [widget]
+<<widget "shout">><</widget>>
+"#
+        .to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        let widgets = story.widgets();
+        assert_eq!(widgets.len(), 3);
+        assert_eq!(widgets["wave"], "A");
+
+        let start = story.dependencies("Start").unwrap();
+        assert_eq!(start.includes, ["Intro"]);
+        assert_eq!(
+            start.macros,
+            ["display", "goto", "greet", "include", "missing", "wave"]
+        );
+        assert_eq!(start.widgets, ["greet", "wave"]);
+        assert_eq!(start.widget_passages, ["A"]);
+        assert_eq!(
+            story.dependencies("Cave").unwrap(),
+            PassageDependencies::default()
+        );
+        assert_eq!(story.unused_widget_passages(), ["B", "C"]);
+    }
+
+    #[test]
+    fn harlowe() {
+        let input = r#":: StoryData
+{"ifid": "abc", "format": "Harlowe"}
+
+:: Start
+(Display: "Intro") (goto: "Intro") <<greet>>
+
+:: Intro
+Hi
+"#
+        .to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        let start = story.dependencies("Start").unwrap();
+        assert_eq!(start.includes, ["Intro"]);
+        assert!(start.macros.is_empty());
+        assert!(story.widgets().is_empty());
+    }
+}
@@ -0,0 +1,109 @@
+/// The byte order mark that may start a UTF-8 file
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// The byte order mark that starts a little-endian UTF-16 file
+const UTF16_LE_BOM: &[u8] = b"\xFF\xFE";
+
+/// The byte order mark that starts a big-endian UTF-16 file
+const UTF16_BE_BOM: &[u8] = b"\xFE\xFF";
+
+/// The reason input could not be decoded
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) enum EncodingError {
+    /// The input starts with a UTF-16 byte order mark. Contains the name of the
+    /// byte order
+    Utf16(&'static str),
+
+    /// The input is not valid UTF-8. Contains the input with invalid sequences
+    /// replaced, the byte offset of the first one, and its first byte
+    InvalidUtf8(String, usize, u8),
+}
+
+/// Decodes the given bytes as UTF-8, dropping any byte order mark. If `lossy`
+/// is set, UTF-16 input with a byte order mark is decoded as well, and invalid
+/// sequences are replaced with U+FFFD instead of causing an error
+pub(crate) fn decode(mut bytes: Vec<u8>, lossy: bool) -> Result<String, EncodingError> {
+    let utf16 = if bytes.starts_with(UTF16_LE_BOM) {
+        Some(("little-endian", u16::from_le_bytes as fn([u8; 2]) -> u16))
+    } else if bytes.starts_with(UTF16_BE_BOM) {
+        Some(("big-endian", u16::from_be_bytes as fn([u8; 2]) -> u16))
+    } else {
+        None
+    };
+    if let Some((order, to_unit)) = utf16 {
+        if !lossy {
+            return Err(EncodingError::Utf16(order));
+        }
+        let units: Vec<u16> = bytes[2..]
+            .chunks(2)
+            .map(|pair| to_unit([pair[0], *pair.get(1).unwrap_or(&0)]))
+            .collect();
+        return Ok(String::from_utf16_lossy(&units));
+    }
+
+    if bytes.starts_with(UTF8_BOM) {
+        bytes.drain(..UTF8_BOM.len());
+    }
+    match String::from_utf8(bytes) {
+        Ok(contents) => Ok(contents),
+        Err(e) => {
+            let contents = String::from_utf8_lossy(e.as_bytes()).into_owned();
+            if lossy {
+                return Ok(contents);
+            }
+            let offset = e.utf8_error().valid_up_to();
+            let byte = e.as_bytes()[offset];
+            Err(EncodingError::InvalidUtf8(contents, offset, byte))
+        }
+    }
+}
+
+/// Removes a byte order mark from the start of the given input, if it has one
+pub(crate) fn strip_bom(mut input: String) -> String {
+    if input.starts_with('\u{feff}') {
+        input.drain(..'\u{feff}'.len_utf8());
+    }
+    input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decoding() {
+        assert_eq!(
+            decode(b"\xEF\xBB\xBF:: Start".to_vec(), false),
+            Ok(":: Start".to_string())
+        );
+        assert_eq!(
+            decode(b"\xFF\xFE:\0:\0".to_vec(), false),
+            Err(EncodingError::Utf16("little-endian"))
+        );
+        assert_eq!(
+            decode(b"\xFF\xFE:\0:\0".to_vec(), true),
+            Ok("::".to_string())
+        );
+        assert_eq!(
+            decode(b"\xFE\xFF\0:\0:".to_vec(), true),
+            Ok("::".to_string())
+        );
+        assert_eq!(
+            decode(b"ab\xFFc".to_vec(), false),
+            Err(EncodingError::InvalidUtf8(
+                "ab\u{fffd}c".to_string(),
+                2,
+                0xFF
+            ))
+        );
+        assert_eq!(
+            decode(b"ab\xFFc".to_vec(), true),
+            Ok("ab\u{fffd}c".to_string())
+        );
+        assert_eq!(strip_bom("\u{feff}:: Start".to_string()), ":: Start");
+        assert_eq!(
+            strip_bom(":: Start\u{feff}".to_string()),
+            ":: Start\u{feff}"
+        );
+    }
+}
@@ -0,0 +1,293 @@
+use crate::Span;
+use crate::Story;
+use crate::StoryData;
+use crate::StoryFormat;
+use crate::TwinePassage;
+use serde_json::json;
+use serde_json::Value;
+
+/// The SugarCube macros that play, load, or define audio tracks, whose first
+/// argument names the tracks
+const SUGARCUBE_MEDIA: &[&str] = &["audio", "cacheaudio", "track", "playlist", "createplaylist"];
+
+/// The macros of the Harlowe Audio Library that play or define audio tracks,
+/// whose first argument names the track
+const HARLOWE_MEDIA: &[&str] = &["track", "newtrack", "playlist"];
+
+/// The macros that define a track from the URLs of its files
+const DEFINITIONS: &[&str] = &["cacheaudio", "newtrack"];
+
+/// An audio cue in a passage, such as SugarCube's `<<audio "theme" play>>` or
+/// Harlowe's `(track: 'theme', 'play')`, as found by [`Story::media_cues`]
+///
+/// Enabled with "macro-links" feature
+///
+/// [`Story::media_cues`]: struct.Story.html#method.media_cues
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MediaCue {
+    /// The name of the track or playlist the cue refers to
+    pub name: String,
+
+    /// The name of the macro, as written
+    pub macro_name: String,
+
+    /// The URLs of the files of the track, if the macro defines it, such as
+    /// SugarCube's `<<cacheaudio>>`
+    pub sources: Vec<String>,
+
+    /// The name of the passage the cue is in
+    pub passage: String,
+
+    /// Where the macro is in its file
+    pub span: Span,
+}
+
+impl Story {
+    /// Finds the audio cues in the normal passages of the story, using the
+    /// macros of the story format named in the `StoryData`, in order of
+    /// passage name and then position. This is the list of tracks a story
+    /// needs, for the people making them to work from
+    ///
+    /// Only macros whose first argument is a string literal are found. The
+    /// SugarCube macros are `<<audio>>`, `<<cacheaudio>>`, `<<playlist>>`,
+    /// `<<createplaylist>>`, and `<<track>>`, where the first argument can
+    /// name several tracks separated by spaces, each of which is a cue of its
+    /// own. Group ids such as `:all` are not tracks, and are left out. The
+    /// Harlowe macros are the `(track:)`, `(newtrack:)`, and `(playlist:)`
+    /// of the Harlowe Audio Library
+    ///
+    /// Enabled with "macro-links" feature
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = r#":: StoryData
+    /// {"ifid": "abc", "format": "SugarCube"}
+    ///
+    /// :: StoryInit
+    /// <<cacheaudio "rain" "audio/rain.mp3" "audio/rain.ogg">>
+    ///
+    /// :: Start
+    /// <<audio "rain thunder" play>><<audio ":all" stop>>
+    /// "#
+    /// .to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let cues = story.media_cues();
+    /// let names: Vec<&str> = cues.iter().map(|cue| cue.name.as_str()).collect();
+    /// assert_eq!(names, ["rain", "thunder", "rain"]);
+    /// assert_eq!(cues[0].passage, "Start");
+    /// assert_eq!(cues[0].span.start, (8, 1));
+    /// assert_eq!(cues[2].sources, ["audio/rain.mp3", "audio/rain.ogg"]);
+    /// ```
+    pub fn media_cues(&self) -> Vec<MediaCue> {
+        let format = match self.data.as_ref().and_then(StoryData::story_format) {
+            Some(format) => format,
+            None => return Vec::new(),
+        };
+        let mut passages: Vec<(&String, &TwinePassage)> = self.passages.iter().collect();
+        passages.sort_by_key(|(name, _)| *name);
+
+        let mut cues = Vec::new();
+        for (passage, twine) in passages {
+            for call in twine.content.get_macro_calls(format) {
+                let name = match format {
+                    StoryFormat::SugarCube => call.name.clone(),
+                    StoryFormat::Harlowe => call.name.to_lowercase().replace(['-', '_'], ""),
+                };
+                let known = match format {
+                    StoryFormat::SugarCube => SUGARCUBE_MEDIA,
+                    StoryFormat::Harlowe => HARLOWE_MEDIA,
+                };
+                if !known.contains(&name.as_str()) {
+                    continue;
+                }
+                let tracks = match call.args.first() {
+                    Some(Some(tracks)) => tracks,
+                    _ => continue,
+                };
+                let sources: Vec<String> = if DEFINITIONS.contains(&name.as_str()) {
+                    call.args[1..].iter().flatten().cloned().collect()
+                } else {
+                    Vec::new()
+                };
+
+                // Only SugarCube lists several tracks in one argument
+                let tracks: Vec<&str> = match format {
+                    StoryFormat::SugarCube => tracks.split_whitespace().collect(),
+                    StoryFormat::Harlowe => vec![tracks.as_str()],
+                };
+                for track in tracks.into_iter().filter(|t| !t.starts_with(':')) {
+                    cues.push(MediaCue {
+                        name: track.to_string(),
+                        macro_name: call.name.clone(),
+                        sources: sources.clone(),
+                        passage: passage.clone(),
+                        span: Span::from(&call.context),
+                    });
+                }
+            }
+        }
+        cues
+    }
+
+    /// Returns the [`media_cues`](#method.media_cues) of the story as a JSON
+    /// array, with an object for each cue holding its `name`, `macro`,
+    /// `sources`, `passage`, `file`, and the `start` and `end` of its span as
+    /// `[line, column]` pairs
+    ///
+    /// Enabled with "macro-links" feature
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = ":: StoryData\n{\"ifid\": \"abc\", \"format\": \"Harlowe\"}\n\n:: Start\n(track: 'rain', 'play')".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let manifest: serde_json::Value = serde_json::from_str(&story.media_manifest_json()).unwrap();
+    /// assert_eq!(manifest[0]["name"], "rain");
+    /// assert_eq!(manifest[0]["macro"], "track");
+    /// assert_eq!(manifest[0]["start"], serde_json::json!([5, 1]));
+    /// ```
+    pub fn media_manifest_json(&self) -> String {
+        let cues: Vec<Value> = self
+            .media_cues()
+            .iter()
+            .map(|cue| {
+                json!({
+                    "name": cue.name,
+                    "macro": cue.macro_name,
+                    "sources": cue.sources,
+                    "passage": cue.passage,
+                    "file": cue.span.file,
+                    "start": cue.span.start,
+                    "end": cue.span.end,
+                })
+            })
+            .collect();
+        Value::Array(cues).to_string()
+    }
+
+    /// Returns the [`media_cues`](#method.media_cues) of the story as CSV,
+    /// with a header row followed by a row for each cue with its name, macro,
+    /// passage, file, line, column, and sources, which are separated by
+    /// spaces. Fields are quoted when needed
+    ///
+    /// Enabled with "macro-links" feature
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = ":: StoryData\n{\"ifid\": \"abc\", \"format\": \"SugarCube\"}\n\n:: Rain, Night\n<<audio \"rain\" play>>".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// assert_eq!(
+    ///     story.media_manifest_csv(),
+    ///     "name,macro,passage,file,line,column,sources\nrain,audio,\"Rain, Night\",,5,1,\n"
+    /// );
+    /// ```
+    pub fn media_manifest_csv(&self) -> String {
+        let mut csv = String::from("name,macro,passage,file,line,column,sources\n");
+        for cue in self.media_cues() {
+            let file = cue
+                .span
+                .file
+                .as_ref()
+                .map(|file| file.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let fields = [
+                cue.name,
+                cue.macro_name,
+                cue.passage,
+                file,
+                cue.span.start.0.to_string(),
+                cue.span.start.1.to_string(),
+                cue.sources.join(" "),
+            ];
+            let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+/// Quotes the given CSV field if it contains a comma, quote, or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sugarcube() {
+        let input = r#":: StoryData
+{"ifid": "abc", "format": "SugarCube"}
+
+:: Start
+<<createplaylist "mix">><<track "rain" volume 0.5>><</createplaylist>>
+/* <<audio "quiet" play>> */<<audio $track play>><<masteraudio stop>>
+
+:: Init
+<<cacheaudio "rain" "a.mp3" 'say "hi".ogg'>>
+"#
+        .to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        let cues = story.media_cues();
+        let cues: Vec<(&str, &str, &str)> = cues
+            .iter()
+            .map(|cue| {
+                (
+                    cue.name.as_str(),
+                    cue.macro_name.as_str(),
+                    cue.passage.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            cues,
+            [
+                ("rain", "cacheaudio", "Init"),
+                ("mix", "createplaylist", "Start"),
+                ("rain", "track", "Start")
+            ]
+        );
+        assert_eq!(
+            story.media_manifest_csv(),
+            "name,macro,passage,file,line,column,sources
+rain,cacheaudio,Init,,9,1,\"a.mp3 say \"\"hi\"\".ogg\"
+mix,createplaylist,Start,,5,1,
+rain,track,Start,,5,25,
+"
+        );
+    }
+
+    #[test]
+    fn harlowe() {
+        let input = r#":: StoryData
+{"ifid": "abc", "format": "Harlowe"}
+
+:: Start
+(New-Track: "rain", "a.mp3") (track: "rain", "loop", true) (Track: $t) <<audio "x">>
+"#
+        .to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        let cues = story.media_cues();
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].sources, ["a.mp3"]);
+        assert_eq!(cues[0].macro_name, "New-Track");
+        assert!(cues[1].sources.is_empty());
+        assert_eq!((cues[1].span.start, cues[1].span.end), ((5, 30), (5, 58)));
+
+        let story = Story::from_string(":: Start\n<<audio \"x\">>".to_string())
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        assert!(story.media_cues().is_empty());
+        assert_eq!(story.media_manifest_json(), "[]");
+    }
+}
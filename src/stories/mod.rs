@@ -1,3 +1,33 @@
+mod backlinks;
+pub(crate) use backlinks::backlinks;
+
+mod debug_json;
+pub(crate) use debug_json::debug_json;
+#[cfg(feature = "stdio-server")]
+pub(crate) use debug_json::error_json;
+pub(crate) use debug_json::story_json;
+#[cfg(feature = "stdio-server")]
+pub(crate) use debug_json::warning_json;
+
+#[cfg(feature = "macro-links")]
+mod dependencies;
+#[cfg(feature = "macro-links")]
+pub use dependencies::PassageDependencies;
+#[cfg(feature = "macro-links")]
+pub(crate) use dependencies::widget_definitions;
+
+mod encoding;
+
+#[cfg(feature = "macro-links")]
+mod media;
+#[cfg(feature = "macro-links")]
+pub use media::MediaCue;
+
+mod checks;
+pub(crate) use checks::CheckPass;
+pub(crate) use checks::CheckedPassage;
+pub(crate) use checks::CheckedStory;
+
 #[cfg(feature = "full-context")]
 mod code_map;
 #[cfg(feature = "full-context")]
@@ -8,8 +38,28 @@ mod context_error_list;
 #[cfg(feature = "full-context")]
 pub use context_error_list::ContextErrorList;
 
+mod source_file;
+pub(crate) use source_file::depfile;
+pub use source_file::SourceFile;
+pub use source_file::SourceFileKind;
+
 mod story;
 pub use story::Story;
 
+mod story_builder;
+pub use story_builder::StoryBuilder;
+
+mod story_diff;
+pub use story_diff::diff;
+pub use story_diff::PassageDiff;
+pub use story_diff::StoryDiff;
+
 mod story_passages;
 pub use story_passages::StoryPassages;
+
+mod suggest;
+pub(crate) use suggest::closest_name;
+
+mod story_visitor;
+pub use story_visitor::walk;
+pub use story_visitor::StoryVisitor;
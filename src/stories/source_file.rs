@@ -0,0 +1,119 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+/// An enum of the ways a file can be handled while parsing from a [`Path`]
+///
+/// [`Path`]: std::path::Path
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SourceFileKind {
+    /// The file was read and parsed
+    Parsed,
+
+    /// The file was skipped because it does not have one of the extensions set
+    /// in the [`ParseOptions`](struct.ParseOptions.html), `.tw` or `.twee` by
+    /// default
+    WrongExtension,
+
+    /// The entry was skipped because it is not a regular file, or it is a
+    /// directory and recursion is not enabled
+    NotAFile,
+
+    /// The file could not be read. Contains the error string
+    Unreadable(String),
+
+    /// The entry was skipped because it matches a pattern in a `.twee-ignore`
+    /// file
+    Ignored,
+}
+
+/// A file that was encountered while parsing a story from a [`Path`], along
+/// with how it was handled
+///
+/// # Examples
+/// ```
+/// use tweep::{SourceFile, SourceFileKind};
+/// let source = SourceFile::new("story.twee", SourceFileKind::Parsed);
+/// assert!(source.is_parsed());
+/// ```
+///
+/// [`Path`]: std::path::Path
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceFile {
+    /// The path of the file
+    pub path: PathBuf,
+
+    /// How the file was handled
+    pub kind: SourceFileKind,
+}
+
+impl SourceFile {
+    /// Creates a new `SourceFile` with the given path and kind
+    pub fn new<P: Into<PathBuf>>(path: P, kind: SourceFileKind) -> Self {
+        SourceFile {
+            path: path.into(),
+            kind,
+        }
+    }
+
+    /// Returns `true` if this file was read and parsed
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{SourceFile, SourceFileKind};
+    /// let source = SourceFile::new("notes.txt", SourceFileKind::WrongExtension);
+    /// assert!(!source.is_parsed());
+    /// ```
+    pub fn is_parsed(&self) -> bool {
+        self.kind == SourceFileKind::Parsed
+    }
+}
+
+/// Escapes a path for use in a Make-style dependency file
+fn escape_make_path(path: &Path) -> String {
+    let mut escaped = String::new();
+    for c in path.to_string_lossy().chars() {
+        match c {
+            ' ' | '#' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '$' => escaped.push_str("$$"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Produces the contents of a Make-style (`.d`) dependency file that lists
+/// every parsed file in `sources` as a prerequisite of `target`
+pub(crate) fn depfile<P: AsRef<Path>>(target: P, sources: &[SourceFile]) -> String {
+    let mut out = escape_make_path(target.as_ref());
+    out.push(':');
+    for source in sources.iter().filter(|s| s.is_parsed()) {
+        out.push_str(" \\\n  ");
+        out.push_str(&escape_make_path(&source.path));
+    }
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn depfile_contents() {
+        let sources = vec![
+            SourceFile::new("story/a passage.twee", SourceFileKind::Parsed),
+            SourceFile::new("story/notes.txt", SourceFileKind::WrongExtension),
+            SourceFile::new("story/$cost#1.tw", SourceFileKind::Parsed),
+        ];
+        assert_eq!(
+            depfile("out/story.html", &sources),
+            "out/story.html: \\\n  story/a\\ passage.twee \\\n  story/$$cost\\#1.tw\n"
+        );
+        assert_eq!(depfile("out.html", &[]), "out.html:\n");
+    }
+}
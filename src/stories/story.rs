@@ -1,22 +1,43 @@
+use crate::stories::CheckedPassage;
+use crate::stories::CheckedStory;
 #[cfg(feature = "full-context")]
 use crate::CodeMap;
 #[cfg(feature = "full-context")]
 use crate::ContextErrorList;
+use crate::Error;
 #[cfg(not(feature = "full-context"))]
 use crate::ErrorList;
+use crate::FastHashMap;
+use crate::FullContext;
+use crate::HeaderContinuation;
 use crate::Output;
+use crate::ParseOptions;
+use crate::Passage;
+use crate::PassageBuilder;
 use crate::PassageContent;
+use crate::PassageRef;
+use crate::ScriptContent;
+use crate::SourceFile;
+use crate::SpecialContent;
 use crate::StoryData;
+use crate::StoryGraph;
 use crate::StoryPassages;
+use crate::StoryTitle;
+use crate::StylesheetContent;
+use crate::TwineLink;
 use crate::TwinePassage;
+use crate::Warning;
+use crate::WarningKind;
 use std::collections::HashMap;
+use std::io::Read;
 use std::path::Path;
+use std::path::PathBuf;
 
 /// A parsed Twee story
 ///
 /// This is the primary interface for tweep. The provided utility functions
-/// allow a Twee 3 story to be parsed from a `String`, a directory or file
-/// `Path`, or a slice of string slices, representing the lines of input. The
+/// allow a Twee 3 story to be parsed from a `String`, a `Read`, a directory or
+/// file `Path`, or a slice of string slices, representing the lines of input. The
 /// output is an `Output<Result<Story, ErrorList>>` which is either the parsed
 /// `Story` or an [`ErrorList`] if the parse failed, along with a list of any
 /// [`Warning`]s generated during parsing. The fields in this struct provide
@@ -24,18 +45,42 @@ use std::path::Path;
 ///
 /// # Parse Errors
 /// * [`BadInputPath`] - The given `Path` cannot be used to parse a story
+/// * [`ReadError`] - The given `Read` could not be read
+/// * [`InvalidEncoding`] - The input is not UTF-8 and the [`ParseOptions`]
+///   do not allow lossy decoding
+/// * [`HtmlInput`] - The input is a story exported from Twine as HTML
+/// * [`ParseAborted`] - A [`DiagnosticSink`] stopped the parse early
+/// * [`WarningsAsErrors`] - Warnings were produced and the [`ParseOptions`]
+///   treat warnings as errors
+/// * [`DuplicatePassage`] - More than one passage with the same name found,
+///   if the [`ParseOptions`] make duplicates an error
 /// See [`Passage`] for other errors that can occur during parsing
 ///
 /// # Parse Warnings
 /// * [`DuplicateStoryTitle`] - More than one `StoryTitle` passage found
 /// * [`DuplicateStoryData`] - More than one `StoryData` passage found
+/// * [`DuplicateStoryAuthor`], [`DuplicateStorySubtitle`],
+///   [`DuplicateStoryMenu`], [`DuplicateStorySettings`] - More than one of the
+///   corresponding special passage found
 /// * [`MissingStoryTitle`] - No `StoryTitle` passage found
 /// * [`MissingStoryData`] - No `StoryData` passage found
 /// * [`DeadLink`] - Found a link to a non-existent passage
+/// * [`SelfLink`] - Found a link from a passage to itself
+/// * [`NoReachableEnding`] - No passage without links can be reached from
+///   the start passage. Only checked if enabled with
+///   [`ParseOptions::with_checked_endings`]
+/// * [`ExcludedLink`] - Found a link to a passage that is only defined in
+///   a file that was skipped
 /// * [`MissingStartPassage`] - No `Start` passage found and no alternate
 ///   passage set in `StoryData`
 /// * [`DeadStartPassage`] - Alternate start passage set in `StoryData`, but
 ///   no such passage found in parsing
+/// * [`UnreadableFile`] - A file could not be read and was skipped, if enabled
+///   in the [`ParseOptions`]
+/// * [`BadIgnoreFile`] - A `.twee-ignore` file could not be read or contains
+///   an invalid pattern
+/// * [`IncludeCycle`] - A file listed in a `StoryIncludes` passage includes
+///   itself, if includes are enabled in the [`ParseOptions`]
 /// See [`Passage`] for other warnings that can occur during parsing
 ///
 ///
@@ -45,6 +90,9 @@ use std::path::Path;
 /// let input = r#":: StoryTitle
 ///RustDoc Sample Story
 ///
+///:: StoryAuthor
+///Jane Doe
+///
 ///:: StoryData
 ///{
 ///  "ifid": "D674C58C-DEFA-4F70-B7A2-27742230C0FC",
@@ -87,8 +135,10 @@ use std::path::Path;
 ///// Get the Story object
 ///let story = res.ok().unwrap();
 ///
-///// StoryTitle and StoryData contents are parsed into special fields
+///// StoryTitle, StoryData, and the other special passages are parsed into
+///// special fields
 ///assert_eq!(story.title.unwrap(), "RustDoc Sample Story");
+///assert_eq!(story.author.unwrap(), "Jane Doe");
 ///assert_eq!(story.data.unwrap().ifid, "D674C58C-DEFA-4F70-B7A2-27742230C0FC");
 ///
 ///// Other passages are parsed into a map, keyed by the passage name
@@ -100,14 +150,34 @@ use std::path::Path;
 ///
 /// [`DuplicateStoryTitle`]: enum.WarningKind.html#variant.DuplicateStoryTitle
 /// [`DuplicateStoryData`]: enum.WarningKind.html#variant.DuplicateStoryData
+/// [`DuplicateStoryAuthor`]: enum.WarningKind.html#variant.DuplicateStoryAuthor
+/// [`DuplicateStorySubtitle`]: enum.WarningKind.html#variant.DuplicateStorySubtitle
+/// [`DuplicateStoryMenu`]: enum.WarningKind.html#variant.DuplicateStoryMenu
+/// [`DuplicateStorySettings`]: enum.WarningKind.html#variant.DuplicateStorySettings
 /// [`MissingStoryTitle`]: enum.WarningKind.html#variant.MissingStoryTitle
 /// [`MissingStoryData`]: enum.WarningKind.html#variant.MissingStoryData
 /// [`DeadLink`]: enum.WarningKind.html#variant.DeadLink
+/// [`SelfLink`]: enum.WarningKind.html#variant.SelfLink
+/// [`NoReachableEnding`]: enum.WarningKind.html#variant.NoReachableEnding
+/// [`ParseOptions::with_checked_endings`]: struct.ParseOptions.html#method.with_checked_endings
+/// [`ExcludedLink`]: enum.WarningKind.html#variant.ExcludedLink
 /// [`MissingStartPassage`]: enum.WarningKind.html#variant.MissingStartPassage
 /// [`DeadStartPassage`]: enum.WarningKind.html#variant.DeadStartPassage
+/// [`UnreadableFile`]: enum.WarningKind.html#variant.UnreadableFile
+/// [`BadIgnoreFile`]: enum.WarningKind.html#variant.BadIgnoreFile
+/// [`IncludeCycle`]: enum.WarningKind.html#variant.IncludeCycle
 /// [`BadInputPath`]: enum.ErrorKind.html#variant.BadInputPath
+/// [`ReadError`]: enum.ErrorKind.html#variant.ReadError
+/// [`InvalidEncoding`]: enum.ErrorKind.html#variant.InvalidEncoding
+/// [`HtmlInput`]: enum.ErrorKind.html#variant.HtmlInput
+/// [`ParseAborted`]: enum.ErrorKind.html#variant.ParseAborted
+/// [`WarningsAsErrors`]: enum.ErrorKind.html#variant.WarningsAsErrors
+/// [`DuplicatePassage`]: enum.ErrorKind.html#variant.DuplicatePassage
+/// [`DiagnosticSink`]: trait.DiagnosticSink.html
+/// [`ParseOptions`]: struct.ParseOptions.html
 /// [`Passage`]: struct.Passage.html
-#[derive(Default)]
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Story {
     /// The story title
     pub title: Option<String>,
@@ -115,6 +185,18 @@ pub struct Story {
     /// The story data as defined by the specification
     pub data: Option<StoryData>,
 
+    /// The contents of the `StoryAuthor` passage
+    pub author: Option<String>,
+
+    /// The contents of the `StorySubtitle` passage
+    pub subtitle: Option<String>,
+
+    /// The contents of the `StoryMenu` passage
+    pub menu: Option<String>,
+
+    /// The contents of the `StorySettings` passage
+    pub settings: Option<String>,
+
     /// Map from passage name to `TwinePassage` for any non-special passages
     pub passages: HashMap<String, TwinePassage>,
 
@@ -124,6 +206,27 @@ pub struct Story {
     /// A list of the contents of any passages tagged with `stylesheet`
     pub stylesheets: Vec<String>,
 
+    /// List of files encountered while parsing from a `Path`, in the order
+    /// they were encountered, including any files that were skipped
+    pub sources: Vec<SourceFile>,
+
+    /// Map from passage name to the context of that passage in the source,
+    /// for every passage in the story, including special passages
+    pub contexts: HashMap<String, FullContext>,
+
+    /// Map from the name of each normal passage parsed from a `Path` to the
+    /// path of the file it was parsed from
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub passage_files: HashMap<String, PathBuf>,
+
+    /// The errors of the passages whose headers could not be parsed, which
+    /// were replaced by placeholders because the [`ParseOptions`] enable
+    /// recovery
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub recovered_errors: Vec<Error>,
+
     /// StoryMap for this story
     #[cfg(feature = "full-context")]
     pub code_map: CodeMap,
@@ -135,6 +238,426 @@ type ParseOutput = Output<Result<Story, ErrorList>>;
 type ParseOutput = Output<Result<Story, ContextErrorList>>;
 
 impl Story {
+    /// Returns the context of the passage with the given name in the source,
+    /// if the story has such a passage. This includes special passages such
+    /// as `StoryTitle`, as well as passages tagged `script` or `stylesheet`
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = ":: StoryTitle\nA Story\n\n:: Start\nHello\n".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let context = story.context_of("Start").unwrap();
+    /// assert_eq!(context.get_start_position().line, 4);
+    /// assert!(story.context_of("StoryTitle").is_some());
+    /// assert!(story.context_of("Missing").is_none());
+    /// ```
+    pub fn context_of(&self, name: &str) -> Option<&FullContext> {
+        self.contexts.get(name)
+    }
+
+    /// Returns the path of the file that the normal passage with the given
+    /// name was parsed from, or `None` if there is no such passage or the
+    /// story was not parsed from a `Path`. Unlike the file name in the
+    /// passage's context, this is the whole path, so passages from files
+    /// with the same name in different directories can be told apart
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # #[cfg(feature = "fs")] {
+    /// use tweep::Story;
+    /// let dir = tempfile::tempdir()?;
+    /// std::fs::create_dir(dir.path().join("en"))?;
+    /// std::fs::create_dir(dir.path().join("de"))?;
+    /// std::fs::write(dir.path().join("en").join("story.tw"), ":: Start\nHello")?;
+    /// std::fs::write(dir.path().join("de").join("story.tw"), ":: Anfang\nHallo")?;
+    /// let options = tweep::ParseOptions::new().with_recursive(true);
+    /// let story = Story::from_path_with_options(dir.path(), &options).take().0.ok().unwrap();
+    /// let file = story.file_of("Anfang").unwrap();
+    /// assert_eq!(file, dir.path().join("de").join("story.tw"));
+    /// assert!(story.file_of("Missing").is_none());
+    /// # }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn file_of(&self, name: &str) -> Option<&Path> {
+        self.passage_files.get(name).map(PathBuf::as_path)
+    }
+
+    /// Returns every link in the story whose target is the passage with the
+    /// given name, ordered by the name of the passage containing the link and
+    /// then by position. Each link carries its context, so this can be used to
+    /// find the references to a passage. With the "macro-links" feature
+    /// enabled, links created by the macros of the story format named in the
+    /// `StoryData` are included
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = ":: Start\n[[Cave]]\n\n:: Cave\n[[Back|Start]] [[Start]]\n".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let links = story.backlinks("Start");
+    /// assert_eq!(links.len(), 2);
+    /// assert_eq!(links[0].get_display_text(), "Back");
+    /// assert_eq!(links[1].file_position().column, 16);
+    /// assert!(story.backlinks("Missing").is_empty());
+    /// ```
+    pub fn backlinks(&self, name: &str) -> Vec<&TwineLink> {
+        let contents = self
+            .passages
+            .iter()
+            .map(|(passage, twine)| (passage.as_str(), &twine.content));
+        let format = self.data.as_ref().and_then(StoryData::story_format);
+        crate::stories::backlinks(contents, format, name)
+    }
+
+    /// Runs every check on the story with the default [`ParseOptions`]. See
+    /// `validate_with_options` for more information
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    pub fn validate(&self) -> Vec<Warning> {
+        self.validate_with_options(&ParseOptions::default())
+    }
+
+    /// Runs every check on the story in one call: the post-parse checks of
+    /// [`StoryPassages::check_with_options`], followed by the [`Lint`]s
+    /// registered in the given [`ParseOptions`]. Warnings of kinds or
+    /// categories suppressed in the options are dropped. Warnings produced
+    /// while parsing are returned in the [`Output`] of the parse and are not
+    /// repeated
+    ///
+    /// A `Story` does not keep the passages of skipped files, so a link to one
+    /// of them is reported as a [`DeadLink`] rather than an [`ExcludedLink`]
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{Story, WarningKind};
+    /// let input = ":: StoryTitle\nA Story\n\n:: Start\n[[Nowhere]]\n".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let kinds: Vec<WarningKind> = story.validate().into_iter().map(|w| w.kind).collect();
+    /// assert_eq!(
+    ///     kinds,
+    ///     vec![WarningKind::MissingStoryData, WarningKind::DeadLink("Nowhere".to_string())]
+    /// );
+    /// ```
+    ///
+    /// [`StoryPassages::check_with_options`]: struct.StoryPassages.html#method.check_with_options
+    /// [`Lint`]: trait.Lint.html
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    /// [`Output`]: struct.Output.html
+    /// [`DeadLink`]: enum.WarningKind.html#variant.DeadLink
+    /// [`ExcludedLink`]: enum.WarningKind.html#variant.ExcludedLink
+    pub fn validate_with_options(&self, options: &ParseOptions) -> Vec<Warning> {
+        let passages = self
+            .passages
+            .iter()
+            .map(|(name, passage)| {
+                let checked = CheckedPassage {
+                    header: &passage.header,
+                    content: &passage.content,
+                    context: self.contexts.get(name),
+                };
+                (name.as_str(), checked)
+            })
+            .collect();
+        let data = match (&self.data, self.contexts.get("StoryData")) {
+            (None, None) => None,
+            (data, context) => Some((data.as_ref(), context)),
+        };
+        let checked = CheckedStory {
+            title: self
+                .title
+                .as_ref()
+                .map(|title| (title.as_str(), self.contexts.get("StoryTitle"))),
+            data,
+            passages,
+            excluded_passages: None,
+            passage_files: Some(&self.passage_files),
+            #[cfg(feature = "macro-links")]
+            scripts: self.scripts.iter().map(String::as_str).collect(),
+        };
+        let mut warnings = checked.check(options);
+        warnings.retain(|warning| !options.is_suppressed(&warning.kind));
+        warnings
+    }
+
+    /// Returns a short human-readable overview of the story, for a quick look
+    /// while debugging or exploring a story. It gives the title, IFID, and
+    /// start passage of the story, the number of normal passages, the number
+    /// of dead links found by [`validate`], and the five most used tags with
+    /// their counts. The format of the overview may change in minor releases
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = r#":: StoryTitle
+    ///A Short Story
+    ///
+    ///:: StoryData
+    ///{"ifid": "D674C58C-DEFA-4F70-B7A2-27742230C0FC"}
+    ///
+    ///:: Start [forest]
+    ///[[Cave]] [[Lake]]
+    ///
+    ///:: Cave [forest dark]
+    ///The end
+    ///"#.to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// assert_eq!(
+    ///     story.summary(),
+    ///     "Title: A Short Story
+    ///IFID: D674C58C-DEFA-4F70-B7A2-27742230C0FC
+    ///Start: Start
+    ///Passages: 2
+    ///Dead links: 1
+    ///Top tags: forest (2), dark (1)
+    ///"
+    /// );
+    /// ```
+    ///
+    /// [`validate`]: #method.validate
+    pub fn summary(&self) -> String {
+        self.summary_with_options(&ParseOptions::default())
+    }
+
+    /// Returns the overview of [`summary`], with the story checked using the
+    /// given [`ParseOptions`]. If the options have flagged words, the overview
+    /// ends with the number of times each one was found in passage text
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{ParseOptions, Story};
+    /// let input = ":: Start\nTODO: lorem ipsum, todo\n".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let options = ParseOptions::new()
+    ///     .with_flagged_words(vec!["TODO".to_string(), "lorem".to_string(), "FIXME".to_string()]);
+    /// let summary = story.summary_with_options(&options);
+    /// assert!(summary.ends_with("Flagged words: TODO (2), lorem (1), FIXME (0)\n"));
+    /// ```
+    ///
+    /// [`summary`]: #method.summary
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    pub fn summary_with_options(&self, options: &ParseOptions) -> String {
+        let none = "(none)";
+        let warnings = self.validate_with_options(options);
+        let dead_links = warnings
+            .iter()
+            .filter(|warning| matches!(warning.kind, WarningKind::DeadLink(_)))
+            .count();
+
+        let mut tag_counts: FastHashMap<&str, usize> = FastHashMap::default();
+        for passage in self.passages.values() {
+            for tag in passage.tags() {
+                *tag_counts.entry(tag.as_str()).or_default() += 1;
+            }
+        }
+        let mut tags: Vec<(&str, usize)> = tag_counts.into_iter().collect();
+        tags.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+        let tags: Vec<String> = tags
+            .iter()
+            .take(5)
+            .map(|(tag, count)| format!("{} ({})", tag, count))
+            .collect();
+
+        let mut summary = format!(
+            "Title: {}\nIFID: {}\nStart: {}\nPassages: {}\nDead links: {}\nTop tags: {}\n",
+            self.title.as_deref().unwrap_or(none),
+            self.data.as_ref().map_or(none, |data| data.ifid.as_str()),
+            self.get_start_passage_name().unwrap_or(none),
+            self.passages.len(),
+            dead_links,
+            if tags.is_empty() {
+                none.to_string()
+            } else {
+                tags.join(", ")
+            },
+        );
+
+        let flagged = options.flagged_words();
+        if !flagged.is_empty() {
+            let counts: Vec<String> = flagged
+                .iter()
+                .map(|word| {
+                    let count = warnings
+                        .iter()
+                        .filter(|w| matches!(&w.kind, WarningKind::FlaggedWord(x) if x == word))
+                        .count();
+                    format!("{} ({})", word, count)
+                })
+                .collect();
+            summary.push_str(&format!("Flagged words: {}\n", counts.join(", ")));
+        }
+        summary
+    }
+
+    /// Returns a JSON dump of the whole story, for use by tools that are not
+    /// written in Rust. The output is deterministic: passages are sorted by
+    /// name, diagnostics by position, and object keys are always the same, so
+    /// two dumps of the same story can be compared as text
+    ///
+    /// The top-level object has these fields:
+    /// * `version` - The version of this schema, currently `1`. It is
+    ///   increased whenever a field is changed or removed, but not when a
+    ///   field is added
+    /// * `title`, `author`, `subtitle`, `menu`, `settings` - The contents of
+    ///   the corresponding special passages, or `null`
+    /// * `data` - The `StoryData`, with the fields `ifid`, `format`,
+    ///   `format_version`, `start`, `tag_colors`, and `zoom`, or `null`
+    /// * `start` - The name of the start passage, or `null`
+    /// * `passages` - The normal passages, each with its `name`, `tags`,
+    ///   `metadata`, `pid`, `span`, `content`, and `links`
+    /// * `scripts`, `stylesheets` - The contents of the script and stylesheet
+    ///   passages, in the order they were parsed
+    /// * `diagnostics` - The warnings found by [`validate`], each with its
+    ///   `code`, `message`, `severity`, `category`, `position`, and
+    ///   `suggestion`
+    ///
+    /// Each link has a `target`, `display_text`, `kind`, `setter`, and `span`.
+    /// A span has a `file`, which may be `null`, and a `start` and `end`, each
+    /// with a one-indexed `line` and `column`. The end is inclusive. The
+    /// `position` of a diagnostic has a `file`, `line`, and `column`, or is
+    /// `null` if the diagnostic has no context. Warnings produced while
+    /// parsing are not kept in a `Story`, so only the checks of [`validate`]
+    /// are included
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = ":: Start [intro]\nGo [[North]]\n".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let json: serde_json::Value = serde_json::from_str(&story.to_debug_json()).unwrap();
+    /// assert_eq!(json["version"], 1);
+    /// assert_eq!(json["passages"][0]["tags"][0], "intro");
+    /// let link = &json["passages"][0]["links"][0];
+    /// assert_eq!(link["target"], "North");
+    /// assert_eq!(link["span"]["start"]["line"], 2);
+    /// assert_eq!(link["span"]["start"]["column"], 4);
+    /// assert_eq!(json["diagnostics"][0]["message"], "No StoryData passage found");
+    /// ```
+    ///
+    /// [`validate`]: #method.validate
+    pub fn to_debug_json(&self) -> String {
+        crate::stories::debug_json(self).to_string()
+    }
+
+    /// Returns the whole parsed story as JSON, in a stable schema meant for
+    /// programs that are not written in Rust, such as web dashboards and
+    /// localization pipelines. Passages are sorted by name and warnings by
+    /// position, so the same story always produces the same output
+    ///
+    /// The top-level object has these fields:
+    /// * `version` - The version of this schema, currently `1`. It is
+    ///   increased whenever a field is changed or removed, but not when a
+    ///   field is added
+    /// * `title`, `author`, `subtitle`, `menu`, `settings` - The contents of
+    ///   the corresponding special passages, or `null`
+    /// * `data` - The `StoryData`, with the fields `ifid`, `format`,
+    ///   `format_version`, `start`, `tag_colors`, and `zoom`, or `null`
+    /// * `start` - The name of the start passage, or `null`
+    /// * `passages` - The normal passages, each with its `name`, `tags`,
+    ///   `metadata`, `pid`, `span`, `content`, and `links`
+    /// * `scripts`, `stylesheets` - The contents of the script and stylesheet
+    ///   passages, in the order they were parsed
+    /// * `warnings` - The warnings found by [`validate`], each with its
+    ///   `code`, `message`, `severity`, `category`, `position`, and
+    ///   `suggestion`
+    ///
+    /// Each link has a `target`, `display_text`, `setter`, `span`, and a
+    /// `kind` of `simple`, `pipe`, `right_arrow`, `left_arrow`, `image`, or
+    /// `macro`. With the "macro-links" feature enabled, the links created by
+    /// the macros of the story format named in the `StoryData` are included
+    /// after the other links of their passage, and the `format` of each link
+    /// is the name of that story format. The `format` of any other link is
+    /// `null`. Spans and positions are as in [`to_debug_json`]
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = ":: Start [intro]\nGo [[North->Cave]]\n".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let json: serde_json::Value = serde_json::from_str(&story.to_json()).unwrap();
+    /// assert_eq!(json["version"], 1);
+    /// assert_eq!(json["passages"][0]["tags"][0], "intro");
+    /// let link = &json["passages"][0]["links"][0];
+    /// assert_eq!(link["target"], "Cave");
+    /// assert_eq!(link["kind"], "right_arrow");
+    /// assert_eq!(json["warnings"][0]["code"], "W0013");
+    /// ```
+    ///
+    /// [`validate`]: #method.validate
+    /// [`to_debug_json`]: #method.to_debug_json
+    pub fn to_json(&self) -> String {
+        crate::stories::story_json(self).to_string()
+    }
+
+    /// Returns a graph of the passages and the links between them in the DOT
+    /// language, for drawing the flow of the story with Graphviz or other
+    /// standard tools
+    ///
+    /// Each normal passage is a node and each link is an edge, with the same
+    /// rules as a [`StoryGraph`]: dead links and image links are left out, and
+    /// a passage that links to another several times has a single edge to it.
+    /// The start passage is drawn with a double border, and the story title,
+    /// if any, is the label of the graph. If the `StoryData` has `tag-colors`,
+    /// each passage is filled with the color of its first tag that has a
+    /// color Twine can render. Nodes and edges are sorted, so the output is
+    /// the same every time
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = ":: Start\nGo [[North]]\n\n:: North\nThe end".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// assert_eq!(
+    ///     story.to_dot(),
+    ///     r#"digraph story {
+    ///     node [shape=box];
+    ///     "North";
+    ///     "Start" [peripheries=2];
+    ///     "Start" -> "North";
+    /// }
+    /// "#
+    /// );
+    /// ```
+    ///
+    /// [`StoryGraph`]: struct.StoryGraph.html
+    pub fn to_dot(&self) -> String {
+        crate::graph::dot(self)
+    }
+
+    /// Returns the names of the normal passages that do not link to any other
+    /// passage, sorted by name. These are the possible endings of the story
+    ///
+    /// Links are followed with the same rules as a [`StoryGraph`], so a
+    /// passage whose only links are dead links or image links is an ending.
+    /// Endings that cannot be reached from the start passage are included, use
+    /// [`StoryGraph::reachable_endings`] to leave them out. A
+    /// [`NoReachableEnding`] warning for a story where none of them can be
+    /// reached is opt-in, with [`ParseOptions::with_checked_endings`]
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = ":: Start\n[[Cave]] [[Nowhere]]\n\n:: Cave\n[[Start]] [img[map.png]]\n\n:: Exit\nThe end\n\n:: Hall\n[[Missing]]".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// assert_eq!(story.endings(), ["Exit", "Hall"]);
+    /// ```
+    ///
+    /// [`StoryGraph`]: struct.StoryGraph.html
+    /// [`StoryGraph::reachable_endings`]: struct.StoryGraph.html#method.reachable_endings
+    /// [`NoReachableEnding`]: enum.WarningKind.html#variant.NoReachableEnding
+    /// [`ParseOptions::with_checked_endings`]: struct.ParseOptions.html#method.with_checked_endings
+    pub fn endings(&self) -> Vec<&str> {
+        // The names in the graph are copies, so look up the story's own names
+        StoryGraph::from(self)
+            .dead_ends()
+            .into_iter()
+            .filter_map(|name| self.passages.get_key_value(name))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
     /// Parses an input `String` and returns the result or a list of errors,
     /// along with a list of any [`Warning`]s
     ///
@@ -143,14 +666,108 @@ impl Story {
         StoryPassages::from_string(input).into_result()
     }
 
+    /// Reads all input from the given [`Read`] and parses it, returning the
+    /// result or a list of errors, along with a list of any [`Warning`]s
+    ///
+    /// As with [`StoryPassages::from_reader`], the input is read into memory
+    /// in full before it is parsed
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = ":: StoryTitle\nA Story\n\n:: Start\nHello".as_bytes();
+    /// let story = Story::from_reader(input).take().0.ok().unwrap();
+    /// assert_eq!(story.title, Some("A Story".to_string()));
+    /// ```
+    ///
+    /// [`Read`]: std::io::Read
+    /// [`StoryPassages::from_reader`]: struct.StoryPassages.html#method.from_reader
+    /// [`Warning`]: struct.Warning.html
+    pub fn from_reader<R: Read>(reader: R) -> ParseOutput {
+        StoryPassages::from_reader(reader).into_result()
+    }
+
+    /// Parses an input `String` using the given [`ParseOptions`] and returns
+    /// the result or a list of errors, along with a list of any [`Warning`]s
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    /// [`Warning`]: struct.Warning.html
+    pub fn from_string_with_options(input: String, options: &ParseOptions) -> ParseOutput {
+        StoryPassages::from_string_with_options(input, options).into_result()
+    }
+
+    /// Reads all input from the given [`Read`] and parses it using the given
+    /// [`ParseOptions`], returning the result or a list of errors, along with
+    /// a list of any [`Warning`]s
+    ///
+    /// As with [`StoryPassages::from_reader_with_options`], the input is read
+    /// into memory in full before it is parsed
+    ///
+    /// [`Read`]: std::io::Read
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    /// [`StoryPassages::from_reader_with_options`]: struct.StoryPassages.html#method.from_reader_with_options
+    /// [`Warning`]: struct.Warning.html
+    pub fn from_reader_with_options<R: Read>(reader: R, options: &ParseOptions) -> ParseOutput {
+        StoryPassages::from_reader_with_options(reader, options).into_result()
+    }
+
+    /// Parses an input `String` that may hold more than one story, separated
+    /// by passages named by the [`story_separator`] in the given
+    /// [`ParseOptions`], and returns the output of each story. See
+    /// [`StoryPassages::split_from_string_with_options`] for how the input is
+    /// split
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{ParseOptions, Story};
+    /// let input = r#":: StoryTitle
+    /// First
+    ///
+    /// :: Start
+    /// Hello
+    ///
+    /// :: StoryBreak
+    ///
+    /// :: StoryTitle
+    /// Second
+    ///
+    /// :: Start
+    /// Goodbye
+    /// "#
+    /// .to_string();
+    /// let options = ParseOptions::new().with_story_separator("StoryBreak".to_string());
+    /// let titles: Vec<Option<String>> = Story::split_from_string_with_options(input, &options)
+    ///     .into_iter()
+    ///     .map(|out| out.take().0.ok().unwrap().title)
+    ///     .collect();
+    /// assert_eq!(titles, [Some("First".to_string()), Some("Second".to_string())]);
+    /// ```
+    ///
+    /// [`story_separator`]: struct.ParseOptions.html#method.with_story_separator
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    /// [`StoryPassages::split_from_string_with_options`]: struct.StoryPassages.html#method.split_from_string_with_options
+    pub fn split_from_string_with_options(
+        input: String,
+        options: &ParseOptions,
+    ) -> Vec<ParseOutput> {
+        StoryPassages::split_from_string_with_options(input, options)
+            .into_iter()
+            .map(|out| out.into_result())
+            .collect()
+    }
+
     /// Parses a `Story` from the given [`Path`]. If the given path is a file,
     /// parses that file and returns the `Story`. If it is a directory, it looks
-    /// for any files with `.tw` or `.twee` extensions and parses them. Returns
-    /// the parsed output or a list of errors, along with a list of any
-    /// [`Warning`]s
+    /// for any files with `.tw` or `.twee` extensions and parses them in order
+    /// of their file names, skipping any that match a pattern in a
+    /// `.twee-ignore` file. Returns the parsed output or a list of errors,
+    /// along with a list of any [`Warning`]s
+    ///
+    /// Enabled with "fs" feature
     ///
     /// [`Path`]: std::path::Path
     /// [`Warning`]: struct.Warning.html
+    #[cfg(feature = "fs")]
     pub fn from_path<P: AsRef<Path>>(input: P) -> ParseOutput {
         StoryPassages::from_path(input).into_result()
     }
@@ -158,11 +775,43 @@ impl Story {
     /// Parses a `Story` from the given [`Path`]s. See `from_path` for
     /// additional information on how directories are handled.
     ///
+    /// Enabled with "fs" feature
+    ///
     /// [`Path`]: std::path::Path
+    #[cfg(feature = "fs")]
     pub fn from_paths<P: AsRef<Path>>(input: &[P]) -> ParseOutput {
         StoryPassages::from_paths(input).into_result()
     }
 
+    /// Parses a `Story` from the given [`Path`] using the given
+    /// [`ParseOptions`]. See `from_path` for additional information on how
+    /// directories are handled.
+    ///
+    /// Enabled with "fs" feature
+    ///
+    /// [`Path`]: std::path::Path
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    #[cfg(feature = "fs")]
+    pub fn from_path_with_options<P: AsRef<Path>>(input: P, options: &ParseOptions) -> ParseOutput {
+        StoryPassages::from_path_with_options(input, options).into_result()
+    }
+
+    /// Parses a `Story` from the given [`Path`]s using the given
+    /// [`ParseOptions`]. See `from_path` for additional information on how
+    /// directories are handled.
+    ///
+    /// Enabled with "fs" feature
+    ///
+    /// [`Path`]: std::path::Path
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    #[cfg(feature = "fs")]
+    pub fn from_paths_with_options<P: AsRef<Path>>(
+        input: &[P],
+        options: &ParseOptions,
+    ) -> ParseOutput {
+        StoryPassages::from_paths_with_options(input, options).into_result()
+    }
+
     /// If a start passage is configured in the StoryData, return the name of
     /// that passage. If no start passage is configured, check for the presence
     /// of a passage called "Start". If that passage exists, return that name,
@@ -179,10 +828,151 @@ impl Story {
                 }
             })
     }
+
+    /// Returns an iterator over the normal passages in the story, sorted by
+    /// passage name
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = ":: B\nTwo\n\n:: A\nOne\n".to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let names: Vec<&str> = story.iter_passages().filter_map(|p| p.name()).collect();
+    /// assert_eq!(names, vec!["A", "B"]);
+    /// ```
+    pub fn iter_passages(&self) -> impl Iterator<Item = PassageRef<'_>> {
+        let mut passages: Vec<&TwinePassage> = self.passages.values().collect();
+        passages.sort_by(|a, b| a.header.name.cmp(&b.header.name));
+        passages.into_iter().map(PassageRef::Twine)
+    }
+
+    /// Returns an iterator over the contents of the script passages in the
+    /// story, in the order they were parsed
+    pub fn iter_scripts(&self) -> impl Iterator<Item = PassageRef<'_>> {
+        self.scripts.iter().map(|s| PassageRef::Script(s))
+    }
+
+    /// Returns an iterator over the contents of the stylesheet passages in the
+    /// story, in the order they were parsed
+    pub fn iter_stylesheets(&self) -> impl Iterator<Item = PassageRef<'_>> {
+        self.stylesheets.iter().map(|s| PassageRef::Stylesheet(s))
+    }
+
+    /// Returns an iterator over every passage in the story: the `StoryTitle`,
+    /// `StoryData`, `StoryAuthor`, `StorySubtitle`, `StoryMenu`, and
+    /// `StorySettings` passages if present, followed by the normal passages
+    /// sorted by name, the scripts, and the stylesheets
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{PassageRef, Story};
+    /// let input = r#":: StoryTitle
+    ///My Story
+    ///
+    ///:: Start
+    ///Hello
+    ///
+    ///:: Code [script]
+    ///let x = 1;
+    ///"#.to_string();
+    /// let story = Story::from_string(input).take().0.ok().unwrap();
+    /// let all: Vec<PassageRef> = story.iter_all().collect();
+    /// assert_eq!(all.len(), 3);
+    /// assert!(matches!(all[2], PassageRef::Script(_)));
+    /// ```
+    pub fn iter_all(&self) -> impl Iterator<Item = PassageRef<'_>> {
+        let title = self.title.as_deref().map(PassageRef::StoryTitle);
+        let data = self.data.as_ref().map(PassageRef::StoryData);
+        let author = self.author.as_deref().map(PassageRef::StoryAuthor);
+        let subtitle = self.subtitle.as_deref().map(PassageRef::StorySubtitle);
+        let menu = self.menu.as_deref().map(PassageRef::StoryMenu);
+        let settings = self.settings.as_deref().map(PassageRef::StorySettings);
+        title
+            .into_iter()
+            .chain(data)
+            .chain(author)
+            .chain(subtitle)
+            .chain(menu)
+            .chain(settings)
+            .chain(self.iter_passages())
+            .chain(self.iter_scripts())
+            .chain(self.iter_stylesheets())
+    }
+
+    /// Returns the contents of a Make-style (`.d`) dependency file that lists
+    /// every file parsed into this story as a prerequisite of `target`
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{SourceFile, SourceFileKind, Story};
+    /// let mut story = Story::default();
+    /// story.sources.push(SourceFile::new("src/story.twee", SourceFileKind::Parsed));
+    /// assert_eq!(story.depfile("story.html"), "story.html: \\\n  src/story.twee\n");
+    /// ```
+    pub fn depfile<P: AsRef<Path>>(&self, target: P) -> String {
+        crate::stories::depfile(target, &self.sources)
+    }
+
+    /// Writes a Make-style (`.d`) dependency file to `path` that lists every
+    /// file parsed into this story as a prerequisite of `target`
+    ///
+    /// Enabled with "fs" feature
+    #[cfg(feature = "fs")]
+    pub fn write_depfile<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        target: P,
+        path: Q,
+    ) -> std::io::Result<()> {
+        std::fs::write(path, self.depfile(target))
+    }
+
+    /// Converts this story back into a [`StoryPassages`], rebuilding the
+    /// [`Passage`] of each of its passages without parsing the story again.
+    /// Every passage keeps the header and context it was parsed with, so a
+    /// story converted from a `StoryPassages` converts back without losing
+    /// anything. Script and stylesheet passages are matched up with their
+    /// contexts in source order. A passage with no context in `contexts`,
+    /// such as one added after parsing, gets a header and context synthesized
+    /// from its name, tags, and content, with scripts and stylesheets named
+    /// `Story JavaScript` and `Story Stylesheet`
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::Story;
+    /// let input = ":: StoryTitle\nA Story\n\n:: Code [script]\nlet x = 1;\n\n:: Start\nHello\n";
+    /// let story = Story::from_string(input.to_string()).take().0.ok().unwrap();
+    /// let passages = story.into_passages();
+    /// assert_eq!(passages.scripts[0].header.name, "Code");
+    /// assert_eq!(passages.passages["Start"].context.get_start_position().line, 7);
+    /// ```
+    ///
+    /// [`StoryPassages`]: struct.StoryPassages.html
+    /// [`Passage`]: struct.Passage.html
+    pub fn into_passages(self) -> StoryPassages {
+        self.into()
+    }
 }
 
 impl std::convert::From<StoryPassages> for Story {
     fn from(mut s: StoryPassages) -> Story {
+        let mut contexts = HashMap::default();
+        for passage in [
+            &s.title,
+            &s.data,
+            &s.author,
+            &s.subtitle,
+            &s.menu,
+            &s.settings,
+        ]
+        .iter()
+        .filter_map(|passage| passage.as_ref())
+        .chain(s.scripts.iter())
+        .chain(s.stylesheets.iter())
+        .chain(s.passages.values())
+        {
+            contexts.insert(passage.header.name.clone(), passage.context.clone());
+        }
+
         let title = match s.title {
             Some(c) => match c.content {
                 PassageContent::StoryTitle(t) => Some(t.title),
@@ -199,6 +989,20 @@ impl std::convert::From<StoryPassages> for Story {
             None => None,
         };
 
+        let special = |passage: Option<crate::Passage>| {
+            passage.map(|p| match p.content {
+                PassageContent::StoryAuthor(c)
+                | PassageContent::StorySubtitle(c)
+                | PassageContent::StoryMenu(c)
+                | PassageContent::StorySettings(c) => c.content,
+                _ => panic!("Expected a special passage"),
+            })
+        };
+        let author = special(s.author);
+        let subtitle = special(s.subtitle);
+        let menu = special(s.menu);
+        let settings = special(s.settings);
+
         let scripts = s
             .scripts
             .into_iter()
@@ -217,8 +1021,35 @@ impl std::convert::From<StoryPassages> for Story {
             })
             .collect();
 
-        let passages: HashMap<String, TwinePassage> =
-            s.passages.drain().map(|(k, v)| (k, v.into())).collect();
+        let file_ids = file_ids(&s.sources);
+        let passage_files = std::mem::take(&mut s.passage_files);
+        let parsed: Vec<&Path> = s
+            .sources
+            .iter()
+            .filter(|source| source.is_parsed())
+            .map(|source| source.path.as_path())
+            .collect();
+        let passages: HashMap<String, TwinePassage> = s
+            .passages
+            .drain()
+            .map(|(k, v)| {
+                // Prefer the path of the file, since file names can repeat
+                let by_path = passage_files
+                    .get(&k)
+                    .and_then(|path| parsed.iter().position(|p| p == path));
+                let file_id = by_path
+                    .or_else(|| {
+                        let name = v.context.get_file_name().as_ref()?;
+                        file_ids.get(name).copied()
+                    })
+                    .unwrap_or(0);
+                let mut passage: TwinePassage = v.into();
+                passage.source_order.0 = file_id;
+                (k, passage)
+            })
+            .collect();
+
+        let sources = s.sources;
 
         #[cfg(feature = "full-context")]
         let code_map = s.code_map;
@@ -226,21 +1057,241 @@ impl std::convert::From<StoryPassages> for Story {
         Story {
             title,
             data,
+            author,
+            subtitle,
+            menu,
+            settings,
             passages,
             scripts,
             stylesheets,
+            sources,
+            contexts,
+            passage_files,
+            recovered_errors: s.recovered_errors,
             #[cfg(feature = "full-context")]
             code_map,
         }
     }
 }
 
+impl std::convert::From<Story> for StoryPassages {
+    fn from(mut s: Story) -> StoryPassages {
+        let mut contexts = std::mem::take(&mut s.contexts);
+        let has_data = s.data.is_some() || contexts.contains_key("StoryData");
+        let mut special = |name: &str, content: Option<(PassageContent, String)>| {
+            content.map(|(content, text)| rebuild(contexts.remove(name), name, &[], content, &text))
+        };
+
+        let title = special(
+            "StoryTitle",
+            s.title.map(|title| {
+                (
+                    StoryTitle {
+                        title: title.clone(),
+                    }
+                    .into(),
+                    title,
+                )
+            }),
+        );
+        let data = if has_data {
+            let text = s
+                .data
+                .as_ref()
+                .and_then(|data| serde_json::to_string(data).ok())
+                .unwrap_or_default();
+            special("StoryData", Some((PassageContent::StoryData(s.data), text)))
+        } else {
+            None
+        };
+        let mut special_content =
+            |name: &str, content: Option<String>, variant: fn(SpecialContent) -> PassageContent| {
+                special(
+                    name,
+                    content.map(|content| {
+                        (
+                            variant(SpecialContent {
+                                content: content.clone(),
+                            }),
+                            content,
+                        )
+                    }),
+                )
+            };
+        let author = special_content("StoryAuthor", s.author, PassageContent::StoryAuthor);
+        let subtitle = special_content("StorySubtitle", s.subtitle, PassageContent::StorySubtitle);
+        let menu = special_content("StoryMenu", s.menu, PassageContent::StoryMenu);
+        let settings = special_content("StorySettings", s.settings, PassageContent::StorySettings);
+
+        let mut passages = HashMap::default();
+        for (name, passage) in s.passages.drain() {
+            let context = contexts.remove(&name).unwrap_or_else(|| {
+                let tags: Vec<&str> = passage.tags().iter().map(|tag| tag.as_str()).collect();
+                synthesize(&name, &tags, &passage.content.content).context
+            });
+            let passage = Passage {
+                header: passage.header,
+                content: PassageContent::Normal(passage.content),
+                context,
+            };
+            passages.insert(name, passage);
+        }
+
+        // The contexts left over belong to scripts and stylesheets, which
+        // are put back in the order they were parsed in
+        let file_ids = file_ids(&s.sources);
+        let mut leftovers: Vec<Passage> = contexts
+            .into_values()
+            .filter_map(|context| {
+                Passage::parse(context, HeaderContinuation::Allow)
+                    .take()
+                    .0
+                    .ok()
+            })
+            .collect();
+        leftovers.sort_by_key(|passage| {
+            let file_id = passage
+                .context
+                .get_file_name()
+                .as_ref()
+                .and_then(|name| file_ids.get(name))
+                .copied()
+                .unwrap_or(0);
+            (file_id, passage.context.get_start_position().line)
+        });
+        let (script_passages, stylesheet_passages): (Vec<Passage>, Vec<Passage>) = leftovers
+            .into_iter()
+            .filter(|passage| {
+                passage.header.has_tag("script") || passage.header.has_tag("stylesheet")
+            })
+            .partition(|passage| passage.header.has_tag("script"));
+
+        let scripts = restore(
+            s.scripts,
+            script_passages,
+            "Story JavaScript",
+            "script",
+            |content| PassageContent::Script(ScriptContent { content }),
+        );
+        let stylesheets = restore(
+            s.stylesheets,
+            stylesheet_passages,
+            "Story Stylesheet",
+            "stylesheet",
+            |content| PassageContent::Stylesheet(StylesheetContent { content }),
+        );
+
+        StoryPassages {
+            title,
+            data,
+            author,
+            subtitle,
+            menu,
+            settings,
+            passages,
+            scripts,
+            stylesheets,
+            sources: s.sources,
+            passage_files: s.passage_files,
+            recovered_errors: s.recovered_errors,
+            #[cfg(feature = "full-context")]
+            code_map: s.code_map,
+            ..Default::default()
+        }
+    }
+}
+
+/// Numbers the parsed files among the given sources in the order they were
+/// read, keeping the first id of a file name that appears more than once
+fn file_ids(sources: &[SourceFile]) -> FastHashMap<String, usize> {
+    let mut file_ids: FastHashMap<String, usize> = FastHashMap::default();
+    for source in sources.iter().filter(|source| source.is_parsed()) {
+        if let Some(name) = source.path.file_name() {
+            let id = file_ids.len();
+            file_ids
+                .entry(name.to_string_lossy().to_string())
+                .or_insert(id);
+        }
+    }
+    file_ids
+}
+
+/// Builds the passage of a special passage with the given content, taking
+/// its header from `context` if it was parsed from one, or else synthesizing
+/// both the header and the context
+fn rebuild(
+    context: Option<FullContext>,
+    name: &str,
+    tags: &[&str],
+    content: PassageContent,
+    text: &str,
+) -> Passage {
+    let parsed = context.and_then(|context| {
+        Passage::parse(context, HeaderContinuation::Allow)
+            .take()
+            .0
+            .ok()
+            .filter(|passage| passage.header.name == name)
+    });
+    let mut passage = parsed.unwrap_or_else(|| synthesize(name, tags, text));
+    passage.content = content;
+    passage
+}
+
+/// Synthesizes a passage with the given name, tags, and content
+fn synthesize(name: &str, tags: &[&str], content: &str) -> Passage {
+    let builder = tags.iter().fold(PassageBuilder::new(name), |builder, tag| {
+        builder.with_tag(tag)
+    });
+    builder
+        .with_content(content)
+        .build()
+        .take()
+        .0
+        .expect("Synthesized passage failed to parse")
+}
+
+/// Rebuilds the script or stylesheet passages holding the given contents,
+/// using the parsed passages if there is one for each content, or else
+/// synthesizing passages named after `name` and tagged with `tag`
+fn restore(
+    contents: Vec<String>,
+    parsed: Vec<Passage>,
+    name: &str,
+    tag: &str,
+    variant: fn(String) -> PassageContent,
+) -> Vec<Passage> {
+    if parsed.len() == contents.len() {
+        return parsed
+            .into_iter()
+            .zip(contents)
+            .map(|(mut passage, content)| {
+                passage.content = variant(content);
+                passage
+            })
+            .collect();
+    }
+    contents
+        .into_iter()
+        .enumerate()
+        .map(|(i, content)| {
+            let name = match i {
+                0 => name.to_string(),
+                _ => format!("{} {}", name, i + 1),
+            };
+            rebuild(None, &name, &[tag], variant(content.clone()), &content)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "fs")]
     use crate::Context;
     use crate::Warning;
     use crate::WarningKind;
+    #[cfg(feature = "fs")]
     use tempfile::tempdir;
 
     #[test]
@@ -280,6 +1331,7 @@ Test Story
     }
 
     #[test]
+    #[cfg(feature = "fs")]
     fn file_input() -> Result<(), Box<dyn std::error::Error>> {
         let input = r#":: A passage
 This
@@ -354,6 +1406,57 @@ Test Story
     }
 
     #[test]
+    fn iterators() {
+        let input = r#":: StoryTitle
+Test Story
+
+:: StoryData
+{
+"ifid": "abc"
+}
+
+:: Zeta {"position":"100,200"}
+Last
+
+:: Alpha [one two]
+First
+
+:: Style [stylesheet]
+body {}
+
+:: Code [script]
+let x = 1;
+"#
+        .to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+
+        let passages: Vec<PassageRef> = story.iter_passages().collect();
+        assert_eq!(passages.len(), 2);
+        assert_eq!(passages[0].name(), Some("Alpha"));
+        assert_eq!(passages[0].tags(), ["one".to_string(), "two".to_string()]);
+        assert_eq!(passages[0].content(), Some("First\n"));
+        assert_eq!(passages[1].name(), Some("Zeta"));
+        assert_eq!(passages[1].metadata().unwrap()["position"], "100,200");
+
+        let scripts: Vec<PassageRef> = story.iter_scripts().collect();
+        assert_eq!(scripts.len(), 1);
+        assert_eq!(scripts[0].name(), None);
+        assert_eq!(scripts[0].content(), Some("let x = 1;"));
+        assert!(scripts[0].tags().is_empty());
+
+        let all: Vec<PassageRef> = story.iter_all().collect();
+        assert_eq!(all.len(), 6);
+        assert_eq!(all[0].content(), Some("Test Story"));
+        assert!(matches!(all[1], PassageRef::StoryData(data) if data.ifid == "abc"));
+        assert_eq!(all[1].content(), None);
+        assert_eq!(all[2].name(), Some("Alpha"));
+        assert!(matches!(all[4], PassageRef::Script(_)));
+        assert_eq!(all[5].content(), Some("body {}"));
+        assert!(matches!(all[5], PassageRef::Stylesheet(_)));
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
     fn dir_input() -> Result<(), Box<dyn std::error::Error>> {
         use std::fs::File;
         let input_one = r#":: Start
@@ -423,4 +1526,386 @@ blah blah
 
         Ok(())
     }
+
+    #[test]
+    fn special_passages() {
+        let input = r#":: StoryTitle
+Test Story
+
+:: StorySettings
+undo:on
+
+:: StorySubtitle
+Subtitle
+
+:: Start
+Hello
+"#
+        .to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        assert_eq!(story.subtitle, Some("Subtitle".to_string()));
+        assert_eq!(story.settings, Some("undo:on".to_string()));
+        assert_eq!(story.author, None);
+        assert_eq!(story.menu, None);
+        let names: Vec<&str> = story.iter_all().filter_map(|p| p.name()).collect();
+        assert_eq!(
+            names,
+            ["StoryTitle", "StorySubtitle", "StorySettings", "Start"]
+        );
+        let settings = story.iter_all().nth(2).unwrap();
+        assert_eq!(settings.content(), Some("undo:on"));
+    }
+
+    #[test]
+    fn passage_contexts() {
+        let input = ":: StoryTitle\nTest Story\n\n:: Start\nHello\n\n:: Code [script]\nx = 1;\n"
+            .to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        let lines: Vec<Option<usize>> = ["StoryTitle", "Start", "Code", "Missing"]
+            .iter()
+            .map(|name| {
+                story
+                    .context_of(name)
+                    .map(|context| context.get_start_position().line)
+            })
+            .collect();
+        assert_eq!(lines, [Some(1), Some(4), Some(7), None]);
+        assert!(story
+            .context_of("Start")
+            .unwrap()
+            .get_contents()
+            .starts_with(":: Start"));
+    }
+
+    #[test]
+    fn backlinks() {
+        let input = r#":: StoryData
+{"ifid": "abc", "format": "SugarCube"}
+
+:: Start
+[[Hub]] <<goto "Hub">>
+
+:: B
+[[ Hub ]] [[Other]]
+
+:: A
+[[Go|Hub]]
+
+:: Hub
+[[Hub]]
+"#
+        .to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        let found: Vec<(usize, usize)> = story
+            .backlinks("Hub")
+            .iter()
+            .map(|link| {
+                let position = link.file_position();
+                (position.line, position.column)
+            })
+            .collect();
+        #[cfg(feature = "macro-links")]
+        assert_eq!(found, [(11, 1), (8, 1), (14, 1), (5, 1), (5, 9)]);
+        #[cfg(not(feature = "macro-links"))]
+        assert_eq!(found, [(11, 1), (8, 1), (14, 1), (5, 1)]);
+    }
+
+    #[test]
+    fn endings() {
+        let input = ":: Start\n[[Loop]]\n\n:: Loop\n[[Start]]\n\n:: End\nBye\n".to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        assert_eq!(story.endings(), ["End"]);
+        let ending = WarningKind::NoReachableEnding("Start".to_string());
+        assert!(!story.validate().iter().any(|w| w.kind == ending));
+
+        let options = ParseOptions::default().with_checked_endings(true);
+        assert!(story
+            .validate_with_options(&options)
+            .iter()
+            .any(|w| w.kind == ending));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let input = r#":: StoryTitle
+Test Story
+
+:: StoryData
+{
+"ifid": "abc",
+"tag-colors": { "one": "red" }
+}
+
+:: Start [one] {"position":"100,200"}
+Go [[Text|Next][$x to 1]]
+
+:: Next
+The end [[oops
+"#
+        .to_string();
+        let (res, warnings) = Story::from_string(input).take();
+        assert_eq!(warnings.len(), 1);
+        let story = res.ok().unwrap();
+        let json = serde_json::to_string(&story).unwrap();
+        let de: Story = serde_json::from_str(&json).unwrap();
+        assert_eq!(de.title, story.title);
+        assert_eq!(de.get_start_passage_name(), Some("Start"));
+        assert_eq!(
+            de.data.as_ref().unwrap().tag_color("one"),
+            Some(&crate::TagColor::Red)
+        );
+        let start = &de.passages["Start"];
+        assert_eq!(start.tags(), &["one".to_string()]);
+        assert_eq!(start.position(), Some((100.0, 200.0)));
+        let link = &start.content.get_links()[0];
+        assert_eq!(link.target, "Next");
+        assert_eq!(link.setter, Some("$x to 1".to_string()));
+        assert_eq!(link.context.get_contents(), "[[Text|Next][$x to 1]]");
+        assert_eq!(
+            link.context.get_start_position(),
+            story.passages["Start"].content.get_links()[0]
+                .context
+                .get_start_position()
+        );
+
+        let json = serde_json::to_string(&warnings).unwrap();
+        let de: Vec<Warning> = serde_json::from_str(&json).unwrap();
+        assert_eq!(de.len(), 1);
+        assert_eq!(de[0].kind, WarningKind::UnclosedLink);
+        assert_eq!(
+            de[0].context.as_ref().unwrap().get_start_position(),
+            warnings[0].context.as_ref().unwrap().get_start_position()
+        );
+    }
+
+    #[test]
+    fn summary() {
+        let story = Story::default();
+        assert_eq!(
+            story.summary(),
+            "Title: (none)\nIFID: (none)\nStart: (none)\nPassages: 0\nDead links: 0\nTop tags: (none)\n"
+        );
+
+        let input = r#":: Start [a b c d e f]
+[[Nowhere]] [[Elsewhere]]
+
+:: Middle [f e]
+
+:: End [f]
+"#
+        .to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        let summary = story.summary();
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(lines[2], "Start: Start");
+        assert_eq!(lines[3], "Passages: 3");
+        assert_eq!(lines[4], "Dead links: 2");
+        assert_eq!(lines[5], "Top tags: f (3), e (2), a (1), b (1), c (1)");
+    }
+
+    #[test]
+    fn validate() {
+        let input = r#":: StoryData
+{
+  "ifid": "abc",
+  "start": "Begin"
+}
+
+:: Start
+[[Start]] [[Nowher]]
+
+:: Nowhere
+Lost
+"#
+        .to_string();
+        let passages = StoryPassages::from_string(input.clone())
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        let render = |warnings: Vec<Warning>| {
+            let mut lines: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+            lines.sort();
+            lines
+        };
+        let expected = render(passages.validate());
+        assert_eq!(expected.len(), 4);
+        assert_eq!(render(story.validate()), expected);
+
+        let options = ParseOptions::new()
+            .with_suppressed_warning(WarningKind::SelfLink(String::new()))
+            .with_lint(std::sync::Arc::new(
+                |header: &crate::PassageHeader,
+                 content: &crate::TwineContent,
+                 context: Option<&FullContext>| {
+                    if content.content.contains("Lost") {
+                        let kind = WarningKind::Lint("Lost".to_string(), header.name.clone());
+                        vec![Warning::new(kind, context.cloned())]
+                    } else {
+                        Vec::new()
+                    }
+                },
+            ));
+        let warnings = story.validate_with_options(&options);
+        assert_eq!(warnings.len(), 4);
+        assert!(!warnings
+            .iter()
+            .any(|w| matches!(w.kind, WarningKind::SelfLink(_))));
+        let lint = warnings.last().unwrap();
+        assert_eq!(
+            lint.kind,
+            WarningKind::Lint("Lost".to_string(), "Nowhere".to_string())
+        );
+        assert_eq!(lint.context.as_ref().unwrap().get_start_position().line, 10);
+        assert_eq!(
+            render(passages.validate_with_options(&options)),
+            render(warnings)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn source_order() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        std::fs::write(
+            dir.path().join("a.twee"),
+            ":: Zebra\nFirst\n\n:: Mole\nSecond\n",
+        )?;
+        std::fs::write(dir.path().join("b.twee"), ":: Aardvark\nThird\n")?;
+        std::fs::write(dir.path().join("notes.txt"), "Not a story")?;
+
+        let story = Story::from_path(dir.path()).take().0.ok().unwrap();
+        let mut passages: Vec<&TwinePassage> = story.passages.values().collect();
+        passages.sort_by_key(|passage| passage.source_order());
+        let names: Vec<&str> = passages.iter().map(|p| p.header.name.as_str()).collect();
+        assert_eq!(names, ["Zebra", "Mole", "Aardvark"]);
+        assert_eq!(story.passages["Mole"].source_order(), (0, 4));
+        assert_eq!(story.passages["Aardvark"].source_order(), (1, 1));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn pids() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        std::fs::write(
+            dir.path().join("b.twee"),
+            ":: Start\n[[Zebra]]\n\n:: Mole\n",
+        )?;
+        std::fs::write(
+            dir.path().join("a.twee"),
+            ":: Zebra\nFirst\n\n:: Aardvark\n",
+        )?;
+
+        for _ in 0..3 {
+            let story = Story::from_path(dir.path()).take().0.ok().unwrap();
+            let mut pids: Vec<(usize, &str)> = story
+                .passages
+                .values()
+                .map(|passage| (passage.pid(), passage.header.name.as_str()))
+                .collect();
+            pids.sort_unstable();
+            assert_eq!(
+                pids,
+                [(1, "Zebra"), (2, "Aardvark"), (3, "Start"), (4, "Mole")]
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn into_passages() {
+        let input = r#":: StoryTitle
+Round Trip
+
+:: StoryData
+{"ifid": "ABC", "start": "Begin"}
+
+:: StoryAuthor
+Someone
+
+:: Second [stylesheet]
+p {}
+
+:: One [script]
+let a = 1;
+
+:: Begin [tag] {"position": "5,5"}
+[[End]]
+
+:: Two [script]
+let b = 2;
+
+:: End
+Bye
+"#
+        .to_string();
+        let original = StoryPassages::from_string(input.clone())
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        let passages = story.into_passages();
+
+        let title = passages.title.as_ref().unwrap();
+        assert_eq!(title.context.get_start_position().line, 1);
+        assert!(matches!(&title.content, PassageContent::StoryTitle(t) if t.title == "Round Trip"));
+        assert!(
+            matches!(&passages.data.as_ref().unwrap().content, PassageContent::StoryData(Some(d)) if d.ifid == "ABC")
+        );
+        assert_eq!(passages.author.as_ref().unwrap().header.name, "StoryAuthor");
+        assert!(passages.menu.is_none());
+
+        let names = |list: &[Passage]| -> Vec<String> {
+            list.iter().map(|p| p.header.name.clone()).collect()
+        };
+        assert_eq!(names(&passages.scripts), names(&original.scripts));
+        assert_eq!(names(&passages.stylesheets), ["Second"]);
+        assert!(
+            matches!(&passages.scripts[1].content, PassageContent::Script(s) if s.content == "let b = 2;")
+        );
+
+        let begin = &passages.passages["Begin"];
+        assert_eq!(begin.context.get_start_position().line, 16);
+        assert_eq!(begin.tags(), original.passages["Begin"].tags());
+        assert_eq!(begin.metadata(), original.passages["Begin"].metadata());
+        assert_eq!(passages.validate().len(), original.validate().len());
+
+        let story = Story::from(passages);
+        assert_eq!(story.title.as_deref(), Some("Round Trip"));
+        assert_eq!(story.scripts, ["let a = 1;", "let b = 2;"]);
+        assert_eq!(story.passages["Begin"].source_order(), (0, 16));
+
+        let mut story = Story::default();
+        story.scripts.push("let c = 3;".to_string());
+        story.scripts.push("let d = 4;".to_string());
+        story.passages.insert(
+            "Start".to_string(),
+            Story::from_string(":: Start [a]\nHi\n".to_string())
+                .take()
+                .0
+                .ok()
+                .unwrap()
+                .passages
+                .remove("Start")
+                .unwrap(),
+        );
+        let passages = story.into_passages();
+        assert_eq!(
+            names(&passages.scripts),
+            ["Story JavaScript", "Story JavaScript 2"]
+        );
+        assert!(passages.scripts[0].header.has_tag("script"));
+        assert_eq!(
+            passages.passages["Start"].context.get_contents(),
+            ":: Start [a]\nHi"
+        );
+        assert!(passages.title.is_none());
+        assert!(passages.data.is_none());
+    }
 }
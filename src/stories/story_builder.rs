@@ -0,0 +1,204 @@
+use crate::ErrorList;
+use crate::Output;
+use crate::PassageBuilder;
+use crate::StoryPassages;
+
+/// A builder for creating a [`StoryPassages`] in code rather than parsing it
+/// from files, such as when generating a story from another format
+///
+/// Every passage is built with a [`PassageBuilder`] and added to the story
+/// with [`StoryPassages::add_passage`], so passages are given pids in the
+/// order they were added, and a passage with the same name as an earlier one
+/// is ignored with a warning. The `StoryData` passage is only created if one
+/// of its fields is set, and needs an ifid to be parsed.
+///
+/// # Examples
+/// ```
+/// use tweep::{PassageBuilder, StoryBuilder};
+/// let story = StoryBuilder::new()
+///     .with_title("Generated")
+///     .with_ifid("D674C58C-DEFA-4F70-B7A2-27742230C0FC")
+///     .with_start("Intro")
+///     .with_passage(PassageBuilder::new("Intro").with_content("[[Next]]"))
+///     .with_passage(PassageBuilder::new("Next").with_content("The end"))
+///     .build()
+///     .take()
+///     .0
+///     .ok()
+///     .unwrap();
+/// assert_eq!(story.get_start_passage_name(), Some("Intro"));
+/// assert!(story.check().is_empty());
+/// ```
+///
+/// [`StoryPassages`]: struct.StoryPassages.html
+/// [`PassageBuilder`]: struct.PassageBuilder.html
+/// [`StoryPassages::add_passage`]: struct.StoryPassages.html#method.add_passage
+#[derive(Clone, Debug, Default)]
+pub struct StoryBuilder {
+    title: Option<String>,
+    data: serde_json::Map<String, serde_json::Value>,
+    passages: Vec<PassageBuilder>,
+    scripts: Vec<String>,
+    stylesheets: Vec<String>,
+}
+
+impl StoryBuilder {
+    /// Creates a builder for an empty story
+    pub fn new() -> Self {
+        StoryBuilder::default()
+    }
+
+    /// Sets the story title
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    /// Sets the ifid in the `StoryData`
+    pub fn with_ifid(mut self, ifid: &str) -> Self {
+        self.data.insert("ifid".to_string(), ifid.into());
+        self
+    }
+
+    /// Sets the story format and its version in the `StoryData`
+    pub fn with_format(mut self, format: &str, version: &str) -> Self {
+        self.data.insert("format".to_string(), format.into());
+        self.data
+            .insert("format-version".to_string(), version.into());
+        self
+    }
+
+    /// Sets the start passage in the `StoryData`
+    pub fn with_start(mut self, start: &str) -> Self {
+        self.data.insert("start".to_string(), start.into());
+        self
+    }
+
+    /// Adds a passage to the story
+    pub fn with_passage(mut self, passage: PassageBuilder) -> Self {
+        self.passages.push(passage);
+        self
+    }
+
+    /// Adds a script to the story, in a passage named `Story JavaScript`
+    pub fn with_script(mut self, script: &str) -> Self {
+        self.scripts.push(script.to_string());
+        self
+    }
+
+    /// Adds a stylesheet to the story, in a passage named `Story Stylesheet`
+    pub fn with_stylesheet(mut self, stylesheet: &str) -> Self {
+        self.stylesheets.push(stylesheet.to_string());
+        self
+    }
+
+    /// Builds the story, along with any warnings produced while building its
+    /// passages or adding them to the story. If any passage fails to build,
+    /// the result is the list of errors from every such passage
+    pub fn build(self) -> Output<Result<StoryPassages, ErrorList>> {
+        let mut builders = Vec::new();
+        if let Some(title) = &self.title {
+            builders.push(PassageBuilder::new("StoryTitle").with_content(title));
+        }
+        if !self.data.is_empty() {
+            let data = serde_json::Value::Object(self.data);
+            let data = serde_json::to_string_pretty(&data).unwrap_or_default();
+            builders.push(PassageBuilder::new("StoryData").with_content(&data));
+        }
+        builders.extend(self.passages);
+        for (name, tag, contents) in [
+            ("Story JavaScript", "script", self.scripts),
+            ("Story Stylesheet", "stylesheet", self.stylesheets),
+        ] {
+            for (i, content) in contents.iter().enumerate() {
+                let name = match i {
+                    0 => name.to_string(),
+                    _ => format!("{} {}", name, i + 1),
+                };
+                builders.push(
+                    PassageBuilder::new(&name)
+                        .with_tag(tag)
+                        .with_content(content),
+                );
+            }
+        }
+
+        let mut story = StoryPassages::default();
+        let mut warnings = Vec::new();
+        let mut errors = ErrorList::default();
+        for builder in builders {
+            let (res, mut passage_warnings) = builder.build().take();
+            warnings.append(&mut passage_warnings);
+            match res {
+                Ok(passage) => warnings.append(&mut story.add_passage(passage)),
+                Err(mut e) => errors.errors.append(&mut e.errors),
+            }
+        }
+
+        let res = if errors.errors.is_empty() {
+            Ok(story)
+        } else {
+            Err(errors)
+        };
+        Output::new(res).with_warnings(warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PassageContent;
+    use crate::WarningKind;
+
+    #[test]
+    fn build() {
+        let (res, warnings) = StoryBuilder::new()
+            .with_title("Built")
+            .with_ifid("ABC")
+            .with_format("Harlowe", "3.3.8")
+            .with_passage(PassageBuilder::new("Start").with_content("[[Cave]]"))
+            .with_passage(PassageBuilder::new("Cave").with_content("Dark"))
+            .with_passage(PassageBuilder::new("Start").with_content("Again"))
+            .with_script("let a = 1;")
+            .with_script("let b = 2;")
+            .with_stylesheet("p {}")
+            .build()
+            .take();
+        let story = res.ok().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].kind,
+            WarningKind::DuplicatePassage("Start".to_string())
+        );
+
+        assert!(
+            matches!(&story.title.as_ref().unwrap().content, PassageContent::StoryTitle(t) if t.title == "Built")
+        );
+        let data = match &story.data.as_ref().unwrap().content {
+            PassageContent::StoryData(Some(data)) => data,
+            _ => panic!("Expected parsed StoryData"),
+        };
+        assert_eq!(data.ifid, "ABC");
+        assert_eq!(data.format_version.as_deref(), Some("3.3.8"));
+
+        let pid = |name: &str| match &story.passages[name].content {
+            PassageContent::Normal(twine) => twine.pid,
+            _ => 0,
+        };
+        assert_eq!((pid("Start"), pid("Cave")), (1, 2));
+        let names: Vec<&str> = story
+            .scripts
+            .iter()
+            .map(|p| p.header.name.as_str())
+            .collect();
+        assert_eq!(names, ["Story JavaScript", "Story JavaScript 2"]);
+        assert_eq!(story.stylesheets[0].header.name, "Story Stylesheet");
+        assert!(story.check().is_empty());
+
+        let (res, _) = StoryBuilder::new()
+            .with_passage(PassageBuilder::new(""))
+            .build()
+            .take();
+        assert!(res.is_err());
+    }
+}
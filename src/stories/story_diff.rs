@@ -0,0 +1,228 @@
+use crate::Story;
+use crate::TwinePassage;
+use std::collections::BTreeSet;
+
+/// The differences between two versions of a [`Story`], as produced by
+/// [`diff`]
+///
+/// Only normal passages are compared. A passage that was removed while
+/// another with the same tags and content was added is reported as renamed
+/// rather than as added and removed. All lists are sorted by passage name.
+///
+/// [`Story`]: struct.Story.html
+/// [`diff`]: fn.diff.html
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StoryDiff {
+    /// The names of the passages only in the new story
+    pub added: Vec<String>,
+
+    /// The names of the passages only in the old story
+    pub removed: Vec<String>,
+
+    /// The old and new names of the passages that were renamed
+    pub renamed: Vec<(String, String)>,
+
+    /// The changes to the passages in both stories, including renamed
+    /// passages, for each passage that changed
+    pub changed: Vec<PassageDiff>,
+}
+
+impl StoryDiff {
+    /// Returns `true` if the two stories have the same passages
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renamed.is_empty()
+            && self.changed.is_empty()
+    }
+}
+
+/// The changes to a single passage between two versions of a story
+///
+/// Links are compared by their targets, so a link whose text changed but
+/// that still points to the same passage is not reported.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PassageDiff {
+    /// The name of the passage in the new story
+    pub name: String,
+
+    /// The tags only on the new passage
+    pub added_tags: Vec<String>,
+
+    /// The tags only on the old passage
+    pub removed_tags: Vec<String>,
+
+    /// The metadata keys that were added, removed, or changed value, in
+    /// sorted order
+    pub changed_metadata: Vec<String>,
+
+    /// The targets of links only in the new passage
+    pub added_links: Vec<String>,
+
+    /// The targets of links only in the old passage
+    pub removed_links: Vec<String>,
+
+    /// Whether the text of the passage changed
+    pub content_changed: bool,
+}
+
+impl PassageDiff {
+    /// Compares two versions of a passage
+    fn new(old: &TwinePassage, new: &TwinePassage) -> Self {
+        let tags = |passage: &TwinePassage| -> BTreeSet<String> {
+            passage.tags().iter().map(|tag| tag.to_string()).collect()
+        };
+        let links = |passage: &TwinePassage| -> BTreeSet<String> {
+            passage
+                .content
+                .get_links()
+                .iter()
+                .map(|link| link.target.to_string())
+                .collect()
+        };
+        let (old_tags, new_tags) = (tags(old), tags(new));
+        let (old_links, new_links) = (links(old), links(new));
+        let keys: BTreeSet<&String> = old.metadata().keys().chain(new.metadata().keys()).collect();
+        PassageDiff {
+            name: new.header.name.clone(),
+            added_tags: new_tags.difference(&old_tags).cloned().collect(),
+            removed_tags: old_tags.difference(&new_tags).cloned().collect(),
+            changed_metadata: keys
+                .into_iter()
+                .filter(|key| old.metadata().get(*key) != new.metadata().get(*key))
+                .cloned()
+                .collect(),
+            added_links: new_links.difference(&old_links).cloned().collect(),
+            removed_links: old_links.difference(&new_links).cloned().collect(),
+            content_changed: old.content.content != new.content.content,
+        }
+    }
+
+    /// Returns `true` if nothing about the passage changed
+    pub fn is_empty(&self) -> bool {
+        self.added_tags.is_empty()
+            && self.removed_tags.is_empty()
+            && self.changed_metadata.is_empty()
+            && self.added_links.is_empty()
+            && self.removed_links.is_empty()
+            && !self.content_changed
+    }
+}
+
+/// Compares two versions of a story, such as before and after a save, and
+/// returns what changed between them
+///
+/// # Examples
+/// ```
+/// use tweep::{diff, Story};
+/// let old = ":: Start\n[[Cave]]\n\n:: Cave\nDark\n".to_string();
+/// let new = ":: Start [intro]\n[[Grotto]]\n\n:: Grotto\nDark\n".to_string();
+/// let old = Story::from_string(old).take().0.ok().unwrap();
+/// let new = Story::from_string(new).take().0.ok().unwrap();
+/// let changes = diff(&old, &new);
+/// assert_eq!(changes.renamed, [("Cave".to_string(), "Grotto".to_string())]);
+/// assert_eq!(changes.changed[0].name, "Start");
+/// assert_eq!(changes.changed[0].added_tags, ["intro"]);
+/// assert_eq!(changes.changed[0].added_links, ["Grotto"]);
+/// ```
+pub fn diff(old: &Story, new: &Story) -> StoryDiff {
+    let mut removed: Vec<&String> = old
+        .passages
+        .keys()
+        .filter(|name| !new.passages.contains_key(*name))
+        .collect();
+    let mut added: Vec<&String> = new
+        .passages
+        .keys()
+        .filter(|name| !old.passages.contains_key(*name))
+        .collect();
+    removed.sort();
+    added.sort();
+
+    let mut renamed = Vec::new();
+    removed.retain(|old_name| {
+        let old_passage = &old.passages[*old_name];
+        let same = |new_name: &&String| {
+            let new_passage = &new.passages[*new_name];
+            new_passage.content.content == old_passage.content.content
+                && new_passage.tags() == old_passage.tags()
+        };
+        match added.iter().position(same) {
+            Some(i) => {
+                renamed.push(((*old_name).clone(), added.remove(i).clone()));
+                false
+            }
+            None => true,
+        }
+    });
+
+    let mut pairs: Vec<(&String, &String)> = old
+        .passages
+        .keys()
+        .filter(|name| new.passages.contains_key(*name))
+        .map(|name| (name, name))
+        .chain(
+            renamed
+                .iter()
+                .map(|(old_name, new_name)| (old_name, new_name)),
+        )
+        .collect();
+    pairs.sort_by_key(|(_, new_name)| *new_name);
+    let changed = pairs
+        .into_iter()
+        .map(|(old_name, new_name)| {
+            PassageDiff::new(&old.passages[old_name], &new.passages[new_name])
+        })
+        .filter(|passage| !passage.is_empty())
+        .collect();
+
+    StoryDiff {
+        added: added.into_iter().cloned().collect(),
+        removed: removed.into_iter().cloned().collect(),
+        renamed,
+        changed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn story(input: &str) -> Story {
+        Story::from_string(input.to_string()).take().0.ok().unwrap()
+    }
+
+    #[test]
+    fn changes() {
+        let old = story(
+            ":: Start [a b] {\"position\":\"10,10\"}\n[[One]] [[Two]]\n\n:: One\nFirst\n\n:: Two\nSecond\n\n:: Gone\nBye\n",
+        );
+        let new = story(
+            ":: Start [b c] {\"position\":\"50,10\"}\n[[One]] [[Three]]\n\n:: One\nFirst!\n\n:: Three\nSecond\n\n:: New\nHi\n",
+        );
+        let changes = diff(&old, &new);
+        assert_eq!(changes.added, ["New"]);
+        assert_eq!(changes.removed, ["Gone"]);
+        assert_eq!(changes.renamed, [("Two".to_string(), "Three".to_string())]);
+
+        let names: Vec<&str> = changes.changed.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["One", "Start"]);
+        assert_eq!(
+            changes.changed[0],
+            PassageDiff {
+                name: "One".to_string(),
+                content_changed: true,
+                ..Default::default()
+            }
+        );
+        let start = &changes.changed[1];
+        assert_eq!(start.added_tags, ["c"]);
+        assert_eq!(start.removed_tags, ["a"]);
+        assert_eq!(start.changed_metadata, ["position"]);
+        assert_eq!(start.added_links, ["Three"]);
+        assert_eq!(start.removed_links, ["Two"]);
+        assert!(start.content_changed);
+
+        assert!(diff(&old, &old).is_empty());
+    }
+}
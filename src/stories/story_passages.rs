@@ -1,30 +1,52 @@
+use crate::metrics::Timer;
+use crate::stories::encoding;
+use crate::stories::encoding::EncodingError;
+use crate::stories::CheckPass;
+use crate::stories::CheckedPassage;
+use crate::stories::CheckedStory;
 #[cfg(feature = "full-context")]
 use crate::CodeMap;
 use crate::Context;
 #[cfg(feature = "full-context")]
 use crate::ContextErrorList;
+use crate::DuplicatePolicy;
 use crate::Error;
+use crate::ErrorKind;
 use crate::ErrorList;
 use crate::FullContext;
+use crate::MergeResolution;
+use crate::MultiLineTitle;
 use crate::Output;
+use crate::ParseMetrics;
+use crate::ParseOptions;
 use crate::Passage;
 use crate::PassageContent;
+use crate::PassageHeader;
 use crate::Position;
 use crate::PositionKind;
+use crate::SourceFile;
+#[cfg(feature = "fs")]
+use crate::SourceFileKind;
+use crate::TwineLink;
 use crate::Warning;
+use crate::WarningCollector;
 use crate::WarningKind;
 #[cfg(feature = "full-context")]
 use bimap::BiMap;
+#[cfg(feature = "fs")]
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use std::collections::HashMap;
 use std::default::Default;
-use std::fs::File;
 use std::io::Read;
 use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 #[cfg(not(feature = "full-context"))]
-type ParseOutput = Output<Result<StoryPassages, ErrorList>>;
+type ParseResult = Result<StoryPassages, ErrorList>;
 #[cfg(feature = "full-context")]
-type ParseOutput = Output<Result<StoryPassages, ContextErrorList>>;
+type ParseResult = Result<StoryPassages, ContextErrorList>;
+type ParseOutput = Output<ParseResult>;
 
 /// A parsed Twee story, that stores the full [`Passage`] object of each field
 ///
@@ -32,7 +54,7 @@ type ParseOutput = Output<Result<StoryPassages, ContextErrorList>>;
 ///
 /// [`Passage`]: struct.Passage.html
 /// [`Story`]: struct.Story.html
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct StoryPassages {
     /// `StoryTitle` passage
     pub title: Option<Passage>,
@@ -40,6 +62,18 @@ pub struct StoryPassages {
     /// `StoryData` passage
     pub data: Option<Passage>,
 
+    /// `StoryAuthor` passage
+    pub author: Option<Passage>,
+
+    /// `StorySubtitle` passage
+    pub subtitle: Option<Passage>,
+
+    /// `StoryMenu` passage
+    pub menu: Option<Passage>,
+
+    /// `StorySettings` passage
+    pub settings: Option<Passage>,
+
     /// Map from passage name to `Passage` for any non-special passages
     pub passages: HashMap<String, Passage>,
 
@@ -49,28 +83,108 @@ pub struct StoryPassages {
     /// List of passages tagged with `stylesheet`
     pub stylesheets: Vec<Passage>,
 
+    /// List of files encountered while parsing from a `Path`, in the order
+    /// they were encountered, including any files that were skipped
+    pub sources: Vec<SourceFile>,
+
+    /// Map from passage name to the path of a file that was skipped while
+    /// parsing from a `Path`, but appears to define a passage with that name.
+    /// Used to explain links to passages that were not parsed
+    pub excluded_passages: HashMap<String, PathBuf>,
+
+    /// Map from the name of each normal passage parsed from a `Path` to the
+    /// path of the file it was parsed from. When duplicate passages are
+    /// merged, the file of the passage that was kept is used
+    pub passage_files: HashMap<String, PathBuf>,
+
+    /// The errors of the passages whose headers could not be parsed, which
+    /// were replaced by placeholders because the [`ParseOptions`] enable
+    /// recovery
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    pub recovered_errors: Vec<Error>,
+
+    /// Map from bucket name to the normal passages that were routed into that
+    /// bucket by the rules in the [`ParseOptions`], rather than into
+    /// `passages`. Each bucket maps passage names to passages
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    pub buckets: HashMap<String, HashMap<String, Passage>>,
+
     /// StoryMap for this story
     #[cfg(feature = "full-context")]
     pub code_map: CodeMap,
 }
 
 impl StoryPassages {
-    /// Renumber pids, starting at the given number and counting up
+    /// Renumber pids, starting at the given number and counting up. Passages
+    /// keep the order of their current pids, and passages with the same pid
+    /// are numbered in the order they appear in the source
     fn renumber_pids(&mut self, start: usize) {
-        let mut pid = start;
-        for passage in self.passages.values_mut() {
+        let mut passages: Vec<&mut Passage> = self.passages.values_mut().collect();
+        passages.sort_by_cached_key(|passage| {
+            let pid = match &passage.content {
+                PassageContent::Normal(twine) => twine.pid,
+                _ => 0,
+            };
+            let context = &passage.context;
+            (
+                pid,
+                context.get_file_name().clone(),
+                context.get_start_position().line,
+                passage.header.name.clone(),
+            )
+        });
+        for (pid, passage) in (start..).zip(passages) {
             if let PassageContent::Normal(twine) = &mut passage.content {
                 twine.pid = pid;
             }
+        }
+    }
 
-            pid += 1;
+    /// Returns the pid after the highest pid in the story
+    fn next_pid(&self) -> usize {
+        self.passages
+            .values()
+            .filter_map(|passage| match &passage.content {
+                PassageContent::Normal(twine) => Some(twine.pid),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+            + 1
+    }
+
+    /// Moves the normal passages that match a routing rule in the given
+    /// options out of `passages` and into their buckets, then renumbers the
+    /// pids of the remaining passages so that they have no gaps
+    fn route_passages(&mut self, options: &ParseOptions) {
+        let routed: Vec<(String, String)> = self
+            .passages
+            .iter()
+            .filter_map(|(name, passage)| {
+                options
+                    .route(&passage.header)
+                    .map(|bucket| (name.clone(), bucket.to_string()))
+            })
+            .collect();
+        if routed.is_empty() {
+            return;
         }
+        for (name, bucket) in routed {
+            let passage = self.passages.remove(&name).unwrap();
+            self.buckets
+                .entry(bucket)
+                .or_default()
+                .insert(name, passage);
+        }
+        self.renumber_pids(1);
     }
 
     #[cfg(feature = "full-context")]
     fn renumber_file_ids(&mut self, start: usize) {
         let mut new_id_file_map = BiMap::new();
-        let mut new_contexts = HashMap::new();
+        let mut new_contexts = crate::FastHashMap::default();
         for (id, context) in self.code_map.contexts.drain() {
             let new_id = id + start;
             new_id_file_map.insert(new_id, context.get_file_name().clone().unwrap());
@@ -85,49 +199,320 @@ impl StoryPassages {
     ///
     /// [`Warning`]: struct.Warning.html
     pub fn from_string(input: String) -> ParseOutput {
-        let context = FullContext::from(None, input);
+        let context = FullContext::from(None, encoding::strip_bom(input));
         StoryPassages::from_context(context)
     }
 
+    /// Reads all input from the given [`Read`] and parses it, returning the
+    /// result or a list of errors, along with a list of any [`Warning`]s
+    ///
+    /// The whole input is read into memory before parsing starts, since the
+    /// contexts of the parsed passages refer back to the full source, so the
+    /// input is not parsed incrementally as it is read
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::StoryPassages;
+    /// let input = ":: Start\nHello".as_bytes();
+    /// let out = StoryPassages::from_reader(input);
+    /// assert!(out.is_ok());
+    /// ```
+    ///
+    /// [`Read`]: std::io::Read
+    /// [`Warning`]: struct.Warning.html
+    pub fn from_reader<R: Read>(reader: R) -> ParseOutput {
+        match StoryPassages::read_input(reader, &ParseOptions::default()) {
+            Ok(contents) => StoryPassages::from_string(contents),
+            Err(e) => Output::new(Err((*e).into())),
+        }
+    }
+
+    /// Reads all input from the given [`Read`] and decodes it into a `String`
+    ///
+    /// [`Read`]: std::io::Read
+    fn read_input<R: Read>(mut reader: R, options: &ParseOptions) -> Result<String, Box<Error>> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|e| Box::new(Error::from(e)))?;
+        StoryPassages::decode(bytes, None, options)
+    }
+
+    /// Decodes the bytes read from the file with the given name, or from a
+    /// reader if there is no name, dropping any byte order mark
+    fn decode(
+        bytes: Vec<u8>,
+        file_name: Option<String>,
+        options: &ParseOptions,
+    ) -> Result<String, Box<Error>> {
+        let error = match encoding::decode(bytes, options.lossy_encoding()) {
+            Ok(contents) => return Ok(contents),
+            Err(e) => e,
+        };
+        Err(Box::new(match error {
+            EncodingError::Utf16(order) => Error::new(
+                ErrorKind::InvalidEncoding(format!("found a UTF-16 ({}) byte order mark", order)),
+                file_name.map(|name| FullContext::from(Some(name), String::new())),
+            ),
+            EncodingError::InvalidUtf8(contents, offset, byte) => {
+                let line_start = contents[..offset].rfind('\n').map_or(0, |i| i + 1);
+                let line = contents[..line_start].matches('\n').count() + 1;
+                let column = offset - line_start + 1;
+                let context = FullContext::from(file_name, contents);
+                // The invalid sequence was replaced by U+FFFD, which is 3
+                // bytes long
+                let context = context
+                    .try_subcontext(Position::rel(line, column)..=Position::rel(line, column + 2))
+                    .unwrap_or(context);
+                Error::new(
+                    ErrorKind::InvalidEncoding(format!("invalid byte 0x{:02X}", byte)),
+                    Some(context),
+                )
+            }
+        }))
+    }
+
+    /// Parses an input `String` using the given [`ParseOptions`] and returns
+    /// the result or a list of errors, along with a list of any [`Warning`]s
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{ParseOptions, StoryPassages, WarningKind};
+    /// let options = ParseOptions::new()
+    ///     .with_suppressed_warning(WarningKind::UnclosedLink);
+    /// let input = ":: Start\n[[Nowhere".to_string();
+    /// let out = StoryPassages::from_string_with_options(input, &options);
+    /// assert!(!out.has_warnings());
+    /// ```
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    /// [`Warning`]: struct.Warning.html
+    pub fn from_string_with_options(input: String, options: &ParseOptions) -> ParseOutput {
+        let context = FullContext::from(None, encoding::strip_bom(input));
+        StoryPassages::from_input_context(context, options)
+    }
+
+    /// Parses an input `String` that may hold more than one story, such as an
+    /// anthology kept in a single file, using the given [`ParseOptions`].
+    /// Returns the output of each story, in the order they appear in the
+    /// input, each with its own warnings
+    ///
+    /// The stories are separated by passages named by the
+    /// [`story_separator`] in the options. Each story runs from the header
+    /// after a separator passage to the next separator, and the separator
+    /// passages themselves are dropped, so each story can have its own
+    /// `StoryTitle`, `StoryData`, and passages with the same names as those
+    /// of the other stories. A story with no passages, such as before a
+    /// separator at the start of the input, is skipped. If no separator is
+    /// set, the whole input is parsed as one story.
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{ParseOptions, StoryPassages};
+    /// let input = r#":: StoryTitle
+    /// First
+    ///
+    /// :: Start
+    /// Hello
+    ///
+    /// :: StoryBreak
+    ///
+    /// :: StoryTitle
+    /// Second
+    ///
+    /// :: Start
+    /// Goodbye
+    /// "#
+    /// .to_string();
+    /// let options = ParseOptions::new().with_story_separator("StoryBreak".to_string());
+    /// let outputs = StoryPassages::split_from_string_with_options(input, &options);
+    /// assert_eq!(outputs.len(), 2);
+    /// let second = outputs[1].get_output().as_ref().ok().unwrap();
+    /// assert!(second.passages.contains_key("Start"));
+    /// assert!(!second.passages.contains_key("StoryBreak"));
+    /// ```
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    /// [`story_separator`]: struct.ParseOptions.html#method.with_story_separator
+    pub fn split_from_string_with_options(
+        input: String,
+        options: &ParseOptions,
+    ) -> Vec<ParseOutput> {
+        let context = FullContext::from(None, encoding::strip_bom(input));
+        let separator = match options.story_separator() {
+            Some(separator) => separator,
+            None => return vec![StoryPassages::from_input_context(context, options)],
+        };
+        story_groups(&context, separator)
+            .into_iter()
+            .map(|group| StoryPassages::from_input_context(group, options))
+            .collect()
+    }
+
+    /// Parses the given context, which holds a string input or one story of
+    /// it, routing its passages and applying the given options to the output
+    fn from_input_context(context: FullContext, options: &ParseOptions) -> ParseOutput {
+        let (options, fatal) = options.with_fatal_tracking();
+        let start = Timer::start();
+        let bytes = context.get_contents().len();
+        let (mut res, mut warnings) =
+            StoryPassages::from_context_with_options(context, &options).take();
+        if let Ok(story) = &mut res {
+            story.route_passages(&options);
+        }
+        let metrics = ParseMetrics {
+            bytes,
+            parse_time: start.elapsed(),
+            ..Default::default()
+        };
+        if !options.handle_warnings(&mut warnings) {
+            return StoryPassages::aborted(warnings);
+        }
+        StoryPassages::finish(res, warnings, &options, &fatal, metrics)
+    }
+
+    /// Reads all input from the given [`Read`] and parses it using the given
+    /// [`ParseOptions`], returning the result or a list of errors, along with
+    /// a list of any [`Warning`]s
+    ///
+    /// The whole input is read into memory before parsing starts, since the
+    /// contexts of the parsed passages refer back to the full source, so the
+    /// input is not parsed incrementally as it is read
+    ///
+    /// [`Read`]: std::io::Read
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    /// [`Warning`]: struct.Warning.html
+    pub fn from_reader_with_options<R: Read>(reader: R, options: &ParseOptions) -> ParseOutput {
+        match StoryPassages::read_input(reader, options) {
+            Ok(contents) => StoryPassages::from_string_with_options(contents, options),
+            Err(e) => Output::new(Err((*e).into())),
+        }
+    }
+
     pub(crate) fn from_context(context: FullContext) -> ParseOutput {
-        let mut out = StoryPassages::parse(context);
-        if out.is_ok() {
-            out.mut_output().as_mut().ok().unwrap().renumber_pids(1);
+        StoryPassages::from_context_with_options(context, &ParseOptions::default())
+    }
+
+    pub(crate) fn from_context_with_options(
+        context: FullContext,
+        options: &ParseOptions,
+    ) -> ParseOutput {
+        let (mut res, mut warnings) = StoryPassages::parse(context, options).take();
+        if let Ok(story) = &mut res {
+            story.renumber_pids(1);
+            warnings.extend(story.normalize_title(options.multi_line_title()));
+            warnings = story.checked().allowed(warnings);
+        }
+        Output::new(res).with_warnings(warnings)
+    }
+
+    /// Normalizes the `StoryTitle` passage with the given policy if it spans
+    /// more than one line, returning a warning if it does
+    fn normalize_title(&mut self, policy: MultiLineTitle) -> Option<Warning> {
+        let passage = self.title.as_mut()?;
+        match &mut passage.content {
+            PassageContent::StoryTitle(title) if title.is_multi_line() => {
+                title.normalize(policy);
+                Some(Warning::new(
+                    WarningKind::MultiLineStoryTitle,
+                    Some(passage.context.clone()),
+                ))
+            }
+            _ => None,
         }
-        out
     }
 
     /// Parses a `StoryPassages` from the given [`Path`]. If the given path is
     /// a file, parses that file and returns the `StoryPassages`. If it is a
     /// directory, it looks for any files with `.tw` or `.twee` extensions and
-    /// parses them. Returns the parsed output or a list of errors, along with a
-    /// list of any [`Warning`]s
+    /// parses them in order of their file names, so that the result is the
+    /// same on every platform. Any file or directory matching a pattern in a
+    /// `.twee-ignore` file, which uses gitignore syntax, in the directory or
+    /// one of its parents is skipped. Returns the parsed output or a list of
+    /// errors, along with a list of any [`Warning`]s
+    ///
+    /// Enabled with "fs" feature
     ///
     /// [`Path`]: std::path::Path
     /// [`Warning`]: struct.Warning.html
+    #[cfg(feature = "fs")]
     pub fn from_path<P: AsRef<Path>>(input: P) -> ParseOutput {
-        let out = StoryPassages::from_path_internal(input);
+        StoryPassages::from_path_with_options(input, &ParseOptions::default())
+    }
+
+    /// Parses a `StoryPassages` from the given [`Path`] using the given
+    /// [`ParseOptions`]. See `from_path` for additional information on how
+    /// directories are handled.
+    ///
+    /// Enabled with "fs" feature
+    ///
+    /// [`Path`]: std::path::Path
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    #[cfg(feature = "fs")]
+    pub fn from_path_with_options<P: AsRef<Path>>(input: P, options: &ParseOptions) -> ParseOutput {
+        let (options, fatal) = options.with_fatal_tracking();
+        let options = &options;
+        let start = Timer::start();
+        let out = StoryPassages::from_path_internal(input, options, &[]);
+        let mut metrics = ParseMetrics {
+            parse_time: start.elapsed(),
+            ..Default::default()
+        };
         let (mut res, mut warnings) = out.take();
         if res.is_ok() {
-            let story = res.ok().unwrap();
-            let mut story_warnings = story.check();
+            let mut story = res.ok().unwrap();
+            story.route_passages(options);
+            let start = Timer::start();
+            let mut story_warnings = story.check_with_options(options);
+            metrics.check_time = start.elapsed();
             warnings.append(&mut story_warnings);
+            if options.metrics() {
+                metrics.count_sources(&story.sources);
+            }
             res = Ok(story);
         }
-        Output::new(res).with_warnings(warnings)
+        if !options.handle_warnings(&mut warnings) {
+            return StoryPassages::aborted(warnings);
+        }
+        StoryPassages::finish(res, warnings, options, &fatal, metrics)
     }
 
     /// Parses a `StoryPassages` from the given [`Path`]s. See `from_path` for
     /// additional information on how directories are handled.
     ///
+    /// Enabled with "fs" feature
+    ///
     /// [`Path`]: std::path::Path
+    #[cfg(feature = "fs")]
     pub fn from_paths<P: AsRef<Path>>(input: &[P]) -> ParseOutput {
+        StoryPassages::from_paths_with_options(input, &ParseOptions::default())
+    }
+
+    /// Parses a `StoryPassages` from the given [`Path`]s using the given
+    /// [`ParseOptions`]. See `from_path` for additional information on how
+    /// directories are handled.
+    ///
+    /// Enabled with "fs" feature
+    ///
+    /// [`Path`]: std::path::Path
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    #[cfg(feature = "fs")]
+    pub fn from_paths_with_options<P: AsRef<Path>>(
+        input: &[P],
+        options: &ParseOptions,
+    ) -> ParseOutput {
+        let (options, fatal) = options.with_fatal_tracking();
+        let options = &options;
+        let start = Timer::start();
         let mut story = StoryPassages::default();
         let mut warnings = Vec::new();
         for path in input {
-            let out = StoryPassages::from_path_internal(path);
+            let out = StoryPassages::from_path_internal(path, options, &[]);
             let (res, mut sub_warnings) = out.take();
             warnings.append(&mut sub_warnings);
+            if !options.handle_warnings(&mut warnings) {
+                return StoryPassages::aborted(warnings);
+            }
             #[allow(unused_mut)]
             if let Err(mut e) = res {
                 #[cfg(feature = "full-context")]
@@ -141,21 +526,46 @@ impl StoryPassages {
                 return Output::new(Err(e)).with_warnings(warnings);
             }
             let sub_story = res.ok().unwrap();
-            let mut merge_warnings = story.merge_from(sub_story);
-            warnings.append(&mut merge_warnings);
+            if let Err(e) = story.merge_parsed(sub_story, options, &mut warnings) {
+                return story.failed(e).with_warnings(warnings);
+            }
+            if !options.handle_warnings(&mut warnings) {
+                return StoryPassages::aborted(warnings);
+            }
         }
-
-        let mut story_warnings = story.check();
+        story.renumber_pids(1);
+        story.route_passages(options);
+
+        let mut metrics = ParseMetrics {
+            parse_time: start.elapsed(),
+            ..Default::default()
+        };
+        let start = Timer::start();
+        let mut story_warnings = story.check_with_options(options);
+        metrics.check_time = start.elapsed();
         warnings.append(&mut story_warnings);
+        if !options.handle_warnings(&mut warnings) {
+            return StoryPassages::aborted(warnings);
+        }
+        if options.metrics() {
+            metrics.count_sources(&story.sources);
+        }
 
-        Output::new(Ok(story)).with_warnings(warnings)
+        StoryPassages::finish(Ok(story), warnings, options, &fatal, metrics)
     }
 
     /// Does the heavy lifting for `from_path`. If given a file, reads its
     /// contents into a `String` and uses `from_context` to parse it. If given a
-    /// directory, finds the twee files, recurses with each file, then assembles
-    /// the outputs into a single output
-    fn from_path_internal<P: AsRef<Path>>(input: P) -> ParseOutput {
+    /// directory, finds the twee files, and the subdirectories if recursion is
+    /// enabled, recurses with each of them, then assembles the outputs into a
+    /// single output. Entries matching a pattern in `ignores`, or in a
+    /// `.twee-ignore` file in the directory, are skipped
+    #[cfg(feature = "fs")]
+    fn from_path_internal<P: AsRef<Path>>(
+        input: P,
+        options: &ParseOptions,
+        ignores: &[Gitignore],
+    ) -> ParseOutput {
         // Get the path
         let path: &Path = input.as_ref();
 
@@ -163,83 +573,120 @@ impl StoryPassages {
         let path_string: String = path.to_string_lossy().to_owned().to_string();
 
         if path.is_file() {
-            // If path is a file, get the file name part
-            let file_name: String = path
-                .file_name()
-                .unwrap()
-                .to_string_lossy()
-                .to_owned()
-                .to_string();
-
-            // Open the file
-            let file = File::open(path);
-
-            if file.is_err() {
-                // Check for errors, return Error if we can't open file
-                let err_string = format!("{}", file.err().unwrap());
-                return Output::new(Err(Error::new(
-                    crate::ErrorKind::BadInputPath(path_string, err_string),
-                    Some(FullContext::from(None, file_name)),
-                )
-                .into()));
+            let mut out = StoryPassages::read_file(path, options);
+            if options.includes() {
+                let mut stack = vec![path.canonicalize().unwrap_or_else(|_| path.to_path_buf())];
+                out = StoryPassages::resolve_includes(out, path, options, &mut stack);
             }
+            out
+        } else if path.is_dir() {
+            let dir = match std::fs::read_dir(path) {
+                Ok(dir) => dir,
+                Err(e) => {
+                    let err_string = format!("{}", e);
+                    return Output::new(Err(Error::new::<Context>(
+                        crate::ErrorKind::BadInputPath(path_string, err_string),
+                        None,
+                    )
+                    .with_io_source(e)
+                    .into()));
+                }
+            };
 
-            // Get the file
-            let mut file = file.ok().unwrap();
-
-            // Slurp the file contents
-            let mut contents = String::new();
-            let res = file.read_to_string(&mut contents);
-
-            if res.is_err() {
-                // Return an error if we can't read the file
-                let err_string = format!("{}", res.err().unwrap());
-                return Output::new(Err(Error::new(
-                    crate::ErrorKind::BadInputPath(path_string, err_string),
-                    Some(FullContext::from(None, file_name)),
-                )
-                .into()));
+            // Add the patterns from any ignore file to those inherited from
+            // parent directories
+            let mut warnings = Vec::new();
+            let mut ignores = ignores.to_vec();
+            let ignore_path = path.join(".twee-ignore");
+            if ignore_path.is_file() {
+                let ignore_path_string = ignore_path.to_string_lossy().to_string();
+                let mut builder = GitignoreBuilder::new(path);
+                if let Some(e) = builder.add(&ignore_path) {
+                    warnings.push(Warning::new::<Context>(
+                        WarningKind::BadIgnoreFile(ignore_path_string.clone(), format!("{}", e)),
+                        None,
+                    ));
+                }
+                match builder.build() {
+                    Ok(ignore) => ignores.push(ignore),
+                    Err(e) => warnings.push(Warning::new::<Context>(
+                        WarningKind::BadIgnoreFile(ignore_path_string, format!("{}", e)),
+                        None,
+                    )),
+                }
             }
 
-            // Create the object from the contents, add file name to Positions
-            let context = FullContext::from(Some(file_name), contents);
-            StoryPassages::from_context(context)
-        } else if path.is_dir() {
-            let dir = std::fs::read_dir(path);
-            if dir.is_err() {
-                let err_string = format!("{}", dir.err().unwrap());
-                return Output::new(Err(Error::new::<Context>(
-                    crate::ErrorKind::BadInputPath(path_string, err_string),
-                    None,
-                )
-                .into()));
-            }
-            let dir = dir.ok().unwrap();
-            let mut story = StoryPassages::default();
-            let mut warnings = Vec::new();
+            // Sort out which entries are twee files, recording why any others
+            // are skipped
+            let mut entries = Vec::new();
             for entry in dir {
                 if entry.is_err() {
                     continue;
                 }
                 let file_path = entry.ok().unwrap().path();
-                let extension = file_path.extension();
-                if extension.is_none() {
-                    continue;
-                }
-                let extension = extension.unwrap().to_string_lossy();
-                if !((extension == "tw" || extension == "twee") && file_path.is_file()) {
+                let is_dir = file_path.is_dir();
+                let skipped = if is_ignored(&ignores, &file_path, is_dir) {
+                    Some(SourceFileKind::Ignored)
+                } else if is_dir && options.recursive() {
+                    None
+                } else if !file_path.is_file() {
+                    Some(SourceFileKind::NotAFile)
+                } else {
+                    let is_twee = file_path
+                        .extension()
+                        .map(|ext| options.extensions().iter().any(|twee| ext == twee.as_str()))
+                        .unwrap_or(false);
+                    if is_twee {
+                        None
+                    } else {
+                        Some(SourceFileKind::WrongExtension)
+                    }
+                };
+                entries.push((file_path, skipped));
+            }
+            options
+                .directory_order()
+                .sort(&mut entries, |(path, _)| path.as_path());
+
+            let twee_paths: Vec<&Path> = entries
+                .iter()
+                .filter(|(_, skipped)| skipped.is_none())
+                .map(|(path, _)| path.as_path())
+                .collect();
+            let mut outputs =
+                StoryPassages::parse_files(&twee_paths, options, &ignores).into_iter();
+
+            // Merge the outputs in directory order so the result does not
+            // depend on how the files were parsed
+            let mut story = StoryPassages::default();
+            for (file_path, skipped) in entries {
+                if let Some(kind) = skipped {
+                    if kind != SourceFileKind::NotAFile {
+                        for name in scan_passage_names(&file_path) {
+                            story
+                                .excluded_passages
+                                .entry(name)
+                                .or_insert_with(|| file_path.clone());
+                        }
+                    }
+                    story.sources.push(SourceFile::new(file_path, kind));
                     continue;
                 }
-                let out = StoryPassages::from_path_internal(file_path);
+                let out = outputs.next().unwrap();
                 let (res, mut sub_warnings) = out.take();
                 if res.is_err() {
                     return Output::new(res).with_warnings(warnings);
                 }
                 let sub_story = res.ok().unwrap();
-                let mut merge_warnings = story.merge_from(sub_story);
                 warnings.append(&mut sub_warnings);
-                warnings.append(&mut merge_warnings);
+                if let Err(e) = story.merge_parsed(sub_story, options, &mut warnings) {
+                    return story.failed(e).with_warnings(warnings);
+                }
+                if !options.handle_warnings(&mut warnings) {
+                    return StoryPassages::aborted(warnings);
+                }
             }
+            story.renumber_pids(1);
             Output::new(Ok(story)).with_warnings(warnings)
         } else {
             let err_string = "Path is not a file or directory".to_string();
@@ -251,16 +698,371 @@ impl StoryPassages {
         }
     }
 
+    /// Reads and parses the file at `path`, without resolving its includes.
+    /// If the [`ParseOptions`] have a cache of parsed files, the output for
+    /// the file is taken from the cache, or stored in it after parsing
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    #[cfg(feature = "fs")]
+    fn read_file(path: &Path, options: &ParseOptions) -> ParseOutput {
+        #[cfg(feature = "watch")]
+        if let Some(cache) = options.cache() {
+            return cache.get_or_parse(path, || StoryPassages::parse_file(path, options));
+        }
+        StoryPassages::parse_file(path, options)
+    }
+
+    /// Does the work of `read_file`, without the cache
+    #[cfg(feature = "fs")]
+    fn parse_file(path: &Path, options: &ParseOptions) -> ParseOutput {
+        // Convert path to string
+        let path_string: String = path.to_string_lossy().to_string();
+
+        // If path is a file, get the file name part
+        let file_name: String = path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_owned()
+            .to_string();
+
+        // Slurp the file contents and decode them
+        let contents = std::fs::read(path).map_err(|e| {
+            let err_string = format!("{}", e);
+            let error = Error::new(
+                ErrorKind::BadInputPath(path_string.clone(), err_string.clone()),
+                Some(FullContext::from(None, file_name.clone())),
+            )
+            .with_io_source(e);
+            (Box::new(error), err_string)
+        });
+        let contents = contents.and_then(|bytes| {
+            StoryPassages::decode(bytes, Some(file_name.clone()), options).map_err(|error| {
+                let err_string = error.kind.to_string();
+                (error, err_string)
+            })
+        });
+
+        let contents = match contents {
+            Ok(contents) => contents,
+            Err((error, err_string)) => {
+                if options.skip_unreadable() {
+                    // Record the file as skipped and continue with an
+                    // empty story
+                    let mut story = StoryPassages::default();
+                    story.sources.push(SourceFile::new(
+                        path,
+                        SourceFileKind::Unreadable(err_string.clone()),
+                    ));
+                    let warning = Warning::new::<Context>(
+                        WarningKind::UnreadableFile(path_string, err_string),
+                        None,
+                    );
+                    return Output::new(Ok(story)).with_warnings(vec![warning]);
+                }
+
+                // Return an error if we can't open, read, or decode the
+                // file
+                return Output::new(Err((*error).into()));
+            }
+        };
+
+        // Create the object from the contents, add file name to Positions
+        let context = FullContext::from(Some(file_name), contents);
+        let mut out = StoryPassages::from_context_with_options(context, options);
+        if out.is_ok() {
+            let story = out.mut_output().as_mut().ok().unwrap();
+            story.sources.push(SourceFile::new(path, SourceFileKind::Parsed));
+            for name in story.passages.keys() {
+                story.passage_files.insert(name.clone(), path.to_path_buf());
+            }
+        }
+        out
+    }
+
+    /// Pulls the files listed in the `StoryIncludes` passage of the parsed
+    /// file at `path`, if there is one, into its output. `stack` holds the
+    /// canonical paths of the files currently being included, which are
+    /// skipped with an [`IncludeCycle`] warning if listed again
+    ///
+    /// [`IncludeCycle`]: enum.WarningKind.html#variant.IncludeCycle
+    #[cfg(feature = "fs")]
+    fn resolve_includes(
+        out: ParseOutput,
+        path: &Path,
+        options: &ParseOptions,
+        stack: &mut Vec<PathBuf>,
+    ) -> ParseOutput {
+        let (res, mut warnings) = out.take();
+        let mut story = match res {
+            Ok(story) => story,
+            Err(e) => return Output::new(Err(e)).with_warnings(warnings),
+        };
+        let includes = match story.remove_passage("StoryIncludes") {
+            Some(includes) => includes,
+            None => return Output::new(Ok(story)).with_warnings(warnings),
+        };
+        let listed = match &includes.content {
+            PassageContent::Normal(twine) => twine.content.clone(),
+            _ => String::new(),
+        };
+
+        // Each included file is parsed on its own, with includes resolved here
+        // so that cycles can be detected
+        let file_options = options.clone().with_includes(false);
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for line in listed
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+        {
+            let include_path = dir.join(line);
+            let canonical = include_path
+                .canonicalize()
+                .unwrap_or_else(|_| include_path.clone());
+            if stack.contains(&canonical) {
+                warnings.push(Warning::new(
+                    WarningKind::IncludeCycle(line.to_string()),
+                    Some(includes.context.clone()),
+                ));
+                continue;
+            }
+
+            let mut out = StoryPassages::from_path_internal(&include_path, &file_options, &[]);
+            if include_path.is_file() {
+                stack.push(canonical);
+                out = StoryPassages::resolve_includes(out, &include_path, options, stack);
+                stack.pop();
+            }
+            let (res, mut sub_warnings) = out.take();
+            warnings.append(&mut sub_warnings);
+            let sub_story = match res {
+                Ok(sub_story) => sub_story,
+                Err(e) => return Output::new(Err(e)).with_warnings(warnings),
+            };
+            if let Err(e) = story.merge_parsed(sub_story, options, &mut warnings) {
+                return story.failed(e).with_warnings(warnings);
+            }
+        }
+        story.renumber_pids(1);
+
+        Output::new(Ok(story)).with_warnings(warnings)
+    }
+
+    /// Produces the output for a parse that was stopped by a [`DiagnosticSink`]
+    ///
+    /// [`DiagnosticSink`]: trait.DiagnosticSink.html
+    fn aborted(warnings: Vec<Warning>) -> ParseOutput {
+        Output::new(Err(Error::new::<Context>(crate::ErrorKind::ParseAborted, None).into()))
+            .with_warnings(warnings)
+    }
+
+    /// Produces the final output of a parse with the given [`ParseOptions`],
+    /// turning a successful result into a [`WarningsAsErrors`] error if any
+    /// warning that makes the parse fail was produced. `fatal` is the flag set
+    /// when such a warning was handled, even if it was then passed to a sink
+    /// or dropped by the maximum number of warnings. If metrics are turned on in
+    /// the options, the given `metrics` are completed with the counts from the
+    /// story and attached to the output
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    /// [`WarningsAsErrors`]: enum.ErrorKind.html#variant.WarningsAsErrors
+    fn finish(
+        mut res: ParseResult,
+        warnings: Vec<Warning>,
+        options: &ParseOptions,
+        fatal: &AtomicBool,
+        mut metrics: ParseMetrics,
+    ) -> ParseOutput {
+        if let (true, Ok(story)) = (options.stripped_comments(), &mut res) {
+            for passage in story.passages.values_mut() {
+                if let PassageContent::Normal(twine) = &mut passage.content {
+                    twine.strip_comments();
+                }
+            }
+        }
+        if let (true, Ok(story)) = (options.metrics(), &res) {
+            metrics.count(story);
+        }
+        let fatal = fatal.load(Ordering::Relaxed)
+            || warnings
+                .iter()
+                .any(|warning| options.is_fatal(&warning.kind));
+        let out = if res.is_ok() && fatal {
+            Output::new(Err(Error::new::<Context>(
+                crate::ErrorKind::WarningsAsErrors,
+                None,
+            )
+            .into()))
+        } else {
+            Output::new(res)
+        };
+        let out = out.with_warnings(warnings);
+        if options.metrics() {
+            out.with_metrics(metrics)
+        } else {
+            out
+        }
+    }
+
+    /// Parses each of the given files, returning the outputs in the same order
+    /// as the input. If the `parallel` feature is enabled and parallel parsing
+    /// is turned on in the [`ParseOptions`], the files are parsed on the rayon
+    /// thread pool
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    #[cfg(feature = "fs")]
+    fn parse_files(
+        paths: &[&Path],
+        options: &ParseOptions,
+        ignores: &[Gitignore],
+    ) -> Vec<ParseOutput> {
+        #[cfg(feature = "parallel")]
+        {
+            if options.parallel() {
+                use rayon::prelude::*;
+                return paths
+                    .par_iter()
+                    .map(|path| StoryPassages::from_path_internal(path, options, ignores))
+                    .collect();
+            }
+        }
+
+        paths
+            .iter()
+            .map(|path| StoryPassages::from_path_internal(path, options, ignores))
+            .collect()
+    }
+
     /// Merges the given `StoryPassages` into this one, producing a possible
     /// list of [`Warning`]s in the process.
     ///
     /// # Warnings
-    /// Produces a warning if a duplicate `StoryTitle` or `StoryData` is found.
+    /// Produces a warning if a duplicate special passage, such as `StoryTitle`
+    /// or `StoryData`, is found.
     /// The duplicate is ignored and the existing one is kept.
-    pub fn merge_from(&mut self, mut other: Self) -> Vec<Warning> {
+    pub fn merge_from(&mut self, other: Self) -> Vec<Warning> {
+        self.merge_from_with_options(other, &ParseOptions::default())
+    }
+
+    /// Merges the given `StoryPassages` into this one using the given
+    /// [`ParseOptions`], producing a possible list of [`Warning`]s in the
+    /// process.
+    ///
+    /// # Warnings
+    /// Produces a warning if a duplicate special passage, such as `StoryTitle`
+    /// or `StoryData`, is found.
+    /// The one from the file that comes first in the special passage
+    /// precedence of the `ParseOptions` is kept. If neither file is in the
+    /// precedence list, or both are from the same file, the existing one is
+    /// kept.
+    ///
+    /// A duplicate normal passage is resolved with the [`DuplicatePolicy`] of
+    /// the `ParseOptions`, producing a warning if a passage is dropped. Since
+    /// merging cannot fail, [`DuplicatePolicy::Error`] keeps the existing
+    /// passage here, the same as [`DuplicatePolicy::KeepFirst`].
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    /// [`Warning`]: struct.Warning.html
+    /// [`DuplicatePolicy`]: enum.DuplicatePolicy.html
+    /// [`DuplicatePolicy::Error`]: enum.DuplicatePolicy.html#variant.Error
+    /// [`DuplicatePolicy::KeepFirst`]: enum.DuplicatePolicy.html#variant.KeepFirst
+    pub fn merge_from_with_options(&mut self, other: Self, options: &ParseOptions) -> Vec<Warning> {
+        let policy = match options.duplicate_policy() {
+            DuplicatePolicy::Error => DuplicatePolicy::KeepFirst,
+            policy => policy,
+        };
+        let warnings = self.merge_passages(other, options, policy).take().1;
+        self.renumber_pids(1);
+        warnings
+    }
+
+    /// Merges the given `StoryPassages` into this one, calling the given
+    /// closure with the existing and incoming passages whenever both have a
+    /// passage of the same name, or both have the same special passage, such
+    /// as `StoryData`. The [`MergeResolution`] it returns decides which one
+    /// is kept, or gives a passage to replace both. This allows a base story
+    /// to be overridden by mod files, for example.
+    ///
+    /// A passage that replaces an existing normal passage keeps its pid, and
+    /// [`passage_files`] gives the file of the incoming passage only when it
+    /// is kept as it is. Any warnings are those of
+    /// [`merge_from`](#method.merge_from), of which there are none for the
+    /// duplicates the closure resolves.
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{MergeResolution, PassageContent, StoryPassages};
+    /// let base = ":: StoryTitle\nBase\n\n:: Start\nHello\n\n:: End\nBye\n";
+    /// let overrides = ":: StoryTitle\nModded\n\n:: Start\nHi there\n";
+    /// let mut story = StoryPassages::from_string(base.to_string()).take().0.ok().unwrap();
+    /// let overrides = StoryPassages::from_string(overrides.to_string()).take().0.ok().unwrap();
+    ///
+    /// let warnings = story.merge_from_with(overrides, |existing, _| match &existing.content {
+    ///     PassageContent::StoryTitle(_) => MergeResolution::KeepExisting,
+    ///     _ => MergeResolution::KeepIncoming,
+    /// });
+    /// assert!(warnings.is_empty());
+    /// if let PassageContent::StoryTitle(title) = &story.title.unwrap().content {
+    ///     assert_eq!(title.title, "Base");
+    /// }
+    /// if let PassageContent::Normal(twine) = &story.passages["Start"].content {
+    ///     assert_eq!(twine.content, "Hi there\n");
+    /// }
+    /// assert_eq!(story.passages.len(), 2);
+    /// ```
+    ///
+    /// [`MergeResolution`]: enum.MergeResolution.html
+    /// [`passage_files`]: #structfield.passage_files
+    pub fn merge_from_with<F>(&mut self, mut other: Self, mut resolver: F) -> Vec<Warning>
+    where
+        F: FnMut(&Passage, &Passage) -> MergeResolution,
+    {
+        resolve_special_passage(&mut self.title, &mut other.title, &mut resolver);
+        resolve_special_passage(&mut self.data, &mut other.data, &mut resolver);
+        resolve_special_passage(&mut self.author, &mut other.author, &mut resolver);
+        resolve_special_passage(&mut self.subtitle, &mut other.subtitle, &mut resolver);
+        resolve_special_passage(&mut self.menu, &mut other.menu, &mut resolver);
+        resolve_special_passage(&mut self.settings, &mut other.settings, &mut resolver);
+
+        let duplicates: Vec<String> = other
+            .passages
+            .keys()
+            .filter(|name| self.passages.contains_key(*name))
+            .cloned()
+            .collect();
+        for name in duplicates {
+            let incoming = other.passages.remove(&name).unwrap();
+            let path = other.passage_files.remove(&name);
+            let existing = self.passages.get_mut(&name).unwrap();
+            if resolve_duplicate(existing, incoming, &mut resolver) {
+                if let Some(path) = path {
+                    self.passage_files.insert(name, path);
+                }
+            }
+        }
+
+        // Nothing is left for the ordinary merge to find duplicated
+        self.merge_from(other)
+    }
+
+    /// Does the work of `merge_from_with_options`, resolving duplicate normal
+    /// passages with the given [`DuplicatePolicy`]. The result is a list of
+    /// errors if the policy makes duplicates fail the parse
+    ///
+    /// [`DuplicatePolicy`]: enum.DuplicatePolicy.html
+    fn merge_passages(
+        &mut self,
+        mut other: Self,
+        options: &ParseOptions,
+        policy: DuplicatePolicy,
+    ) -> Output<Result<(), ErrorList>> {
         let mut warnings = Vec::new();
+        let mut errors = Ok(());
 
-        other.renumber_pids(self.passages.len() + 1);
+        // Put the incoming passages after the existing ones. Duplicates can
+        // leave gaps, so the caller renumbers once everything is merged
+        other.renumber_pids(self.next_pid());
 
         #[cfg(feature = "full-context")]
         {
@@ -271,144 +1073,646 @@ impl StoryPassages {
             }
         }
 
-        match (&self.title, &other.title) {
-            (None, Some(_)) => self.title = other.title,
-            (Some(self_title), Some(other_title)) => {
-                let mut warning = Warning::new(
-                    WarningKind::DuplicateStoryTitle,
-                    Some(other_title.context.clone()),
-                );
-                warning.set_referent(self_title.context.clone());
-                warnings.push(warning)
-            }
-            _ => (),
+        let precedence = options.special_passage_precedence();
+        if let Some(warning) = merge_special_passage(
+            &mut self.title,
+            other.title,
+            WarningKind::DuplicateStoryTitle,
+            precedence,
+        ) {
+            warnings.push(warning);
         }
-
-        match (&self.data, &other.data) {
-            (None, Some(_)) => self.data = other.data,
-            (Some(self_data), Some(other_data)) => {
-                let mut warning = Warning::new(
-                    WarningKind::DuplicateStoryData,
-                    Some(other_data.context.clone()),
-                );
-                warning.set_referent(self_data.context.clone());
-                warnings.push(warning);
-            }
-            _ => (),
+        if let Some(warning) = merge_special_passage(
+            &mut self.data,
+            other.data,
+            WarningKind::DuplicateStoryData,
+            precedence,
+        ) {
+            warnings.push(warning);
+        }
+        if let Some(warning) = merge_special_passage(
+            &mut self.author,
+            other.author,
+            WarningKind::DuplicateStoryAuthor,
+            precedence,
+        ) {
+            warnings.push(warning);
+        }
+        if let Some(warning) = merge_special_passage(
+            &mut self.subtitle,
+            other.subtitle,
+            WarningKind::DuplicateStorySubtitle,
+            precedence,
+        ) {
+            warnings.push(warning);
+        }
+        if let Some(warning) = merge_special_passage(
+            &mut self.menu,
+            other.menu,
+            WarningKind::DuplicateStoryMenu,
+            precedence,
+        ) {
+            warnings.push(warning);
+        }
+        if let Some(warning) = merge_special_passage(
+            &mut self.settings,
+            other.settings,
+            WarningKind::DuplicateStorySettings,
+            precedence,
+        ) {
+            warnings.push(warning);
         }
 
         for (name, passage) in other.passages.drain() {
-            let entry = self.passages.entry(name.clone());
-            use std::collections::hash_map::Entry::*;
-            match entry {
-                Vacant(_) => {
-                    entry.or_insert(passage);
-                },
-                Occupied(v) => {
-                    let warning = Warning::new(WarningKind::DuplicatePassage(name), Some(passage.context.clone())).with_referent(v.get().context.clone());
-                    warnings.push(warning);
+            let existed = self.passages.contains_key(&name);
+            match insert_passage(&mut self.passages, passage, policy) {
+                Ok(warning) => warnings.extend(warning),
+                Err(e) => errors = ErrorList::merge(&mut errors, &mut Err::<(), _>(e)),
+            }
+
+            // Keep the file of whichever passage was kept
+            if let Some(path) = other.passage_files.remove(&name) {
+                if !existed || policy == DuplicatePolicy::KeepLast {
+                    self.passage_files.insert(name, path);
                 }
             }
         }
 
         self.scripts.append(&mut other.scripts);
         self.stylesheets.append(&mut other.stylesheets);
+        self.sources.append(&mut other.sources);
+        self.recovered_errors.append(&mut other.recovered_errors);
+        for (name, path) in other.excluded_passages {
+            self.excluded_passages.entry(name).or_insert(path);
+        }
+        for (bucket, passages) in other.buckets {
+            let existing = self.buckets.entry(bucket).or_default();
+            for (name, passage) in passages {
+                existing.entry(name).or_insert(passage);
+            }
+        }
 
-        warnings
+        Output::new(errors).with_warnings(warnings)
     }
 
-    /// Performs a set of post-parse checks and returns a list of any warnings
+    /// Merges the given `StoryPassages` into this one as part of a parse with
+    /// the given [`ParseOptions`], appending any warnings to `warnings`
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    #[cfg(feature = "fs")]
+    fn merge_parsed(
+        &mut self,
+        other: Self,
+        options: &ParseOptions,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<(), ErrorList> {
+        let (res, mut merge_warnings) = self
+            .merge_passages(other, options, options.duplicate_policy())
+            .take();
+        warnings.append(&mut merge_warnings);
+        res
+    }
+
+    /// Produces the output for a parse that failed with the given errors,
+    /// keeping the code map of this story so that the errors can be located
+    #[cfg(feature = "fs")]
+    fn failed(self, errors: ErrorList) -> ParseOutput {
+        #[cfg(feature = "full-context")]
+        let errors = ContextErrorList {
+            error_list: errors,
+            code_map: self.code_map,
+        };
+        Output::new(Err(errors))
+    }
+
+    /// Adds the given [`Passage`] to the story, producing a possible list of
+    /// [`Warning`]s in the process. A normal passage is given the pid after
+    /// the highest pid in the story, so it never shares a pid with another
+    /// passage even if there are gaps in the existing pids
     ///
     /// # Warnings
-    /// * [`MissingStoryTitle`] - No `StoryTitle` passage found
-    /// * [`MissingStoryData`] - No `StoryData` passage found
-    /// * [`DeadLink`] - Found a link to a non-existent passage
-    /// * [`MissingStartPassage`] - No `Start` passage found and no alternate
-    ///   passage set in `StoryData`
-    /// * [`DeadStartPassage`] - Alternate start passage set in `StoryData`, but
-    ///   no such passage found in parsing
+    /// Produces a warning if the passage is a duplicate of a normal passage or
+    /// a special passage, such as `StoryTitle` or `StoryData`, already in the
+    /// story. The duplicate is ignored and the existing one is kept.
     ///
-    /// [`MissingStoryTitle`]: enum.WarningKind.html#variant.MissingStoryTitle
-    /// [`MissingStoryData`]: enum.WarningKind.html#variant.MissingStoryData
-    /// [`DeadLink`]: enum.WarningKind.html#variant.DeadLink
-    /// [`MissingStartPassage`]: enum.WarningKind.html#variant.MissingStartPassage
-    /// [`DeadStartPassage`]: enum.WarningKind.html#variant.DeadStartPassage
-    pub fn check(&self) -> Vec<Warning> {
+    /// # Examples
+    /// ```
+    /// use tweep::StoryPassages;
+    /// let mut story = StoryPassages::from_string(":: A\nText".to_string()).take().0.ok().unwrap();
+    /// let other = StoryPassages::from_string(":: B\nMore".to_string()).take().0.ok().unwrap();
+    /// for (_, passage) in other.passages {
+    ///     assert!(story.add_passage(passage).is_empty());
+    /// }
+    /// assert_eq!(story.passages.len(), 2);
+    /// ```
+    ///
+    /// [`Passage`]: struct.Passage.html
+    /// [`Warning`]: struct.Warning.html
+    pub fn add_passage(&mut self, mut passage: Passage) -> Vec<Warning> {
         let mut warnings = Vec::new();
-        if self.title.is_none() {
-            warnings.push(Warning::new::<Context>(
-                WarningKind::MissingStoryTitle,
-                None,
-            ));
-        }
-
-        let mut missing_start = !self.passages.contains_key("Start");
-
-        self.data
-            .as_ref()
-            .or_else(|| {
-                // There is no StoryData, generate a warning
-                warnings.push(Warning::new::<Context>(WarningKind::MissingStoryData, None));
-
-                // Return None to prevent additional processing
-                None
-            })
-            .and_then(|passage| {
-                // There was an attempt to parse a StoryData passage
-                if let PassageContent::StoryData(maybe_data) = &passage.content {
-                    maybe_data
-                        .as_ref()
-                        // If there is parsed StoryData, get the start field
-                        .and_then(|data| data.start.as_ref())
-                        // If there is a start field
-                        .and_then(|start| {
-                            // Even if the start field is a dead link, it's not
-                            // missing a start passage
-                            missing_start = false;
-
-                            // Check if the configured start passage exists
-                            if !self.passages.contains_key(start) {
-                                // There is an alternate start passage specified,
-                                // but it does not exist
-                                warnings.push(Warning::new(
-                                    WarningKind::DeadStartPassage(start.clone()),
-                                    Some(passage.context.clone()),
-                                ));
-                            }
-
-                            // Return something
-                            Some(())
-                        })
+        match &mut passage.content {
+            PassageContent::Normal(twine) => {
+                let name = passage.header.name.clone();
+                if let Some(existing) = self.passages.get(&name) {
+                    warnings.push(
+                        Warning::new(
+                            WarningKind::DuplicatePassage(name),
+                            Some(passage.context.clone()),
+                        )
+                        .with_referent(existing.context.clone()),
+                    );
                 } else {
-                    None
+                    twine.pid = self.next_pid();
+                    self.passages.insert(name, passage);
                 }
-            });
-
-        if missing_start {
-            warnings.push(Warning::new::<Context>(
-                WarningKind::MissingStartPassage,
-                None,
-            ));
+            }
+            PassageContent::StoryTitle(_) => {
+                if let Some(existing) = &self.title {
+                    warnings.push(
+                        Warning::new(
+                            WarningKind::DuplicateStoryTitle,
+                            Some(passage.context.clone()),
+                        )
+                        .with_referent(existing.context.clone()),
+                    );
+                } else {
+                    self.title = Some(passage);
+                }
+            }
+            PassageContent::StoryData(_) => {
+                if let Some(existing) = &self.data {
+                    warnings.push(
+                        Warning::new(
+                            WarningKind::DuplicateStoryData,
+                            Some(passage.context.clone()),
+                        )
+                        .with_referent(existing.context.clone()),
+                    );
+                } else {
+                    self.data = Some(passage);
+                }
+            }
+            PassageContent::StoryAuthor(_) => warnings.extend(keep_first(
+                &mut self.author,
+                passage,
+                WarningKind::DuplicateStoryAuthor,
+            )),
+            PassageContent::StorySubtitle(_) => warnings.extend(keep_first(
+                &mut self.subtitle,
+                passage,
+                WarningKind::DuplicateStorySubtitle,
+            )),
+            PassageContent::StoryMenu(_) => warnings.extend(keep_first(
+                &mut self.menu,
+                passage,
+                WarningKind::DuplicateStoryMenu,
+            )),
+            PassageContent::StorySettings(_) => warnings.extend(keep_first(
+                &mut self.settings,
+                passage,
+                WarningKind::DuplicateStorySettings,
+            )),
+            PassageContent::Script(_) => self.scripts.push(passage),
+            PassageContent::Stylesheet(_) => self.stylesheets.push(passage),
         }
+        warnings
+    }
 
-        for passage in self.passages.values() {
-            if let PassageContent::Normal(twine) = &passage.content {
-                for link in twine.get_links() {
-                    // Trim the target so that a whitespace warning and a dead
-                    // link warning aren't both generated
-                    if !self.passages.contains_key(link.target.trim()) {
-                        warnings.push(Warning::new(
-                            WarningKind::DeadLink(link.target.clone()),
-                            Some(link.context.clone()),
-                        ));
+    /// Removes the normal passage with the given name from the story and
+    /// returns it, if it exists. The pids of the remaining passages are
+    /// renumbered so that there are no gaps, and the passage is removed from
+    /// [`passage_files`]
+    ///
+    /// Links to the removed passage are not changed, and will produce
+    /// [`DeadLink`] warnings when the story is checked.
+    ///
+    /// [`DeadLink`]: enum.WarningKind.html#variant.DeadLink
+    /// [`passage_files`]: #structfield.passage_files
+    pub fn remove_passage(&mut self, name: &str) -> Option<Passage> {
+        let removed = self.passages.remove(name)?;
+        self.passage_files.remove(name);
+        if let PassageContent::Normal(removed_twine) = &removed.content {
+            for passage in self.passages.values_mut() {
+                if let PassageContent::Normal(twine) = &mut passage.content {
+                    if twine.pid > removed_twine.pid {
+                        twine.pid -= 1;
                     }
                 }
             }
         }
+        Some(removed)
+    }
+
+    /// Renames the normal passage `old` to `new`, rewriting every link to it
+    /// in the other passages of the story. If `old` is the start passage set in
+    /// the `StoryData`, the start passage is also updated, and its file in
+    /// [`passage_files`] is moved to the new name. Does nothing if there is no
+    /// passage named `old`
+    ///
+    /// # Warnings
+    /// Produces a [`DuplicatePassage`] warning if a passage named `new`
+    /// already exists. In that case, nothing is renamed.
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::StoryPassages;
+    /// let input = ":: Start\nGo [[Next]]\n\n:: Next\nThe end".to_string();
+    /// let mut story = StoryPassages::from_string(input).take().0.ok().unwrap();
+    /// assert!(story.rename_passage("Next", "Finale").is_empty());
+    /// assert!(story.passages.contains_key("Finale"));
+    /// # use tweep::PassageContent;
+    /// if let PassageContent::Normal(twine) = &story.passages["Start"].content {
+    ///     assert_eq!(twine.content, "Go [[Finale]]\n");
+    /// }
+    /// ```
+    ///
+    /// [`DuplicatePassage`]: enum.WarningKind.html#variant.DuplicatePassage
+    /// [`passage_files`]: #structfield.passage_files
+    pub fn rename_passage(&mut self, old: &str, new: &str) -> Vec<Warning> {
+        if old == new || !self.passages.contains_key(old) {
+            return Vec::new();
+        }
+        if let Some(existing) = self.passages.get(new) {
+            let passage = &self.passages[old];
+            return vec![Warning::new(
+                WarningKind::DuplicatePassage(new.to_string()),
+                Some(passage.context.clone()),
+            )
+            .with_referent(existing.context.clone())];
+        }
+
+        let mut passage = self.passages.remove(old).unwrap();
+        passage.header.name = new.to_string();
+        self.passages.insert(new.to_string(), passage);
+        if let Some(path) = self.passage_files.remove(old) {
+            self.passage_files.insert(new.to_string(), path);
+        }
+
+        for passage in self.passages.values_mut() {
+            if let PassageContent::Normal(twine) = &mut passage.content {
+                twine.rename_link_target(old, new);
+            }
+        }
+
+        if let Some(PassageContent::StoryData(Some(data))) =
+            self.data.as_mut().map(|passage| &mut passage.content)
+        {
+            if data.start.as_deref() == Some(old) {
+                data.start = Some(new.to_string());
+            }
+        }
+
+        Vec::new()
+    }
+
+    /// Sets the start passage in the `StoryData` to the passage with the given
+    /// name
+    ///
+    /// # Warnings
+    /// * [`MissingStoryData`] - There is no `StoryData` to set the start
+    ///   passage in. Nothing is changed
+    /// * [`DeadStartPassage`] - There is no passage with the given name. The
+    ///   start passage is still set
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::StoryPassages;
+    /// let input = ":: StoryData\n{\"ifid\": \"abc\"}\n\n:: Intro\nHello".to_string();
+    /// let mut story = StoryPassages::from_string(input).take().0.ok().unwrap();
+    /// assert!(story.set_start_passage("Intro").is_empty());
+    /// assert_eq!(story.get_start_passage_name(), Some("Intro"));
+    /// ```
+    ///
+    /// [`MissingStoryData`]: enum.WarningKind.html#variant.MissingStoryData
+    /// [`DeadStartPassage`]: enum.WarningKind.html#variant.DeadStartPassage
+    pub fn set_start_passage(&mut self, name: &str) -> Vec<Warning> {
+        let passage = match &mut self.data {
+            Some(passage) => passage,
+            None => return vec![Warning::new::<Context>(WarningKind::MissingStoryData, None)],
+        };
+        let data = match &mut passage.content {
+            PassageContent::StoryData(Some(data)) => data,
+            _ => return vec![Warning::new::<Context>(WarningKind::MissingStoryData, None)],
+        };
+        data.start = Some(name.to_string());
+
+        if self.passages.contains_key(name) {
+            Vec::new()
+        } else {
+            vec![Warning::new(
+                WarningKind::DeadStartPassage(name.to_string()),
+                Some(passage.context.clone()),
+            )]
+        }
+    }
+
+    /// Performs a set of post-parse checks and returns a list of any warnings
+    ///
+    /// # Warnings
+    /// * [`MissingStoryTitle`] - No `StoryTitle` passage found
+    /// * [`MissingStoryData`] - No `StoryData` passage found
+    /// * [`DeadLink`] - Found a link to a non-existent passage. The closest
+    ///   passage name, if any is close enough, is attached as a suggestion
+    /// * [`ExcludedLink`] - Found a link to a passage that is only defined in
+    ///   a file that was skipped
+    /// * [`MissingStartPassage`] - No `Start` passage found and no alternate
+    ///   passage set in `StoryData`
+    /// * [`DeadStartPassage`] - Alternate start passage set in `StoryData`, but
+    ///   no such passage found in parsing
+    /// * [`NormalizationConflict`] - Two passage names differ only by Unicode
+    ///   normalization form
+    /// * [`DuplicatePid`] - More than one passage has the same pid, which can
+    ///   happen if pids are assigned by hand
+    /// * [`PidGap`] - The pids of the passages skip over some numbers
+    /// * [`SelfLink`] - A passage links to itself
+    /// * [`NoReachableEnding`] - Every path from the start passage loops
+    ///   forever, because no passage without links can be reached from it.
+    ///   Only checked if enabled with [`ParseOptions::with_checked_endings`]
+    /// * [`HtmlUnsafeTitle`] - The story title contains angle brackets or
+    ///   control characters
+    /// * [`HtmlUnsafeName`] - A passage name contains angle brackets or
+    ///   control characters. [`escape_html`] gives the escaped form
+    /// * [`LinkSyntaxInName`] - A passage name contains `->`, `<-`, or `|`, so
+    ///   it can only be linked to with display text
+    /// * [`InvisibleCharacter`] - A passage name or link target contains an
+    ///   invisible character such as a zero-width space.
+    ///   [`strip_invisible_in_source`] gives the edits that remove them
+    /// * [`UndefinedWidget`] - A SugarCube macro is invoked that is not built
+    ///   in, defined with `Macro.add` in a script passage, or defined as a
+    ///   widget. The closest widget name, if any is close enough, is attached
+    ///   as a suggestion
+    /// * [`UnbalancedCondition`] - The condition of a conditional macro has an
+    ///   unclosed or mismatched bracket or quote
+    /// * [`AssignmentInCondition`] - The condition of a conditional macro
+    ///   assigns with `=` or `to`, where a comparison was likely intended
+    ///
+    /// With the "macro-links" feature enabled, links created by the macros of
+    /// the story format named in the `StoryData` are checked along with normal
+    /// links, the conditions of `<<if>>`, `(if:)`, and the other conditional
+    /// macros are checked, and macro invocations are checked for
+    /// [`UndefinedWidget`] if the story format is SugarCube. Some of the checks
+    /// can also be run on their own with [`check_special_passages`],
+    /// [`check_start`], and [`check_dead_links`].
+    ///
+    /// Warnings can be allowed within a passage by inline pragmas. Warnings
+    /// named in the `tweep-allow` list in the metadata of a passage, such as
+    /// `{"tweep-allow": ["DeadLink"]}`, are allowed anywhere in it, and those
+    /// named in a comment such as `/* tweep-disable-next-line dead-link */`
+    /// are allowed on the line after it. A warning can be named by its name
+    /// or by its code, such as `W0016` for a dead link. Names ignore case and
+    /// separators, a comment that names no warnings allows all of them, and a
+    /// warning from a [`Lint`] can also be named by the name of the lint.
+    /// Pragmas allow the warnings found while parsing a passage, such as
+    /// [`UnclosedLink`], in the same way.
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{StoryPassages, WarningKind};
+    /// let input = r#":: Start {"tweep-allow": ["SelfLink"]}
+    /// [[Start]]
+    /// /* tweep-disable-next-line dead-link */
+    /// [[Nowhere]]
+    /// [[Missing]]
+    /// "#;
+    /// let story = StoryPassages::from_string(input.to_string()).take().0.ok().unwrap();
+    /// let kinds: Vec<WarningKind> = story.check().into_iter().map(|w| w.kind).collect();
+    /// assert!(kinds.contains(&WarningKind::DeadLink("Missing".to_string())));
+    /// assert!(!kinds.contains(&WarningKind::DeadLink("Nowhere".to_string())));
+    /// assert!(!kinds.contains(&WarningKind::SelfLink("Start".to_string())));
+    /// ```
+    ///
+    /// [`UnclosedLink`]: enum.WarningKind.html#variant.UnclosedLink
+    /// [`check_special_passages`]: #method.check_special_passages
+    /// [`check_start`]: #method.check_start
+    /// [`check_dead_links`]: #method.check_dead_links
+    /// [`Lint`]: trait.Lint.html
+    /// [`MissingStoryTitle`]: enum.WarningKind.html#variant.MissingStoryTitle
+    /// [`MissingStoryData`]: enum.WarningKind.html#variant.MissingStoryData
+    /// [`DeadLink`]: enum.WarningKind.html#variant.DeadLink
+    /// [`ExcludedLink`]: enum.WarningKind.html#variant.ExcludedLink
+    /// [`NormalizationConflict`]: enum.WarningKind.html#variant.NormalizationConflict
+    /// [`DuplicatePid`]: enum.WarningKind.html#variant.DuplicatePid
+    /// [`PidGap`]: enum.WarningKind.html#variant.PidGap
+    /// [`SelfLink`]: enum.WarningKind.html#variant.SelfLink
+    /// [`NoReachableEnding`]: enum.WarningKind.html#variant.NoReachableEnding
+    /// [`ParseOptions::with_checked_endings`]: struct.ParseOptions.html#method.with_checked_endings
+    /// [`HtmlUnsafeTitle`]: enum.WarningKind.html#variant.HtmlUnsafeTitle
+    /// [`HtmlUnsafeName`]: enum.WarningKind.html#variant.HtmlUnsafeName
+    /// [`escape_html`]: fn.escape_html.html
+    /// [`LinkSyntaxInName`]: enum.WarningKind.html#variant.LinkSyntaxInName
+    /// [`InvisibleCharacter`]: enum.WarningKind.html#variant.InvisibleCharacter
+    /// [`strip_invisible_in_source`]: fn.strip_invisible_in_source.html
+    /// [`UndefinedWidget`]: enum.WarningKind.html#variant.UndefinedWidget
+    /// [`UnbalancedCondition`]: enum.WarningKind.html#variant.UnbalancedCondition
+    /// [`AssignmentInCondition`]: enum.WarningKind.html#variant.AssignmentInCondition
+    /// [`MissingStartPassage`]: enum.WarningKind.html#variant.MissingStartPassage
+    /// [`DeadStartPassage`]: enum.WarningKind.html#variant.DeadStartPassage
+    pub fn check(&self) -> Vec<Warning> {
+        self.check_with_options(&ParseOptions::default())
+    }
+
+    /// Performs the same checks as `check`, matching link targets and the
+    /// start passage to passage names as configured in the given
+    /// [`ParseOptions`], then runs any [`Lint`]s registered in the options
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{ParseOptions, StoryPassages};
+    /// let input = ":: Start\n[[The  End]]\n\n:: The End\nBye".to_string();
+    /// let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+    /// assert_eq!(story.check().len(), 3);
+    /// let options = ParseOptions::new().with_collapsed_whitespace(true);
+    /// assert_eq!(story.check_with_options(&options).len(), 2);
+    /// ```
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    /// [`Lint`]: trait.Lint.html
+    pub fn check_with_options(&self, options: &ParseOptions) -> Vec<Warning> {
+        self.checked().check(options)
+    }
+
+    /// Performs only the checks of `check` that look at the `StoryTitle` and
+    /// `StoryData` passages, for callers that know nothing else has changed
+    ///
+    /// # Warnings
+    /// * [`MissingStoryTitle`] - No `StoryTitle` passage found
+    /// * [`MissingStoryData`] - No `StoryData` passage found
+    /// * [`HtmlUnsafeTitle`] - The story title contains angle brackets or
+    ///   control characters
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{StoryPassages, WarningKind};
+    /// let input = ":: StoryTitle\nA Story\n\n:: Start\n[[Nowhere]]".to_string();
+    /// let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+    /// let kinds: Vec<WarningKind> =
+    ///     story.check_special_passages().into_iter().map(|w| w.kind).collect();
+    /// assert_eq!(kinds, [WarningKind::MissingStoryData]);
+    /// ```
+    ///
+    /// [`MissingStoryTitle`]: enum.WarningKind.html#variant.MissingStoryTitle
+    /// [`MissingStoryData`]: enum.WarningKind.html#variant.MissingStoryData
+    /// [`HtmlUnsafeTitle`]: enum.WarningKind.html#variant.HtmlUnsafeTitle
+    pub fn check_special_passages(&self) -> Vec<Warning> {
+        self.checked()
+            .check_pass(CheckPass::SpecialPassages, &ParseOptions::default())
+    }
+
+    /// Performs only the checks of `check` that look for the start passage,
+    /// for callers that know only the start passage or the passage names
+    /// may have changed
+    ///
+    /// # Warnings
+    /// * [`MissingStartPassage`] - No `Start` passage found and no alternate
+    ///   passage set in `StoryData`
+    /// * [`DeadStartPassage`] - Alternate start passage set in `StoryData`, but
+    ///   no such passage found in parsing
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{StoryPassages, WarningKind};
+    /// let input = ":: Intro\nHello".to_string();
+    /// let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+    /// let kinds: Vec<WarningKind> = story.check_start().into_iter().map(|w| w.kind).collect();
+    /// assert_eq!(kinds, [WarningKind::MissingStartPassage]);
+    /// ```
+    ///
+    /// [`MissingStartPassage`]: enum.WarningKind.html#variant.MissingStartPassage
+    /// [`DeadStartPassage`]: enum.WarningKind.html#variant.DeadStartPassage
+    pub fn check_start(&self) -> Vec<Warning> {
+        self.check_start_with_options(&ParseOptions::default())
+    }
+
+    /// Performs the same checks as `check_start`, matching the start passage
+    /// to passage names as configured in the given [`ParseOptions`]
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    pub fn check_start_with_options(&self, options: &ParseOptions) -> Vec<Warning> {
+        self.checked().check_pass(CheckPass::Start, options)
+    }
+
+    /// Performs only the checks of `check` that look at the targets of links,
+    /// for callers that know only the links or the passage names may have
+    /// changed, such as an editor reacting to a single edit
+    ///
+    /// # Warnings
+    /// * [`DeadLink`] - Found a link to a non-existent passage. The closest
+    ///   passage name, if any is close enough, is attached as a suggestion
+    /// * [`ExcludedLink`] - Found a link to a passage that is only defined in
+    ///   a file that was skipped
+    /// * [`SelfLink`] - A passage links to itself
+    /// * [`InvisibleCharacter`] - A link target contains an invisible
+    ///   character such as a zero-width space
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{StoryPassages, WarningKind};
+    /// let input = ":: Start\n[[Nowhere]]".to_string();
+    /// let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+    /// let kinds: Vec<WarningKind> = story.check_dead_links().into_iter().map(|w| w.kind).collect();
+    /// assert_eq!(kinds, [WarningKind::DeadLink("Nowhere".to_string())]);
+    /// ```
+    ///
+    /// [`DeadLink`]: enum.WarningKind.html#variant.DeadLink
+    /// [`ExcludedLink`]: enum.WarningKind.html#variant.ExcludedLink
+    /// [`SelfLink`]: enum.WarningKind.html#variant.SelfLink
+    /// [`InvisibleCharacter`]: enum.WarningKind.html#variant.InvisibleCharacter
+    pub fn check_dead_links(&self) -> Vec<Warning> {
+        self.check_dead_links_with_options(&ParseOptions::default())
+    }
+
+    /// Performs the same checks as `check_dead_links`, matching link targets
+    /// to passage names as configured in the given [`ParseOptions`]
+    ///
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    pub fn check_dead_links_with_options(&self, options: &ParseOptions) -> Vec<Warning> {
+        self.checked().check_pass(CheckPass::Links, options)
+    }
+
+    /// Performs the same checks as `check`, followed by any [`Lint`]s
+    /// registered in the default [`ParseOptions`], and returns the warnings
+    ///
+    /// [`Lint`]: trait.Lint.html
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    pub fn validate(&self) -> Vec<Warning> {
+        self.validate_with_options(&ParseOptions::default())
+    }
 
+    /// Runs every check on the parsed story in one call: the checks of
+    /// `check_with_options`, followed by the [`Lint`]s registered in the given
+    /// [`ParseOptions`]. Warnings of kinds or categories suppressed in the
+    /// options are dropped. Warnings produced while parsing are returned in
+    /// the [`Output`] of the parse and are not repeated
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{IssueCategory, ParseOptions, StoryPassages};
+    /// let input = ":: Start\n[[Start]] [[Nowhere]]\n".to_string();
+    /// let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+    /// assert_eq!(story.validate().len(), 4);
+    /// let options = ParseOptions::new().with_suppressed_category(IssueCategory::Structure);
+    /// assert!(story.validate_with_options(&options).is_empty());
+    /// ```
+    ///
+    /// [`Lint`]: trait.Lint.html
+    /// [`ParseOptions`]: struct.ParseOptions.html
+    /// [`Output`]: struct.Output.html
+    pub fn validate_with_options(&self, options: &ParseOptions) -> Vec<Warning> {
+        let mut warnings = self.check_with_options(options);
+        warnings.retain(|warning| !options.is_suppressed(&warning.kind));
         warnings
     }
 
+    /// Builds the view of this story that the post-parse checks run on
+    fn checked(&self) -> CheckedStory<'_> {
+        let data = self.data.as_ref().map(|passage| {
+            let data = match &passage.content {
+                PassageContent::StoryData(data) => data.as_ref(),
+                _ => None,
+            };
+            (data, Some(&passage.context))
+        });
+        let passages = self
+            .passages
+            .iter()
+            .filter_map(|(name, passage)| match &passage.content {
+                PassageContent::Normal(content) => Some((
+                    name.as_str(),
+                    CheckedPassage {
+                        header: &passage.header,
+                        content,
+                        context: Some(&passage.context),
+                    },
+                )),
+                _ => None,
+            })
+            .collect();
+        CheckedStory {
+            title: self
+                .title
+                .as_ref()
+                .and_then(|passage| match &passage.content {
+                    PassageContent::StoryTitle(title) => {
+                        Some((title.title.as_str(), Some(&passage.context)))
+                    }
+                    _ => None,
+                }),
+            data,
+            passages,
+            excluded_passages: Some(&self.excluded_passages),
+            passage_files: Some(&self.passage_files),
+            #[cfg(feature = "macro-links")]
+            scripts: self
+                .scripts
+                .iter()
+                .filter_map(|passage| match &passage.content {
+                    PassageContent::Script(script) => Some(script.content.as_str()),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+
     /// If a start passage is configured in the StoryData, return the name of
     /// that passage. If no start passage is configured, check for the presence
     /// of a passage called "Start". If that passage exists, return that name,
@@ -430,25 +1734,108 @@ impl StoryPassages {
             })
     }
 
-    pub(crate) fn parse(context: FullContext) -> ParseOutput {
+    /// Returns every link in the story whose target is the normal passage
+    /// with the given name, ordered by the name of the passage containing the
+    /// link and then by position. See [`Story::backlinks`] for details
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::StoryPassages;
+    /// let input = ":: Start\n[[Cave]]\n\n:: Cave\n[[Back|Start]]\n".to_string();
+    /// let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+    /// let links = story.backlinks("Cave");
+    /// assert_eq!(links.len(), 1);
+    /// assert_eq!(links[0].context.get_start_position().line, 2);
+    /// ```
+    ///
+    /// [`Story::backlinks`]: struct.Story.html#method.backlinks
+    pub fn backlinks(&self, name: &str) -> Vec<&TwineLink> {
+        let contents = self
+            .passages
+            .iter()
+            .filter_map(|(passage, p)| match &p.content {
+                PassageContent::Normal(twine) => Some((passage.as_str(), twine)),
+                _ => None,
+            });
+        let format = self.data.as_ref().and_then(|d| match &d.content {
+            PassageContent::StoryData(Some(data)) => data.story_format(),
+            _ => None,
+        });
+        crate::stories::backlinks(contents, format, name)
+    }
+
+    /// Returns the contents of a Make-style (`.d`) dependency file that lists
+    /// every file parsed into this story as a prerequisite of `target`
+    ///
+    /// # Examples
+    /// ```
+    /// use tweep::{SourceFile, SourceFileKind, StoryPassages};
+    /// let mut story = StoryPassages::default();
+    /// story.sources.push(SourceFile::new("src/story.twee", SourceFileKind::Parsed));
+    /// assert_eq!(story.depfile("story.html"), "story.html: \\\n  src/story.twee\n");
+    /// ```
+    pub fn depfile<P: AsRef<Path>>(&self, target: P) -> String {
+        crate::stories::depfile(target, &self.sources)
+    }
+
+    /// Writes a Make-style (`.d`) dependency file to `path` that lists every
+    /// file parsed into this story as a prerequisite of `target`
+    ///
+    /// Enabled with "fs" feature
+    #[cfg(feature = "fs")]
+    pub fn write_depfile<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        target: P,
+        path: Q,
+    ) -> std::io::Result<()> {
+        std::fs::write(path, self.depfile(target))
+    }
+
+    pub(crate) fn parse(context: FullContext, options: &ParseOptions) -> ParseOutput {
+        let policy = options.duplicate_policy();
         let contents = context.get_contents();
 
         #[cfg(feature = "full-context")]
         let mut code_map = CodeMap::default();
 
+        // A story exported from Twine fails with a single error, rather than
+        // an error for every line that isn't a passage header
+        if let Some(line) = html_start(contents) {
+            let line_context = context.subcontext(
+                Position::rel(line, 1)..=context.end_of_line(line, PositionKind::Relative),
+            );
+            let e: ErrorList = Error::new(ErrorKind::HtmlInput, Some(line_context)).into();
+            #[cfg(feature = "full-context")]
+            let e = {
+                code_map.add(context);
+                ContextErrorList {
+                    error_list: e,
+                    code_map,
+                }
+            };
+            return Output::new(Err(e));
+        }
+
         // Story variables
         let mut title: Option<Passage> = None;
         let mut data: Option<Passage> = None;
-        let mut passages:HashMap<String, Passage> = HashMap::new();
+        let mut author: Option<Passage> = None;
+        let mut subtitle: Option<Passage> = None;
+        let mut menu: Option<Passage> = None;
+        let mut settings: Option<Passage> = None;
+        let mut passages: HashMap<String, Passage> = HashMap::default();
         let mut scripts = Vec::new();
         let mut stylesheets = Vec::new();
 
         // Running list of warnings
-        let mut warnings = Vec::new();
+        let mut warnings = WarningCollector::new(options);
 
         // Running list of errors
         let mut errors = Ok(());
 
+        // Errors of the passages replaced by placeholders in recovery mode
+        let mut recovered_errors = Vec::new();
+
         // Get an iterator to go through each line
         let mut iter = contents.split('\n').enumerate();
         // The first line must be a header, skip over it so we don't have an
@@ -458,7 +1845,10 @@ impl StoryPassages {
         // The starting position of the current passage
         let mut start = Position::rel(1, 1);
 
-        let end_line = context.get_end_position().line;
+        // Lines are counted from the start of the context, which may not be
+        // the first line of the file
+        let first_line = context.get_start_position().line;
+        let end_line = context.get_end_position().line - first_line + 1;
         while start.line <= end_line {
             let subcontext_start = start;
             let subcontext_end =
@@ -468,15 +1858,35 @@ impl StoryPassages {
                     *context.get_end_position()
                 };
 
-            let next_line = subcontext_end.line + 1;
+            let next_line = subcontext_end.line - first_line + 2;
             let subcontext = context.subcontext(subcontext_start..=subcontext_end);
+            let recovery_context = subcontext.clone();
             // Parse the passage
-            let (mut res, mut passage_warnings) = Passage::parse(subcontext).take();
-            warnings.append(&mut passage_warnings);
+            let (mut res, mut passage_warnings) =
+                Passage::parse(subcontext, options.header_continuation()).take();
 
             // Update the start position
             start = Position::rel(next_line, 1);
 
+            // In recovery mode, a passage whose header is broken becomes a
+            // placeholder and its errors are kept with the story
+            if res.is_err() && options.recovery() {
+                let (placeholder, mut placeholder_warnings) =
+                    Passage::placeholder(recovery_context, options.header_continuation()).take();
+                if let Ok(passage) = placeholder {
+                    recovered_errors.append(&mut res.err().unwrap().errors);
+                    passage_warnings.append(&mut placeholder_warnings);
+                    res = Ok(passage);
+                }
+            }
+
+            // The warnings of a passage with inline pragmas may still be
+            // allowed, so they can't be counted against the maximum yet
+            match &res {
+                Ok(passage) if has_pragmas(passage) => warnings.keep(passage_warnings),
+                _ => warnings.extend(passage_warnings),
+            }
+
             // If there's an error, update the row before returning
             if res.is_err() {
                 errors = ErrorList::merge(&mut errors, &mut res);
@@ -487,14 +1897,10 @@ impl StoryPassages {
 
             // Handle passage types appropriately
             match &passage.content {
-                PassageContent::Normal(_) => {
-                    let name = &passage.header.name;
-                    if passages.contains_key(name) {
-                        warnings.push(Warning::new(WarningKind::DuplicatePassage(name.clone()), Some(passage.context.clone())).with_referent(passages.get(name).unwrap().context.clone()));
-                    } else {
-                        passages.insert(name.clone(), passage);
-                    }
-                }
+                PassageContent::Normal(_) => match insert_passage(&mut passages, passage, policy) {
+                    Ok(warning) => warnings.extend(warning),
+                    Err(e) => errors = ErrorList::merge(&mut errors, &mut Err::<(), _>(e)),
+                },
                 PassageContent::StoryTitle(_) => {
                     if let Some(existing) = &title {
                         let mut warning = Warning::new(
@@ -519,6 +1925,26 @@ impl StoryPassages {
                         data = Some(passage);
                     }
                 }
+                PassageContent::StoryAuthor(_) => warnings.extend(keep_first(
+                    &mut author,
+                    passage,
+                    WarningKind::DuplicateStoryAuthor,
+                )),
+                PassageContent::StorySubtitle(_) => warnings.extend(keep_first(
+                    &mut subtitle,
+                    passage,
+                    WarningKind::DuplicateStorySubtitle,
+                )),
+                PassageContent::StoryMenu(_) => warnings.extend(keep_first(
+                    &mut menu,
+                    passage,
+                    WarningKind::DuplicateStoryMenu,
+                )),
+                PassageContent::StorySettings(_) => warnings.extend(keep_first(
+                    &mut settings,
+                    passage,
+                    WarningKind::DuplicateStorySettings,
+                )),
                 PassageContent::Script(_) => scripts.push(passage),
                 PassageContent::Stylesheet(_) => stylesheets.push(passage),
             }
@@ -531,9 +1957,18 @@ impl StoryPassages {
                 let story = StoryPassages {
                     title,
                     data,
+                    author,
+                    subtitle,
+                    menu,
+                    settings,
                     passages,
                     scripts,
                     stylesheets,
+                    sources: Vec::new(),
+                    excluded_passages: HashMap::default(),
+                    passage_files: HashMap::default(),
+                    recovered_errors,
+                    buckets: HashMap::default(),
                     #[cfg(feature = "full-context")]
                     code_map,
                 };
@@ -548,15 +1983,287 @@ impl StoryPassages {
                 Output::new(Err(e))
             }
         }
-        .with_warnings(warnings)
+        .with_warnings(warnings.finish())
+    }
+}
+
+/// Splits the given context into the stories between the passages with the
+/// given name, dropping those passages and any story with no passages
+fn story_groups(context: &FullContext, separator: &str) -> Vec<FullContext> {
+    let headers: Vec<usize> = context
+        .lines()
+        .filter(|(_, line, _)| line.trim_start().starts_with("::"))
+        .map(|(line, _, _)| line)
+        .collect();
+    let end_line = context.get_end_position().line;
+    let is_separator = |line: usize| {
+        let header = context
+            .subcontext(Position::rel(line, 1)..=context.end_of_line(line, PositionKind::Relative));
+        let (res, _) = PassageHeader::parse(header).take();
+        res.is_ok_and(|header| header.name == separator)
+    };
+
+    let mut groups = Vec::new();
+    let mut start = 1;
+    for (i, &line) in headers.iter().enumerate() {
+        if !is_separator(line) {
+            continue;
+        }
+        if headers[..i].iter().any(|&header| header >= start) {
+            groups.push(context.subcontext(
+                Position::rel(start, 1)..=context.end_of_line(line - 1, PositionKind::Relative),
+            ));
+        }
+        start = headers.get(i + 1).copied().unwrap_or(end_line + 1);
+    }
+    if start <= end_line {
+        groups.push(context.subcontext(Position::rel(start, 1)..));
+    }
+    groups
+}
+
+/// Returns the 1-indexed line that the given contents start on, if they are
+/// the HTML of a story exported from Twine rather than Twee code: a published
+/// story starts with a doctype or an `<html>` tag, and an archive with a
+/// `<tw-storydata>` tag
+fn html_start(contents: &str) -> Option<usize> {
+    let trimmed = contents.trim_start_matches(|c: char| c.is_whitespace() || c == '\u{feff}');
+    let start: String = trimmed.chars().take(14).collect::<String>().to_lowercase();
+    if ["<!doctype html", "<html", "<tw-storydata"]
+        .iter()
+        .any(|tag| start.starts_with(tag))
+    {
+        Some(
+            contents[..contents.len() - trimmed.len()]
+                .matches('\n')
+                .count()
+                + 1,
+        )
+    } else {
+        None
+    }
+}
+
+/// The largest skipped file, in bytes, that is scanned for passage names
+#[cfg(feature = "fs")]
+const MAX_SCANNED_FILE_SIZE: u64 = 1 << 20;
+
+/// Finds the names of the passages that a skipped file appears to define,
+/// without fully parsing it. Files that are too large, unreadable, or not
+/// UTF-8 are treated as defining no passages
+#[cfg(feature = "fs")]
+fn scan_passage_names(path: &Path) -> Vec<String> {
+    let is_small = std::fs::metadata(path)
+        .map(|metadata| metadata.is_file() && metadata.len() <= MAX_SCANNED_FILE_SIZE)
+        .unwrap_or(false);
+    if !is_small {
+        return Vec::new();
+    }
+    let contents = match std::fs::read(path) {
+        Ok(bytes) => encoding::decode(bytes, true).unwrap_or_default(),
+        Err(_) => return Vec::new(),
+    };
+
+    let mut names = Vec::new();
+    for line in contents.lines() {
+        if !line.starts_with("::") {
+            continue;
+        }
+
+        // The name runs until the first unescaped tag or metadata block
+        let mut name = String::new();
+        let mut chars = line[2..].chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => name.extend(chars.next()),
+                '[' | '{' => break,
+                _ => name.push(c),
+            }
+        }
+        let name = name.trim();
+        if !name.is_empty() {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Checks whether the given directory entry is ignored by any of the given
+/// ignore files. The innermost ignore file with a matching pattern decides, so
+/// a nested `.twee-ignore` can re-include an entry with a `!` pattern
+#[cfg(feature = "fs")]
+fn is_ignored(ignores: &[Gitignore], path: &Path, is_dir: bool) -> bool {
+    for ignore in ignores.iter().rev() {
+        let matched = ignore.matched(path, is_dir);
+        if !matched.is_none() {
+            return matched.is_ignore();
+        }
+    }
+    false
+}
+
+/// Inserts a normal passage into the given map, resolving a duplicate name
+/// with the given [`DuplicatePolicy`]. Returns a warning for a passage that is
+/// dropped, or an error if the policy makes duplicates fail the parse
+///
+/// [`DuplicatePolicy`]: enum.DuplicatePolicy.html
+fn insert_passage(
+    passages: &mut HashMap<String, Passage>,
+    passage: Passage,
+    policy: DuplicatePolicy,
+) -> Result<Option<Warning>, ErrorList> {
+    let name = passage.header.name.clone();
+    let existing = match passages.get_mut(&name) {
+        Some(existing) => existing,
+        None => {
+            passages.insert(name, passage);
+            return Ok(None);
+        }
+    };
+    let duplicate = |dropped: &Passage, kept: &Passage| {
+        Warning::new(
+            WarningKind::DuplicatePassage(name.clone()),
+            Some(dropped.context.clone()),
+        )
+        .with_referent(kept.context.clone())
+    };
+
+    match policy {
+        DuplicatePolicy::KeepFirst => Ok(Some(duplicate(&passage, existing))),
+        DuplicatePolicy::KeepLast => {
+            let mut dropped = std::mem::replace(existing, passage);
+            if let (PassageContent::Normal(old), PassageContent::Normal(new)) =
+                (&mut dropped.content, &mut existing.content)
+            {
+                new.pid = old.pid;
+            }
+            Ok(Some(duplicate(&dropped, existing)))
+        }
+        DuplicatePolicy::Merge => {
+            if let (PassageContent::Normal(twine), PassageContent::Normal(other)) =
+                (&mut existing.content, passage.content)
+            {
+                twine.append(other);
+            }
+            existing.header.merge(passage.header);
+            Ok(None)
+        }
+        DuplicatePolicy::Error => Err(Error::new(
+            crate::ErrorKind::DuplicatePassage(name),
+            Some(passage.context),
+        )
+        .into()),
+    }
+}
+
+/// Resolves a duplicate passage with the given closure, putting the result in
+/// place of the existing passage. Returns `true` if the incoming passage was
+/// kept as it is
+fn resolve_duplicate<F>(existing: &mut Passage, incoming: Passage, resolver: &mut F) -> bool
+where
+    F: FnMut(&Passage, &Passage) -> MergeResolution,
+{
+    let (replacement, kept_incoming) = match resolver(existing, &incoming) {
+        MergeResolution::KeepExisting => return false,
+        MergeResolution::KeepIncoming => (incoming, true),
+        MergeResolution::Replace(passage) => (*passage, false),
+    };
+    let dropped = std::mem::replace(existing, replacement);
+    if let (PassageContent::Normal(old), PassageContent::Normal(new)) =
+        (&dropped.content, &mut existing.content)
+    {
+        new.pid = old.pid;
+    }
+    kept_incoming
+}
+
+/// Resolves a special passage that is in both the given slot and the given
+/// incoming slot with the given closure, emptying the incoming slot
+fn resolve_special_passage<F>(
+    slot: &mut Option<Passage>,
+    incoming: &mut Option<Passage>,
+    resolver: &mut F,
+) where
+    F: FnMut(&Passage, &Passage) -> MergeResolution,
+{
+    if let (Some(existing), Some(_)) = (slot.as_mut(), incoming.as_ref()) {
+        resolve_duplicate(existing, incoming.take().unwrap(), resolver);
+    }
+}
+
+/// Puts a special passage into the given slot if it is empty. Otherwise the
+/// passage is dropped and a warning of the given kind is returned for it
+fn keep_first(slot: &mut Option<Passage>, passage: Passage, kind: WarningKind) -> Option<Warning> {
+    match slot {
+        Some(existing) => Some(
+            Warning::new(kind, Some(passage.context.clone()))
+                .with_referent(existing.context.clone()),
+        ),
+        None => {
+            *slot = Some(passage);
+            None
+        }
+    }
+}
+
+/// Returns `true` if the given passage is a normal passage with inline pragmas
+fn has_pragmas(passage: &Passage) -> bool {
+    match &passage.content {
+        PassageContent::Normal(content) => CheckedPassage {
+            header: &passage.header,
+            content,
+            context: Some(&passage.context),
+        }
+        .has_pragmas(),
+        _ => false,
     }
 }
 
+/// Merges an incoming special passage into the `existing` slot. If both are
+/// present, the one whose file comes first in `precedence` is kept, falling
+/// back to the existing one, and a warning of the given kind is returned for
+/// the passage that is dropped
+fn merge_special_passage(
+    existing: &mut Option<Passage>,
+    incoming: Option<Passage>,
+    kind: WarningKind,
+    precedence: &[String],
+) -> Option<Warning> {
+    let rank = |passage: &Passage| {
+        passage
+            .context
+            .get_file_name()
+            .as_ref()
+            .and_then(|name| precedence.iter().position(|p| p == name))
+            .unwrap_or(precedence.len())
+    };
+
+    let incoming = incoming?;
+    let current = match existing {
+        Some(current) => current,
+        None => {
+            *existing = Some(incoming);
+            return None;
+        }
+    };
+
+    let (kept, dropped) = if rank(&incoming) < rank(current) {
+        let dropped = std::mem::replace(current, incoming);
+        (&*current, dropped)
+    } else {
+        (&*current, incoming)
+    };
+    Some(Warning::new(kind, Some(dropped.context.clone())).with_referent(kept.context.clone()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::Warning;
     use crate::WarningKind;
+    #[cfg(feature = "fs")]
+    use std::fs::File;
+    #[cfg(feature = "fs")]
     use tempfile::tempdir;
 
     #[test]
@@ -594,6 +2301,7 @@ Test Story
     }
 
     #[test]
+    #[cfg(feature = "fs")]
     fn file_input() -> Result<(), Box<dyn std::error::Error>> {
         let input = r#":: A passage
 This
@@ -648,6 +2356,7 @@ Test Story
     }
 
     #[test]
+    #[cfg(feature = "fs")]
     fn dir_input() -> Result<(), Box<dyn std::error::Error>> {
         let input_one = r#":: Start
 At the start, link to [[A passage]]
@@ -721,6 +2430,7 @@ blah blah
     }
 
     #[test]
+    #[cfg(feature = "fs")]
     fn multi_path() -> Result<(), Box<dyn std::error::Error>> {
         let input_one = r#":: Start
 At the start, link to [[A passage]]
@@ -744,57 +2454,1353 @@ Links back to [[Start]]
 "ifid": "ABC"
 }
 
-:: Warning titl\]e two
-blah blah
-"#
-        .to_string();
+:: Warning titl\]e two
+blah blah
+"#
+        .to_string();
+
+        use std::io::Write;
+        let dir = tempdir()?;
+        let file_path_one = dir.path().join("test.twee");
+        let mut file_one = File::create(file_path_one.clone())?;
+        write!(file_one, "{}", input_one.clone())?;
+        let file_path_two = dir.path().join("test2.tw");
+        let mut file_two = File::create(file_path_two.clone())?;
+        write!(file_two, "{}", input_two.clone())?;
+
+        let paths = vec![file_path_one, file_path_two];
+        let out = StoryPassages::from_paths(&paths);
+        assert_eq!(out.has_warnings(), true);
+        let (res, warnings) = out.take();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(res.is_ok(), true);
+        let story = res.ok().unwrap();
+        assert_eq!(story.title.is_some(), true);
+        let title_content = story.title.unwrap().content;
+        if let PassageContent::StoryTitle(title) = title_content {
+            assert_eq!(title.title, "Test Story");
+        } else {
+            panic!("Expected StoryTitle");
+        }
+
+        let context = FullContext::from(Some("test.twee".to_string()), input_one);
+        assert!(warnings.contains(&{
+            let warning = Warning::new(
+                WarningKind::EscapedOpenCurly,
+                Some(context.subcontext(Position::rel(10, 6)..=Position::rel(10, 7))),
+            );
+            warning
+        }));
+
+        let context = FullContext::from(Some("test2.tw".to_string()), input_two);
+        assert!(warnings.contains(&{
+            let warning = Warning::new(
+                WarningKind::EscapedCloseSquare,
+                Some(context.subcontext(Position::rel(9, 16)..=Position::rel(9, 17))),
+            );
+            warning
+        }));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn dir_sources() -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+        let dir = tempdir()?;
+        let twee_path = dir.path().join("test.twee");
+        let mut twee_file = File::create(twee_path.clone())?;
+        writeln!(twee_file, ":: Start\nHello")?;
+        let text_path = dir.path().join("notes.txt");
+        let mut text_file = File::create(text_path.clone())?;
+        writeln!(text_file, ":: Not a passage")?;
+        let sub_path = dir.path().join("sub.tw");
+        std::fs::create_dir(sub_path.clone())?;
+
+        let out = StoryPassages::from_path(dir.path());
+        let (res, _) = out.take();
+        assert!(res.is_ok());
+        let story = res.ok().unwrap();
+        assert_eq!(story.sources.len(), 3);
+        assert!(story
+            .sources
+            .contains(&SourceFile::new(twee_path, SourceFileKind::Parsed)));
+        assert!(story
+            .sources
+            .contains(&SourceFile::new(text_path, SourceFileKind::WrongExtension)));
+        assert!(story
+            .sources
+            .contains(&SourceFile::new(sub_path, SourceFileKind::NotAFile)));
+
+        let story = StoryPassages::from_string(":: Start\nHello".to_string())
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        assert!(story.sources.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn skip_unreadable() -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+        let dir = tempdir()?;
+        let good_path = dir.path().join("good.twee");
+        let mut good_file = File::create(good_path.clone())?;
+        writeln!(good_file, ":: Start\nHello\n\n:: StoryTitle\nTest Story")?;
+        let bad_path = dir.path().join("bad.twee");
+        let mut bad_file = File::create(bad_path.clone())?;
+        // Invalid UTF-8 can't be read into a String
+        bad_file.write_all(&[0xff, 0xfe, 0xfd])?;
+
+        let out = StoryPassages::from_path(dir.path());
+        assert!(out.is_err());
+
+        let options = ParseOptions::new().with_skip_unreadable(true);
+        let out = StoryPassages::from_path_with_options(dir.path(), &options);
+        let (res, warnings) = out.take();
+        assert!(res.is_ok());
+        let story = res.ok().unwrap();
+        assert!(story.passages.contains_key("Start"));
+        assert!(story.sources.contains(&SourceFile::new(good_path, SourceFileKind::Parsed)));
+        assert!(story.sources.iter().any(|s| s.path == bad_path
+            && matches!(s.kind, SourceFileKind::Unreadable(_))));
+        assert!(warnings.iter().any(|w| matches!(&w.kind,
+            WarningKind::UnreadableFile(path, _) if *path == bad_path.to_string_lossy())));
+
+        let out = StoryPassages::from_paths_with_options(&[bad_path], &options);
+        assert!(out.is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn max_warnings() -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+        let dir = tempdir()?;
+        for i in 0..3 {
+            let file_path = dir.path().join(format!("test{}.twee", i));
+            let mut file = File::create(file_path)?;
+            writeln!(file, ":: Passage {}\n[[Dead {}]] [[Dead {}]]", i, i, i + 10)?;
+        }
+
+        // 6 dead links plus missing title, data, and start
+        let out = StoryPassages::from_path(dir.path());
+        assert_eq!(out.get_warnings().len(), 9);
+
+        let options = ParseOptions::new().with_max_warnings(4);
+        let out = StoryPassages::from_path_with_options(dir.path(), &options);
+        let warnings = out.get_warnings();
+        assert_eq!(warnings.len(), 5);
+        assert_eq!(warnings[4].kind, WarningKind::WarningsSuppressed(5));
+
+        let out = StoryPassages::from_paths_with_options(&[dir.path()], &options);
+        let warnings = out.get_warnings();
+        assert_eq!(warnings.len(), 5);
+        assert_eq!(warnings[4].kind, WarningKind::WarningsSuppressed(5));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn max_warnings_across_files() -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+        let dir = tempdir()?;
+        for i in 0..3 {
+            let mut file = File::create(dir.path().join(format!("test{}.twee", i)))?;
+            writeln!(file, ":: Passage {}\n[[Open\n[[Shut", i)?;
+        }
+
+        // 6 unclosed links plus missing title, data, and start, with the
+        // warnings of each file capped as it is parsed
+        let out = StoryPassages::from_path(dir.path());
+        assert_eq!(out.get_warnings().len(), 9);
+
+        let options = ParseOptions::new().with_max_warnings(1);
+        for out in [
+            StoryPassages::from_path_with_options(dir.path(), &options),
+            StoryPassages::from_paths_with_options(&[dir.path()], &options),
+        ] {
+            let warnings = out.get_warnings();
+            assert_eq!(warnings.len(), 2);
+            assert_eq!(warnings[0].kind, WarningKind::UnclosedLink);
+            assert_eq!(warnings[1].kind, WarningKind::WarningsSuppressed(8));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn duplicate_across_files() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        std::fs::write(dir.path().join("a.twee"), ":: Start\n[[B]]\n\n:: B\nFirst\n")?;
+        std::fs::write(dir.path().join("b.twee"), ":: B\nSecond\n\n:: C\nEnd\n")?;
+
+        for out in [
+            StoryPassages::from_path(dir.path()),
+            StoryPassages::from_paths(&[dir.path().join("a.twee"), dir.path().join("b.twee")]),
+        ] {
+            let (res, warnings) = out.take();
+            let story = res.ok().unwrap();
+            assert!(warnings
+                .iter()
+                .any(|w| w.kind == WarningKind::DuplicatePassage("B".to_string())));
+            assert!(!warnings
+                .iter()
+                .any(|w| matches!(w.kind, WarningKind::PidGap(_, _))));
+            let mut pids: Vec<(usize, &str)> = story
+                .passages
+                .iter()
+                .filter_map(|(name, passage)| match &passage.content {
+                    PassageContent::Normal(twine) => Some((twine.pid, name.as_str())),
+                    _ => None,
+                })
+                .collect();
+            pids.sort_unstable();
+            assert_eq!(pids, [(1, "Start"), (2, "B"), (3, "C")]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn edited_passage_files() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        std::fs::write(dir.path().join("a.twee"), ":: Start\n[[C]]\n")?;
+        std::fs::write(dir.path().join("b.twee"), ":: C\nMiddle\n\n:: E\nEnd\n")?;
+        let mut story = StoryPassages::from_path(dir.path()).take().0.ok().unwrap();
+        let b = dir.path().join("b.twee");
+
+        assert!(story.rename_passage("C", "D").is_empty());
+        assert_eq!(story.passage_files.get("D"), Some(&b));
+        assert!(!story.passage_files.contains_key("C"));
+
+        assert!(story.remove_passage("E").is_some());
+        assert!(!story.passage_files.contains_key("E"));
+
+        let story = crate::Story::from(story);
+        assert_eq!(story.file_of("D"), Some(b.as_path()));
+        assert!(story.file_of("C").is_none());
+        assert!(story.file_of("E").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn warnings_capped_while_parsing() {
+        let input = ":: Start\n[[A\n[[B\n[[C\n\n:: Quiet {\"tweep-allow\": \"unclosed-link\"}\n[[D\n[[E\n\n:: End\n[[F\n";
+        let options = ParseOptions::new()
+            .with_max_warnings(2)
+            .with_denied_warning(WarningKind::UnclosedLink);
+
+        // Without fatal tracking, nothing can be left out of the parse
+        let context = FullContext::from(None, input.to_string());
+        let (_, warnings) = StoryPassages::parse(context, &options).take();
+        assert_eq!(warnings.len(), 6);
+
+        // The warnings of the passage with a pragma are kept for it to allow,
+        // and the rest past the cap are only counted
+        let (options, fatal) = options.with_fatal_tracking();
+        let context = FullContext::from(None, input.to_string());
+        let (_, warnings) = StoryPassages::parse(context, &options).take();
+        assert_eq!(warnings.len(), 5);
+        assert_eq!(warnings[4].kind, WarningKind::WarningsSuppressed(2));
+        assert!(fatal.load(Ordering::Relaxed));
+
+        let context = FullContext::from(None, input.to_string());
+        let (res, warnings) = StoryPassages::from_context_with_options(context, &options).take();
+        assert!(res.is_ok());
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(warnings[2].kind, WarningKind::WarningsSuppressed(2));
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn denied_past_max_warnings() -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+        let input = ":: A\\[x\nHi\n\n:: B\n[[Nowhere\n";
+        let options = ParseOptions::new().with_denied_warning(WarningKind::UnclosedLink);
+        assert!(StoryPassages::from_string_with_options(input.to_string(), &options).is_err());
+
+        // The denied warning is dropped by the cap, but still fails the parse
+        let options = options.with_max_warnings(1);
+        let (res, warnings) =
+            StoryPassages::from_string_with_options(input.to_string(), &options).take();
+        assert!(res.is_err());
+        assert_eq!(warnings[0].kind, WarningKind::EscapedOpenSquare);
+        assert_eq!(warnings[1].kind, WarningKind::WarningsSuppressed(1));
+
+        let dir = tempdir()?;
+        let mut file = File::create(dir.path().join("test.twee"))?;
+        write!(file, "{}", input)?;
+        assert!(StoryPassages::from_path_with_options(dir.path(), &options).is_err());
+        assert!(StoryPassages::from_paths_with_options(&[dir.path()], &options).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn diagnostic_sink() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::SinkAction;
+        use std::io::Write;
+        use std::sync::{Arc, Mutex};
+        let dir = tempdir()?;
+        for i in 0..3 {
+            let file_path = dir.path().join(format!("test{}.twee", i));
+            let mut file = File::create(file_path)?;
+            writeln!(file, ":: Passage {}\n[[Dead {}]] [[Dead {}]]", i, i, i + 10)?;
+        }
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink_received = received.clone();
+        let options = ParseOptions::new().with_sink(Arc::new(move |warning: Warning| {
+            sink_received.lock().unwrap().push(warning);
+            SinkAction::Continue
+        }));
+        let out = StoryPassages::from_path_with_options(dir.path(), &options);
+        assert!(out.is_ok());
+        assert!(!out.has_warnings());
+        assert_eq!(received.lock().unwrap().len(), 9);
+
+        let count = Arc::new(Mutex::new(0));
+        let sink_count = count.clone();
+        let options = ParseOptions::new().with_sink(Arc::new(move |_: Warning| {
+            *sink_count.lock().unwrap() += 1;
+            SinkAction::Abort
+        }));
+        let out = StoryPassages::from_paths_with_options(&[dir.path()], &options);
+        assert!(out.is_err());
+        assert_eq!(*count.lock().unwrap(), 1);
+        let (res, _) = out.take();
+        let err = res.err().unwrap();
+        #[cfg(feature = "full-context")]
+        let err = err.error_list;
+        assert_eq!(err.errors[0].kind, crate::ErrorKind::ParseAborted);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn dir_options() -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+        let dir = tempdir()?;
+        let mut file = File::create(dir.path().join("test.twee"))?;
+        writeln!(file, ":: Start\n[[Notes]] [[Nested]]")?;
+        let mut file = File::create(dir.path().join("notes.txt"))?;
+        writeln!(file, ":: Notes\nHello")?;
+        let sub_path = dir.path().join("sub");
+        std::fs::create_dir(sub_path.clone())?;
+        let mut file = File::create(sub_path.join("nested.tw"))?;
+        writeln!(file, ":: Nested\nHello")?;
+
+        let story = StoryPassages::from_path(dir.path()).take().0.ok().unwrap();
+        assert_eq!(story.passages.len(), 1);
+
+        let options = ParseOptions::new()
+            .with_extensions(vec!["twee".to_string(), "txt".to_string()])
+            .with_recursive(true);
+        let (res, warnings) = StoryPassages::from_path_with_options(dir.path(), &options).take();
+        let story = res.ok().unwrap();
+        assert!(warnings
+            .iter()
+            .all(|warning| warning.kind != WarningKind::DeadLink("Notes".to_string())));
+        assert!(story.passages.contains_key("Notes"));
+        // nested.tw is found by recursing, but its extension is not in the list
+        assert!(!story.passages.contains_key("Nested"));
+        assert!(story.sources.contains(&SourceFile::new(
+            sub_path.join("nested.tw"),
+            SourceFileKind::WrongExtension
+        )));
+
+        let options = ParseOptions::new().with_recursive(true);
+        let story = StoryPassages::from_path_with_options(dir.path(), &options)
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        assert!(story.passages.contains_key("Nested"));
+        assert!(!story.passages.contains_key("Notes"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn warning_options() {
+        use crate::SinkAction;
+        use std::sync::Arc;
+        let input = ":: Start\n[[Nowhere".to_string();
+
+        let options = ParseOptions::new().with_suppressed_warning(WarningKind::UnclosedLink);
+        let out = StoryPassages::from_string_with_options(input.clone(), &options);
+        assert!(out.is_ok());
+        assert!(!out.has_warnings());
+
+        let options = ParseOptions::new().with_warnings_as_errors(true);
+        let (res, warnings) =
+            StoryPassages::from_string_with_options(input.clone(), &options).take();
+        let err = res.err().unwrap();
+        #[cfg(feature = "full-context")]
+        let err = err.error_list;
+        assert_eq!(err.errors[0].kind, crate::ErrorKind::WarningsAsErrors);
+        assert_eq!(warnings.len(), 1);
+
+        let sink_options = options
+            .clone()
+            .with_sink(Arc::new(|_: Warning| SinkAction::Continue));
+        let out = StoryPassages::from_reader_with_options(input.as_bytes(), &sink_options);
+        assert!(out.is_err());
+        assert!(!out.has_warnings());
+
+        let options = options.with_suppressed_warning(WarningKind::UnclosedLink);
+        let out = StoryPassages::from_string_with_options(input, &options);
+        assert!(out.is_ok());
+
+        let out = StoryPassages::from_string_with_options(":: Start\nHello".to_string(), &options);
+        assert!(out.is_ok());
+    }
+
+    #[test]
+    fn dead_link_suggestions() {
+        let input = ":: Start\n[[forest path]] [[Cave]] [[Mountain]]\n\n:: Forest Path\n\n:: Cove\n\n:: Grave\n"
+            .to_string();
+        let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+        let mut suggestions: Vec<(String, Option<String>)> = story
+            .check()
+            .into_iter()
+            .filter_map(|w| match w.kind {
+                WarningKind::DeadLink(target) => Some((target, w.suggestion)),
+                _ => None,
+            })
+            .collect();
+        suggestions.sort();
+        assert_eq!(
+            suggestions,
+            [
+                ("Cave".to_string(), Some("Cove".to_string())),
+                ("Mountain".to_string(), None),
+                ("forest path".to_string(), Some("Forest Path".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn routing() {
+        use crate::RouteRule;
+        let input = ":: Start\n[[Todo]]\n\n:: Todo [notes]\n\n:: Draft: Cave\n\n:: Draft: Sea [notes]\n\n:: End\n"
+            .to_string();
+        let options = ParseOptions::new()
+            .with_route(RouteRule::Name("Draft:*".to_string()), "drafts".to_string())
+            .with_route(RouteRule::Tag("notes".to_string()), "notes".to_string());
+        let story = StoryPassages::from_string_with_options(input, &options)
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        let mut names: Vec<&String> = story.passages.keys().collect();
+        names.sort();
+        assert_eq!(names, ["End", "Start"]);
+        let bucket = |name: &str| {
+            let mut names: Vec<&String> = story.buckets[name].keys().collect();
+            names.sort();
+            names
+        };
+        assert_eq!(bucket("drafts"), ["Draft: Cave", "Draft: Sea"]);
+        assert_eq!(bucket("notes"), ["Todo"]);
+
+        let warnings = story.check();
+        assert!(!warnings
+            .iter()
+            .any(|w| matches!(w.kind, WarningKind::PidGap(_, _))));
+        assert!(warnings
+            .iter()
+            .any(|w| w.kind == WarningKind::DeadLink("Todo".to_string())));
+    }
+
+    #[test]
+    fn suppressed_categories() {
+        use crate::IssueCategory;
+        let input = ":: Start\n[[ Nowhere ]] [[Unclosed\n\n:: Start\n".to_string();
+        let options = ParseOptions::new()
+            .with_suppressed_category(IssueCategory::Style)
+            .with_suppressed_category(IssueCategory::Syntax);
+        let (_, warnings) = StoryPassages::from_string_with_options(input, &options).take();
+        let kinds: Vec<WarningKind> = warnings.into_iter().map(|w| w.kind).collect();
+        assert_eq!(kinds, [WarningKind::DuplicatePassage("Start".to_string())]);
+    }
+
+    #[test]
+    fn link_problems() {
+        let input = ":: Start\n[[Start]] [[ ]] [[Text|]] [[Next]]\n\n:: Next\n<- [[Back|Start]]\n"
+            .to_string();
+        let (res, warnings) = StoryPassages::from_string(input).take();
+        let kinds: Vec<&WarningKind> = warnings.iter().map(|w| &w.kind).collect();
+        assert_eq!(
+            kinds,
+            [&WarningKind::EmptyLinkTarget, &WarningKind::EmptyLinkTarget]
+        );
+        let start = |w: &Warning| *w.context.as_ref().unwrap().get_start_position();
+        assert_eq!(start(&warnings[1]), Position::abs(2, 17));
+
+        let story = res.ok().unwrap();
+        let self_links: Vec<Warning> = story
+            .check()
+            .into_iter()
+            .filter(|w| matches!(w.kind, WarningKind::SelfLink(_)))
+            .collect();
+        assert_eq!(self_links.len(), 1);
+        assert_eq!(
+            self_links[0].kind,
+            WarningKind::SelfLink("Start".to_string())
+        );
+        assert_eq!(start(&self_links[0]), Position::abs(2, 1));
+    }
+
+    #[test]
+    fn pragmas() {
+        let input = ":: Start {\"tweep-allow\": [\"shouting\", \"no-reachable-ending\"]}\n[[A]]\n<!-- tweep-disable-next-line -->\n[[B]] [[Start]]\n\n:: Other\n/* tweep-disable-next-line W0016 */ [[C]]\n[[D]]\n";
+        let story = StoryPassages::from_string(input.to_string())
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        let options = ParseOptions::new().with_lint(std::sync::Arc::new(
+            |header: &crate::PassageHeader,
+             _: &crate::TwineContent,
+             context: Option<&FullContext>| {
+                let kind = WarningKind::Lint("shouting".to_string(), header.name.clone());
+                vec![Warning::new(kind, context.cloned())]
+            },
+        ));
+        let mut kinds: Vec<String> = story
+            .check_with_options(&options)
+            .into_iter()
+            .map(|w| w.kind.to_string())
+            .filter(|kind| !kind.contains("StoryTitle") && !kind.contains("StoryData"))
+            .collect();
+        kinds.sort();
+        assert_eq!(
+            kinds,
+            [
+                "Dead link to nonexistant passage: A",
+                "Dead link to nonexistant passage: C",
+                "shouting: Other",
+            ]
+        );
+
+        // Pragmas also allow the warnings found while parsing
+        let input = ":: Start
+/* tweep-disable-next-line unclosed-link */
+[[Open
+[[Shut
+";
+        let (res, warnings) = StoryPassages::from_string(input.to_string()).take();
+        assert!(res.is_ok());
+        let lines: Vec<(WarningKind, usize)> = warnings
+            .into_iter()
+            .map(|w| (w.kind, w.context.unwrap().get_start_position().line))
+            .collect();
+        assert_eq!(lines, [(WarningKind::UnclosedLink, 4)]);
+    }
+
+    #[test]
+    fn reachable_endings() {
+        let endings = |input: &str| -> Vec<Warning> {
+            let story = StoryPassages::from_string(input.to_string())
+                .take()
+                .0
+                .ok()
+                .unwrap();
+            let options = ParseOptions::new().with_checked_endings(true);
+            story
+                .check_with_options(&options)
+                .into_iter()
+                .filter(|w| matches!(w.kind, WarningKind::NoReachableEnding(_)))
+                .collect()
+        };
+
+        let warnings =
+            endings(":: Start\n[[A]]\n\n:: A\n[[B]]\n\n:: B\n[[A]] [[Start]]\n\n:: End\nBye\n");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].kind,
+            WarningKind::NoReachableEnding("Start".to_string())
+        );
+        assert_eq!(
+            *warnings[0].context.as_ref().unwrap().get_start_position(),
+            Position::abs(1, 1)
+        );
+
+        // A link to a missing passage is not an edge, so B is an ending
+        assert!(endings(":: Start\n[[B]]\n\n:: B\n[[Nowhere]]\n").is_empty());
+        assert!(endings(":: Start\n[[A]]\n\n:: A\n[[Start]] [[End]]\n\n:: End\n").is_empty());
+
+        // Without a start passage there is nothing to check
+        assert!(endings(":: A\n[[B]]\n\n:: B\n[[A]]\n").is_empty());
+
+        // The check is off by default
+        let story = StoryPassages::from_string(":: Start\n[[Start]]\n".to_string())
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        assert!(!story
+            .check()
+            .iter()
+            .any(|w| matches!(w.kind, WarningKind::NoReachableEnding(_))));
+    }
+
+    #[test]
+    fn duplicate_policies() {
+        use crate::DuplicatePolicy;
+        let input = ":: Start [a] {\"x\": 1}\nFirst [[A]]\n\n:: Start [b] {\"x\": 2, \"y\": 3}\nSecond [[B]]\n"
+            .to_string();
+        let parse = |policy| {
+            let options = ParseOptions::new().with_duplicate_policy(policy);
+            StoryPassages::from_string_with_options(input.clone(), &options).take()
+        };
+        let content = |story: &StoryPassages| match &story.passages["Start"].content {
+            PassageContent::Normal(twine) => twine.content.clone(),
+            _ => panic!("Start is not a normal passage"),
+        };
+        let duplicate = WarningKind::DuplicatePassage("Start".to_string());
+
+        let (res, warnings) = parse(DuplicatePolicy::KeepFirst);
+        assert_eq!(content(&res.ok().unwrap()), "First [[A]]\n");
+        assert_eq!(warnings[0].kind, duplicate);
+        assert_eq!(
+            warnings[0]
+                .context
+                .as_ref()
+                .unwrap()
+                .get_start_position()
+                .line,
+            4
+        );
+
+        let (res, warnings) = parse(DuplicatePolicy::KeepLast);
+        let story = res.ok().unwrap();
+        assert_eq!(content(&story), "Second [[B]]\n");
+        assert_eq!(story.passages["Start"].tags(), &["b"]);
+        assert_eq!(warnings[0].kind, duplicate);
+        assert_eq!(
+            warnings[0]
+                .context
+                .as_ref()
+                .unwrap()
+                .get_start_position()
+                .line,
+            1
+        );
+
+        let (res, warnings) = parse(DuplicatePolicy::Merge);
+        let story = res.ok().unwrap();
+        assert_eq!(content(&story), "First [[A]]\nSecond [[B]]\n");
+        assert_eq!(story.passages["Start"].tags(), &["a", "b"]);
+        assert_eq!(story.passages["Start"].metadata()["x"], 1);
+        assert_eq!(story.passages["Start"].metadata()["y"], 3);
+        assert!(!warnings.iter().any(|w| w.kind == duplicate));
+        let targets: Vec<String> = story
+            .check()
+            .into_iter()
+            .filter_map(|w| match w.kind {
+                WarningKind::DeadLink(target) => Some(target),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(targets, ["A", "B"]);
+
+        let (res, _) = parse(DuplicatePolicy::Error);
+        let err = res.err().unwrap();
+        #[cfg(feature = "full-context")]
+        let err = err.error_list;
+        assert_eq!(
+            err.errors[0].kind,
+            crate::ErrorKind::DuplicatePassage("Start".to_string())
+        );
+    }
+
+    #[test]
+    fn recovery() {
+        let input =
+            ":: Start\n[[Cave]] [[Pit]]\n\n:: Cave [dark\n[[Start]]\n\n::\nOrphan\n\n:: Pit\nEnd\n";
+        let out = StoryPassages::from_string(input.to_string());
+        assert!(out.take().0.is_err());
+
+        let options = ParseOptions::new().with_recovery(true);
+        let story = StoryPassages::from_string_with_options(input.to_string(), &options)
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        let mut names: Vec<&str> = story.passages.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["Cave", "Pit", "Start", "Untitled Passage (line 7)"]);
+        let kinds: Vec<&ErrorKind> = story.recovered_errors.iter().map(|e| &e.kind).collect();
+        assert_eq!(kinds, [&ErrorKind::UnclosedTagBlock, &ErrorKind::EmptyName]);
+
+        let cave = &story.passages["Cave"];
+        assert!(cave.tags().is_empty());
+        assert_eq!(cave.context.get_start_position().line, 4);
+        if let PassageContent::Normal(twine) = &cave.content {
+            assert_eq!(twine.content, "[[Start]]\n");
+            assert_eq!(twine.get_links()[0].target, "Start");
+        }
+
+        let story = crate::Story::from(story);
+        assert_eq!(story.recovered_errors.len(), 2);
+        assert!(story.passages.contains_key("Cave"));
+    }
+
+    #[test]
+    fn merge_with_resolver() {
+        let base = r#":: StoryData
+{"ifid": "abc", "format": "SugarCube", "start": "Start"}
+
+:: Start
+Hello
+
+:: End
+Bye
+"#;
+        let mods = r#":: StoryData
+{"ifid": "def", "start": "Intro"}
+
+:: Start
+Hi
+
+:: End
+So long
+
+:: Intro
+Welcome
+"#;
+        let mut story = StoryPassages::from_string(base.to_string())
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        let mods = StoryPassages::from_string(mods.to_string())
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        let start_pid = match &story.passages["Start"].content {
+            PassageContent::Normal(twine) => twine.pid,
+            _ => panic!("Expected a normal passage"),
+        };
+
+        let mut seen = Vec::new();
+        let warnings = story.merge_from_with(mods, |existing, incoming| {
+            seen.push(existing.header.name.clone());
+            match (&existing.content, &incoming.content) {
+                (PassageContent::StoryData(Some(old)), PassageContent::StoryData(Some(new))) => {
+                    let mut combined = existing.clone();
+                    let mut data = old.clone();
+                    data.start = new.start.clone();
+                    combined.content = PassageContent::StoryData(Some(data));
+                    MergeResolution::Replace(Box::new(combined))
+                }
+                _ if existing.header.name == "Start" => MergeResolution::KeepIncoming,
+                _ => MergeResolution::KeepExisting,
+            }
+        });
+        assert!(warnings.is_empty());
+        seen.sort();
+        assert_eq!(seen, ["End", "Start", "StoryData"]);
+
+        match &story.data.as_ref().unwrap().content {
+            PassageContent::StoryData(Some(data)) => {
+                assert_eq!(data.ifid, "abc");
+                assert_eq!(data.start.as_deref(), Some("Intro"));
+            }
+            _ => panic!("Expected story data"),
+        }
+        let content = |name: &str| match &story.passages[name].content {
+            PassageContent::Normal(twine) => (twine.content.clone(), twine.pid),
+            _ => panic!("Expected a normal passage"),
+        };
+        assert_eq!(content("Start"), ("Hi\n".to_string(), start_pid));
+        assert_eq!(content("End").0, "Bye\n");
+        assert_eq!(content("Intro").0, "Welcome\n");
+        assert_eq!(story.passages.len(), 3);
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn duplicate_policy_across_files() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::DuplicatePolicy;
+        use std::io::Write;
+        let dir = tempdir()?;
+        let mut file = File::create(dir.path().join("a.twee"))?;
+        writeln!(file, ":: Start\nFrom a")?;
+        let mut file = File::create(dir.path().join("b.twee"))?;
+        writeln!(file, ":: Start\nFrom b")?;
+
+        let options = ParseOptions::new().with_duplicate_policy(DuplicatePolicy::KeepLast);
+        let story = StoryPassages::from_path_with_options(dir.path(), &options)
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        let start = &story.passages["Start"];
+        assert_eq!(start.context.get_file_name().as_deref(), Some("b.twee"));
+        assert_eq!(story.passage_files["Start"], dir.path().join("b.twee"));
+
+        let options = options.with_duplicate_policy(DuplicatePolicy::Error);
+        let out = StoryPassages::from_path_with_options(dir.path(), &options);
+        let err = out.take().0.err().unwrap();
+        #[cfg(feature = "full-context")]
+        let err = err.error_list;
+        assert_eq!(
+            err.errors[0].kind,
+            crate::ErrorKind::DuplicatePassage("Start".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn metrics() -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+        let dir = tempdir()?;
+        let mut file = File::create(dir.path().join("a.twee"))?;
+        writeln!(file, ":: Start\n[[B]] [[C]]")?;
+        let mut file = File::create(dir.path().join("b.twee"))?;
+        writeln!(file, ":: B\n[[Start]]\n\n:: C")?;
+        File::create(dir.path().join("notes.txt"))?;
+
+        let out = StoryPassages::from_path(dir.path());
+        assert!(out.get_metrics().is_none());
+
+        let options = ParseOptions::new().with_metrics(true);
+        let out = StoryPassages::from_path_with_options(dir.path(), &options);
+        let metrics = *out.get_metrics().unwrap();
+        assert_eq!(metrics.files, 2);
+        assert_eq!(metrics.passages, 3);
+        assert_eq!(metrics.links, 3);
+        assert_eq!(metrics.bytes, 42);
+
+        let path = dir.path().join("b.twee");
+        let out = StoryPassages::from_paths_with_options(&[path], &options);
+        let metrics = *out.get_metrics().unwrap();
+        assert_eq!(metrics.files, 1);
+        assert_eq!(metrics.passages, 2);
+        assert_eq!(metrics.bytes, 21);
+
+        let options = options.with_warnings_as_errors(true);
+        let out = StoryPassages::from_path_with_options(dir.path(), &options);
+        assert!(out.is_err());
+        assert_eq!(out.get_metrics().unwrap().files, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "macro-links")]
+    fn macro_links() {
+        let input = |format: &str| {
+            format!(
+                r#":: StoryData
+{{"ifid": "abc", "format": "{}"}}
+
+:: Start
+<<goto "End">> <<link "Leave" "Nowhere">><</link>>
+(link-goto: "Elsewhere")
+
+:: End
+"#,
+                format
+            )
+        };
+        let dead_links = |format: &str| -> Vec<String> {
+            let story = StoryPassages::from_string(input(format))
+                .take()
+                .0
+                .ok()
+                .unwrap();
+            story
+                .check()
+                .into_iter()
+                .filter_map(|w| match w.kind {
+                    WarningKind::DeadLink(target) => Some(target),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(dead_links("SugarCube"), ["Nowhere"]);
+        assert_eq!(dead_links("Harlowe"), ["Elsewhere"]);
+        assert!(dead_links("Snowman").is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "macro-links")]
+    fn undefined_widgets() {
+        let input = r#":: StoryData
+{"ifid": "abc", "format": "SugarCube"}
+
+:: Start
+<<set $x to 1>><<= $x>><<greet>><<gret "Ann">><<dice>>
+/* <<hidden>> */<<shout>><</shout>>
+
+:: Widgets [widget]
+<<widget "greet">>Hi<</widget>>
+<<widget "wave">>Bye<</widget>>
+
+:: Macros [script]
+Macro.add('dice', {});
+Macro.add(["shout", 'yell'], {});
+"#
+        .to_string();
+        let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+        let warnings: Vec<Warning> = story
+            .check()
+            .into_iter()
+            .filter(|w| matches!(w.kind, WarningKind::UndefinedWidget(_, _)))
+            .collect();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].kind,
+            WarningKind::UndefinedWidget(
+                "gret".to_string(),
+                vec!["greet".to_string(), "wave".to_string()]
+            )
+        );
+        assert_eq!(
+            warnings[0].kind.to_string(),
+            "Undefined widget <<gret>>, known widgets: greet, wave"
+        );
+        assert_eq!(warnings[0].get_suggestion(), Some("greet"));
+        let context = warnings[0].context.as_ref().unwrap();
+        assert_eq!(context.get_start_position().line, 5);
+        assert_eq!(context.get_start_position().column, 33);
+
+        let story: crate::Story = story.into();
+        assert_eq!(
+            story
+                .validate()
+                .iter()
+                .filter(|w| w.kind.code() == "W0043")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "macro-links")]
+    fn conditions() {
+        let input = |format: &str| {
+            format!(
+                r#":: StoryData
+{{"ifid": "abc", "format": "{}"}}
+
+:: Start
+<<if $a = 1>><<elseif ($b == "(" >>
+<<if $c to 2 and $total >= 3 and $d !== 'x=y'>><<set $e to 1>>
+(if: $hp > (2)])[x] (else-if: $f = 1)[y] (if: $g is 'a b')[z]
+"#,
+                format
+            )
+        };
+        let problems = |format: &str| -> Vec<(&str, usize, usize)> {
+            let story = StoryPassages::from_string(input(format))
+                .take()
+                .0
+                .ok()
+                .unwrap();
+            story
+                .check()
+                .into_iter()
+                .filter(|w| w.kind.code() == "W0044" || w.kind.code() == "W0045")
+                .map(|w| {
+                    let position = *w.context.as_ref().unwrap().get_start_position();
+                    (w.kind.code(), position.line, position.column)
+                })
+                .collect()
+        };
+        assert_eq!(
+            problems("SugarCube"),
+            [("W0045", 5, 9), ("W0044", 5, 23), ("W0045", 6, 9)]
+        );
+        assert_eq!(problems("Harlowe"), [("W0044", 7, 15), ("W0045", 7, 34)]);
+        assert!(problems("Snowman").is_empty());
+    }
+
+    #[test]
+    fn pid_problems() {
+        let input = ":: Start\n\n:: A\n\n:: B\n\n:: C\n".to_string();
+        let mut story = StoryPassages::from_string(input).take().0.ok().unwrap();
+        let is_pid_warning = |w: &Warning| {
+            matches!(
+                w.kind,
+                WarningKind::DuplicatePid(_) | WarningKind::PidGap(_, _)
+            )
+        };
+        assert!(!story.check().iter().any(is_pid_warning));
+
+        for (name, pid) in [("Start", 1), ("A", 1), ("B", 4), ("C", 5)].iter() {
+            if let PassageContent::Normal(twine) =
+                &mut story.passages.get_mut(*name).unwrap().content
+            {
+                twine.pid = *pid;
+            }
+        }
+        let warnings: Vec<Warning> = story.check().into_iter().filter(is_pid_warning).collect();
+        let context = |name: &str| story.passages[name].context.clone();
+        assert_eq!(
+            warnings,
+            [
+                Warning::new(WarningKind::DuplicatePid(1), Some(context("Start")))
+                    .with_referent(context("A")),
+                Warning::new(WarningKind::PidGap(2, 3), Some(context("B"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn comments() {
+        let input =
+            ":: Start\n[[A]] /* [[Gone]]\n[[Also gone]] */ <!-- [[Old]] -->\n\n:: A\n".to_string();
+        let out = StoryPassages::from_string(input.clone());
+        assert!(!out.has_warnings());
+        let story = out.take().0.ok().unwrap();
+        assert!(!story
+            .check()
+            .iter()
+            .any(|w| matches!(w.kind, WarningKind::DeadLink(_))));
+
+        let options = ParseOptions::new().with_stripped_comments(true);
+        let story = StoryPassages::from_string_with_options(input, &options)
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        match &story.passages["Start"].content {
+            PassageContent::Normal(twine) => {
+                assert_eq!(twine.content, "[[A]]  \n");
+                assert_eq!(twine.get_comments().len(), 2);
+            }
+            _ => panic!("Expected normal passage"),
+        }
+    }
+
+    #[test]
+    fn name_matching() {
+        let input = ":: Start\n[[Cafe\u{301}]] [[The  End]]\n\n:: Caf\u{e9}\n\n:: The End\n\n:: Cafe\u{301} \n".to_string();
+        let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+        let dead_links = |warnings: &[Warning]| -> Vec<String> {
+            let mut targets: Vec<String> = warnings
+                .iter()
+                .filter_map(|w| match &w.kind {
+                    WarningKind::DeadLink(target) => Some(target.clone()),
+                    _ => None,
+                })
+                .collect();
+            targets.sort();
+            targets
+        };
+
+        let warnings = story.check();
+        assert_eq!(dead_links(&warnings), ["The  End"]);
+        let conflicts: Vec<&WarningKind> = warnings
+            .iter()
+            .map(|w| &w.kind)
+            .filter(|kind| matches!(kind, WarningKind::NormalizationConflict(_, _)))
+            .collect();
+        assert_eq!(
+            conflicts,
+            [&WarningKind::NormalizationConflict(
+                "Caf\u{e9}".to_string(),
+                "Cafe\u{301}".to_string()
+            )]
+        );
+
+        let options = ParseOptions::new().with_collapsed_whitespace(true);
+        assert!(dead_links(&story.check_with_options(&options)).is_empty());
+
+        let mut story = story;
+        story.remove_passage("Cafe\u{301}");
+        assert_eq!(dead_links(&story.check()), ["Cafe\u{301}", "The  End"]);
+        let options = options.with_normalized_names(true);
+        assert!(dead_links(&story.check_with_options(&options)).is_empty());
+    }
+
+    #[test]
+    fn severities() {
+        use crate::{Severity, SinkAction};
+        use std::sync::Arc;
+        let input = ":: Start\\[\n[[Nowhere".to_string();
+
+        // Escaped characters are only informational
+        let options = ParseOptions::new()
+            .with_warnings_as_errors(true)
+            .with_suppressed_warning(WarningKind::UnclosedLink);
+        let (res, warnings) =
+            StoryPassages::from_string_with_options(input.clone(), &options).take();
+        assert!(res.is_ok());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].severity(), Severity::Info);
+
+        let options = ParseOptions::new().with_denied_warning(WarningKind::UnclosedLink);
+        assert!(StoryPassages::from_string_with_options(input.clone(), &options).is_err());
+        let sink_options = options
+            .clone()
+            .with_sink(Arc::new(|_: Warning| SinkAction::Continue));
+        assert!(StoryPassages::from_string_with_options(input.clone(), &sink_options).is_err());
+
+        let options = options
+            .with_warnings_as_errors(true)
+            .with_severity(WarningKind::UnclosedLink, Severity::Info);
+        assert_eq!(options.severity(&WarningKind::UnclosedLink), Severity::Info);
+        assert!(StoryPassages::from_string_with_options(input, &options).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn directory_order() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::DirectoryOrder;
+        use std::io::Write;
+        let dir = tempdir()?;
+        for name in ["b.twee", "c.tw", "a.twee"].iter() {
+            let mut file = File::create(dir.path().join(name))?;
+            writeln!(file, ":: StoryTitle\n{}", name)?;
+        }
+
+        let file_names = |story: &StoryPassages| -> Vec<String> {
+            story
+                .sources
+                .iter()
+                .map(|source| {
+                    source
+                        .path
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string()
+                })
+                .collect()
+        };
+
+        let (res, warnings) = StoryPassages::from_path(dir.path()).take();
+        let story = res.ok().unwrap();
+        assert_eq!(file_names(&story), ["a.twee", "b.twee", "c.tw"]);
+        // The title from a.twee is kept, and the others are duplicates
+        let duplicates: Vec<&Option<String>> = warnings
+            .iter()
+            .filter(|w| w.kind == WarningKind::DuplicateStoryTitle)
+            .map(|w| w.context.as_ref().unwrap().get_file_name())
+            .collect();
+        assert_eq!(
+            duplicates,
+            [&Some("b.twee".to_string()), &Some("c.tw".to_string())]
+        );
+
+        let options =
+            ParseOptions::new().with_directory_order(DirectoryOrder::Custom(|a, b| b.cmp(a)));
+        let story = StoryPassages::from_path_with_options(dir.path(), &options)
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        assert_eq!(file_names(&story), ["c.tw", "b.twee", "a.twee"]);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn ignore_file() -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+        let dir = tempdir()?;
+        let mut file = File::create(dir.path().join(".twee-ignore"))?;
+        writeln!(file, "scratch*.twee\ndrafts/\n!drafts/keep.twee")?;
+        for name in ["story.twee", "scratch.twee", "scratch2.twee"].iter() {
+            let mut file = File::create(dir.path().join(name))?;
+            writeln!(file, ":: {}\nHello", name)?;
+        }
+        let sub_path = dir.path().join("sub");
+        std::fs::create_dir(sub_path.clone())?;
+        let mut file = File::create(sub_path.join(".twee-ignore"))?;
+        writeln!(file, "!scratch3.twee")?;
+        for name in ["nested.twee", "scratch3.twee", "scratch4.twee"].iter() {
+            let mut file = File::create(sub_path.join(name))?;
+            writeln!(file, ":: {}\nHello", name)?;
+        }
+        std::fs::create_dir(dir.path().join("drafts"))?;
+
+        let options = ParseOptions::new().with_recursive(true);
+        let (res, warnings) = StoryPassages::from_path_with_options(dir.path(), &options).take();
+        let story = res.ok().unwrap();
+        let mut names: Vec<&String> = story.passages.keys().collect();
+        names.sort();
+        assert_eq!(names, ["nested.twee", "scratch3.twee", "story.twee"]);
+        assert!(story.sources.contains(&SourceFile::new(
+            dir.path().join("scratch.twee"),
+            SourceFileKind::Ignored
+        )));
+        assert!(story.sources.contains(&SourceFile::new(
+            sub_path.join("scratch4.twee"),
+            SourceFileKind::Ignored
+        )));
+        assert!(story.sources.contains(&SourceFile::new(
+            dir.path().join("drafts"),
+            SourceFileKind::Ignored
+        )));
+        assert!(warnings
+            .iter()
+            .all(|w| !matches!(w.kind, WarningKind::BadIgnoreFile(_, _))));
+
+        let mut file = File::create(dir.path().join(".twee-ignore"))?;
+        writeln!(file, "bad{{")?;
+        let (res, warnings) = StoryPassages::from_path(dir.path()).take();
+        assert!(res.is_ok());
+        assert!(warnings
+            .iter()
+            .any(|w| matches!(w.kind, WarningKind::BadIgnoreFile(_, _))));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn excluded_link() -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+        let dir = tempdir()?;
+        let mut file = File::create(dir.path().join("story.twee"))?;
+        writeln!(file, ":: Start\n[[Bonus]] [[Secret]] [[Nowhere]]")?;
+        let mut file = File::create(dir.path().join("notes.txt"))?;
+        writeln!(file, ":: Bonus [tag] {{\"position\":\"0,0\"}}\nHello")?;
+        let mut file = File::create(dir.path().join("secret.twee"))?;
+        writeln!(file, ":: Secret\nHello")?;
+        let mut file = File::create(dir.path().join(".twee-ignore"))?;
+        writeln!(file, "secret.twee")?;
+
+        let (res, warnings) = StoryPassages::from_path(dir.path()).take();
+        assert!(res.is_ok());
+        let mut links: Vec<&WarningKind> = warnings
+            .iter()
+            .map(|w| &w.kind)
+            .filter(|kind| {
+                matches!(
+                    kind,
+                    WarningKind::DeadLink(_) | WarningKind::ExcludedLink(_, _)
+                )
+            })
+            .collect();
+        links.sort_by_key(|kind| format!("{}", kind));
+        assert_eq!(
+            links,
+            [
+                &WarningKind::DeadLink("Nowhere".to_string()),
+                &WarningKind::ExcludedLink(
+                    "Bonus".to_string(),
+                    dir.path().join("notes.txt").to_string_lossy().to_string()
+                ),
+                &WarningKind::ExcludedLink(
+                    "Secret".to_string(),
+                    dir.path().join("secret.twee").to_string_lossy().to_string()
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn includes() -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+        let dir = tempdir()?;
+        let main_path = dir.path().join("main.twee");
+        let mut file = File::create(main_path.clone())?;
+        writeln!(
+            file,
+            ":: Start\n[[One]] [[Two]]\n\n:: StoryIncludes\nchapters/one.twee\n\ntwo.tw"
+        )?;
+        std::fs::create_dir(dir.path().join("chapters"))?;
+        let mut file = File::create(dir.path().join("chapters").join("one.twee"))?;
+        writeln!(file, ":: One\nHello\n\n:: StoryIncludes\n../main.twee")?;
+        let mut file = File::create(dir.path().join("two.tw"))?;
+        writeln!(file, ":: Two\nHello")?;
+
+        let story = StoryPassages::from_path(main_path.clone())
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        assert!(story.passages.contains_key("StoryIncludes"));
+        assert!(!story.passages.contains_key("One"));
+
+        let options = ParseOptions::new().with_includes(true);
+        let (res, warnings) = StoryPassages::from_path_with_options(main_path, &options).take();
+        let story = res.ok().unwrap();
+        let mut names: Vec<&String> = story.passages.keys().collect();
+        names.sort();
+        assert_eq!(names, ["One", "Start", "Two"]);
+        assert_eq!(story.sources.len(), 3);
+        let cycles: Vec<&WarningKind> = warnings
+            .iter()
+            .map(|w| &w.kind)
+            .filter(|kind| matches!(kind, WarningKind::IncludeCycle(_)))
+            .collect();
+        assert_eq!(
+            cycles,
+            [&WarningKind::IncludeCycle("../main.twee".to_string())]
+        );
+        assert!(warnings
+            .iter()
+            .all(|w| !matches!(w.kind, WarningKind::DeadLink(_))));
+        #[cfg(feature = "full-context")]
+        assert_eq!(story.code_map.contexts.len(), 3);
+
+        Ok(())
+    }
 
+    #[test]
+    #[cfg(feature = "fs")]
+    #[cfg(feature = "parallel")]
+    fn parallel_dir_input() -> Result<(), Box<dyn std::error::Error>> {
         use std::io::Write;
         let dir = tempdir()?;
-        let file_path_one = dir.path().join("test.twee");
-        let mut file_one = File::create(file_path_one.clone())?;
-        write!(file_one, "{}", input_one.clone())?;
-        let file_path_two = dir.path().join("test2.tw");
-        let mut file_two = File::create(file_path_two.clone())?;
-        write!(file_two, "{}", input_two.clone())?;
-
-        let paths = vec![file_path_one, file_path_two];
-        let out = StoryPassages::from_paths(&paths);
-        assert_eq!(out.has_warnings(), true);
-        let (res, warnings) = out.take();
-        assert_eq!(warnings.len(), 2);
-        assert_eq!(res.is_ok(), true);
-        let story = res.ok().unwrap();
-        assert_eq!(story.title.is_some(), true);
-        let title_content = story.title.unwrap().content;
-        if let PassageContent::StoryTitle(title) = title_content {
-            assert_eq!(title.title, "Test Story");
-        } else {
-            panic!("Expected StoryTitle");
+        for i in 0..8 {
+            let file_path = dir.path().join(format!("test{}.twee", i));
+            let mut file = File::create(file_path)?;
+            writeln!(file, ":: Passage {}\n[[Passage {}]] [[Dead {}]]", i, (i + 1) % 8, i)?;
+            writeln!(file, ":: StoryTitle\nTitle {}", i)?;
         }
 
-        let context = FullContext::from(Some("test.twee".to_string()), input_one);
-        assert!(warnings.contains(&{
-            let warning = Warning::new(
-                WarningKind::EscapedOpenCurly,
-                Some(context.subcontext(Position::rel(10, 6)..=Position::rel(10, 7))),
-            );
-            warning
-        }));
-
-        let context = FullContext::from(Some("test2.tw".to_string()), input_two);
-        assert!(warnings.contains(&{
-            let warning = Warning::new(
-                WarningKind::EscapedCloseSquare,
-                Some(context.subcontext(Position::rel(9, 16)..=Position::rel(9, 17))),
-            );
-            warning
-        }));
+        let (serial, serial_warnings) = StoryPassages::from_path(dir.path()).take();
+        let options = ParseOptions::new().with_parallel(true);
+        let (parallel, parallel_warnings) =
+            StoryPassages::from_path_with_options(dir.path(), &options).take();
+        let serial = serial.ok().unwrap();
+        let parallel = parallel.ok().unwrap();
+
+        assert_eq!(parallel.passages.len(), 8);
+        // Dead link warnings are generated in passage map order, so only the
+        // contents of the warning lists can be compared
+        assert_eq!(parallel_warnings.len(), serial_warnings.len());
+        assert!(parallel_warnings.iter().all(|w| serial_warnings.contains(w)));
+        assert_eq!(parallel.sources, serial.sources);
+        assert_eq!(
+            parallel.title.unwrap().context,
+            serial.title.unwrap().context
+        );
 
         Ok(())
     }
 
     #[test]
+    #[cfg(feature = "fs")]
     fn dir_input_duplicates() -> Result<(), Box<dyn std::error::Error>> {
         let input_one = r#":: Start
 At the start, link to [[A passage]]
@@ -839,14 +3845,16 @@ A Test Story
         let (res, warnings) = out.take();
         assert_eq!(warnings.len(), 2);
 
-        // We can't know the parse order, so we can't know anything other than
-        // the type of warnings we expect
+        // test.twee sorts before test2.tw, so the duplicates are in test2.tw
         assert!(warnings
             .iter()
             .any(|w| WarningKind::DuplicateStoryData == w.kind));
         assert!(warnings
             .iter()
             .any(|w| WarningKind::DuplicateStoryTitle == w.kind));
+        assert!(warnings
+            .iter()
+            .all(|w| w.context.as_ref().unwrap().get_file_name() == &Some("test2.tw".to_string())));
 
         assert_eq!(res.is_ok(), true);
 
@@ -906,6 +3914,71 @@ Link to [[A passage]]
         );
     }
 
+    #[test]
+    fn special_passages() {
+        let input = r#":: StoryAuthor
+Jane Doe
+
+:: StorySubtitle
+A Subtitle
+
+:: StoryMenu
+[[Start]]
+
+:: StorySettings
+undo:on
+
+:: Start
+Hello
+
+:: StoryAuthor
+John Doe
+"#
+        .to_string();
+        let context = FullContext::from(None, input);
+        let (res, warnings) = StoryPassages::from_context(context.clone()).take();
+        let mut story = res.ok().unwrap();
+        assert!(!story.passages.contains_key("StoryAuthor"));
+        assert_eq!(
+            warnings,
+            [Warning::new(
+                WarningKind::DuplicateStoryAuthor,
+                Some(context.subcontext(Position::rel(16, 1)..=Position::abs(17, 8)))
+            )
+            .with_referent(story.author.as_ref().unwrap().context.clone())]
+        );
+        let content = |passage: &Option<Passage>| match &passage.as_ref().unwrap().content {
+            PassageContent::StoryAuthor(c)
+            | PassageContent::StorySubtitle(c)
+            | PassageContent::StoryMenu(c)
+            | PassageContent::StorySettings(c) => c.content.clone(),
+            _ => panic!("Expected a special passage"),
+        };
+        assert_eq!(content(&story.author), "Jane Doe");
+        assert_eq!(content(&story.subtitle), "A Subtitle");
+        assert_eq!(content(&story.menu), "[[Start]]");
+        assert_eq!(content(&story.settings), "undo:on");
+
+        let other = StoryPassages::from_string(":: StoryMenu\nOther".to_string())
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        let warnings = story.merge_from(other);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::DuplicateStoryMenu);
+        assert_eq!(content(&story.menu), "[[Start]]");
+
+        let mut other = StoryPassages::from_string(":: StorySubtitle\nOther".to_string())
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        let warnings = story.add_passage(other.subtitle.take().unwrap());
+        assert_eq!(warnings[0].kind, WarningKind::DuplicateStorySubtitle);
+        assert_eq!(content(&story.subtitle), "A Subtitle");
+    }
+
     #[test]
     fn duplicate_story_title() {
         let input = r#":: A passage
@@ -1016,6 +4089,185 @@ Test Story
         assert_eq!(warnings, expected);
     }
 
+    #[test]
+    fn crlf() {
+        let input = ":: StoryTitle\r\nCRLF Story\r\n\r\n:: Start [a b]\r\nGo [[Next]]\r\n[[Bad\r\n\r\n:: Next\r\nBye\r\n"
+            .to_string();
+        let (res, warnings) = StoryPassages::from_string(input).take();
+        let story = res.ok().unwrap();
+        match story.title.as_ref().unwrap().content {
+            PassageContent::StoryTitle(ref title) => assert_eq!(title.title, "CRLF Story"),
+            _ => panic!("Expected StoryTitle"),
+        }
+        let names: crate::FastHashSet<&str> = story.passages.keys().map(String::as_str).collect();
+        assert_eq!(names, ["Start", "Next"].iter().copied().collect());
+
+        let start = &story.passages["Start"];
+        assert_eq!(start.header.tags, ["a", "b"]);
+        assert_eq!(*start.context.get_end_position(), Position::abs(6, 5));
+        match &start.content {
+            PassageContent::Normal(twine) => {
+                assert_eq!(twine.content, "Go [[Next]]\n[[Bad\n");
+                assert_eq!(twine.get_links()[0].target, "Next");
+            }
+            _ => panic!("Expected a normal passage"),
+        }
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::UnclosedLink);
+        #[cfg(feature = "full-context")]
+        {
+            let context = warnings[0].context.as_ref().unwrap();
+            assert_eq!(context.get_contents(), "[[Bad");
+            assert_eq!(*context.get_end_position(), Position::abs(6, 5));
+        }
+    }
+
+    #[test]
+    fn max_complexity() {
+        let input = r#":: StoryTitle
+Complex
+
+:: StoryData
+{"ifid": "ABC", "format": "SugarCube"}
+
+:: Start
+[[A]] [[B]]
+
+:: A
+<<if $x>>[[B]]<<else>><<goto "B">><</if>>
+
+:: B
+The end
+"#
+        .to_string();
+        let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+        let options = ParseOptions::new().with_max_complexity(2);
+        let warnings = story.check_with_options(&options);
+        #[cfg(feature = "macro-links")]
+        {
+            assert_eq!(warnings.len(), 1);
+            assert_eq!(
+                warnings[0].kind,
+                WarningKind::ComplexPassage("A".to_string(), 3, 2)
+            );
+            assert_eq!(
+                *warnings[0].context.as_ref().unwrap().get_start_position(),
+                Position::abs(10, 1)
+            );
+        }
+        #[cfg(not(feature = "macro-links"))]
+        assert!(warnings.is_empty());
+        assert!(story.check().is_empty());
+    }
+
+    #[test]
+    fn flagged_words() {
+        let input = r#":: StoryData
+{"ifid": "abc", "format": "SugarCube"}
+
+:: Start
+Some text <<set $todo to 1>> [[TODO list]]
+<!-- lorem --> FIXME_1 and Fixme.
+Lorem  ipsum, lorem ipsum dolor
+
+:: TODO list
+Done
+"#
+        .to_string();
+        let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+        let options = ParseOptions::new().with_flagged_words(vec![
+            "todo".to_string(),
+            "FIXME".to_string(),
+            "lorem ipsum".to_string(),
+        ]);
+        let flagged: Vec<(String, usize, usize)> = story
+            .check_with_options(&options)
+            .into_iter()
+            .filter(|w| matches!(w.kind, WarningKind::FlaggedWord(_)))
+            .map(|w| {
+                let start = *w.context.unwrap().get_start_position();
+                (w.kind.to_string(), start.line, start.column)
+            })
+            .collect();
+        let flag = |word: &str| format!("Flagged word in passage text: {}", word);
+        #[cfg(feature = "macro-links")]
+        let expected = vec![(flag("FIXME"), 6, 28), (flag("lorem ipsum"), 7, 15)];
+        #[cfg(not(feature = "macro-links"))]
+        let expected = vec![
+            (flag("todo"), 5, 18),
+            (flag("FIXME"), 6, 28),
+            (flag("lorem ipsum"), 7, 15),
+        ];
+        assert_eq!(flagged, expected);
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn languages() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        std::fs::create_dir(dir.path().join("en"))?;
+        std::fs::create_dir(dir.path().join("de"))?;
+        std::fs::write(dir.path().join("main.tw"), ":: Start\n[[Hall_en]]\n")?;
+        std::fs::write(
+            dir.path().join("en").join("hall.tw"),
+            ":: Hall_en\n[[Hall_de]] [[Start]]\n\n:: Door_fr\n",
+        )?;
+        std::fs::write(
+            dir.path().join("de").join("hall.tw"),
+            ":: Hall_de\n[[Keller]]\n\n:: Keller\n",
+        )?;
+
+        let options = ParseOptions::new()
+            .with_recursive(true)
+            .with_languages(vec!["en".to_string(), "de".to_string()]);
+        let story = StoryPassages::from_path_with_options(dir.path(), &options)
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        let warnings: Vec<(WarningKind, usize, usize)> = story
+            .check_with_options(&options)
+            .into_iter()
+            .filter(|w| {
+                matches!(
+                    w.kind,
+                    WarningKind::MissingTranslation(_, _) | WarningKind::CrossLanguageLink(_, _)
+                )
+            })
+            .map(|w| {
+                let position = *w.context.unwrap().get_start_position();
+                (w.kind, position.line, position.column)
+            })
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                (
+                    WarningKind::MissingTranslation("Door_fr".to_string(), "de".to_string()),
+                    4,
+                    1
+                ),
+                (
+                    WarningKind::MissingTranslation("Keller".to_string(), "en".to_string()),
+                    4,
+                    1
+                ),
+                (
+                    WarningKind::CrossLanguageLink("Hall_de".to_string(), "en".to_string()),
+                    2,
+                    1
+                ),
+            ]
+        );
+
+        assert!(story
+            .check()
+            .iter()
+            .all(|w| !matches!(w.kind, WarningKind::MissingTranslation(_, _))));
+        Ok(())
+    }
+
     #[test]
     fn alt_start() {
         let input = r#":: Alt Start
@@ -1063,12 +4315,12 @@ body {font-size: 1.5em;}
 {
     "ifid": "2B68ECD6-348F-4CF5-96F8-549A512A8128",
     "format": "Harlowe",
-    "formatVersion": "2.1.0",
-    "zoom": 100
+    "format-version": "2.1.0",
+    "zoom": 1
 }"#
         .to_string();
         let context = FullContext::from(None, input);
-        let out = StoryPassages::parse(context);
+        let out = StoryPassages::parse(context, &ParseOptions::default());
         assert_eq!(out.has_warnings(), false);
     }
 
@@ -1158,10 +4410,487 @@ Test Story
         assert_eq!(story.get_start_passage_name(), None);
     }
 
+    #[test]
+    fn reader_input() {
+        let input = ":: Start\nLink to [[Another passage]]\n\n:: Another passage\nBlah\n";
+        let out = StoryPassages::from_reader(input.as_bytes());
+        let (res, _) = out.take();
+        let story = res.ok().unwrap();
+        assert_eq!(story.passages.len(), 2);
+        assert_eq!(story.get_start_passage_name(), Some("Start"));
+
+        // Invalid UTF-8 can't be decoded
+        let input: &[u8] = &[0xfd, 0xfe, 0xff];
+        let err = StoryPassages::from_reader(input).take().0.err().unwrap();
+        #[cfg(feature = "full-context")]
+        let err = err.error_list;
+        assert_eq!(
+            err.errors[0].kind,
+            ErrorKind::InvalidEncoding("invalid byte 0xFD".to_string())
+        );
+
+        // A failed read is a read error
+        struct Broken;
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "broken",
+                ))
+            }
+        }
+        let out = StoryPassages::from_reader(Broken);
+        assert!(out.is_err());
+        let (res, _) = out.take();
+        let err = res.err().unwrap();
+        #[cfg(feature = "full-context")]
+        let err = err.error_list;
+        assert!(matches!(err.errors[0].kind, crate::ErrorKind::ReadError(_)));
+
+        // The I/O error is kept as the source of the error
+        use std::error::Error as _;
+        let source = err.source().unwrap().source().unwrap();
+        let io_error = source.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(io_error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn html_input() {
+        for input in [
+            "\u{feff}\n<!DOCTYPE html>\n<html><body></body></html>\n",
+            "<tw-storydata name=\"Story\">\n<tw-passagedata pid=\"1\" name=\"Start\">Hi</tw-passagedata>\n</tw-storydata>",
+        ] {
+            let (res, warnings) = StoryPassages::from_string(input.to_string()).take();
+            let err = res.err().unwrap();
+            #[cfg(feature = "full-context")]
+            let err = err.error_list;
+            assert_eq!(err.errors.len(), 1);
+            assert_eq!(err.errors[0].kind, ErrorKind::HtmlInput);
+            assert!(warnings.is_empty());
+        }
+        let (res, _) = StoryPassages::from_string("\n<html>".to_string()).take();
+        let err = res.err().unwrap();
+        #[cfg(feature = "full-context")]
+        let err = err.error_list;
+        let start = *err.errors[0].context.as_ref().unwrap().get_start_position();
+        assert_eq!(start, Position::abs(2, 1));
+
+        // Twee code that happens to contain HTML is parsed as usual
+        let input = ":: Start\n<html>\n".to_string();
+        assert!(StoryPassages::from_string(input).take().0.is_ok());
+    }
+
+    #[test]
+    fn check_passes() {
+        let input = ":: StoryData\n{\"ifid\": \"abc\", \"start\": \"Intro\"}\n\n:: Start\n[[Start]] [[Nowhere]]\n/* tweep-disable-next-line */\n[[Gone]]\n";
+        let story = StoryPassages::from_string(input.to_string())
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        let kinds = |warnings: Vec<Warning>| -> Vec<WarningKind> {
+            warnings.into_iter().map(|w| w.kind).collect()
+        };
+        assert_eq!(
+            kinds(story.check_special_passages()),
+            [WarningKind::MissingStoryTitle]
+        );
+        assert_eq!(
+            kinds(story.check_start()),
+            [WarningKind::DeadStartPassage("Intro".to_string())]
+        );
+        let links = kinds(story.check_dead_links());
+        assert_eq!(links.len(), 2);
+        assert!(links.contains(&WarningKind::SelfLink("Start".to_string())));
+        assert!(links.contains(&WarningKind::DeadLink("Nowhere".to_string())));
+
+        // Each pass finds the same warnings as the full check
+        let all = kinds(story.check());
+        assert!(links.iter().all(|kind| all.contains(kind)));
+    }
+
+    #[test]
+    fn split_stories() {
+        let input = ":: Break\nIgnored\n\n:: StoryTitle\nOne\n\n:: Start\n[[Two]]\n\n:: Break [x]\n:: Break\n\n:: StoryTitle\nTwo\n\n:: Start\n[[Three]]\n\n:: Break\n";
+        let options = ParseOptions::new().with_story_separator("Break".to_string());
+        let outputs = StoryPassages::split_from_string_with_options(input.to_string(), &options);
+        let stories: Vec<StoryPassages> = outputs
+            .into_iter()
+            .map(|out| {
+                let (res, warnings) = out.take();
+                assert!(warnings.is_empty());
+                res.ok().unwrap()
+            })
+            .collect();
+        assert_eq!(stories.len(), 2);
+        assert_eq!(stories[0].passages.len(), 1);
+        assert!(stories[1].title.is_some());
+        let start = &stories[1].passages["Start"];
+        assert_eq!(*start.context.get_start_position(), Position::abs(16, 1));
+        let dead: Vec<WarningKind> = stories
+            .iter()
+            .flat_map(|story| story.check())
+            .map(|w| w.kind)
+            .filter(|kind| matches!(kind, WarningKind::DeadLink(_)))
+            .collect();
+        assert_eq!(
+            dead,
+            [
+                WarningKind::DeadLink("Two".to_string()),
+                WarningKind::DeadLink("Three".to_string())
+            ]
+        );
+
+        // Without a separator, the input is one story
+        let outputs = StoryPassages::split_from_string_with_options(
+            input.to_string(),
+            &ParseOptions::default(),
+        );
+        assert_eq!(outputs.len(), 1);
+        assert!(outputs[0]
+            .get_warnings()
+            .iter()
+            .any(|w| w.kind == WarningKind::DuplicateStoryTitle));
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn encoding() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let bom_path = dir.path().join("bom.twee");
+        std::fs::write(&bom_path, b"\xEF\xBB\xBF:: Start\nHello\n")?;
+        let story = StoryPassages::from_path(&bom_path).take().0.ok().unwrap();
+        assert!(story.passages.contains_key("Start"));
+        let story = StoryPassages::from_string("\u{feff}:: Start\nHello\n".to_string())
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        assert!(story.passages.contains_key("Start"));
+
+        let utf16_path = dir.path().join("utf16.twee");
+        let utf16: Vec<u8> = std::iter::once(0xfeff)
+            .chain(":: Start\nHello\n".encode_utf16())
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        std::fs::write(&utf16_path, utf16)?;
+        let err = StoryPassages::from_path(&utf16_path)
+            .take()
+            .0
+            .err()
+            .unwrap();
+        #[cfg(feature = "full-context")]
+        let err = err.error_list;
+        let error = &err.errors[0];
+        assert_eq!(
+            error.kind,
+            ErrorKind::InvalidEncoding(
+                "found a UTF-16 (little-endian) byte order mark".to_string()
+            )
+        );
+        #[cfg(feature = "full-context")]
+        assert_eq!(
+            error.context.as_ref().unwrap().get_file_name(),
+            &Some("utf16.twee".to_string())
+        );
+        let options = ParseOptions::new().with_lossy_encoding(true);
+        let story = StoryPassages::from_path_with_options(&utf16_path, &options)
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        assert!(story.passages.contains_key("Start"));
+
+        let invalid_path = dir.path().join("invalid.twee");
+        std::fs::write(&invalid_path, b":: Start\nCaf\xE9\n")?;
+        let err = StoryPassages::from_path(&invalid_path)
+            .take()
+            .0
+            .err()
+            .unwrap();
+        #[cfg(feature = "full-context")]
+        let err = err.error_list;
+        let error = &err.errors[0];
+        assert_eq!(
+            error.kind,
+            ErrorKind::InvalidEncoding("invalid byte 0xE9".to_string())
+        );
+        assert_eq!(
+            *error.context.as_ref().unwrap().get_start_position(),
+            Position::abs(2, 4)
+        );
+        #[cfg(feature = "full-context")]
+        assert_eq!(error.context.as_ref().unwrap().get_contents(), "\u{fffd}");
+
+        let options = ParseOptions::new().with_skip_unreadable(true);
+        let (res, warnings) = StoryPassages::from_path_with_options(&invalid_path, &options).take();
+        assert!(res.is_ok());
+        assert!(
+            matches!(&warnings[0].kind, WarningKind::UnreadableFile(_, e) if e.contains("0xE9"))
+        );
+
+        Ok(())
+    }
+
+    fn pids(story: &StoryPassages) -> Vec<(String, usize)> {
+        let mut pids: Vec<(String, usize)> = story
+            .passages
+            .iter()
+            .filter_map(|(name, passage)| match &passage.content {
+                PassageContent::Normal(twine) => Some((name.clone(), twine.pid)),
+                _ => None,
+            })
+            .collect();
+        pids.sort_by_key(|(_, pid)| *pid);
+        pids
+    }
+
+    #[test]
+    fn editing() {
+        let input = r#":: StoryData
+{
+"ifid": "abc",
+"start": "Hub"
+}
+
+:: Hub
+[[Left]] [[Go right->Right]] [[Left<-Back]]
+
+:: Left
+[[Hub]]
+
+:: Right
+[[Text|Left]]
+"#
+        .to_string();
+        let mut story = StoryPassages::from_string(input).take().0.ok().unwrap();
+
+        // Renaming onto an existing passage is a conflict
+        let warnings = story.rename_passage("Left", "Right");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].kind,
+            WarningKind::DuplicatePassage("Right".to_string())
+        );
+        assert!(story.passages.contains_key("Left"));
+
+        assert!(story.rename_passage("Left", "West").is_empty());
+        assert!(!story.passages.contains_key("Left"));
+        assert_eq!(story.passages["West"].header.name, "West");
+        assert!(!story
+            .check()
+            .iter()
+            .any(|w| matches!(w.kind, WarningKind::DeadLink(_))));
+        if let PassageContent::Normal(twine) = &story.passages["Hub"].content {
+            assert_eq!(
+                twine.content,
+                "[[West]] [[Go right->Right]] [[West<-Back]]\n"
+            );
+        }
+        if let PassageContent::Normal(twine) = &story.passages["Right"].content {
+            assert_eq!(twine.get_links()[0].target, "West");
+        }
+
+        // Renaming the start passage updates StoryData
+        assert!(story.rename_passage("Hub", "Center").is_empty());
+        assert_eq!(story.get_start_passage_name(), Some("Center"));
+
+        // Removing a passage closes the gap in pids
+        let removed = story.remove_passage("West").unwrap();
+        assert_eq!(removed.header.name, "West");
+        assert!(story.remove_passage("West").is_none());
+        let remaining: Vec<usize> = pids(&story).iter().map(|(_, pid)| *pid).collect();
+        assert_eq!(remaining, [1, 2]);
+
+        // Adding a passage uses the next pid, and duplicates are ignored
+        let passage = removed;
+        assert!(story.add_passage(passage).is_empty());
+        assert_eq!(pids(&story).last().unwrap(), &("West".to_string(), 3));
+        let dup = StoryPassages::from_string(":: West\nAgain".to_string())
+            .take()
+            .0
+            .ok()
+            .unwrap()
+            .remove_passage("West")
+            .unwrap();
+        let warnings = story.add_passage(dup);
+        assert_eq!(
+            warnings[0].kind,
+            WarningKind::DuplicatePassage("West".to_string())
+        );
+        assert_eq!(story.passages.len(), 3);
+
+        // Removing a passage from the map directly leaves a gap, which a new
+        // passage doesn't fill
+        let right = story.passages.remove("Right").unwrap();
+        assert!(story.add_passage(right).is_empty());
+        assert_eq!(pids(&story).last().unwrap(), &("Right".to_string(), 4));
+
+        assert!(story.set_start_passage("West").is_empty());
+        assert_eq!(story.get_start_passage_name(), Some("West"));
+        let warnings = story.set_start_passage("Nowhere");
+        assert_eq!(
+            warnings[0].kind,
+            WarningKind::DeadStartPassage("Nowhere".to_string())
+        );
+
+        let mut no_data = StoryPassages::from_string(":: A\nText".to_string())
+            .take()
+            .0
+            .ok()
+            .unwrap();
+        let warnings = no_data.set_start_passage("A");
+        assert_eq!(warnings[0].kind, WarningKind::MissingStoryData);
+    }
+
+    #[test]
+    fn special_passage_precedence() {
+        let parse = |file: &str, input: &str| {
+            let context = FullContext::from(Some(file.to_string()), input.to_string());
+            StoryPassages::from_context(context).take().0.ok().unwrap()
+        };
+        let title = |story: &StoryPassages| match &story.title.as_ref().unwrap().content {
+            PassageContent::StoryTitle(title) => title.title.clone(),
+            _ => panic!("Expected title to be StoryTitle"),
+        };
+        let stories = || {
+            vec![
+                parse(
+                    "a.twee",
+                    ":: StoryTitle\nA\n\n:: StoryData\n{\"ifid\": \"a\"}",
+                ),
+                parse("main.twee", ":: StoryTitle\nMain"),
+                parse(
+                    "b.twee",
+                    ":: StoryTitle\nB\n\n:: StoryData\n{\"ifid\": \"b\"}",
+                ),
+            ]
+        };
+
+        // Without a precedence list, the first one found is kept
+        let mut story = StoryPassages::default();
+        for other in stories() {
+            story.merge_from(other);
+        }
+        assert_eq!(title(&story), "A");
+
+        let options = ParseOptions::new()
+            .with_special_passage_precedence(vec!["main.twee".to_string(), "b.twee".to_string()]);
+        let mut story = StoryPassages::default();
+        let mut warnings = Vec::new();
+        for other in stories() {
+            warnings.append(&mut story.merge_from_with_options(other, &options));
+        }
+        assert_eq!(title(&story), "Main");
+        match &story.data.as_ref().unwrap().content {
+            PassageContent::StoryData(Some(data)) => assert_eq!(data.ifid, "b"),
+            _ => panic!("Expected data to be StoryData"),
+        }
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(warnings[0].kind, WarningKind::DuplicateStoryTitle);
+        assert_eq!(
+            warnings[0].get_referent().unwrap().get_file_name(),
+            &Some("main.twee".to_string())
+        );
+        assert_eq!(
+            warnings[0].context.as_ref().unwrap().get_file_name(),
+            &Some("a.twee".to_string())
+        );
+    }
+
     #[test]
     fn from_string_error() {
         let input = "".to_string();
         let out = StoryPassages::from_string(input);
         assert!(out.is_err());
     }
+
+    #[test]
+    fn multi_line_title() {
+        let input = ":: Start\nHello\n\n:: StoryTitle\nThe Long\nDark Night\n".to_string();
+        let title = |story: &StoryPassages| match &story.title.as_ref().unwrap().content {
+            PassageContent::StoryTitle(title) => title.title.clone(),
+            _ => panic!("Expected title to be StoryTitle"),
+        };
+
+        let (res, warnings) = StoryPassages::from_string(input.clone()).take();
+        assert_eq!(title(&res.ok().unwrap()), "The Long\nDark Night");
+        let warning = warnings
+            .iter()
+            .find(|w| w.kind == WarningKind::MultiLineStoryTitle)
+            .unwrap();
+        assert_eq!(
+            warning.context.as_ref().unwrap().get_start_position().line,
+            4
+        );
+
+        let options = ParseOptions::new().with_multi_line_title(MultiLineTitle::FirstLine);
+        let (res, warnings) = StoryPassages::from_string_with_options(input, &options).take();
+        assert_eq!(title(&res.ok().unwrap()), "The Long");
+        assert!(warnings
+            .iter()
+            .any(|w| w.kind == WarningKind::MultiLineStoryTitle));
+
+        let input = ":: Start\nHello\n\n:: StoryTitle\nShort\n\n".to_string();
+        let (_, warnings) = StoryPassages::from_string_with_options(input, &options).take();
+        assert!(!warnings
+            .iter()
+            .any(|w| w.kind == WarningKind::MultiLineStoryTitle));
+    }
+
+    #[test]
+    fn html_unsafe() {
+        let input =
+            ":: StoryTitle\n<Untitled>\n\n:: Start\n[[a > b]]\n\n:: a > b\n\n:: Tom & Jerry\n"
+                .to_string();
+        let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+        let warnings: Vec<Warning> = story
+            .check()
+            .into_iter()
+            .filter(|w| w.kind.category() == crate::IssueCategory::Style)
+            .collect();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(
+            warnings[0].kind,
+            WarningKind::HtmlUnsafeTitle("<Untitled>".to_string())
+        );
+        assert_eq!(
+            warnings[1].kind,
+            WarningKind::HtmlUnsafeName("a > b".to_string())
+        );
+        assert_eq!(
+            warnings[1]
+                .context
+                .as_ref()
+                .unwrap()
+                .get_start_position()
+                .line,
+            7
+        );
+
+        let story = crate::Story::from(story);
+        let kinds: Vec<WarningKind> = story.validate().into_iter().map(|w| w.kind).collect();
+        assert!(kinds.contains(&WarningKind::HtmlUnsafeTitle("<Untitled>".to_string())));
+        assert!(kinds.contains(&WarningKind::HtmlUnsafeName("a > b".to_string())));
+    }
+
+    #[test]
+    fn link_syntax_in_name() {
+        let input = ":: Start\n[[Go|Left->Right]] [[A|B]]\n\n:: Left->Right\n\n:: A|B\n\n:: Fine\n"
+            .to_string();
+        let story = StoryPassages::from_string(input).take().0.ok().unwrap();
+        let kinds: Vec<WarningKind> = story
+            .check()
+            .into_iter()
+            .map(|w| w.kind)
+            .filter(|kind| matches!(kind, WarningKind::LinkSyntaxInName(_)))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                WarningKind::LinkSyntaxInName("A|B".to_string()),
+                WarningKind::LinkSyntaxInName("Left->Right".to_string()),
+            ]
+        );
+    }
 }
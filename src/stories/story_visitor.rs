@@ -0,0 +1,154 @@
+use crate::PassageHeader;
+use crate::Story;
+use crate::TwineContent;
+use crate::TwineLink;
+use crate::TwinePassage;
+
+/// A set of callbacks for the parts of a [`Story`], called by [`walk`]
+///
+/// Every method has an empty default implementation, so a visitor only needs
+/// to implement the callbacks it is interested in.
+///
+/// # Examples
+/// ```
+/// use tweep::{walk, Story, StoryVisitor, TwineLink, TwinePassage};
+/// #[derive(Default)]
+/// struct LinkCounter(usize);
+///
+/// impl StoryVisitor for LinkCounter {
+///     fn visit_link(&mut self, _passage: &TwinePassage, _link: &TwineLink) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// let input = ":: Start\n[[A]] [[B]]\n\n:: A\n[[Start]]\n".to_string();
+/// let story = Story::from_string(input).take().0.ok().unwrap();
+/// let mut counter = LinkCounter::default();
+/// walk(&story, &mut counter);
+/// assert_eq!(counter.0, 3);
+/// ```
+///
+/// [`Story`]: struct.Story.html
+/// [`walk`]: fn.walk.html
+pub trait StoryVisitor {
+    /// Called once for the story, before any of its passages
+    fn visit_story(&mut self, _story: &Story) {}
+
+    /// Called for each normal passage, before its header and content
+    fn visit_passage(&mut self, _passage: &TwinePassage) {}
+
+    /// Called for the header of each passage
+    fn visit_header(&mut self, _passage: &TwinePassage, _header: &PassageHeader) {}
+
+    /// Called for each metadata key of each passage, in order of the keys
+    fn visit_metadata(&mut self, _passage: &TwinePassage, _key: &str, _value: &serde_json::Value) {}
+
+    /// Called for the content of each passage
+    fn visit_content(&mut self, _passage: &TwinePassage, _content: &TwineContent) {}
+
+    /// Called for each link in the content of each passage, in the order they
+    /// appear
+    fn visit_link(&mut self, _passage: &TwinePassage, _link: &TwineLink) {}
+}
+
+/// Walks the given [`Story`], calling the methods of the given
+/// [`StoryVisitor`] for the story and then for each normal passage in order
+/// of passage name. For each passage, the passage, its header, each metadata
+/// key, its content, and each link are visited in that order
+///
+/// [`Story`]: struct.Story.html
+/// [`StoryVisitor`]: trait.StoryVisitor.html
+pub fn walk<V: StoryVisitor + ?Sized>(story: &Story, visitor: &mut V) {
+    visitor.visit_story(story);
+
+    let mut passages: Vec<&TwinePassage> = story.passages.values().collect();
+    passages.sort_by(|a, b| a.header.name.cmp(&b.header.name));
+    for passage in passages {
+        visitor.visit_passage(passage);
+        visitor.visit_header(passage, &passage.header);
+        for (key, value) in passage.metadata() {
+            visitor.visit_metadata(passage, key, value);
+        }
+        visitor.visit_content(passage, &passage.content);
+        for link in passage.content.get_links() {
+            visitor.visit_link(passage, link);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl StoryVisitor for Recorder {
+        fn visit_story(&mut self, story: &Story) {
+            self.0
+                .push(format!("story {}", story.title.as_ref().unwrap()));
+        }
+
+        fn visit_passage(&mut self, passage: &TwinePassage) {
+            self.0.push(format!("passage {}", passage.header.name));
+        }
+
+        fn visit_header(&mut self, _passage: &TwinePassage, header: &PassageHeader) {
+            self.0.push(format!("header {:?}", header.tags));
+        }
+
+        fn visit_metadata(
+            &mut self,
+            _passage: &TwinePassage,
+            key: &str,
+            value: &serde_json::Value,
+        ) {
+            self.0.push(format!("metadata {}={}", key, value));
+        }
+
+        fn visit_content(&mut self, _passage: &TwinePassage, content: &TwineContent) {
+            self.0.push(format!("content {:?}", content.content));
+        }
+
+        fn visit_link(&mut self, passage: &TwinePassage, link: &TwineLink) {
+            self.0
+                .push(format!("link {}->{}", passage.header.name, link.target));
+        }
+    }
+
+    #[test]
+    fn walk_order() {
+        let input = r#":: StoryTitle
+Test
+
+:: Start [a] {"position":"1,2"}
+[[B]][[Other]]
+
+:: Other
+[[Start]]
+"#
+        .to_string();
+        let story = Story::from_string(input).take().0.ok().unwrap();
+        let mut recorder = Recorder::default();
+        walk(&story, &mut recorder);
+        assert_eq!(
+            recorder.0,
+            [
+                "story Test",
+                "passage Other",
+                "header []",
+                "metadata position=\"10,10\"",
+                "metadata size=\"100,100\"",
+                "content \"[[Start]]\\n\"",
+                "link Other->Start",
+                "passage Start",
+                "header [\"a\"]",
+                "metadata position=\"1,2\"",
+                "metadata size=\"100,100\"",
+                "content \"[[B]][[Other]]\\n\"",
+                "link Start->B",
+                "link Start->Other",
+            ]
+        );
+    }
+}
@@ -0,0 +1,75 @@
+/// Returns the name in `names` that is closest to `target`, for suggesting a
+/// fix for a dead link or a misspelled key. Names are compared ignoring case,
+/// using the Levenshtein distance between them. Only names within a distance of a third of the
+/// length of `target`, and at least 1, are considered, and ties are broken by
+/// picking the name that sorts first
+pub(crate) fn closest_name<'a, I>(target: &str, names: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let target: Vec<char> = target.to_lowercase().chars().collect();
+    let max_distance = std::cmp::max(1, target.len() / 3);
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let candidate: Vec<char> = name.to_lowercase().chars().collect();
+            let distance = levenshtein(&target, &candidate);
+            if distance <= max_distance {
+                Some((distance, name))
+            } else {
+                None
+            }
+        })
+        .min()
+        .map(|(_, name)| name)
+}
+
+/// Computes the number of single character insertions, deletions, and
+/// substitutions needed to turn `a` into `b`
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb {
+                diagonal
+            } else {
+                1 + std::cmp::min(diagonal, std::cmp::min(above, row[j]))
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance() {
+        let chars = |s: &str| s.chars().collect::<Vec<char>>();
+        assert_eq!(levenshtein(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(levenshtein(&chars(""), &chars("abc")), 3);
+        assert_eq!(levenshtein(&chars("café"), &chars("cafe")), 1);
+        assert_eq!(levenshtein(&chars("same"), &chars("same")), 0);
+    }
+
+    #[test]
+    fn closest() {
+        let names = ["Forest", "Forest Path", "Cave", "Cove"];
+        assert_eq!(
+            closest_name("Forrest", names.iter().copied()),
+            Some("Forest")
+        );
+        assert_eq!(
+            closest_name("forest path", names.iter().copied()),
+            Some("Forest Path")
+        );
+        assert_eq!(closest_name("Cabe", names.iter().copied()), Some("Cave"));
+        assert_eq!(closest_name("Mountain", names.iter().copied()), None);
+        assert_eq!(closest_name("Anything", std::iter::empty()), None);
+    }
+}
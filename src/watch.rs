@@ -0,0 +1,247 @@
+use crate::diff;
+#[cfg(feature = "full-context")]
+use crate::ContextErrorList;
+#[cfg(not(feature = "full-context"))]
+use crate::ErrorList;
+use crate::Output;
+use crate::ParseOptions;
+use crate::Story;
+use crate::StoryDiff;
+use crate::StoryPassages;
+use crate::Warning;
+use notify::EventKind;
+use notify::RecommendedWatcher;
+use notify::RecursiveMode;
+use notify::Watcher;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[cfg(not(feature = "full-context"))]
+type StoryOutput = Output<Result<Story, ErrorList>>;
+#[cfg(feature = "full-context")]
+type StoryOutput = Output<Result<Story, ContextErrorList>>;
+
+#[cfg(not(feature = "full-context"))]
+type PassagesOutput = Output<Result<StoryPassages, ErrorList>>;
+#[cfg(feature = "full-context")]
+type PassagesOutput = Output<Result<StoryPassages, ContextErrorList>>;
+
+/// How long to wait for more file system events before parsing again, so
+/// that a save that touches several files produces a single update
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// The outputs of files that were parsed successfully, keyed by path, so that
+/// parsing a story again only needs to parse the files that changed
+#[derive(Default)]
+pub(crate) struct ParseCache {
+    files: Mutex<HashMap<PathBuf, (StoryPassages, Vec<Warning>)>>,
+}
+
+impl ParseCache {
+    /// Returns the cached output for the file at `path`, or produces it with
+    /// `parse` and caches it if it was successful
+    pub(crate) fn get_or_parse<F>(&self, path: &Path, parse: F) -> PassagesOutput
+    where
+        F: FnOnce() -> PassagesOutput,
+    {
+        if let Some((story, warnings)) = self.files.lock().unwrap().get(path) {
+            return Output::new(Ok(story.clone())).with_warnings(warnings.clone());
+        }
+        let out = parse();
+        if let Ok(story) = out.get_output() {
+            let entry = (story.clone(), out.get_warnings().clone());
+            self.files.lock().unwrap().insert(path.to_path_buf(), entry);
+        }
+        out
+    }
+
+    /// Drops the cached outputs for `path` and any files under it, returning
+    /// `true` if there were any
+    fn invalidate(&self, path: &Path) -> bool {
+        let mut files = self.files.lock().unwrap();
+        let count = files.len();
+        files.retain(|file, _| !file.starts_with(path));
+        files.len() != count
+    }
+}
+
+/// A new version of a story being watched with [`watch`]
+///
+/// [`watch`]: fn.watch.html
+pub struct StoryUpdate {
+    /// The files whose changes caused the story to be parsed again. Empty for
+    /// the first update, which holds the story as it was when watching began
+    pub paths: Vec<PathBuf>,
+
+    /// The output of parsing the story
+    pub output: StoryOutput,
+
+    /// The changes from the last version of the story that was parsed
+    /// successfully, or `None` if this parse failed or there is no earlier
+    /// version
+    pub diff: Option<StoryDiff>,
+}
+
+/// The handle for a story being watched with [`watch`]. The story stops being
+/// watched when this is dropped, after which no more updates are sent
+///
+/// [`watch`]: fn.watch.html
+pub struct StoryWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+/// Watches the story at the given [`Path`], a file or a directory of Twee
+/// files as for [`Story::from_path`], and parses it again with the given
+/// [`ParseOptions`] whenever it changes. Each version of the story is sent to
+/// the returned receiver as a [`StoryUpdate`], starting with the story as it
+/// is now
+///
+/// Parsing is incremental: the output for each file is kept until the file
+/// changes, so a change to one file of a large story only parses that file
+/// again before the story is checked. Changes that do not involve a Twee file,
+/// a `.twee-ignore` file, or a file that was part of the story are ignored.
+/// Updates are sent from a background thread until the returned
+/// [`StoryWatcher`] is dropped, or the receiver is dropped.
+///
+/// Enabled with "watch" feature
+///
+/// # Examples
+/// ```no_run
+/// let (_watcher, updates) = tweep::watch("story", &tweep::ParseOptions::default()).unwrap();
+/// for update in updates {
+///     if let Some(diff) = update.diff {
+///         println!("Added {:?}, removed {:?}", diff.added, diff.removed);
+///     }
+/// }
+/// ```
+///
+/// [`Path`]: std::path::Path
+/// [`Story::from_path`]: struct.Story.html#method.from_path
+/// [`ParseOptions`]: struct.ParseOptions.html
+/// [`StoryUpdate`]: struct.StoryUpdate.html
+/// [`StoryWatcher`]: struct.StoryWatcher.html
+pub fn watch<P: AsRef<Path>>(
+    path: P,
+    options: &ParseOptions,
+) -> notify::Result<(StoryWatcher, Receiver<StoryUpdate>)> {
+    let path = path.as_ref().canonicalize()?;
+    let cache = Arc::new(ParseCache::default());
+    let options = options.clone().with_cache(cache.clone());
+
+    let (event_tx, event_rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(event_tx)?;
+    watcher.watch(&path, RecursiveMode::Recursive)?;
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let output = Story::from_path_with_options(&path, &options);
+        let mut last = output.get_output().as_ref().ok().cloned();
+        let update = StoryUpdate {
+            paths: Vec::new(),
+            output,
+            diff: None,
+        };
+        if tx.send(update).is_err() {
+            return;
+        }
+
+        while let Ok(event) = event_rx.recv() {
+            // Gather the events for a burst of changes
+            let mut events = vec![event];
+            while let Ok(event) = event_rx.recv_timeout(DEBOUNCE) {
+                events.push(event);
+            }
+
+            let mut paths = Vec::new();
+            for event in events.into_iter().filter_map(Result::ok) {
+                if matches!(event.kind, EventKind::Access(_)) {
+                    continue;
+                }
+                for changed in event.paths {
+                    let cached = cache.invalidate(&changed);
+                    if (cached || is_story_file(&changed, &options)) && !paths.contains(&changed) {
+                        paths.push(changed);
+                    }
+                }
+            }
+            if paths.is_empty() {
+                continue;
+            }
+
+            let output = Story::from_path_with_options(&path, &options);
+            let story = output.get_output().as_ref().ok().cloned();
+            let diff = match (&last, &story) {
+                (Some(old), Some(new)) => Some(diff(old, new)),
+                _ => None,
+            };
+            if story.is_some() {
+                last = story;
+            }
+            let update = StoryUpdate {
+                paths,
+                output,
+                diff,
+            };
+            if tx.send(update).is_err() {
+                return;
+            }
+        }
+    });
+
+    Ok((StoryWatcher { _watcher: watcher }, rx))
+}
+
+/// Returns `true` if a change to the file or directory at `path` could change
+/// the story: it is a Twee file, a `.twee-ignore` file, or a directory
+fn is_story_file(path: &Path, options: &ParseOptions) -> bool {
+    let is_twee = path
+        .extension()
+        .map(|ext| options.extensions().iter().any(|twee| ext == twee.as_str()))
+        .unwrap_or(false);
+    is_twee || path.ends_with(".twee-ignore") || path.is_dir()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Instant;
+    use tempfile::tempdir;
+
+    #[test]
+    fn updates() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("a.tw"), ":: Start\n[[Cave]]\n")?;
+        fs::write(dir.path().join("b.tw"), ":: Cave\nDark\n")?;
+        let (_watcher, updates) = watch(dir.path(), &ParseOptions::default())?;
+
+        let first = updates.recv_timeout(Duration::from_secs(10))?;
+        assert!(first.paths.is_empty());
+        assert!(first.diff.is_none());
+        let story = first.output.get_output().as_ref().ok().unwrap();
+        assert_eq!(story.passages.len(), 2);
+
+        fs::write(dir.path().join("b.tw"), ":: Cave\nDark [[Start]]\n")?;
+        fs::write(dir.path().join("notes.txt"), "Not part of the story")?;
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let diff = loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let update = updates.recv_timeout(timeout)?;
+            assert!(update.paths.iter().all(|p| p.ends_with("b.tw")));
+            match update.diff {
+                Some(diff) if !diff.is_empty() => break diff,
+                _ => continue,
+            }
+        };
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].name, "Cave");
+        assert_eq!(diff.changed[0].added_links, ["Start"]);
+        Ok(())
+    }
+}